@@ -23,6 +23,7 @@
 # One-file mode re-extracts to a temp directory on every launch (3-5s penalty).
 # One-directory mode keeps files on disk at install time – fast cold start.
 
+import json
 import sys
 from pathlib import Path
 
@@ -32,6 +33,11 @@ from pathlib import Path
 HERE = Path(SPECPATH)          # backend/
 ROOT = HERE                    # spec lives in backend/
 
+# The app version, from the one place it is set.  Written to version.json
+# next to the executable below and reported by GET /api/version.
+TAURI_CONF = HERE.parent / "src-tauri" / "tauri.conf.json"
+APP_VERSION = json.loads(TAURI_CONF.read_text(encoding="utf-8"))["version"]
+
 # ---------------------------------------------------------------------------
 # Analysis
 # ---------------------------------------------------------------------------
@@ -156,3 +162,13 @@ coll = COLLECT(
     upx_exclude=[],
     name="almready-backend",
 )
+
+# ---------------------------------------------------------------------------
+# version.json
+# ---------------------------------------------------------------------------
+# Read by the shell to tell whether the running backend is the bundled one
+# (src-tauri/src/sidecar_version.rs) and by the backend itself for
+# GET /api/version.  Written after COLLECT, which replaces the directory.
+(Path(DISTPATH) / "almready-backend" / "version.json").write_text(
+    json.dumps({"version": APP_VERSION}) + "\n", encoding="utf-8"
+)
@@ -6,21 +6,24 @@ Startup: uvicorn app.main:app --reload
 
 from __future__ import annotations
 
+import hmac
 import json
 import logging
 import os
 import shutil
 import time
-from concurrent.futures import ProcessPoolExecutor, wait as _cf_wait
+from concurrent.futures import ProcessPoolExecutor
 from contextlib import asynccontextmanager
 from datetime import datetime, timedelta, timezone
 
-from fastapi import FastAPI, Request
+from fastapi import FastAPI, Header, HTTPException, Request
 from fastapi.middleware.cors import CORSMiddleware
+from fastapi.responses import JSONResponse
 from starlette.middleware.base import BaseHTTPMiddleware
 
 import app.state as state
 from app.routers import balance, calculate, curves, sessions, whatif
+from app.version import APP_VERSION
 
 logging.basicConfig(
     level=logging.INFO,
@@ -51,8 +54,12 @@ def _cleanup_old_sessions(max_age_days: int | None = None) -> None:
                 )
                 if created.tzinfo is None:
                     created = created.replace(tzinfo=timezone.utc)
-                if created >= cutoff:
-                    continue
+            else:
+                # A session being created has its directory before its
+                # meta.json; the sweep runs while requests are served.
+                created = datetime.fromtimestamp(entry.stat().st_mtime, tz=timezone.utc)
+            if created >= cutoff:
+                continue
             shutil.rmtree(entry)
             state._positions_df_cache.pop(entry.name, None)
             purged += 1
@@ -76,20 +83,30 @@ async def _periodic_cleanup() -> None:
 
 @asynccontextmanager
 async def _lifespan(app: FastAPI):
-    """Startup: purge stale sessions, pre-warm process pool, schedule cleanup."""
+    """Startup: purge stale sessions, pre-warm process pool, schedule cleanup.
+
+    The purge and the warm-up run in the background so /api/health answers
+    at once; /api/ready/{component} reports when each is done.
+    """
     import asyncio
 
     import engine.workers as _workers
 
-    _cleanup_old_sessions()
+    sweep = asyncio.create_task(asyncio.to_thread(_cleanup_old_sessions))
 
     n_workers = os.cpu_count() or 1
     state._executor = ProcessPoolExecutor(max_workers=n_workers)
-    _cf_wait([state._executor.submit(_workers.warmup) for _ in range(n_workers)])
+    warming = [state._executor.submit(_workers.warmup) for _ in range(n_workers)]
+
+    state._startup = {
+        "pool": lambda: all(f.done() for f in warming),
+        "session": sweep.done,
+    }
 
     cleanup_task = asyncio.create_task(_periodic_cleanup())
     yield
     cleanup_task.cancel()
+    await sweep
     state._executor.shutdown(wait=True)
     state._executor = None
 
@@ -112,6 +129,26 @@ class _RequestLoggingMiddleware(BaseHTTPMiddleware):
 
 app.add_middleware(_RequestLoggingMiddleware)
 
+
+# ALMREADY_AUTH_TOKEN: per-session secret generated by the Tauri shell for
+# every spawn.  When set, /api/* requires it in X-ALMReady-Token so other
+# local processes and DNS-rebinding pages can't use the API.  It is never
+# accepted in the URL, where access logs, history and Referer would keep it.
+# Unset in dev.
+_AUTH_TOKEN = os.environ.get("ALMREADY_AUTH_TOKEN", "")
+
+
+class _AuthTokenMiddleware(BaseHTTPMiddleware):
+    async def dispatch(self, request: Request, call_next):
+        if _AUTH_TOKEN and request.url.path.startswith("/api/"):
+            sent = request.headers.get("x-almready-token", "")
+            if not hmac.compare_digest(sent.encode(), _AUTH_TOKEN.encode()):
+                return JSONResponse({"detail": "missing or invalid token"}, status_code=401)
+        return await call_next(request)
+
+
+app.add_middleware(_AuthTokenMiddleware)
+
 # ALMREADY_CORS_ORIGINS: comma-separated extra origins injected by the Tauri
 # shell at runtime (e.g. "tauri://localhost,https://tauri.localhost").
 _extra_origins = [
@@ -148,3 +185,50 @@ app.include_router(whatif.router)
 @app.get("/api/health")
 def health() -> dict[str, str]:
     return {"status": "ok"}
+
+
+@app.get("/api/ready/{component}")
+def ready(component: str) -> JSONResponse:
+    """Whether one piece of startup work has finished: ``pool`` (worker
+    warm-up) or ``session`` (the stale-session purge).
+
+    The Tauri shell polls each after /api/health and opens the main window
+    once all answer 200 (see src-tauri/src/readiness.rs).
+    """
+    is_done = state._startup.get(component)
+    if is_done is None:
+        raise HTTPException(status_code=404, detail=f"Unknown component: {component}")
+    if not is_done():
+        return JSONResponse({"status": "starting"}, status_code=503)
+    return JSONResponse({"status": "ready"})
+
+
+@app.get("/api/version")
+def version() -> dict[str, str]:
+    """The packaged app version (see app/version.py).
+
+    The Tauri shell refuses to open a frontend whose major.minor differs, and
+    compares it with the bundled sidecar's version.json to spot a pending
+    engine update.
+    """
+    if APP_VERSION is None:
+        raise HTTPException(status_code=503, detail="version unknown")
+    return {"version": APP_VERSION}
+
+
+@app.post("/api/shutdown", status_code=202)
+def shutdown(
+    request: Request,
+    x_almready_shell: str | None = Header(default=None),
+) -> dict[str, str]:
+    """Begin a graceful exit, exactly as on SIGTERM (lifespan shutdown runs).
+
+    Only available under the Tauri shell, which registers the uvicorn server
+    in sidecar_main.py.  The custom header forces a CORS preflight, so a web
+    page on another origin can't trigger this with a simple POST.
+    """
+    server = getattr(request.app.state, "uvicorn_server", None)
+    if server is None or x_almready_shell != "shutdown":
+        raise HTTPException(status_code=404)
+    server.should_exit = True
+    return {"status": "shutting down"}
@@ -2,18 +2,23 @@
 
 from __future__ import annotations
 
+import json
+import shutil
 import uuid
+import zipfile
 from datetime import datetime, timezone
+from pathlib import Path
 
-from fastapi import APIRouter
+from fastapi import APIRouter, HTTPException
 
 import app.state as state
-from app.schemas import SessionMeta
+from app.schemas import OpenSessionRequest, SessionMeta
 from app.session import (
     _assert_session_exists,
     _curves_summary_path,
     _get_session_meta,
     _persist_session_meta,
+    _session_dir,
     _summary_path,
 )
 
@@ -41,3 +46,62 @@ def get_session(session_id: str) -> SessionMeta:
     meta.has_balance = _summary_path(session_id).exists()
     meta.has_curves = _curves_summary_path(session_id).exists()
     return meta
+
+
+@router.post("/api/session/open", response_model=SessionMeta)
+def open_session_file(body: OpenSessionRequest) -> SessionMeta:
+    """Import a ``.almready`` session file as a new session.
+
+    The file is a ZIP of a session directory: ``meta.json`` plus the
+    artifacts next to it, all at the top level.  The import gets a fresh
+    session_id so opening the same file twice never clobbers a session.
+    """
+    source = Path(body.path)
+    if not source.is_file():
+        raise HTTPException(status_code=404, detail=f"Session file not found: {source}")
+
+    try:
+        archive = zipfile.ZipFile(source)
+    except (zipfile.BadZipFile, OSError):
+        raise HTTPException(status_code=400, detail="Not an ALMReady session file")
+
+    with archive:
+        names = [name for name in archive.namelist() if not name.endswith("/")]
+        if "meta.json" not in names:
+            raise HTTPException(status_code=400, detail="Session file has no meta.json")
+        # Flat archives only: a nested or relative entry could land outside
+        # the session directory.
+        for name in names:
+            if "/" in name or "\\" in name or name in {".", ".."}:
+                raise HTTPException(status_code=400, detail=f"Unexpected entry in session file: {name}")
+        try:
+            saved = SessionMeta(**json.loads(archive.read("meta.json")))
+        except (json.JSONDecodeError, TypeError, ValueError) as exc:
+            raise HTTPException(status_code=400, detail=f"Corrupted session metadata: {exc}")
+        if saved.schema_version != "v1":
+            raise HTTPException(
+                status_code=400,
+                detail=f"Unsupported session schema {saved.schema_version}",
+            )
+
+        session_id = str(uuid.uuid4())
+        sdir = _session_dir(session_id)
+        try:
+            for name in names:
+                if name != "meta.json":
+                    (sdir / name).write_bytes(archive.read(name))
+        except (zipfile.BadZipFile, OSError) as exc:
+            shutil.rmtree(sdir, ignore_errors=True)
+            raise HTTPException(status_code=400, detail=f"Cannot read session file: {exc}")
+
+    meta = SessionMeta(
+        session_id=session_id,
+        created_at=saved.created_at,
+        status="active",
+        schema_version=saved.schema_version,
+    )
+    state._SESSIONS[session_id] = meta
+    _persist_session_meta(meta)
+    meta.has_balance = _summary_path(session_id).exists()
+    meta.has_curves = _curves_summary_path(session_id).exists()
+    return meta
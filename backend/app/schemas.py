@@ -18,6 +18,11 @@ class SessionMeta(BaseModel):
     has_curves: bool = False
 
 
+class OpenSessionRequest(BaseModel):
+    """Body of POST /api/session/open: a ``.almready`` file on local disk."""
+    path: str
+
+
 # ── Balance Tree ────────────────────────────────────────────────────────────
 
 class BalanceSheetSummary(BaseModel):
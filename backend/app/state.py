@@ -11,7 +11,7 @@ from __future__ import annotations
 import os
 from concurrent.futures import ProcessPoolExecutor
 from pathlib import Path
-from typing import Any
+from typing import Any, Callable
 
 # Persistent process pool – created at startup in main._lifespan().
 _executor: ProcessPoolExecutor | None = None
@@ -23,6 +23,10 @@ _SESSIONS: dict = {}
 _upload_progress: dict[str, dict[str, Any]] = {}
 _calc_progress: dict[str, dict[str, Any]] = {}
 
+# Startup work still running after the server answers, by component name,
+# for GET /api/ready/{component}; each callable tells whether it finished.
+_startup: dict[str, Callable[[], bool]] = {}
+
 # Cached positions DataFrames for fast detail/contract queries.
 # Populated lazily on first request; invalidated on upload/delete.
 import pandas as pd
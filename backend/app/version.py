@@ -0,0 +1,33 @@
+"""The app version the backend reports at GET /api/version.
+
+It is set in one place, src-tauri/tauri.conf.json.  The PyInstaller build
+(almready-backend.spec) copies it to version.json next to the sidecar
+executable, where the Tauri shell reads it too; from a source checkout it is
+read from tauri.conf.json directly.
+"""
+
+from __future__ import annotations
+
+import json
+import sys
+from pathlib import Path
+
+_REPO_ROOT = Path(__file__).resolve().parents[2]
+
+
+def _version_file() -> Path:
+    if getattr(sys, "frozen", False):
+        return Path(sys.executable).parent / "version.json"
+    return _REPO_ROOT / "src-tauri" / "tauri.conf.json"
+
+
+def read_version(path: Path) -> str | None:
+    """The "version" field of the JSON file at *path*, or None if unreadable."""
+    try:
+        version = json.loads(path.read_text(encoding="utf-8"))["version"]
+    except (OSError, ValueError, KeyError, TypeError):
+        return None
+    return version if isinstance(version, str) else None
+
+
+APP_VERSION: str | None = read_version(_version_file())
@@ -10,15 +10,25 @@ Tests the complete lifecycle:
   GET  /api/sessions/{id}/results → verify results
   GET  /api/sessions/{id}/results/chart-data → verify charts
   GET  /api/health → health check
+  GET  /api/version → packaged app version
+  GET  /api/ready/{component} → startup work finished
+  POST /api/shutdown → sidecar-only graceful exit
+  POST /api/session/open → import a .almready session file
 
 All data is synthetic and created in-memory.
 """
 
 from __future__ import annotations
 
+import json
+import time
+import zipfile
+from pathlib import Path
+
 import pytest
 from starlette.testclient import TestClient
 
+from app.version import read_version
 from engine.tests.conftest import make_synthetic_curves_excel, make_synthetic_zip
 
 
@@ -31,6 +41,85 @@ class TestHealthCheck:
         assert resp.json() == {"status": "ok"}
 
 
+# ── Readiness ──────────────────────────────────────────────────────────────
+
+class TestReadiness:
+    @pytest.mark.parametrize("component", ["pool", "session"])
+    def test_startup_components_become_ready(
+        self, test_client: TestClient, component: str,
+    ) -> None:
+        deadline = time.monotonic() + 60
+        resp = test_client.get(f"/api/ready/{component}")
+        while resp.status_code == 503 and time.monotonic() < deadline:
+            time.sleep(0.1)
+            resp = test_client.get(f"/api/ready/{component}")
+        assert resp.status_code == 200
+        assert resp.json() == {"status": "ready"}
+
+    def test_unknown_component_is_404(self, test_client: TestClient) -> None:
+        assert test_client.get("/api/ready/licence").status_code == 404
+
+
+# ── Version ────────────────────────────────────────────────────────────────
+
+class TestVersion:
+    def test_version_is_the_packaged_app_version(self, test_client: TestClient) -> None:
+        conf = Path(__file__).resolve().parents[3] / "src-tauri" / "tauri.conf.json"
+        expected = json.loads(conf.read_text(encoding="utf-8"))["version"]
+
+        resp = test_client.get("/api/version")
+        assert resp.status_code == 200
+        assert resp.json() == {"version": expected}
+
+    def test_unreadable_version_file_is_unknown(self, tmp_path: Path) -> None:
+        assert read_version(tmp_path / "missing.json") is None
+        (tmp_path / "version.json").write_text('{"name": "almready"}', encoding="utf-8")
+        assert read_version(tmp_path / "version.json") is None
+        (tmp_path / "version.json").write_text('{"version": "1.4.0"}', encoding="utf-8")
+        assert read_version(tmp_path / "version.json") == "1.4.0"
+
+
+# ── Auth token ─────────────────────────────────────────────────────────────
+
+class TestAuthToken:
+    def test_token_is_only_accepted_in_the_header(
+        self, test_client: TestClient, monkeypatch: pytest.MonkeyPatch
+    ) -> None:
+        import app.main
+
+        monkeypatch.setattr(app.main, "_AUTH_TOKEN", "s3cret")
+        assert test_client.get("/api/health").status_code == 401
+        assert test_client.get("/api/health?token=s3cret").status_code == 401
+        resp = test_client.get("/api/health", headers={"X-ALMReady-Token": "s3cret"})
+        assert resp.status_code == 200
+
+
+# ── Shutdown ───────────────────────────────────────────────────────────────
+
+class TestShutdown:
+    def test_shutdown_unavailable_outside_sidecar(self, test_client: TestClient) -> None:
+        # No uvicorn server is registered on app.state under TestClient/dev.
+        resp = test_client.post("/api/shutdown", headers={"X-ALMReady-Shell": "shutdown"})
+        assert resp.status_code == 404
+
+    def test_shutdown_requires_shell_header(self, test_client: TestClient) -> None:
+        class _Server:
+            should_exit = False
+
+        server = _Server()
+        test_client.app.state.uvicorn_server = server
+        try:
+            resp = test_client.post("/api/shutdown")
+            assert resp.status_code == 404
+            assert not server.should_exit
+
+            resp = test_client.post("/api/shutdown", headers={"X-ALMReady-Shell": "shutdown"})
+            assert resp.status_code == 202
+            assert server.should_exit
+        finally:
+            del test_client.app.state.uvicorn_server
+
+
 # ── Session management ─────────────────────────────────────────────────────
 
 class TestSessionManagement:
@@ -56,6 +145,55 @@ class TestSessionManagement:
         assert resp.status_code == 404
 
 
+# ── Session files ──────────────────────────────────────────────────────────
+
+def _write_session_file(path: Path, entries: dict[str, str]) -> Path:
+    with zipfile.ZipFile(path, "w") as zf:
+        for name, content in entries.items():
+            zf.writestr(name, content)
+    return path
+
+
+class TestOpenSessionFile:
+    META = json.dumps({"session_id": "saved-id", "created_at": "2026-01-05T09:00:00+00:00"})
+
+    def test_session_file_is_imported_as_a_new_session(
+        self, test_client: TestClient, tmp_path: Path,
+    ) -> None:
+        source = _write_session_file(
+            tmp_path / "Q3 Stress Test – Übersicht.almready",
+            {"meta.json": self.META, "balance_summary.json": "{}"},
+        )
+        resp = test_client.post("/api/session/open", json={"path": str(source)})
+        assert resp.status_code == 200
+        data = resp.json()
+        assert data["session_id"] != "saved-id"
+        assert data["created_at"] == "2026-01-05T09:00:00+00:00"
+        assert data["has_balance"] is True
+        assert data["has_curves"] is False
+
+        again = test_client.post("/api/session/open", json={"path": str(source)})
+        assert again.json()["session_id"] != data["session_id"]
+        assert test_client.get(f"/api/sessions/{data['session_id']}").status_code == 200
+
+    def test_missing_file_is_404(self, test_client: TestClient, tmp_path: Path) -> None:
+        resp = test_client.post("/api/session/open", json={"path": str(tmp_path / "gone.almready")})
+        assert resp.status_code == 404
+
+    def test_malformed_files_are_rejected(self, test_client: TestClient, tmp_path: Path) -> None:
+        not_a_zip = tmp_path / "text.almready"
+        not_a_zip.write_text("hello", encoding="utf-8")
+        no_meta = _write_session_file(tmp_path / "no-meta.almready", {"balance_summary.json": "{}"})
+        escaping = _write_session_file(
+            tmp_path / "escaping.almready",
+            {"meta.json": self.META, "../outside.json": "{}"},
+        )
+        for path in (not_a_zip, no_meta, escaping):
+            resp = test_client.post("/api/session/open", json={"path": str(path)})
+            assert resp.status_code == 400, path
+        assert not (tmp_path / "outside.json").exists()
+
+
 # ── Balance upload (ZIP) ───────────────────────────────────────────────────
 
 class TestBalanceUpload:
@@ -10,14 +10,20 @@ Startup protocol:
      ProcessPoolExecutor workers inside a frozen executable on Windows.
   2. A free OS port is discovered by binding to 127.0.0.1:0.
   3. "PORT:{port}" is printed to stdout (flushed) so the Tauri Rust shell can
-     read it and know where to proxy health-check polling.
+     read it and know where to proxy health-check polling.  The port is also
+     written to ALMREADY_PORT_FILE, which the shell falls back to if it
+     cannot read stdout.
   4. uvicorn starts the FastAPI app on that port.  The lifespan startup
      (ProcessPoolExecutor warm-up) completes, then /api/health returns 200.
      The Tauri shell polls until healthy, then shows the app window.
+  5. On exit the shell POSTs /api/shutdown (or sends SIGTERM) and gives the
+     process a few seconds to finish in-flight writes before killing it.
 
 Environment variables set by the Tauri shell before spawning this process:
   ALMREADY_DATA_DIR   – OS user-data directory for session persistence
   ALMREADY_CORS_ORIGINS – Tauri webview origins for CORS whitelist
+  ALMREADY_PORT_FILE  – where to write the chosen port (optional)
+  ALMREADY_AUTH_TOKEN – per-session secret required on /api/* requests
 """
 
 from __future__ import annotations
@@ -43,6 +49,21 @@ def _find_free_port() -> int:
         return s.getsockname()[1]
 
 
+def _write_port_file(port: int) -> None:
+    """Write the port to ALMREADY_PORT_FILE, if set, via a temp file so the
+    shell never reads a partial value."""
+    path = os.environ.get("ALMREADY_PORT_FILE")
+    if not path:
+        return
+    tmp = f"{path}.tmp"
+    try:
+        with open(tmp, "w", encoding="ascii") as f:
+            f.write(str(port))
+        os.replace(tmp, path)
+    except OSError as exc:
+        print(f"could not write port file {path}: {exc}", file=sys.stderr)
+
+
 def main() -> None:
     # Ensure the backend package is importable when running from the frozen
     # one-directory bundle (the executable lives inside the bundle directory
@@ -54,13 +75,14 @@ def main() -> None:
     port = _find_free_port()
 
     # Signal the Tauri shell with the chosen port before uvicorn blocks.
+    _write_port_file(port)
     print(f"PORT:{port}", flush=True)
 
     import uvicorn
 
     # Pass the app object directly (not as a string) so uvicorn does not
     # attempt a dynamic string import inside the frozen binary.
-    uvicorn.run(
+    config = uvicorn.Config(
         _fastapi_app,
         host="127.0.0.1",
         port=port,
@@ -73,6 +95,10 @@ def main() -> None:
         # already logs requests.
         access_log=False,
     )
+    server = uvicorn.Server(config)
+    # Lets POST /api/shutdown request the same graceful exit as SIGTERM.
+    _fastapi_app.state.uvicorn_server = server
+    server.run()
 
 
 if __name__ == "__main__":
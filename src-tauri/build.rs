@@ -1,3 +1,13 @@
 fn main() {
+    // Packaging a release sets ALMREADY_RELEASE; such a build must carry the
+    // license secret or no license would ever validate (see license.rs).
+    // Other builds without it report licenses as unverifiable at runtime.
+    println!("cargo:rerun-if-env-changed=ALMREADY_RELEASE");
+    println!("cargo:rerun-if-env-changed=ALMREADY_LICENSE_SECRET");
+    let is_set = |var: &str| std::env::var_os(var).is_some_and(|value| !value.is_empty());
+    if is_set("ALMREADY_RELEASE") && !is_set("ALMREADY_LICENSE_SECRET") {
+        panic!("ALMREADY_RELEASE is set but ALMREADY_LICENSE_SECRET is not");
+    }
+
     tauri_build::build()
 }
@@ -0,0 +1,182 @@
+//! Sound and visual cues for events the user may be waiting on with the app
+//! in the background: a finished or failed run, an engine crash, a completed
+//! export.
+//!
+//! `Settings::alert_style` picks the cue:
+//!
+//! * `sound` – the platform's notification sound for the event;
+//! * `visual` – the main window requests attention (taskbar flash on
+//!   Windows, dock bounce on macOS, urgency hint on Linux);
+//! * `both`, or `none` for neither.
+//!
+//! Native notifications (see `journal`) are separate and stay governed by
+//! the OS notification settings.
+//!
+//! macOS   → `afplay` with a sound from /System/Library/Sounds
+//! Windows → `MessageBeep` with the matching system sound
+//! Linux   → `canberra-gtk-play` with a freedesktop sound event id
+//!
+//! Sounds play on a blocking task, never on the async runtime.  A machine
+//! with no audio device or without the player just stays silent: the
+//! failure is logged at debug level and nothing else happens.
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State, UserAttentionType};
+
+use crate::{
+    settings::SettingsStore,
+    shell_log::{self, Level},
+    window_factory::MAIN_LABEL,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertStyle {
+    Sound,
+    #[default]
+    Visual,
+    Both,
+    None,
+}
+
+impl AlertStyle {
+    fn sound(self) -> bool {
+        matches!(self, AlertStyle::Sound | AlertStyle::Both)
+    }
+
+    fn visual(self) -> bool {
+        matches!(self, AlertStyle::Visual | AlertStyle::Both)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alert {
+    JobFinished,
+    JobFailed,
+    BackendCrashed,
+    ExportComplete,
+}
+
+impl Alert {
+    fn is_failure(self) -> bool {
+        matches!(self, Alert::JobFailed | Alert::BackendCrashed)
+    }
+}
+
+// ── Platform sounds ─────────────────────────────────────────────────────────
+
+#[cfg(target_os = "macos")]
+fn play_sound(alert: Alert) -> Result<(), String> {
+    let name = if alert.is_failure() { "Basso" } else { "Glass" };
+    let status = std::process::Command::new("afplay")
+        .arg(format!("/System/Library/Sounds/{name}.aiff"))
+        .status()
+        .map_err(|e| format!("afplay: {e}"))?;
+    if !status.success() {
+        return Err(format!("afplay exited with {status}"));
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn play_sound(alert: Alert) -> Result<(), String> {
+    use windows::Win32::UI::WindowsAndMessaging::{MessageBeep, MB_ICONASTERISK, MB_ICONHAND};
+
+    let sound = if alert.is_failure() {
+        MB_ICONHAND
+    } else {
+        MB_ICONASTERISK
+    };
+    // SAFETY: plain call; the sound is queued and played asynchronously.
+    unsafe { MessageBeep(sound) }.map_err(|e| format!("MessageBeep: {e}"))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn play_sound(alert: Alert) -> Result<(), String> {
+    let event = if alert.is_failure() {
+        "dialog-error"
+    } else {
+        "complete"
+    };
+    let status = std::process::Command::new("canberra-gtk-play")
+        .args(["--id", event])
+        .status()
+        .map_err(|e| format!("canberra-gtk-play: {e}"))?;
+    if !status.success() {
+        return Err(format!("canberra-gtk-play exited with {status}"));
+    }
+    Ok(())
+}
+
+// ── Raising alerts ──────────────────────────────────────────────────────────
+
+fn raise_with(app: &AppHandle, style: AlertStyle, alert: Alert) {
+    if style.visual() {
+        if let Some(window) = app.get_webview_window(MAIN_LABEL) {
+            let attention = if alert.is_failure() {
+                UserAttentionType::Critical
+            } else {
+                UserAttentionType::Informational
+            };
+            let _ = window.request_user_attention(Some(attention));
+        }
+    }
+    if style.sound() {
+        tauri::async_runtime::spawn_blocking(move || {
+            if let Err(e) = play_sound(alert) {
+                shell_log::log(Level::Debug, "alerts", format!("no alert sound: {e}"));
+            }
+        });
+    }
+}
+
+/// Give the cue chosen in settings for `alert`.
+pub fn raise(app: &AppHandle, alert: Alert) {
+    let style = app.state::<SettingsStore>().get().alert_style;
+    raise_with(app, style, alert);
+}
+
+// ── Commands ────────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub fn get_alert_style(store: State<'_, SettingsStore>) -> AlertStyle {
+    store.get().alert_style
+}
+
+#[tauri::command]
+pub fn set_alert_style(store: State<'_, SettingsStore>, style: AlertStyle) -> Result<(), String> {
+    let mut settings = store.get();
+    settings.alert_style = style;
+    store.replace(settings)
+}
+
+/// Give a "run finished" cue in `style` (the saved one if omitted), so the
+/// settings page can demonstrate a choice before saving it.
+#[tauri::command]
+pub fn preview_alert(app: AppHandle, style: Option<AlertStyle>) {
+    let style = style.unwrap_or_else(|| app.state::<SettingsStore>().get().alert_style);
+    raise_with(&app, style, Alert::JobFinished);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn styles_select_cues_and_use_lowercase_names() {
+        let cues = |s: AlertStyle| (s.sound(), s.visual());
+        assert_eq!(cues(AlertStyle::Sound), (true, false));
+        assert_eq!(cues(AlertStyle::Visual), (false, true));
+        assert_eq!(cues(AlertStyle::Both), (true, true));
+        assert_eq!(cues(AlertStyle::None), (false, false));
+
+        assert_eq!(
+            serde_json::from_str::<AlertStyle>(r#""both""#).unwrap(),
+            AlertStyle::Both
+        );
+        assert_eq!(
+            serde_json::to_string(&AlertStyle::None).unwrap(),
+            r#""none""#
+        );
+    }
+}
@@ -0,0 +1,440 @@
+//! `almready://api/…` – the backend API under a URI scheme of the app's
+//! own, so the webview never makes a cross-origin request to 127.0.0.1 and
+//! the backend needn't list the webview origins in `ALMREADY_CORS_ORIGINS`.
+//!
+//! A request to `almready://api/{path}` (or `almready://localhost/api/{path}`,
+//! which `src/lib/api.ts` uses) is sent on to
+//! `http://127.0.0.1:{port}/api/{path}` with its method, headers, query and
+//! body; status, headers and body come back as the backend sent them.  The
+//! port and session token are read from `BackendProcess` on every request,
+//! so the scheme keeps working across `restart_backend`, and the frontend
+//! needn't hold the token at all.  A token the page sends itself is dropped
+//! and replaced.
+//!
+//! WebView2 (Windows) only serves custom schemes as
+//! `http://almready.localhost/…`; there the path must start with `/api/`.
+//!
+//! `UriSchemeResponder::respond` takes a complete body (wry has no
+//! streaming responder), so a reply is read chunk by chunk into one buffer
+//! sized from its `Content-Length` and handed over without another copy.
+//! One declaring more than `MAX_REPLY_BYTES` is refused with 502 before any
+//! of it is read, one growing past it while read likewise.  Exports go
+//! through `exports`, which streams to disk; this is for API calls.
+//!
+//! The scheme is still a different origin from the page, so replies carry
+//! `Access-Control-Allow-Origin` and preflights are answered here without
+//! reaching the backend – but only for the app's own pages
+//! (`ALLOWED_ORIGINS`, plus the Vite dev server in debug builds).  Any other
+//! request is refused with 403 before it reaches the backend, including one
+//! without an `Origin` header unless its `Referer` is one of those pages.
+
+use tauri::{
+    http::{header, HeaderMap, HeaderValue, Method, Request, Response, StatusCode, Uri},
+    Manager, UriSchemeContext, UriSchemeResponder,
+};
+
+use crate::{
+    http, session_token,
+    shell_log::{self, Level},
+    BackendProcess,
+};
+
+pub const SCHEME: &str = "almready";
+
+/// The app's pages: `tauri://localhost` on macOS and Linux,
+/// `http://tauri.localhost` on Windows.
+const ALLOWED_ORIGINS: [&str; 2] = ["tauri://localhost", "http://tauri.localhost"];
+
+/// `build.devUrl`, allowed in debug builds only.
+const DEV_ORIGIN: &str = "http://localhost:8080";
+
+/// Largest reply that is buffered and passed on.
+const MAX_REPLY_BYTES: usize = 64 * 1024 * 1024;
+
+/// Hop-by-hop and length headers, which belong to each connection rather
+/// than the request; the client sets its own.
+const NOT_FORWARDED: [header::HeaderName; 5] = [
+    header::HOST,
+    header::CONNECTION,
+    header::CONTENT_LENGTH,
+    header::TRANSFER_ENCODING,
+    header::UPGRADE,
+];
+
+/// Backend path and query for `uri`: `almready://api/x?y` and
+/// `http://almready.localhost/api/x?y` both give `/api/x?y`.  `None` for
+/// anything outside `/api/`.
+fn backend_path(uri: &Uri) -> Option<String> {
+    let path = match uri.host() {
+        Some("api") => format!("/api{}", uri.path()),
+        _ => uri.path().to_string(),
+    };
+    if !path.starts_with("/api/") {
+        return None;
+    }
+    Some(match uri.query() {
+        Some(query) => format!("{path}?{query}"),
+        None => path,
+    })
+}
+
+/// `headers` without the connection headers and without a session token:
+/// the shell adds the real one.
+fn forwarded(headers: &HeaderMap) -> HeaderMap {
+    let mut forwarded = headers.clone();
+    for name in &NOT_FORWARDED {
+        forwarded.remove(name);
+    }
+    forwarded.remove(session_token::HEADER);
+    forwarded
+}
+
+/// A plain-text reply from the shell itself.
+fn reply(status: StatusCode, message: &str) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
+        .body(message.as_bytes().to_vec())
+        .unwrap_or_default()
+}
+
+fn is_allowed(origin: &HeaderValue) -> bool {
+    let Ok(origin) = origin.to_str() else {
+        return false;
+    };
+    ALLOWED_ORIGINS.contains(&origin) || (cfg!(debug_assertions) && origin == DEV_ORIGIN)
+}
+
+/// Whether the request comes from one of the app's pages: by its `Origin`,
+/// or, when it has none, by the origin of its `Referer`.
+fn from_the_app(headers: &HeaderMap) -> bool {
+    if let Some(origin) = headers.get(header::ORIGIN) {
+        return is_allowed(origin);
+    }
+    let Some(referer) = headers
+        .get(header::REFERER)
+        .and_then(|referer| referer.to_str().ok()?.parse::<Uri>().ok())
+    else {
+        return false;
+    };
+    let (Some(scheme), Some(authority)) = (referer.scheme_str(), referer.authority()) else {
+        return false;
+    };
+    HeaderValue::from_str(&format!("{scheme}://{authority}")).is_ok_and(|o| is_allowed(&o))
+}
+
+/// Let the requesting page read the reply, if it is one of ours.
+fn allow_origin(response: &mut Response<Vec<u8>>, origin: Option<&HeaderValue>) {
+    let headers = response.headers_mut();
+    headers.insert(header::VARY, HeaderValue::from_static("Origin"));
+    if let Some(origin) = origin.filter(|origin| is_allowed(origin)) {
+        headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin.clone());
+    }
+}
+
+fn preflight(request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let mut response = Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .header(
+            header::ACCESS_CONTROL_ALLOW_METHODS,
+            "GET, POST, PUT, PATCH, DELETE, OPTIONS",
+        )
+        .header(header::ACCESS_CONTROL_MAX_AGE, "600");
+    if let Some(requested) = request
+        .headers()
+        .get(header::ACCESS_CONTROL_REQUEST_HEADERS)
+    {
+        response = response.header(header::ACCESS_CONTROL_ALLOW_HEADERS, requested);
+    }
+    response.body(Vec::new()).unwrap_or_default()
+}
+
+/// The reply to `request`, before CORS headers.  `port` and `token` are
+/// the backend's at the time of the request.
+async fn respond_to(
+    request: Request<Vec<u8>>,
+    port: Option<u16>,
+    token: Option<String>,
+) -> Response<Vec<u8>> {
+    if !from_the_app(request.headers()) {
+        return reply(StatusCode::FORBIDDEN, "This origin may not use the API.");
+    }
+    if request.method() == Method::OPTIONS {
+        return preflight(&request);
+    }
+    forward(request, port, token).await
+}
+
+async fn forward(
+    request: Request<Vec<u8>>,
+    port: Option<u16>,
+    token: Option<String>,
+) -> Response<Vec<u8>> {
+    let Some(path) = backend_path(request.uri()) else {
+        return reply(StatusCode::NOT_FOUND, "Only /api/ is served here.");
+    };
+    let Some(port) = port else {
+        return reply(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "The engine is not running.",
+        );
+    };
+    let url = match http::port_to_url(port, &path) {
+        Ok(url) => url,
+        Err(e) => return reply(StatusCode::BAD_REQUEST, &e),
+    };
+
+    let (parts, body) = request.into_parts();
+    let mut outgoing = http::client()
+        .request(parts.method.clone(), url)
+        .headers(forwarded(&parts.headers))
+        .body(body);
+    if let Some(token) = token {
+        outgoing = outgoing.header(session_token::HEADER, token);
+    }
+    let mut upstream = match outgoing.send().await {
+        Ok(upstream) => upstream,
+        Err(e) => {
+            shell_log::log(
+                Level::Warn,
+                "api-protocol",
+                format!("{} {path}: {e}", parts.method),
+            );
+            return reply(StatusCode::BAD_GATEWAY, "The engine did not answer.");
+        }
+    };
+
+    let too_large = || {
+        shell_log::log(
+            Level::Warn,
+            "api-protocol",
+            format!(
+                "{} {path}: reply over {MAX_REPLY_BYTES} bytes refused",
+                parts.method
+            ),
+        );
+        reply(
+            StatusCode::BAD_GATEWAY,
+            "The engine's reply is too large for almready://.",
+        )
+    };
+    let declared = upstream.content_length().unwrap_or(0);
+    if declared > MAX_REPLY_BYTES as u64 {
+        return too_large();
+    }
+
+    let status = upstream.status();
+    let headers = forwarded(upstream.headers());
+    let mut body = Vec::with_capacity(declared as usize);
+    loop {
+        match upstream.chunk().await {
+            Ok(Some(chunk)) if body.len() + chunk.len() > MAX_REPLY_BYTES => {
+                return too_large();
+            }
+            Ok(Some(chunk)) => body.extend_from_slice(&chunk),
+            Ok(None) => break,
+            Err(e) => {
+                shell_log::log(
+                    Level::Warn,
+                    "api-protocol",
+                    format!("{} {path}: reading the reply: {e}", parts.method),
+                );
+                return reply(StatusCode::BAD_GATEWAY, "The engine's reply was cut off.");
+            }
+        }
+    }
+    let mut response = Response::new(body);
+    *response.status_mut() = status;
+    *response.headers_mut() = headers;
+    response
+}
+
+/// The `register_asynchronous_uri_scheme_protocol` handler for `SCHEME`.
+pub fn handle(
+    ctx: UriSchemeContext<'_, tauri::Wry>,
+    request: Request<Vec<u8>>,
+    responder: UriSchemeResponder,
+) {
+    let backend = ctx.app_handle().state::<BackendProcess>();
+    let (port, token) = (backend.port.get(), backend.token());
+    let origin = request.headers().get(header::ORIGIN).cloned();
+    tauri::async_runtime::spawn(async move {
+        let mut response = respond_to(request, port, token).await;
+        allow_origin(&mut response, origin.as_ref());
+        responder.respond(response);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(uri: &str) -> Option<String> {
+        backend_path(&uri.parse().unwrap())
+    }
+
+    #[test]
+    fn api_paths_map_to_the_backend_on_every_platform() {
+        assert_eq!(path("almready://api/health"), Some("/api/health".into()));
+        assert_eq!(
+            path("almready://api/results/7?page=2"),
+            Some("/api/results/7?page=2".into())
+        );
+        // WebView2's form.
+        assert_eq!(
+            path("http://almready.localhost/api/health"),
+            Some("/api/health".into())
+        );
+        assert_eq!(path("http://almready.localhost/index.html"), None);
+        assert_eq!(path("almready://localhost/docs"), None);
+    }
+
+    #[test]
+    fn connection_and_token_headers_are_not_forwarded() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::HOST, HeaderValue::from_static("api"));
+        headers.insert(header::CONTENT_LENGTH, HeaderValue::from_static("12"));
+        headers.insert(session_token::HEADER, HeaderValue::from_static("forged"));
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        let headers = forwarded(&headers);
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers[header::CONTENT_TYPE], "application/json");
+    }
+
+    fn request(method: Method, uri: &str, origin: Option<&'static str>) -> Request<Vec<u8>> {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(origin) = origin {
+            request = request.header(header::ORIGIN, origin);
+        }
+        request
+            .header(session_token::HEADER, "forged")
+            .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "content-type")
+            .body(b"{}".to_vec())
+            .unwrap()
+    }
+
+    async fn answer(request: Request<Vec<u8>>, port: Option<u16>) -> Response<Vec<u8>> {
+        let origin = request.headers().get(header::ORIGIN).cloned();
+        let mut response = respond_to(request, port, Some("s3cret".into())).await;
+        allow_origin(&mut response, origin.as_ref());
+        response
+    }
+
+    /// Answers every request with its own head, lower-cased, as the body.
+    async fn echo_server() -> u16 {
+        http::test_server::serve(|head, _| ("201 Created", head.to_string())).await
+    }
+
+    #[tokio::test]
+    async fn requests_reach_the_backend_with_only_the_shells_token() {
+        let port = echo_server().await;
+        let response = answer(
+            request(
+                Method::POST,
+                "almready://api/sessions?x=1",
+                Some("tauri://localhost"),
+            ),
+            Some(port),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        assert_eq!(
+            response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "tauri://localhost"
+        );
+        let head = String::from_utf8(response.into_body()).unwrap();
+        assert!(
+            head.starts_with("post /api/sessions?x=1 http/1.1"),
+            "{head}"
+        );
+        assert_eq!(head.matches("x-almready-token").count(), 1, "{head}");
+        assert!(head.contains("x-almready-token: s3cret"), "{head}");
+    }
+
+    #[tokio::test]
+    async fn without_a_backend_or_outside_api_the_shell_answers() {
+        let origin = Some("http://tauri.localhost");
+        let response = answer(request(Method::GET, "almready://api/health", origin), None).await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let response = answer(request(Method::GET, "almready://localhost/x", origin), None).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn only_the_apps_own_origins_get_cors_headers() {
+        for origin in ["tauri://localhost", "http://tauri.localhost", DEV_ORIGIN] {
+            let response = answer(
+                request(Method::OPTIONS, "almready://api/health", Some(origin)),
+                None,
+            )
+            .await;
+            assert_eq!(response.status(), StatusCode::NO_CONTENT);
+            assert_eq!(
+                response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+                origin
+            );
+            assert_eq!(
+                response.headers()[header::ACCESS_CONTROL_ALLOW_HEADERS],
+                "content-type"
+            );
+        }
+
+        for origin in ["https://evil.example", "null", "http://localhost:5173"] {
+            for method in [Method::OPTIONS, Method::POST] {
+                let response = answer(
+                    request(method, "almready://api/shutdown", Some(origin)),
+                    Some(1),
+                )
+                .await;
+                assert_eq!(response.status(), StatusCode::FORBIDDEN, "{origin}");
+                assert!(!response
+                    .headers()
+                    .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+            }
+        }
+
+        let response = answer(request(Method::GET, "almready://api/health", None), Some(1)).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert!(!response
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+
+    #[tokio::test]
+    async fn without_an_origin_only_the_apps_own_referer_is_forwarded() {
+        for (referer, status) in [
+            (
+                "tauri://localhost/index.html",
+                StatusCode::SERVICE_UNAVAILABLE,
+            ),
+            (
+                "http://tauri.localhost/results",
+                StatusCode::SERVICE_UNAVAILABLE,
+            ),
+            ("https://evil.example/page", StatusCode::FORBIDDEN),
+            ("not a url", StatusCode::FORBIDDEN),
+        ] {
+            let mut request = request(Method::GET, "almready://api/health", None);
+            request
+                .headers_mut()
+                .insert(header::REFERER, HeaderValue::from_static(referer));
+            assert_eq!(answer(request, None).await.status(), status, "{referer}");
+        }
+    }
+
+    #[tokio::test]
+    async fn oversized_replies_are_refused() {
+        let port =
+            http::test_server::serve(|_, _| ("200 OK", "x".repeat(MAX_REPLY_BYTES + 1))).await;
+        let response = answer(
+            request(Method::GET, "almready://api/big", Some("tauri://localhost")),
+            Some(port),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    }
+}
@@ -0,0 +1,89 @@
+//! Pointing the app at a manually started backend, for debugging.
+//!
+//! With `ALMREADY_BACKEND_PORT` set, startup skips the bundled sidecar
+//! entirely: it waits for the health check on that port and opens the main
+//! window with it injected.  The shell owns no child in that case, so
+//! closing the app never stops the external backend, the watchdog isn't
+//! started and `restart_backend` is refused.  No session token is passed
+//! either, so start that backend without `ALMREADY_AUTH_TOKEN` set.
+//!
+//! `ALMREADY_BACKEND_HOST` may be given too, but only as the loopback
+//! address: the shell's HTTP client and the frontend both talk to
+//! 127.0.0.1 only.  Run a remote backend behind an SSH tunnel instead.
+
+use tauri::AppHandle;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::http::BACKEND_HOST;
+
+pub const PORT_ENV: &str = "ALMREADY_BACKEND_PORT";
+pub const HOST_ENV: &str = "ALMREADY_BACKEND_HOST";
+
+/// The port to use instead of spawning the sidecar, if overridden.
+fn parse(port: Option<&str>, host: Option<&str>) -> Result<Option<u16>, String> {
+    if let Some(host) = host.map(str::trim).filter(|h| !h.is_empty()) {
+        if !matches!(host, BACKEND_HOST | "localhost") {
+            return Err(format!(
+                "{HOST_ENV}={host:?} is not supported: the backend must listen on \
+                 {BACKEND_HOST}.  Forward a remote backend to a local port instead."
+            ));
+        }
+    }
+    let Some(port) = port.map(str::trim).filter(|p| !p.is_empty()) else {
+        return Ok(None);
+    };
+    match port.parse::<u16>() {
+        Ok(0) | Err(_) => Err(format!(
+            "{PORT_ENV}={port:?} is not a valid port number (1–65535)."
+        )),
+        Ok(port) => Ok(Some(port)),
+    }
+}
+
+pub fn from_env() -> Result<Option<u16>, String> {
+    let port = std::env::var(PORT_ENV).ok();
+    let host = std::env::var(HOST_ENV).ok();
+    parse(port.as_deref(), host.as_deref())
+}
+
+/// Show a fatal error about the override and wait until it is dismissed.
+pub async fn error_dialog(app: &AppHandle, message: &str) {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .message(message)
+        .title("ALMReady")
+        .kind(MessageDialogKind::Error)
+        .buttons(MessageDialogButtons::OkCustom("Quit".into()))
+        .show(move |_| {
+            let _ = tx.send(());
+        });
+    let _ = rx.await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unset_or_empty_means_no_override() {
+        assert_eq!(parse(None, None), Ok(None));
+        assert_eq!(parse(Some("  "), Some("")), Ok(None));
+    }
+
+    #[test]
+    fn valid_ports_and_loopback_hosts() {
+        assert_eq!(parse(Some("8000"), None), Ok(Some(8000)));
+        assert_eq!(parse(Some(" 8000\n"), Some("127.0.0.1")), Ok(Some(8000)));
+        assert_eq!(parse(Some("8000"), Some("localhost")), Ok(Some(8000)));
+    }
+
+    #[test]
+    fn rejects_bad_ports_and_remote_hosts() {
+        for port in ["abc", "0", "65536", "-1", "80 80"] {
+            let err = parse(Some(port), None).unwrap_err();
+            assert!(err.contains(PORT_ENV), "{err}");
+        }
+        let err = parse(Some("8000"), Some("10.0.0.5")).unwrap_err();
+        assert!(err.contains(HOST_ENV), "{err}");
+    }
+}
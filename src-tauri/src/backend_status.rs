@@ -0,0 +1,101 @@
+//! Backend port, lifecycle status and on-demand restart for the frontend.
+//!
+//! `window.__BACKEND_PORT__` is injected once, when a window is built; these
+//! commands let the webview ask at any time where the backend is and
+//! whether it is up, so an error boundary can tell "backend restarting"
+//! apart from a network bug.  `restart_backend` replaces "quit and reopen
+//! the app" when the engine has wedged itself.
+
+use std::sync::atomic::Ordering;
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+
+use crate::{
+    cursor::{self, CursorIcon},
+    launch_sidecar, lifecycle,
+    shell_log::{self, Level},
+    terminate_backend, watchdog, BackendProcess, Launch,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BackendStatus {
+    /// Spawned, waiting for its port and health check.
+    Starting,
+    Ready,
+    /// Not running: not started yet, dev mode, or stopped by the shell.
+    #[default]
+    Stopped,
+    /// Exited on its own, stopped answering health checks, or never came up.
+    Crashed,
+}
+
+/// The port of the running backend, if any.
+#[tauri::command]
+pub fn get_backend_port(backend: State<'_, BackendProcess>) -> Option<u16> {
+    backend.port.get()
+}
+
+/// The port of the backend, as soon as one is running; resolves at once if
+/// it already is.
+#[tauri::command]
+pub async fn wait_for_backend_port(backend: State<'_, BackendProcess>) -> Result<u16, String> {
+    Ok(backend.port.wait().await)
+}
+
+#[tauri::command]
+pub fn get_backend_status(backend: State<'_, BackendProcess>) -> BackendStatus {
+    backend.status()
+}
+
+/// Stop the sidecar gracefully, start a new one and wait until it is
+/// healthy.  Returns the new port, which is also sent to every window as
+/// `backend-port-changed`.  Fails at once if a restart is already running.
+#[tauri::command]
+pub async fn restart_backend(app: AppHandle) -> Result<u16, String> {
+    let backend = app.state::<BackendProcess>();
+    if backend.is_shutting_down() {
+        return Err("The app is closing.".into());
+    }
+    if backend.external.load(Ordering::SeqCst) {
+        return Err("The backend was started outside the app; restart it there.".into());
+    }
+    if backend.restarting.swap(true, Ordering::SeqCst) {
+        return Err("A backend restart is already in progress.".into());
+    }
+    // Startup or the watchdog is bringing a sidecar up already.
+    if backend.status() == BackendStatus::Starting {
+        backend.restarting.store(false, Ordering::SeqCst);
+        return Err("The backend is still starting.".into());
+    }
+    let result = cursor::with_cursor(&app, CursorIcon::Progress, restart(&app)).await;
+    backend.restarting.store(false, Ordering::SeqCst);
+    result
+}
+
+async fn restart(app: &AppHandle) -> Result<u16, String> {
+    let backend = app.state::<BackendProcess>();
+    // The watchdog would see the stopped sidecar as a crash and respawn it.
+    if let Some(watchdog) = backend.watchdog.lock().unwrap().take() {
+        watchdog.abort();
+    }
+    shell_log::log(Level::Info, "backend", "restarting on request");
+    terminate_backend(app).await;
+    lifecycle::restarting(app);
+
+    match launch_sidecar(app, false).await {
+        Launch::Ready(port) => {
+            shell_log::log(Level::Info, "backend", format!("restarted on port {port}"));
+            watchdog::notify_port_changed(app, port);
+            watchdog::spawn(app);
+            Ok(port)
+        }
+        Launch::Cancelled => Err("The app is closing.".into()),
+        Launch::SpawnFailed(e) => Err(e.to_string()),
+        Launch::Failed { error, exit_code } => Err(match exit_code {
+            Some(code) => format!("{error} The engine process exited with code {code}."),
+            None => error.to_string(),
+        }),
+    }
+}
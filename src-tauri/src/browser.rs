@@ -0,0 +1,159 @@
+//! The user's default web browser, so the frontend can say where a
+//! documentation link will open ("Open in Chrome?").
+//!
+//! macOS   → `LSCopyDefaultHandlerForURLScheme("https")`, a bundle id
+//! Windows → `ProgId` under
+//!           `HKCU\Software\Microsoft\Windows\Shell\Associations\UrlAssociations\https\UserChoice`
+//! Linux   → `xdg-settings get default-web-browser`, a desktop file name
+//!
+//! Each platform reports an identifier rather than a name; `friendly_name`
+//! maps the common ones and otherwise tidies the identifier up.
+
+use crate::shell_log::{self, Level};
+
+/// Known identifiers (matched case-insensitively, as a prefix so Windows
+/// `FirefoxURL-308046B0AF4A39CB` and Linux `firefox_firefox.desktop` match).
+const KNOWN_BROWSERS: &[(&str, &str)] = &[
+    // macOS bundle ids
+    ("com.google.chrome", "Google Chrome"),
+    ("com.microsoft.edgemac", "Microsoft Edge"),
+    ("org.mozilla.firefox", "Firefox"),
+    ("com.apple.safari", "Safari"),
+    ("com.brave.browser", "Brave"),
+    ("com.operasoftware.opera", "Opera"),
+    ("company.thebrowser.browser", "Arc"),
+    ("com.vivaldi.vivaldi", "Vivaldi"),
+    // Windows ProgIds
+    ("chromehtml", "Google Chrome"),
+    ("msedgehtm", "Microsoft Edge"),
+    ("firefoxurl", "Firefox"),
+    ("bravehtml", "Brave"),
+    ("operastable", "Opera"),
+    ("vivaldihtm", "Vivaldi"),
+    ("ie.https", "Internet Explorer"),
+    // Linux desktop files
+    ("google-chrome", "Google Chrome"),
+    ("chromium", "Chromium"),
+    ("microsoft-edge", "Microsoft Edge"),
+    ("firefox", "Firefox"),
+    ("brave", "Brave"),
+    ("opera", "Opera"),
+    ("vivaldi", "Vivaldi"),
+    ("org.gnome.epiphany", "GNOME Web"),
+];
+
+fn friendly_name(id: &str) -> String {
+    let lower = id.to_ascii_lowercase();
+    if let Some((_, name)) = KNOWN_BROWSERS
+        .iter()
+        .find(|(prefix, _)| lower.starts_with(prefix))
+    {
+        return (*name).to_string();
+    }
+    // Unknown: `org.example.Browser` / `example-browser.desktop` → the last
+    // meaningful component.
+    let id = id.strip_suffix(".desktop").unwrap_or(id);
+    id.rsplit('.').next().unwrap_or(id).to_string()
+}
+
+// ── Platform queries ────────────────────────────────────────────────────────
+
+#[cfg(target_os = "macos")]
+fn query() -> Result<String, String> {
+    use system_configuration::core_foundation::{
+        base::TCFType,
+        string::{CFString, CFStringRef},
+    };
+
+    #[link(name = "CoreServices", kind = "framework")]
+    extern "C" {
+        fn LSCopyDefaultHandlerForURLScheme(scheme: CFStringRef) -> CFStringRef;
+    }
+
+    let scheme = CFString::from_static_string("https");
+    // SAFETY: `scheme` is a valid CFString; the result follows the Copy rule
+    // and is released by the wrapper.
+    let handler = unsafe { LSCopyDefaultHandlerForURLScheme(scheme.as_concrete_TypeRef()) };
+    if handler.is_null() {
+        return Err("no default handler for https".into());
+    }
+    Ok(unsafe { CFString::wrap_under_create_rule(handler) }.to_string())
+}
+
+#[cfg(target_os = "windows")]
+fn query() -> Result<String, String> {
+    use windows::{
+        core::w,
+        Win32::System::Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_SZ},
+    };
+
+    let mut buf = [0u16; 256];
+    let mut size = std::mem::size_of_val(&buf) as u32;
+    // SAFETY: `buf` and `size` describe a writable buffer of `size` bytes.
+    unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            w!(r"Software\Microsoft\Windows\Shell\Associations\UrlAssociations\https\UserChoice"),
+            w!("ProgId"),
+            RRF_RT_REG_SZ,
+            None,
+            Some(buf.as_mut_ptr().cast()),
+            Some(&mut size as *mut u32),
+        )
+    }
+    .ok()
+    .map_err(|e| format!("UserChoice ProgId: {e}"))?;
+    let len = buf.iter().position(|&c| c == 0).unwrap_or(buf.len());
+    Ok(String::from_utf16_lossy(&buf[..len]))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn query() -> Result<String, String> {
+    let out = std::process::Command::new("xdg-settings")
+        .args(["get", "default-web-browser"])
+        .output()
+        .map_err(|e| format!("xdg-settings: {e}"))?;
+    if !out.status.success() {
+        return Err(format!("xdg-settings exited with {}", out.status));
+    }
+    let id = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    if id.is_empty() {
+        return Err("no default web browser set".into());
+    }
+    Ok(id)
+}
+
+// ── Commands ────────────────────────────────────────────────────────────────
+
+/// Human-readable name of the browser `https` links open in.
+#[tauri::command]
+pub fn get_default_browser() -> Result<String, String> {
+    query().map(|id| friendly_name(&id)).inspect_err(|e| {
+        shell_log::log(
+            Level::Warn,
+            "browser",
+            format!("default browser query failed: {e}"),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_known_and_unknown_identifiers() {
+        for (id, name) in [
+            ("com.google.chrome", "Google Chrome"),
+            ("com.apple.Safari", "Safari"),
+            ("MSEdgeHTM", "Microsoft Edge"),
+            ("FirefoxURL-308046B0AF4A39CB", "Firefox"),
+            ("firefox_firefox.desktop", "Firefox"),
+            ("google-chrome.desktop", "Google Chrome"),
+            ("org.example.Surf", "Surf"),
+            ("surf.desktop", "surf"),
+        ] {
+            assert_eq!(friendly_name(id), name, "{id}");
+        }
+    }
+}
@@ -0,0 +1,106 @@
+//! Everything the About dialog shows, in one `invoke`.
+//!
+//! `get_build_info` gathers the app's package info, the build profile, the
+//! platform, the CPU features the engine can use and the webview version.
+//! Nothing here changes while the app runs.
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildInfo {
+    pub app: AppInfo,
+    pub system: SystemInfo,
+    pub webview: WebviewInfo,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppInfo {
+    pub name: String,
+    pub version: String,
+    pub identifier: String,
+    pub tauri_version: &'static str,
+    /// A debug build (`cargo tauri dev`), not a release bundle.
+    pub debug: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemInfo {
+    pub os: &'static str,
+    pub family: &'static str,
+    pub arch: &'static str,
+    /// SIMD extensions available on this CPU.
+    pub cpu_features: Vec<&'static str>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebviewInfo {
+    /// WebView2 / WKWebView / WebKitGTK version, if it can be determined.
+    pub version: Option<String>,
+}
+
+#[cfg(target_arch = "x86_64")]
+fn cpu_features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    macro_rules! detect {
+        ($($name:tt),*) => {
+            $(if std::arch::is_x86_feature_detected!($name) {
+                features.push($name);
+            })*
+        };
+    }
+    detect!("sse2", "sse4.2", "avx", "avx2", "fma", "avx512f");
+    features
+}
+
+#[cfg(target_arch = "aarch64")]
+fn cpu_features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if std::arch::is_aarch64_feature_detected!("neon") {
+        features.push("neon");
+    }
+    features
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn cpu_features() -> Vec<&'static str> {
+    Vec::new()
+}
+
+#[tauri::command]
+pub fn get_build_info(app: AppHandle) -> BuildInfo {
+    let package = app.package_info();
+    BuildInfo {
+        app: AppInfo {
+            name: package.name.clone(),
+            version: package.version.to_string(),
+            identifier: app.config().identifier.clone(),
+            tauri_version: tauri::VERSION,
+            debug: cfg!(debug_assertions),
+        },
+        system: SystemInfo {
+            os: std::env::consts::OS,
+            family: std::env::consts::FAMILY,
+            arch: std::env::consts::ARCH,
+            cpu_features: cpu_features(),
+        },
+        webview: WebviewInfo {
+            version: tauri::webview_version().ok(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn x86_64_always_has_sse2() {
+        assert!(cpu_features().contains(&"sse2"));
+    }
+}
@@ -0,0 +1,247 @@
+//! Check the bundled backend against its packaging manifest before
+//! spawning it.
+//!
+//! Antivirus has been seen quarantining pieces of the PyInstaller onedir
+//! bundle: `almready-backend.exe` is still there but DLLs under `_internal`
+//! are gone, and the engine dies at once with nothing more useful than
+//! "exited before printing its port".  Packaging writes
+//! `almready-backend/manifest.sha256` next to the executable, one line per
+//! file:
+//!
+//! ```text
+//! <sha-256 hex> <size in bytes> <path relative to almready-backend/>
+//! ```
+//!
+//! Before each spawn every listed file must exist with its size – cheap,
+//! and enough to catch quarantined files – and the executable plus the
+//! files in the `critical_backend_files` setting are hashed as well.  The
+//! first problem fails the launch with `BackendError::BundleDamaged`, whose
+//! dialog names the file and suggests reinstalling.  Without a manifest (dev
+//! builds) nothing is checked.  The last result goes into the support
+//! bundle.
+
+use std::{fs::File, io::BufReader, path::Path, sync::Mutex};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    failure::BackendError,
+    integrity,
+    settings::SettingsStore,
+    shell_log::{self, Level},
+};
+
+pub const MANIFEST: &str = "manifest.sha256";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Entry {
+    sha256: String,
+    size: u64,
+    path: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Problem {
+    Missing,
+    WrongSize,
+    Modified,
+}
+
+impl Problem {
+    pub fn describe(self) -> &'static str {
+        match self {
+            Problem::Missing => "is missing",
+            Problem::WrongSize => "has the wrong size",
+            Problem::Modified => "has been modified",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "result", rename_all = "kebab-case")]
+pub enum Report {
+    /// No manifest: a dev build.
+    Skipped,
+    /// The manifest itself couldn't be read; the launch goes ahead.
+    ManifestUnreadable {
+        error: String,
+    },
+    Passed {
+        files: usize,
+        hashed: usize,
+    },
+    Damaged {
+        file: String,
+        problem: Problem,
+    },
+}
+
+/// Managed state: the last check's report, for the support bundle.
+#[derive(Default)]
+pub struct BundleIntegrity(Mutex<Option<Report>>);
+
+impl BundleIntegrity {
+    pub fn last(&self) -> Option<Report> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+fn parse(text: &str) -> Result<Vec<Entry>, String> {
+    let mut entries = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.splitn(3, ' ');
+        let (Some(sha256), Some(size), Some(path)) = (fields.next(), fields.next(), fields.next())
+        else {
+            return Err(format!("line {}: expected <sha256> <size> <path>", i + 1));
+        };
+        let size = size
+            .parse()
+            .map_err(|_| format!("line {}: bad size {size:?}", i + 1))?;
+        entries.push(Entry {
+            sha256: sha256.to_ascii_lowercase(),
+            size,
+            path: path.trim().to_string(),
+        });
+    }
+    Ok(entries)
+}
+
+/// Sizes of every entry, then hashes of those in `hash`.  Returns the
+/// number hashed, or the first problem.
+fn verify(dir: &Path, entries: &[Entry], hash: &[String]) -> Result<usize, (String, Problem)> {
+    for entry in entries {
+        match std::fs::metadata(dir.join(&entry.path)) {
+            Ok(meta) if meta.len() == entry.size => {}
+            Ok(_) => return Err((entry.path.clone(), Problem::WrongSize)),
+            Err(_) => return Err((entry.path.clone(), Problem::Missing)),
+        }
+    }
+    let mut hashed = 0;
+    for entry in entries.iter().filter(|e| hash.contains(&e.path)) {
+        let digest = File::open(dir.join(&entry.path))
+            .and_then(|file| integrity::sha256_hex(BufReader::new(file)))
+            .map_err(|_| (entry.path.clone(), Problem::Missing))?;
+        if digest != entry.sha256 {
+            return Err((entry.path.clone(), Problem::Modified));
+        }
+        hashed += 1;
+    }
+    Ok(hashed)
+}
+
+fn report(exe_path: &Path, critical: &[String]) -> Report {
+    let Some(dir) = exe_path.parent() else {
+        return Report::Skipped;
+    };
+    let text = match std::fs::read_to_string(dir.join(MANIFEST)) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Report::Skipped,
+        Err(e) => {
+            return Report::ManifestUnreadable {
+                error: e.to_string(),
+            }
+        }
+    };
+    let entries = match parse(&text) {
+        Ok(entries) => entries,
+        Err(error) => return Report::ManifestUnreadable { error },
+    };
+    let exe = exe_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let hash: Vec<String> = std::iter::once(exe)
+        .chain(critical.iter().cloned())
+        .collect();
+    match verify(dir, &entries, &hash) {
+        Ok(hashed) => Report::Passed {
+            files: entries.len(),
+            hashed,
+        },
+        Err((file, problem)) => Report::Damaged { file, problem },
+    }
+}
+
+/// Check the bundle around `exe_path` and remember the result.  Fails only
+/// for a damaged bundle.
+pub fn check(app: &AppHandle, exe_path: &Path) -> Result<(), BackendError> {
+    let critical = app.state::<SettingsStore>().get().critical_backend_files;
+    let started = std::time::Instant::now();
+    let report = report(exe_path, &critical);
+    *app.state::<BundleIntegrity>().0.lock().unwrap() = Some(report.clone());
+    match report {
+        Report::Skipped => Ok(()),
+        Report::ManifestUnreadable { error } => {
+            shell_log::log(
+                Level::Warn,
+                "bundle-check",
+                format!("{MANIFEST} unreadable, not checking the bundle: {error}"),
+            );
+            Ok(())
+        }
+        Report::Passed { files, hashed } => {
+            shell_log::log(
+                Level::Info,
+                "bundle-check",
+                format!(
+                    "{files} files present, {hashed} hashed, in {} ms",
+                    started.elapsed().as_millis()
+                ),
+            );
+            Ok(())
+        }
+        Report::Damaged { file, problem } => {
+            shell_log::log(
+                Level::Error,
+                "bundle-check",
+                format!("{file} {}", problem.describe()),
+            );
+            Err(BackendError::BundleDamaged { file, problem })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_missing_resized_and_modified_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        std::fs::create_dir_all(dir.join("_internal")).unwrap();
+        std::fs::write(dir.join("almready-backend"), "abc").unwrap();
+        std::fs::write(dir.join("_internal").join("python3.dll"), "dll").unwrap();
+        let abc = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        let manifest =
+            format!("# generated\n{abc} 3 almready-backend\n{abc} 3 _internal/python3.dll\n");
+        let entries = parse(&manifest).unwrap();
+        let exe = vec!["almready-backend".to_string()];
+
+        assert_eq!(verify(dir, &entries, &exe), Ok(1));
+        // Same size, different content: only hashing notices.
+        let all = vec![exe[0].clone(), "_internal/python3.dll".to_string()];
+        assert_eq!(
+            verify(dir, &entries, &all),
+            Err(("_internal/python3.dll".into(), Problem::Modified))
+        );
+        std::fs::write(dir.join("_internal").join("python3.dll"), "dll!").unwrap();
+        assert_eq!(
+            verify(dir, &entries, &exe),
+            Err(("_internal/python3.dll".into(), Problem::WrongSize))
+        );
+        std::fs::remove_file(dir.join("_internal").join("python3.dll")).unwrap();
+        assert_eq!(
+            verify(dir, &entries, &exe),
+            Err(("_internal/python3.dll".into(), Problem::Missing))
+        );
+
+        assert!(parse("abc almready-backend").is_err());
+    }
+}
@@ -0,0 +1,192 @@
+//! What the clipboard holds, so the frontend can choose between plain and
+//! formatted paste before reading it.
+//!
+//! macOS   → `NSPasteboard.generalPasteboard.pasteboardItems[0].types` (UTIs)
+//! Windows → `EnumClipboardFormats`, standard formats by id and registered
+//!           ones by `GetClipboardFormatNameW`
+//! Linux   → `xclip -selection clipboard -t TARGETS -o` (X11 atoms), or
+//!           `wl-paste --list-types` on Wayland without xclip
+//!
+//! Every platform's native names go through `to_mime`; names with no MIME
+//! equivalent (X11 `TIMESTAMP`, private Windows formats) are left out.
+
+use crate::shell_log::{self, Level};
+
+/// MIME type for a native clipboard format name.
+fn to_mime(native: &str) -> Option<String> {
+    let mime = match native {
+        // Windows standard formats (see `format_name`)
+        "CF_UNICODETEXT" | "CF_TEXT" | "CF_OEMTEXT" => "text/plain",
+        "CF_HDROP" => "text/uri-list",
+        "CF_DIB" | "CF_DIBV5" | "CF_BITMAP" => "image/bmp",
+        // Windows registered formats
+        "HTML Format" => "text/html",
+        "Rich Text Format" => "text/rtf",
+        "PNG" => "image/png",
+        // macOS UTIs
+        "public.utf8-plain-text" | "public.utf16-plain-text" | "public.plain-text" => "text/plain",
+        "public.html" => "text/html",
+        "public.rtf" => "text/rtf",
+        "public.file-url" | "public.url" => "text/uri-list",
+        "public.png" => "image/png",
+        "public.tiff" => "image/tiff",
+        "public.jpeg" => "image/jpeg",
+        // X11 atoms
+        "UTF8_STRING" | "STRING" | "TEXT" | "COMPOUND_TEXT" => "text/plain",
+        "x-special/gnome-copied-files" => "text/uri-list",
+        // Already a MIME type (X11, Wayland); drop parameters such as charset.
+        other if other.contains('/') && !other.starts_with("x-special/") => {
+            let essence = other.split(';').next().unwrap_or(other).trim();
+            return Some(essence.to_ascii_lowercase());
+        }
+        _ => return None,
+    };
+    Some(mime.to_string())
+}
+
+/// MIME types for `native` names, first occurrence wins.
+fn mime_types(native: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut types: Vec<String> = Vec::new();
+    for mime in native.into_iter().filter_map(|name| to_mime(&name)) {
+        if !types.contains(&mime) {
+            types.push(mime);
+        }
+    }
+    types
+}
+
+// ── Platform queries ────────────────────────────────────────────────────────
+
+#[cfg(target_os = "macos")]
+fn query() -> Result<Vec<String>, String> {
+    use objc2_app_kit::NSPasteboard;
+
+    #[allow(unused_unsafe)]
+    let types = unsafe {
+        let pasteboard = NSPasteboard::generalPasteboard();
+        let Some(item) = pasteboard
+            .pasteboardItems()
+            .and_then(|items| items.firstObject())
+        else {
+            return Ok(Vec::new());
+        };
+        item.types()
+    };
+    Ok(types.iter().map(|t| t.to_string()).collect())
+}
+
+#[cfg(target_os = "windows")]
+fn format_name(format: u32) -> Option<String> {
+    use windows::Win32::System::DataExchange::GetClipboardFormatNameW;
+
+    let standard = match format {
+        1 => "CF_TEXT",
+        2 => "CF_BITMAP",
+        7 => "CF_OEMTEXT",
+        8 => "CF_DIB",
+        13 => "CF_UNICODETEXT",
+        15 => "CF_HDROP",
+        17 => "CF_DIBV5",
+        _ => "",
+    };
+    if !standard.is_empty() {
+        return Some(standard.to_string());
+    }
+    let mut buf = [0u16; 128];
+    // SAFETY: `buf` is a writable buffer of the length passed.
+    let len = unsafe { GetClipboardFormatNameW(format, &mut buf) };
+    (len > 0).then(|| String::from_utf16_lossy(&buf[..len as usize]))
+}
+
+#[cfg(target_os = "windows")]
+fn query() -> Result<Vec<String>, String> {
+    use windows::Win32::System::DataExchange::{
+        CloseClipboard, EnumClipboardFormats, OpenClipboard,
+    };
+
+    // SAFETY: the clipboard is opened without an owner window and closed
+    // before returning; formats are only enumerated in between.
+    unsafe { OpenClipboard(None) }.map_err(|e| format!("OpenClipboard: {e}"))?;
+    let mut names = Vec::new();
+    let mut format = 0;
+    loop {
+        format = unsafe { EnumClipboardFormats(format) };
+        if format == 0 {
+            break;
+        }
+        names.extend(format_name(format));
+    }
+    let _ = unsafe { CloseClipboard() };
+    Ok(names)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn query() -> Result<Vec<String>, String> {
+    use std::process::Command;
+
+    let out = Command::new("xclip")
+        .args(["-selection", "clipboard", "-t", "TARGETS", "-o"])
+        .output()
+        .or_else(|_| Command::new("wl-paste").arg("--list-types").output())
+        .map_err(|e| format!("neither xclip nor wl-paste is available: {e}"))?;
+    // Both fail when the clipboard is empty.
+    if !out.status.success() {
+        return Ok(Vec::new());
+    }
+    Ok(String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect())
+}
+
+// ── Commands ────────────────────────────────────────────────────────────────
+
+/// MIME types currently on the clipboard, e.g. `["text/html",
+/// "text/plain"]`, in the order the source application offered them.
+/// Empty when the clipboard is empty or can't be read.
+#[tauri::command]
+pub fn get_clipboard_format() -> Vec<String> {
+    query()
+        .map(mime_types)
+        .inspect_err(|e| {
+            shell_log::log(
+                Level::Warn,
+                "clipboard",
+                format!("clipboard format query failed: {e}"),
+            )
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn types(native: &[&str]) -> Vec<String> {
+        mime_types(native.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn maps_each_platforms_names() {
+        assert_eq!(
+            types(&["HTML Format", "CF_UNICODETEXT", "CF_TEXT", "CF_LOCALE"]),
+            ["text/html", "text/plain"]
+        );
+        assert_eq!(
+            types(&["public.rtf", "public.utf8-plain-text", "public.file-url"]),
+            ["text/rtf", "text/plain", "text/uri-list"]
+        );
+        assert_eq!(
+            types(&[
+                "TIMESTAMP",
+                "TARGETS",
+                "text/html",
+                "UTF8_STRING",
+                "text/plain;charset=utf-8",
+                "x-special/gnome-copied-files",
+            ]),
+            ["text/html", "text/plain", "text/uri-list"]
+        );
+    }
+}
@@ -0,0 +1,681 @@
+//! Backend protocol conformance suite, for the backend's CI.
+//!
+//! `almready conformance --sidecar <path> [--json]` starts the given backend
+//! binary the way the shell does and checks it against the shell↔backend
+//! contract:
+//!
+//! * `handshake.port-line` – `PORT:{n}` on stdout (flushed) within
+//!   `PORT_TIMEOUT`;
+//! * `handshake.port-file` – the same port in `ALMREADY_PORT_FILE`;
+//! * `handshake.stage-syntax`, `handshake.error-syntax`,
+//!   `handshake.ctrl-syntax` – every `STAGE:`, `ERROR:` and `CTRL:` line on
+//!   stdout, read to the end, is well-formed, and no `STAGE:` follows
+//!   `PORT:`;
+//! * `health.ready` – `GET /api/health` answers 200 `{"status":"ok"}` with
+//!   the session token within the shell's health timeout;
+//! * `auth.token-required` – health and shutdown refuse requests without
+//!   the token or with a wrong one;
+//! * `version.endpoint` – `GET /api/version` answers a semantic version;
+//! * `readiness.endpoint` – `GET /api/ready/{component}` answers 200 for
+//!   every component in `readiness::COMPONENTS` within the default
+//!   readiness timeout;
+//! * `shutdown.endpoint` – `POST /api/shutdown` is accepted and the process
+//!   exits within `SHUTDOWN_GRACE`;
+//! * `shutdown.sigterm` – a second instance exits within `SHUTDOWN_GRACE`
+//!   of SIGTERM (skipped on Windows, which has none).
+//!
+//! The checks go through the shell's own code – `sidecar_command`,
+//! `port_reader`, `wait_for_backend`, `http`, `readiness` and `ask_to_exit`
+//! – so a pass
+//! means the packaged app will accept the build.  A check whose
+//! prerequisite failed is reported as skipped.  Each change to the protocol
+//! adds its check here in the same change.
+//!
+//! The report goes to stdout as text, or with `--json` as
+//! `{format_version, sidecar, passed, checks: [{id, outcome, detail,
+//! duration_ms}]}` with `outcome` one of `pass`, `fail`, `skip`; both
+//! formats are stable.  Exit code 0 when nothing failed, 1 otherwise, 2 for
+//! bad arguments.  The backend runs with a scratch data directory and its
+//! stderr passes through to ours.  On Windows release builds run it with
+//! stdout redirected, as there is no console.
+
+use std::{
+    collections::BTreeMap,
+    io::{BufRead as _, BufReader},
+    path::{Path, PathBuf},
+    process::Stdio,
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+use tokio::sync::oneshot;
+
+use crate::{
+    ask_to_exit,
+    http::{self, ComponentProbe},
+    port_reader::{self, LineKind, PortOutcome},
+    process_tree::ProcessTree,
+    readiness, session_token, sidecar_command,
+    timeouts::Timeouts,
+    wait_for_backend, PORT_FILE_WAIT, PORT_TIMEOUT, SHUTDOWN_GRACE,
+};
+
+/// The contract is checked against the default budgets, not a machine's
+/// configured ones (see `timeouts`).
+const HEALTH_TIMEOUT: Duration = Timeouts::DEFAULT.startup;
+const HEALTH_INTERVAL: Duration = Timeouts::DEFAULT.health_interval;
+const READINESS_TIMEOUT: Duration = Timeouts::DEFAULT.readiness;
+
+/// How long stdout may stay open after the backend was stopped.
+const STDOUT_WAIT: Duration = Duration::from_secs(5);
+
+const USAGE: &str = "usage: almready conformance --sidecar <path> [--json]";
+
+const FORMAT_VERSION: u32 = 1;
+
+const PORT_LINE: &str = "handshake.port-line";
+const PORT_FILE: &str = "handshake.port-file";
+const STAGE_SYNTAX: &str = "handshake.stage-syntax";
+const ERROR_SYNTAX: &str = "handshake.error-syntax";
+const CTRL_SYNTAX: &str = "handshake.ctrl-syntax";
+const HEALTH: &str = "health.ready";
+const TOKEN: &str = "auth.token-required";
+const VERSION: &str = "version.endpoint";
+const READINESS: &str = "readiness.endpoint";
+const SHUTDOWN_ENDPOINT: &str = "shutdown.endpoint";
+const SIGTERM: &str = "shutdown.sigterm";
+
+// ── Command line ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Options {
+    sidecar: PathBuf,
+    json: bool,
+}
+
+/// `None` unless the first argument is `conformance`.
+fn parse_args(args: &[String]) -> Option<Result<Options, String>> {
+    match args.get(1) {
+        Some(command) if command == "conformance" => Some(parse_options(&args[2..])),
+        _ => None,
+    }
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut sidecar = None;
+    let mut json = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--sidecar" => {
+                let path = args.next().ok_or("--sidecar needs a path")?;
+                sidecar = Some(PathBuf::from(path));
+            }
+            "--json" => json = true,
+            other => return Err(format!("unknown argument {other:?}")),
+        }
+    }
+    Ok(Options {
+        sidecar: sidecar.ok_or("--sidecar is required")?,
+        json,
+    })
+}
+
+// ── Report ──────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Outcome {
+    Pass,
+    Fail,
+    Skip,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct Check {
+    id: &'static str,
+    outcome: Outcome,
+    detail: String,
+    duration_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct Report {
+    format_version: u32,
+    sidecar: String,
+    passed: bool,
+    checks: Vec<Check>,
+}
+
+impl Report {
+    fn new(sidecar: &Path, checks: Vec<Check>) -> Self {
+        Report {
+            format_version: FORMAT_VERSION,
+            sidecar: sidecar.display().to_string(),
+            passed: checks.iter().all(|c| c.outcome != Outcome::Fail),
+            checks,
+        }
+    }
+
+    fn exit_code(&self) -> i32 {
+        if self.passed {
+            0
+        } else {
+            1
+        }
+    }
+
+    fn text(&self) -> String {
+        let mut text = format!("ALMReady backend conformance: {}\n\n", self.sidecar);
+        let mut counts = BTreeMap::new();
+        for check in &self.checks {
+            let label = match check.outcome {
+                Outcome::Pass => "PASS",
+                Outcome::Fail => "FAIL",
+                Outcome::Skip => "SKIP",
+            };
+            *counts.entry(label).or_insert(0) += 1;
+            text.push_str(&format!("{label}  {:<22}{}\n", check.id, check.detail));
+        }
+        let count = |label: &str| counts.get(label).copied().unwrap_or(0);
+        text.push_str(&format!(
+            "\n{} passed, {} failed, {} skipped\n",
+            count("PASS"),
+            count("FAIL"),
+            count("SKIP")
+        ));
+        text
+    }
+}
+
+// ── Checks ──────────────────────────────────────────────────────────────────
+
+struct Suite {
+    sidecar: PathBuf,
+    data_dir: PathBuf,
+    checks: Vec<Check>,
+}
+
+/// A backend started for the suite.
+struct Instance {
+    tree: ProcessTree,
+    port_rx: oneshot::Receiver<PortOutcome>,
+    /// Reads stdout to the end; returns every line.
+    stdout: JoinHandle<Vec<String>>,
+    token: String,
+}
+
+impl Suite {
+    fn record(&mut self, id: &'static str, started: Instant, result: Result<String, String>) {
+        let (outcome, detail) = match result {
+            Ok(detail) => (Outcome::Pass, detail),
+            Err(detail) => (Outcome::Fail, detail),
+        };
+        self.checks.push(Check {
+            id,
+            outcome,
+            detail,
+            duration_ms: started.elapsed().as_millis() as u64,
+        });
+    }
+
+    fn skip(&mut self, ids: &[&'static str], why: &str) {
+        for &id in ids {
+            self.checks.push(Check {
+                id,
+                outcome: Outcome::Skip,
+                detail: why.to_string(),
+                duration_ms: 0,
+            });
+        }
+    }
+
+    fn port_file(&self) -> PathBuf {
+        port_reader::port_file_path(&self.data_dir)
+    }
+
+    fn start(&self) -> Result<Instance, String> {
+        let token = session_token::generate().map_err(|e| format!("session token: {e}"))?;
+        let port_file = self.port_file();
+        let _ = std::fs::remove_file(&port_file);
+        let mut command = sidecar_command(
+            &self.sidecar,
+            &self.data_dir,
+            &BTreeMap::new(),
+            &port_file,
+            &token,
+        );
+        command.stderr(Stdio::inherit());
+        let mut tree = ProcessTree::spawn(&mut command)
+            .map_err(|e| format!("cannot start {:?}: {e}", self.sidecar))?;
+        let stdout = tree
+            .child_mut()
+            .stdout
+            .take()
+            .ok_or("stdout pipe not available")?;
+        // The shell stops reading at the PORT line; the suite reads on, to
+        // check what follows it too.
+        let (port_tx, port_found) = std::sync::mpsc::channel();
+        let stdout = std::thread::spawn(move || {
+            let mut lines = Vec::new();
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if let Some(port) = port_reader::parse_port_line(&line) {
+                    let _ = port_tx.send(port);
+                }
+                lines.push(line);
+            }
+            lines
+        });
+        let port_rx = port_reader::spawn(move || port_found.recv().ok());
+        Ok(Instance {
+            tree,
+            port_rx,
+            stdout,
+            token,
+        })
+    }
+
+    async fn run(mut self) -> Vec<Check> {
+        if let Err(e) = std::fs::create_dir_all(&self.data_dir) {
+            self.skip(
+                &[
+                    PORT_LINE,
+                    PORT_FILE,
+                    HEALTH,
+                    TOKEN,
+                    VERSION,
+                    READINESS,
+                    SHUTDOWN_ENDPOINT,
+                    STAGE_SYNTAX,
+                    ERROR_SYNTAX,
+                    CTRL_SYNTAX,
+                    SIGTERM,
+                ],
+                &format!("no scratch data directory: {e}"),
+            );
+            return self.checks;
+        }
+        self.handshake_and_http().await;
+        self.sigterm().await;
+        let _ = std::fs::remove_dir_all(&self.data_dir);
+        self.checks
+    }
+
+    async fn handshake_and_http(&mut self) {
+        let started = Instant::now();
+        let rest = [
+            PORT_FILE,
+            HEALTH,
+            TOKEN,
+            VERSION,
+            READINESS,
+            SHUTDOWN_ENDPOINT,
+        ];
+        let mut instance = match self.start() {
+            Ok(instance) => instance,
+            Err(e) => {
+                self.record(PORT_LINE, started, Err(e));
+                return self.skip(&rest, &format!("needs {PORT_LINE}"));
+            }
+        };
+        let port = match port_line(instance.port_rx).await {
+            Ok(port) => {
+                let detail = format!("PORT:{port} after {:.1} s", started.elapsed().as_secs_f64());
+                self.record(PORT_LINE, started, Ok(detail));
+                port
+            }
+            Err(e) => {
+                self.record(PORT_LINE, started, Err(e));
+                self.skip(&rest, &format!("needs {PORT_LINE}"));
+                instance.tree.kill();
+                return self.line_syntax(instance.stdout).await;
+            }
+        };
+
+        let started = Instant::now();
+        let result = port_file(&self.port_file(), port).await;
+        self.record(PORT_FILE, started, result);
+
+        let started = Instant::now();
+        if wait_for_backend(port, Some(&instance.token), HEALTH_TIMEOUT, HEALTH_INTERVAL).await {
+            self.record(HEALTH, started, Ok("healthy with the session token".into()));
+        } else {
+            self.record(
+                HEALTH,
+                started,
+                Err("never answered 200 {\"status\":\"ok\"}".into()),
+            );
+            self.skip(
+                &[TOKEN, VERSION, READINESS, SHUTDOWN_ENDPOINT],
+                &format!("needs {HEALTH}"),
+            );
+            instance.tree.kill();
+            return self.line_syntax(instance.stdout).await;
+        }
+
+        let started = Instant::now();
+        let result = token_required(port, &instance.token).await;
+        self.record(TOKEN, started, result);
+
+        let started = Instant::now();
+        let result = version_endpoint(port, &instance.token).await;
+        self.record(VERSION, started, result);
+
+        let started = Instant::now();
+        let result = readiness_endpoint(port, &instance.token).await;
+        self.record(READINESS, started, result);
+
+        let started = Instant::now();
+        let exit = ask_to_exit(&mut instance.tree, Some(port), Some(&instance.token)).await;
+        let result = match (exit.accepted, exit.exited) {
+            (true, true) => Ok(format!(
+                "accepted, exited after {:.1} s",
+                started.elapsed().as_secs_f64()
+            )),
+            (true, false) => Err(format!("accepted, still running after {SHUTDOWN_GRACE:?}")),
+            (false, _) => Err("POST /api/shutdown was not accepted with 202".into()),
+        };
+        self.record(SHUTDOWN_ENDPOINT, started, result);
+        instance.tree.kill();
+        self.line_syntax(instance.stdout).await;
+    }
+
+    /// The `*-syntax` checks, over all of stdout once the backend is gone.
+    async fn line_syntax(&mut self, stdout: JoinHandle<Vec<String>>) {
+        let started = Instant::now();
+        let ids = [
+            (STAGE_SYNTAX, LineKind::Stage),
+            (ERROR_SYNTAX, LineKind::Error),
+            (CTRL_SYNTAX, LineKind::Ctrl),
+        ];
+        let joined = tokio::task::spawn_blocking(move || stdout.join());
+        let lines = match tokio::time::timeout(STDOUT_WAIT, joined).await {
+            Ok(Ok(Ok(lines))) => lines,
+            Ok(_) => return self.skip(&ids.map(|(id, _)| id), "stdout reader failed"),
+            Err(_) => {
+                let why = format!(
+                    "stdout still open {} s after the backend was stopped",
+                    STDOUT_WAIT.as_secs()
+                );
+                return self.skip(&ids.map(|(id, _)| id), &why);
+            }
+        };
+        for (id, kind) in ids {
+            self.record(id, started, line_syntax(&lines, kind));
+        }
+    }
+
+    async fn sigterm(&mut self) {
+        if cfg!(windows) {
+            return self.skip(&[SIGTERM], "Windows has no SIGTERM");
+        }
+        let started = Instant::now();
+        let mut instance = match self.start() {
+            Ok(instance) => instance,
+            Err(e) => return self.record(SIGTERM, started, Err(e)),
+        };
+        let ready = match port_line(instance.port_rx).await {
+            Ok(port) => {
+                wait_for_backend(port, Some(&instance.token), HEALTH_TIMEOUT, HEALTH_INTERVAL).await
+            }
+            Err(_) => false,
+        };
+        if !ready {
+            self.skip(&[SIGTERM], "second instance never became healthy");
+            instance.tree.kill();
+            return;
+        }
+        let started = Instant::now();
+        let result = if ask_to_exit(&mut instance.tree, None, None).await.exited {
+            Ok(format!(
+                "exited after {:.1} s",
+                started.elapsed().as_secs_f64()
+            ))
+        } else {
+            Err(format!("still running after {SHUTDOWN_GRACE:?}"))
+        };
+        self.record(SIGTERM, started, result);
+        instance.tree.kill();
+    }
+}
+
+async fn port_line(port_rx: oneshot::Receiver<PortOutcome>) -> Result<u16, String> {
+    match tokio::time::timeout(PORT_TIMEOUT, port_rx).await {
+        Ok(Ok(PortOutcome::Port(port))) => Ok(port),
+        Ok(Ok(PortOutcome::ReaderPanicked(message))) => {
+            Err(format!("stdout reader failed: {message}"))
+        }
+        Ok(_) => Err("stdout closed without a PORT line".into()),
+        Err(_) => Err(format!(
+            "no PORT line within {} s – is stdout flushed after printing it?",
+            PORT_TIMEOUT.as_secs()
+        )),
+    }
+}
+
+async fn port_file(path: &Path, expected: u16) -> Result<String, String> {
+    let deadline = Instant::now() + PORT_FILE_WAIT;
+    loop {
+        match port_reader::read_port_file(path) {
+            Some(port) if port == expected => return Ok(format!("names port {port}")),
+            Some(port) => return Err(format!("names port {port}, stdout said {expected}")),
+            None if Instant::now() >= deadline => {
+                return Err(format!(
+                    "no port in {path:?} after {} s",
+                    PORT_FILE_WAIT.as_secs()
+                ))
+            }
+            None => tokio::time::sleep(Duration::from_millis(250)).await,
+        }
+    }
+}
+
+/// Every `kind` line in `lines` is well-formed, and no `STAGE:` line comes
+/// after the first `PORT:` line.
+fn line_syntax(lines: &[String], kind: LineKind) -> Result<String, String> {
+    let mut port_seen = false;
+    let mut count = 0;
+    for line in lines {
+        let Some((found, value)) = port_reader::parse_line(line) else {
+            continue;
+        };
+        if found == LineKind::Port && value.is_ok() {
+            port_seen = true;
+        }
+        if found != kind {
+            continue;
+        }
+        value?;
+        if kind == LineKind::Stage && port_seen {
+            return Err(format!("{line:?} printed after the PORT line"));
+        }
+        count += 1;
+    }
+    let prefix = kind.prefix();
+    Ok(match (count, kind) {
+        (0, _) => format!("no {prefix} lines"),
+        (n, LineKind::Stage) => format!("{n} well-formed {prefix} lines, all before PORT:"),
+        (n, _) => format!("{n} well-formed {prefix} lines"),
+    })
+}
+
+async fn version_endpoint(port: u16, token: &str) -> Result<String, String> {
+    match http::fetch_version(port, Some(token)).await? {
+        Some(version) => match semver::Version::parse(version.trim()) {
+            Ok(_) => Ok(format!("reports {version}")),
+            Err(e) => Err(format!("{version:?} is not a semantic version: {e}")),
+        },
+        None => Err("GET /api/version answered 404".into()),
+    }
+}
+
+async fn readiness_endpoint(port: u16, token: &str) -> Result<String, String> {
+    let deadline = Instant::now() + READINESS_TIMEOUT;
+    for component in readiness::COMPONENTS {
+        let name = component.name;
+        loop {
+            match http::probe_component(port, Some(token), name).await {
+                ComponentProbe::Ready => break,
+                ComponentProbe::NotReported => {
+                    return Err(format!("GET /api/ready/{name} answered 404"))
+                }
+                ComponentProbe::NotReady if Instant::now() >= deadline => {
+                    return Err(format!(
+                        "{name} not ready within {} s",
+                        READINESS_TIMEOUT.as_secs()
+                    ))
+                }
+                ComponentProbe::NotReady => tokio::time::sleep(Duration::from_millis(250)).await,
+            }
+        }
+    }
+    let names: Vec<_> = readiness::COMPONENTS.iter().map(|c| c.name).collect();
+    Ok(format!("{} ready", names.join(", ")))
+}
+
+async fn token_required(port: u16, token: &str) -> Result<String, String> {
+    if http::probe_health(port, None).await {
+        return Err("/api/health answered without a token".into());
+    }
+    if http::probe_health(port, Some("not-the-session-token")).await {
+        return Err("/api/health answered with a wrong token".into());
+    }
+    if http::request_shutdown(port, None).await {
+        return Err("/api/shutdown accepted a request without a token".into());
+    }
+    if !http::probe_health(port, Some(token)).await {
+        return Err("/api/health fails with the right token after refusing the others".into());
+    }
+    Ok("requests without the token or with a wrong one are refused".into())
+}
+
+// ── Entry point ─────────────────────────────────────────────────────────────
+
+/// Run the suite if `args` ask for it; the exit code, or `None` otherwise.
+pub fn main(args: &[String]) -> Option<i32> {
+    let options = match parse_args(args)? {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{e}\n{USAGE}");
+            return Some(2);
+        }
+    };
+    let suite = Suite {
+        sidecar: options.sidecar.clone(),
+        data_dir: std::env::temp_dir().join(format!("almready-conformance-{}", std::process::id())),
+        checks: Vec::new(),
+    };
+    let checks = tauri::async_runtime::block_on(suite.run());
+    let report = Report::new(&options.sidecar, checks);
+    if options.json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(json) => println!("{json}"),
+            Err(e) => eprintln!("cannot serialize the report: {e}"),
+        }
+    } else {
+        print!("{}", report.text());
+    }
+    Some(report.exit_code())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn only_the_conformance_command_is_claimed() {
+        assert_eq!(parse_args(&args(&["almready"])), None);
+        assert_eq!(
+            parse_args(&args(&["almready", "--dump-window-config"])),
+            None
+        );
+        assert_eq!(
+            parse_args(&args(&[
+                "almready",
+                "conformance",
+                "--sidecar",
+                "b",
+                "--json"
+            ])),
+            Some(Ok(Options {
+                sidecar: "b".into(),
+                json: true
+            }))
+        );
+        assert!(matches!(
+            parse_args(&args(&["almready", "conformance"])),
+            Some(Err(_))
+        ));
+        assert!(matches!(
+            parse_args(&args(&["almready", "conformance", "--sidecar"])),
+            Some(Err(_))
+        ));
+    }
+
+    #[test]
+    fn stdout_lines_are_checked_with_the_shells_parser() {
+        let lines = args(&[
+            "STAGE:imports",
+            "INFO: loading",
+            "ERROR:pool failed to start",
+            "PORT:51234",
+            "CTRL:ready",
+        ]);
+        assert!(line_syntax(&lines, LineKind::Stage).is_ok());
+        assert_eq!(
+            line_syntax(&lines, LineKind::Error),
+            Ok("1 well-formed ERROR: lines".into())
+        );
+        assert!(line_syntax(&lines, LineKind::Ctrl).is_ok());
+        assert_eq!(
+            line_syntax(&args(&["PORT:1"]), LineKind::Ctrl),
+            Ok("no CTRL: lines".into())
+        );
+
+        let late_stage = args(&["PORT:51234", "STAGE:session"]);
+        assert!(line_syntax(&late_stage, LineKind::Stage)
+            .unwrap_err()
+            .contains("after the PORT line"));
+        let malformed = args(&["ERROR:", "CTRL:Go Now"]);
+        assert!(line_syntax(&malformed, LineKind::Error).is_err());
+        assert!(line_syntax(&malformed, LineKind::Ctrl).is_err());
+    }
+
+    #[test]
+    fn a_failed_check_fails_the_report_but_a_skipped_one_does_not() {
+        let check = |id, outcome| Check {
+            id,
+            outcome,
+            detail: "x".into(),
+            duration_ms: 1,
+        };
+        let report = Report::new(
+            Path::new("backend"),
+            vec![
+                check(PORT_LINE, Outcome::Pass),
+                check(SIGTERM, Outcome::Skip),
+            ],
+        );
+        assert_eq!(report.exit_code(), 0);
+        assert!(report.text().ends_with("1 passed, 0 failed, 1 skipped\n"));
+
+        let report = Report::new(
+            Path::new("backend"),
+            vec![
+                check(PORT_LINE, Outcome::Fail),
+                check(PORT_FILE, Outcome::Skip),
+            ],
+        );
+        assert_eq!(report.exit_code(), 1);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["format_version"], 1);
+        assert_eq!(json["passed"], false);
+        assert_eq!(json["checks"][0]["id"], "handshake.port-line");
+        assert_eq!(json["checks"][0]["outcome"], "fail");
+    }
+}
@@ -0,0 +1,104 @@
+//! Webview cookie inspection (session debugging) and clearing (logout).
+//!
+//! Tauri's webview cookie API wraps the platform stores directly, and
+//! dispatches each call to the thread that owns the webview:
+//!
+//! macOS   → `WKHTTPCookieStore` of the WKWebView's data store
+//! Windows → `ICoreWebView2CookieManager`
+//! Linux   → WebKitGTK's `WebKitCookieManager`
+//!
+//! On Windows the cookie calls block on the webview's UI thread, so the
+//! commands here are `async` (run off the main thread) to avoid deadlocking.
+
+use serde::Serialize;
+use tauri::{webview::Cookie as WebviewCookie, AppHandle, Manager, WebviewWindow};
+
+use crate::{http::BACKEND_HOST, window_factory::MAIN_LABEL};
+
+/// Cookie the backend sets after login.
+const SESSION_COOKIE: &str = "session_id";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    pub domain: Option<String>,
+    pub path: Option<String>,
+    /// Unix seconds; `None` for session cookies.
+    pub expires: Option<i64>,
+}
+
+impl From<&WebviewCookie<'_>> for Cookie {
+    fn from(c: &WebviewCookie<'_>) -> Self {
+        Cookie {
+            name: c.name().to_string(),
+            value: c.value().to_string(),
+            domain: c.domain().map(str::to_string),
+            path: c.path().map(str::to_string),
+            expires: c.expires_datetime().map(|t| t.unix_timestamp()),
+        }
+    }
+}
+
+fn main_window(app: &AppHandle) -> Result<WebviewWindow, String> {
+    app.get_webview_window(MAIN_LABEL)
+        .ok_or_else(|| "main window is not open".to_string())
+}
+
+/// RFC 6265 §5.1.3 domain-match: would a cookie set for `cookie_domain` be
+/// sent to `host`?  A leading dot on the cookie domain is ignored.
+fn domain_matches(cookie_domain: &str, host: &str) -> bool {
+    let cookie_domain = cookie_domain.trim_start_matches('.').to_ascii_lowercase();
+    let host = host.trim_start_matches('.').to_ascii_lowercase();
+    host == cookie_domain || host.ends_with(&format!(".{cookie_domain}"))
+}
+
+fn for_domain<'a>(
+    cookies: &'a [WebviewCookie<'static>],
+    domain: &'a str,
+) -> impl Iterator<Item = &'a WebviewCookie<'static>> {
+    cookies
+        .iter()
+        .filter(move |c| c.domain().is_some_and(|d| domain_matches(d, domain)))
+}
+
+/// Cookies stored in the main webview that apply to `domain`.
+#[tauri::command]
+pub async fn get_webview_cookies(app: AppHandle, domain: String) -> Result<Vec<Cookie>, String> {
+    let cookies = main_window(&app)?
+        .cookies()
+        .map_err(|e| format!("read cookies: {e}"))?;
+    Ok(for_domain(&cookies, &domain).map(Cookie::from).collect())
+}
+
+/// Value of the backend's `session_id` cookie in the main webview, if it
+/// has set one.
+#[tauri::command]
+pub async fn get_session_cookie(app: AppHandle) -> Result<Option<String>, String> {
+    let cookies = get_webview_cookies(app, BACKEND_HOST.to_string()).await?;
+    Ok(cookies
+        .into_iter()
+        .find(|c| c.name == SESSION_COOKIE)
+        .map(|c| c.value))
+}
+
+/// Delete cookies from the main webview: those matching `domain`, or all of
+/// them when `domain` is `None`.  Returns how many were deleted.
+#[tauri::command]
+pub async fn clear_webview_cookies(app: AppHandle, domain: Option<String>) -> Result<u32, String> {
+    let window = main_window(&app)?;
+    let cookies = window.cookies().map_err(|e| format!("read cookies: {e}"))?;
+    let targets: Vec<&WebviewCookie<'static>> = match &domain {
+        Some(domain) => for_domain(&cookies, domain).collect(),
+        None => cookies.iter().collect(),
+    };
+
+    let mut deleted = 0;
+    for cookie in targets {
+        window
+            .delete_cookie(cookie.clone())
+            .map_err(|e| format!("delete cookie {:?}: {e}", cookie.name()))?;
+        deleted += 1;
+    }
+    Ok(deleted)
+}
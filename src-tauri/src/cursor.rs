@@ -0,0 +1,55 @@
+//! OS cursor for loading states.
+//!
+//! The webview asks for a wait/progress cursor around long backend
+//! operations (file imports, recalculations) and puts it back with
+//! `Default` when they finish, in a `finally` so failures reset it too.
+//! Shell-driven waits do the same through `with_cursor`.
+
+use serde::Deserialize;
+use tauri::{AppHandle, Manager, WebviewWindow};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CursorIcon {
+    Default,
+    Wait,
+    Progress,
+    NotAllowed,
+}
+
+impl From<CursorIcon> for tauri::CursorIcon {
+    fn from(cursor: CursorIcon) -> Self {
+        match cursor {
+            CursorIcon::Default => tauri::CursorIcon::Default,
+            CursorIcon::Wait => tauri::CursorIcon::Wait,
+            CursorIcon::Progress => tauri::CursorIcon::Progress,
+            CursorIcon::NotAllowed => tauri::CursorIcon::NotAllowed,
+        }
+    }
+}
+
+fn set_all(app: &AppHandle, cursor: CursorIcon) {
+    for window in app.webview_windows().values() {
+        let _ = window.set_cursor_icon(cursor.into());
+    }
+}
+
+/// Show `cursor` on every window while `operation` runs, then reset it.
+pub async fn with_cursor<T>(
+    app: &AppHandle,
+    cursor: CursorIcon,
+    operation: impl std::future::Future<Output = T>,
+) -> T {
+    set_all(app, cursor);
+    let result = operation.await;
+    set_all(app, CursorIcon::Default);
+    result
+}
+
+/// Set the cursor of the calling window.
+#[tauri::command]
+pub fn set_cursor(window: WebviewWindow, cursor: CursorIcon) -> Result<(), String> {
+    window
+        .set_cursor_icon(cursor.into())
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,276 @@
+//! DataDirAccess – the one way the shell touches files in the data directory.
+//!
+//! On Windows a file the backend holds open exclusively can't be read or
+//! written by the shell (ERROR_SHARING_VIOLATION / ERROR_LOCK_VIOLATION),
+//! and a shell access that gets there first makes the backend fail the same
+//! way.  So every one-off file operation the shell performs inside
+//! `ALMREADY_DATA_DIR` runs through `DataDirAccess::with`, which:
+//!
+//! 1. for files the backend owns (`sessions/`), first asks the running
+//!    backend for a short lease on them (`POST /api/files/lease {paths,
+//!    duration}`), during which it leaves them alone;
+//! 2. runs the operation, retrying with exponential backoff while it fails
+//!    with a sharing violation.  This is all there is when the backend has
+//!    no lease endpoint, isn't running, or the files aren't its own.
+//!
+//! Not delivered yet: the backend has no `/api/files/lease`, and honouring
+//! one would mean checking for leases at every place it writes a session
+//! file.  Until it does, every lease request is refused (404) and step 2 is
+//! the whole policy.  Likewise, the housekeeping scheduler's
+//! `requires_idle` only follows the job flag the frontend reports; it does
+//! not coordinate with the backend.
+//!
+//! Unix has no mandatory locks, so there the retry never triggers.  The
+//! append-only log writers (`logpipe::LogFile`) keep their handles open and
+//! are not routed through here: the backend never opens `logs/`.
+//!
+//! Lease requests block on the async runtime, so `with` must be called from
+//! a synchronous command or a blocking task – never from async code.
+
+use std::{
+    io,
+    path::{Component, Path, PathBuf},
+    time::Duration,
+};
+
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    http, paths,
+    shell_log::{self, Level},
+    BackendProcess,
+};
+
+/// Tries per operation; with `RETRY_BASE` doubling that is up to ~1.5 s of
+/// waiting before a sharing violation is reported.
+const RETRY_ATTEMPTS: u32 = 6;
+const RETRY_BASE: Duration = Duration::from_millis(50);
+
+/// How long a lease asks the backend to stay off the files.
+const LEASE_DURATION: Duration = Duration::from_secs(10);
+
+/// Subdirectory of the data directory holding the backend's live files.
+const BACKEND_DIR: &str = "sessions";
+
+/// ERROR_SHARING_VIOLATION or ERROR_LOCK_VIOLATION.
+pub fn is_sharing_violation(e: &io::Error) -> bool {
+    cfg!(windows) && matches!(e.raw_os_error(), Some(32 | 33))
+}
+
+/// Run `op` up to `attempts` times, sleeping `base`, `2 × base`, … between
+/// tries, for as long as it fails with an error `is_transient` accepts.
+fn retry<T>(
+    attempts: u32,
+    base: Duration,
+    is_transient: impl Fn(&io::Error) -> bool,
+    mut sleep: impl FnMut(Duration),
+    mut op: impl FnMut() -> io::Result<T>,
+) -> io::Result<T> {
+    let mut delay = base;
+    let mut attempt = 1;
+    loop {
+        match op() {
+            Err(e) if attempt < attempts && is_transient(&e) => {
+                sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+struct LeaseTarget {
+    port: u16,
+    token: Option<String>,
+}
+
+pub struct DataDirAccess {
+    root: PathBuf,
+    backend: Option<LeaseTarget>,
+}
+
+impl DataDirAccess {
+    /// Access that leases `sessions/` files from the backend while it runs.
+    pub fn for_app(app: &AppHandle) -> Result<Self, String> {
+        let backend = app.state::<BackendProcess>();
+        let port = backend.port.get();
+        Ok(DataDirAccess {
+            root: paths::data_dir(app)?,
+            backend: port.map(|port| LeaseTarget {
+                port,
+                token: backend.token(),
+            }),
+        })
+    }
+
+    /// Retries only: for files the backend never opens, and for code that
+    /// runs while no backend is up (startup, spawning the sidecar).
+    pub fn unleased() -> Self {
+        DataDirAccess {
+            root: PathBuf::new(),
+            backend: None,
+        }
+    }
+
+    /// `paths` that belong to the backend, relative to the data directory
+    /// with `/` separators, as the lease endpoint expects them.
+    fn backend_paths(&self, paths: &[&Path]) -> Vec<String> {
+        if self.root.as_os_str().is_empty() {
+            return Vec::new();
+        }
+        paths
+            .iter()
+            .filter_map(|path| path.strip_prefix(&self.root).ok())
+            .filter(|rel| rel.starts_with(BACKEND_DIR))
+            .map(|rel| {
+                rel.components()
+                    .filter_map(|c| match c {
+                        Component::Normal(part) => Some(part.to_string_lossy()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join("/")
+            })
+            .collect()
+    }
+
+    /// Run `op`, which touches `paths`, under this module's policy.
+    pub fn with<T>(&self, paths: &[&Path], op: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+        let leased = self.backend_paths(paths);
+        if let (Some(backend), false) = (&self.backend, leased.is_empty()) {
+            let granted = tauri::async_runtime::block_on(http::request_lease(
+                backend.port,
+                backend.token.as_deref(),
+                &leased,
+                LEASE_DURATION,
+            ));
+            if !granted {
+                shell_log::log(
+                    Level::Debug,
+                    "data-dir",
+                    format!("no lease for {leased:?}, relying on retries"),
+                );
+            }
+        }
+        retry(
+            RETRY_ATTEMPTS,
+            RETRY_BASE,
+            is_sharing_violation,
+            std::thread::sleep,
+            op,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locked() -> io::Error {
+        io::Error::other("sharing violation")
+    }
+
+    #[test]
+    fn retries_transient_errors_with_backoff() {
+        let mut sleeps = Vec::new();
+        let mut calls = 0;
+        let result = retry(
+            6,
+            Duration::from_millis(50),
+            |e| e.kind() == io::ErrorKind::Other,
+            |d| sleeps.push(d.as_millis()),
+            || {
+                calls += 1;
+                if calls < 4 {
+                    Err(locked())
+                } else {
+                    Ok(calls)
+                }
+            },
+        );
+        assert_eq!(result.unwrap(), 4);
+        assert_eq!(sleeps, [50, 100, 200]);
+    }
+
+    #[test]
+    fn gives_up_after_attempts_and_never_retries_other_errors() {
+        let mut calls = 0;
+        let result: io::Result<()> = retry(
+            3,
+            Duration::ZERO,
+            |e| e.kind() == io::ErrorKind::Other,
+            |_| {},
+            || {
+                calls += 1;
+                Err(locked())
+            },
+        );
+        assert!(result.is_err());
+        assert_eq!(calls, 3);
+
+        let mut calls = 0;
+        let result: io::Result<()> = retry(
+            3,
+            Duration::ZERO,
+            |e| e.kind() == io::ErrorKind::Other,
+            |_| {},
+            || {
+                calls += 1;
+                Err(io::ErrorKind::NotFound.into())
+            },
+        );
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::NotFound);
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn only_backend_files_are_leased() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("almready-data");
+        let access = DataDirAccess {
+            root: root.clone(),
+            backend: None,
+        };
+        let session = root.join("sessions").join("abc").join("meta.json");
+        let settings = root.join("settings.json");
+        let elsewhere = tmp.path().join("sessions").join("x");
+        assert_eq!(
+            access.backend_paths(&[&session, &settings, &elsewhere]),
+            ["sessions/abc/meta.json"]
+        );
+        assert!(DataDirAccess::unleased()
+            .backend_paths(&[&session])
+            .is_empty());
+    }
+
+    /// A real sharing violation: the file is held open with no sharing and
+    /// released while `with` is backing off.
+    #[cfg(windows)]
+    #[test]
+    fn waits_out_a_file_held_open_without_sharing() {
+        use std::os::windows::fs::OpenOptionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("held.json");
+        std::fs::write(&path, "{}").unwrap();
+
+        let held = std::fs::OpenOptions::new()
+            .read(true)
+            .share_mode(0)
+            .open(&path)
+            .unwrap();
+        let err = std::fs::read_to_string(&path).unwrap_err();
+        assert!(is_sharing_violation(&err), "{err:?}");
+
+        let release = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(150));
+            drop(held);
+        });
+        let text = DataDirAccess::unleased()
+            .with(&[&path], || std::fs::read_to_string(&path))
+            .unwrap();
+        assert_eq!(text, "{}");
+        release.join().unwrap();
+    }
+}
@@ -0,0 +1,223 @@
+//! One report of everything that leaves the app working but degraded, so
+//! the frontend renders a single banner area instead of one notification
+//! per feature.
+//!
+//! A feature that detects a degraded state calls `set` with its entry and
+//! `clear` once it is over; entries are keyed by `Code`, so setting one
+//! again replaces it.  Every change is sent to all windows as
+//! `degradations-changed` (the full report) and refreshes the tray tooltip.
+//! The report at window creation is part of `window.__ALMREADY_CONFIG__`,
+//! and `get_degradations` answers late listeners.
+//!
+//! | code                       | set by                                   |
+//! |----------------------------|------------------------------------------|
+//! | `license-invalid`          | the startup license check                |
+//! | `read-only-install`        | `install_location` at startup            |
+//! | `storage-high`             | `storage::check_usage`, cleared below it |
+//! | `offline`                  | `network::check`, cleared when online    |
+//! | `engine-output-unreadable` | a failed stdout reader (`port_reader`)   |
+//! | `external-backend`         | `ALMREADY_BACKEND_PORT` (no restarts)    |
+//! | `portable-data-unwritable` | `paths` when portable mode falls back    |
+//!
+//! `message_key` is a frontend i18n key; `details` carries what the
+//! message needs to be specific.
+
+use std::{collections::BTreeMap, sync::Mutex};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+
+use crate::{
+    events::{self, Audience},
+    tray,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Code {
+    LicenseInvalid,
+    ReadOnlyInstall,
+    StorageHigh,
+    Offline,
+    EngineOutputUnreadable,
+    ExternalBackend,
+    PortableDataUnwritable,
+}
+
+/// Ordered from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Degradation {
+    pub code: Code,
+    pub severity: Severity,
+    pub message_key: &'static str,
+    pub details: serde_json::Value,
+}
+
+impl Degradation {
+    pub fn new(code: Code, severity: Severity, details: serde_json::Value) -> Self {
+        let message_key = match code {
+            Code::LicenseInvalid => "degradation.license-invalid",
+            Code::ReadOnlyInstall => "degradation.read-only-install",
+            Code::StorageHigh => "degradation.storage-high",
+            Code::Offline => "degradation.offline",
+            Code::EngineOutputUnreadable => "degradation.engine-output-unreadable",
+            Code::ExternalBackend => "degradation.external-backend",
+            Code::PortableDataUnwritable => "degradation.portable-data-unwritable",
+        };
+        Degradation {
+            code,
+            severity,
+            message_key,
+            details,
+        }
+    }
+}
+
+/// Managed state: the active degradations by code.
+#[derive(Default)]
+pub struct Degradations(Mutex<BTreeMap<Code, Degradation>>);
+
+impl Degradations {
+    /// Returns whether the report changed.
+    fn insert(&self, degradation: Degradation) -> bool {
+        let mut active = self.0.lock().unwrap();
+        if active.get(&degradation.code) == Some(&degradation) {
+            return false;
+        }
+        active.insert(degradation.code, degradation);
+        true
+    }
+
+    /// Returns whether the report changed.
+    fn remove(&self, code: Code) -> bool {
+        self.0.lock().unwrap().remove(&code).is_some()
+    }
+
+    /// Active entries, most severe first.
+    pub fn report(&self) -> Vec<Degradation> {
+        let mut report: Vec<_> = self.0.lock().unwrap().values().cloned().collect();
+        report.sort_by(|a, b| b.severity.cmp(&a.severity).then(a.code.cmp(&b.code)));
+        report
+    }
+
+    pub fn count(&self) -> usize {
+        self.0.lock().unwrap().len()
+    }
+}
+
+fn notify(app: &AppHandle) {
+    let report = app.state::<Degradations>().report();
+    events::emit(app, Audience::AllWindows, "degradations-changed", report);
+    tray::refresh(app);
+}
+
+/// Register (or update) a degraded state.
+pub fn set(app: &AppHandle, degradation: Degradation) {
+    if app.state::<Degradations>().insert(degradation) {
+        notify(app);
+    }
+}
+
+/// The degraded state `code` is over.
+pub fn clear(app: &AppHandle, code: Code) {
+    if app.state::<Degradations>().remove(code) {
+        notify(app);
+    }
+}
+
+#[tauri::command]
+pub fn get_degradations(degradations: State<'_, Degradations>) -> Vec<Degradation> {
+    degradations.report()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn codes(degradations: &Degradations) -> Vec<Code> {
+        degradations.report().iter().map(|d| d.code).collect()
+    }
+
+    #[test]
+    fn read_only_install_with_high_storage_lists_both_until_cleared() {
+        let degradations = Degradations::default();
+        assert!(degradations.insert(Degradation::new(
+            Code::StorageHigh,
+            Severity::Warning,
+            json!({ "bytes": 60_000_000 }),
+        )));
+        assert!(degradations.insert(Degradation::new(
+            Code::ReadOnlyInstall,
+            Severity::Warning,
+            json!({ "kind": "DiskImage" }),
+        )));
+        assert_eq!(
+            codes(&degradations),
+            [Code::ReadOnlyInstall, Code::StorageHigh]
+        );
+
+        // The same entry again is no change; new details are.
+        assert!(!degradations.insert(Degradation::new(
+            Code::StorageHigh,
+            Severity::Warning,
+            json!({ "bytes": 60_000_000 }),
+        )));
+        assert!(degradations.insert(Degradation::new(
+            Code::StorageHigh,
+            Severity::Warning,
+            json!({ "bytes": 70_000_000 }),
+        )));
+        assert_eq!(
+            degradations.report()[1].details,
+            json!({ "bytes": 70_000_000 })
+        );
+
+        assert!(degradations.remove(Code::StorageHigh));
+        assert!(!degradations.remove(Code::StorageHigh));
+        assert_eq!(codes(&degradations), [Code::ReadOnlyInstall]);
+    }
+
+    #[test]
+    fn external_backend_with_invalid_license_puts_the_error_first() {
+        let degradations = Degradations::default();
+        degradations.insert(Degradation::new(
+            Code::ExternalBackend,
+            Severity::Info,
+            json!({ "port": 8000 }),
+        ));
+        degradations.insert(Degradation::new(
+            Code::LicenseInvalid,
+            Severity::Error,
+            json!({ "reason": "expired" }),
+        ));
+        assert_eq!(degradations.count(), 2);
+        assert_eq!(
+            serde_json::to_value(degradations.report()).unwrap(),
+            json!([
+                {
+                    "code": "license-invalid",
+                    "severity": "error",
+                    "messageKey": "degradation.license-invalid",
+                    "details": { "reason": "expired" },
+                },
+                {
+                    "code": "external-backend",
+                    "severity": "info",
+                    "messageKey": "degradation.external-backend",
+                    "details": { "port": 8000 },
+                },
+            ])
+        );
+    }
+}
@@ -0,0 +1,124 @@
+//! Detached workspace windows: a view of the app (say the results
+//! dashboard) popped out of the main window, e.g. onto a second monitor.
+//!
+//! `open_window` builds `index.html#/{route}` with the same injected globals
+//! as the main window (port, session token, data dir, feature flags), so it
+//! talks to the same sidecar.  The frontend names the window; its label is
+//! that name after `LABEL_PREFIX`, which `capabilities/default.json` grants
+//! by pattern.  Each label keeps its own geometry in `window_state`; opening
+//! a name that is already open focuses that window instead.
+//!
+//! The main window and detached windows are the workspace windows: the
+//! sidecar is stopped only when the last of them closes, so closing the
+//! main window leaves a detached dashboard working.  Splash and log viewer
+//! don't count.
+
+use tauri::{AppHandle, Manager, State};
+
+use crate::{
+    events::Capability,
+    log_viewer,
+    settings::SettingsStore,
+    splash,
+    window_factory::{WindowConfig, WindowFactory, WindowPreset, WindowSpec},
+    window_state, BackendProcess,
+};
+
+/// Labels of windows that are not workspace windows.
+const UTILITY_LABELS: [&str; 2] = [splash::LABEL, log_viewer::LABEL];
+
+/// Every detached window's label starts with this.
+pub const LABEL_PREFIX: &str = "detached-";
+
+const DEFAULT_SIZE: (f64, f64) = (1200.0, 800.0);
+
+/// Whether closing `label` leaves other workspace windows open.
+pub fn others_open(app: &AppHandle, label: &str) -> bool {
+    app.webview_windows()
+        .keys()
+        .any(|other| other != label && !UTILITY_LABELS.contains(&other.as_str()))
+}
+
+/// Whether `label` is a detached window's.
+pub fn is_detached(label: &str) -> bool {
+    label.starts_with(LABEL_PREFIX)
+}
+
+/// The label of the detached window called `name`.
+fn label_for(name: &str) -> Result<String, String> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(format!(
+            "Invalid window name {name:?}: use letters, digits, '-' and '_'."
+        ));
+    }
+    Ok(format!("{LABEL_PREFIX}{name}"))
+}
+
+/// The page to load for `route`, e.g. `results` → `index.html#/results`.
+fn page(route: &str) -> String {
+    format!("index.html#/{}", route.trim_start_matches(['#', '/']))
+}
+
+/// Open `route` in a window of its own called `name`, or focus that window
+/// if it is already open.
+///
+/// Async so the window is built off the main thread (a sync command that
+/// creates a window deadlocks on Windows).
+#[tauri::command]
+pub async fn open_window(
+    app: AppHandle,
+    backend: State<'_, BackendProcess>,
+    name: String,
+    route: String,
+) -> Result<(), String> {
+    let label = label_for(&name)?;
+    if let Some(window) = app.get_webview_window(&label) {
+        let _ = window.unminimize();
+        return window.set_focus().map_err(|e| e.to_string());
+    }
+
+    let port = backend.port.get().ok_or("The engine is not running.")?;
+    let page = page(&route);
+    let compact = app.state::<SettingsStore>().get().compact_mode;
+    let spec = WindowSpec {
+        label: &label,
+        page: &page,
+        title: "ALMReady",
+        size: DEFAULT_SIZE,
+        min_size: None,
+        center: true,
+        preset: WindowPreset::for_mode(compact),
+        capabilities: &[Capability::Lifecycle],
+    };
+    let mut config = WindowConfig::for_app(&app, &spec, port);
+    window_state::restore_into(&app, &mut config);
+    WindowFactory::build(&app, config).map_err(|e| format!("failed to open {label}: {e}"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window_factory::MAIN_LABEL;
+
+    #[test]
+    fn labels_are_prefixed_plain_names() {
+        assert_eq!(label_for("results"), Ok("detached-results".into()));
+        assert_eq!(label_for("results-2_b"), Ok("detached-results-2_b".into()));
+        assert!(label_for("").is_err());
+        assert!(label_for("a b").is_err());
+        assert!(label_for("../x").is_err());
+
+        assert!(is_detached(&label_for(MAIN_LABEL).unwrap()));
+        assert!(!is_detached(MAIN_LABEL));
+        assert!(!is_detached(log_viewer::LABEL));
+        assert!(!is_detached(splash::LABEL));
+
+        assert_eq!(page("results"), "index.html#/results");
+        assert_eq!(page("#/results/summary"), "index.html#/results/summary");
+    }
+}
@@ -0,0 +1,89 @@
+//! The backend for `cargo tauri dev`, started by the shell itself.
+//!
+//! Debug builds have no bundled sidecar.  Instead of relying on a uvicorn
+//! started by hand (or by `npm run dev:all`), `spawn_sidecar` then runs the
+//! repository's `backend/sidecar_main.py` – the PyInstaller entry point,
+//! which picks a free port and prints `PORT:{n}` like the bundled build –
+//! with the project's `.venv` Python when there is one.  Everything else is
+//! as for the bundled sidecar: environment, port parsing, health check,
+//! process tree (so closing the window stops it) and watchdog.
+//!
+//! `ALMREADY_DEV_BACKEND_CMD` replaces the command, split on whitespace and
+//! run in `backend/`, e.g. `uv run python sidecar_main.py`.  It must print
+//! `PORT:{n}` on stdout too.
+
+use std::path::{Path, PathBuf};
+
+pub const CMD_ENV: &str = "ALMREADY_DEV_BACKEND_CMD";
+
+/// Whether this build falls back to the dev backend when `exe_path` (the
+/// bundled sidecar) is missing.
+pub fn applies(exe_path: &Path) -> bool {
+    cfg!(debug_assertions) && !exe_path.exists()
+}
+
+/// `backend/` in the source tree this shell was built from.
+pub fn backend_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap_or(Path::new(env!("CARGO_MANIFEST_DIR")))
+        .join("backend")
+}
+
+fn venv_python(repo: &Path) -> PathBuf {
+    if cfg!(windows) {
+        repo.join(".venv").join("Scripts").join("python.exe")
+    } else {
+        repo.join(".venv").join("bin").join("python")
+    }
+}
+
+/// Program and arguments: `ALMREADY_DEV_BACKEND_CMD` if set, else the
+/// entry point under the venv Python (or `python` on `PATH`).
+fn command_line(custom: Option<&str>, repo: &Path) -> Option<(PathBuf, Vec<String>)> {
+    if let Some(custom) = custom.filter(|c| !c.trim().is_empty()) {
+        let mut words = custom.split_whitespace().map(str::to_string);
+        let program = PathBuf::from(words.next()?);
+        return Some((program, words.collect()));
+    }
+    let venv = venv_python(repo);
+    let python = if venv.is_file() {
+        venv
+    } else {
+        PathBuf::from("python")
+    };
+    Some((python, vec!["sidecar_main.py".into()]))
+}
+
+/// The command to run in `backend_dir()`.
+pub fn command() -> (PathBuf, Vec<String>) {
+    let custom = std::env::var(CMD_ENV).ok();
+    let repo = backend_dir()
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    command_line(custom.as_deref(), &repo)
+        .unwrap_or_else(|| (PathBuf::from("python"), vec!["sidecar_main.py".into()]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_command_is_split_on_whitespace() {
+        let repo = Path::new("/nonexistent/almready");
+        assert_eq!(
+            command_line(Some("uv run  python sidecar_main.py"), repo),
+            Some((
+                PathBuf::from("uv"),
+                vec!["run".into(), "python".into(), "sidecar_main.py".into()]
+            ))
+        );
+        // Blank means unset; without a venv, `python` on PATH.
+        assert_eq!(
+            command_line(Some("  "), repo),
+            Some((PathBuf::from("python"), vec!["sidecar_main.py".into()]))
+        );
+    }
+}
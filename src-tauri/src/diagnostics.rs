@@ -0,0 +1,63 @@
+//! Self-checks for the diagnostics page.
+//!
+//! Each check answers one yes/no question about the environment the shell
+//! is running in, so field issues of a known class can be identified at a
+//! glance.
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::{http, install_location::InstallLocation, user_scope, BackendProcess};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SelfCheck {
+    pub name: &'static str,
+    pub ok: bool,
+    pub detail: String,
+}
+
+fn yes_no(ok: bool) -> String {
+    if ok { "yes" } else { "no" }.to_string()
+}
+
+#[tauri::command]
+pub async fn run_self_checks(app: AppHandle) -> Vec<SelfCheck> {
+    let bypass = http::loopback_bypasses_proxy().await;
+    let location = app.state::<InstallLocation>();
+    let reader_failure = app
+        .state::<BackendProcess>()
+        .reader_failure
+        .lock()
+        .unwrap()
+        .clone();
+    let shared = tauri::async_runtime::spawn_blocking(user_scope::multi_user_session)
+        .await
+        .ok()
+        .flatten();
+    vec![
+        SelfCheck {
+            name: "loopback requests bypass proxy",
+            ok: bypass,
+            detail: yes_no(bypass),
+        },
+        SelfCheck {
+            name: "running from a usable install location",
+            ok: !location.is_read_only(),
+            detail: format!("{:?} at {}", location.kind, location.path.display()),
+        },
+        SelfCheck {
+            name: "engine output read without errors",
+            ok: reader_failure.is_none(),
+            detail: reader_failure.unwrap_or_else(|| yes_no(true)),
+        },
+        // A fact for support rather than a problem: see `user_scope`.
+        SelfCheck {
+            name: "running in a multi-user session",
+            ok: true,
+            detail: match shared {
+                Some(reason) => format!("yes, {reason}"),
+                None => yes_no(false),
+            },
+        },
+    ]
+}
@@ -0,0 +1,96 @@
+//! Disk write speed, for performance diagnostics.
+//!
+//! Slow ALM processing is sometimes slow storage (a network-redirected
+//! profile, a failing drive).  `test_disk_write_speed` writes `TEST_SIZE`
+//! bytes in `CHUNK_SIZE` chunks to a temporary file in the data directory,
+//! where the backend keeps its sessions, syncs it to disk and deletes it
+//! again, and returns the rate in MB/s.
+//!
+//! It only runs with `ALMREADY_ALLOW_BENCH=1`, so a production install
+//! never runs a benchmark by accident.  Support bundles include the result
+//! when it is allowed.
+
+use std::{
+    io::Write as _,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use tauri::AppHandle;
+
+use crate::{
+    data_dir_access::DataDirAccess,
+    paths,
+    shell_log::{self, Level},
+};
+
+pub const ALLOW_ENV: &str = "ALMREADY_ALLOW_BENCH";
+
+const TEST_SIZE: usize = 10 * 1024 * 1024;
+const CHUNK_SIZE: usize = 64 * 1024;
+const FILE_NAME: &str = ".write-speed-test";
+
+pub fn allowed() -> bool {
+    std::env::var(ALLOW_ENV).is_ok_and(|v| v == "1")
+}
+
+fn mb_per_sec(bytes: usize, elapsed: Duration) -> f64 {
+    bytes as f64 / 1_000_000.0 / elapsed.as_secs_f64().max(1e-9)
+}
+
+fn write_test_file(path: &Path) -> std::io::Result<Duration> {
+    let chunk = vec![0xA5u8; CHUNK_SIZE];
+    let started = Instant::now();
+    let mut file = std::fs::File::create(path)?;
+    for _ in 0..TEST_SIZE / CHUNK_SIZE {
+        file.write_all(&chunk)?;
+    }
+    // Timed up to the data being on disk, not in the page cache.
+    file.sync_all()?;
+    Ok(started.elapsed())
+}
+
+/// Write and delete the test file in `dir`; MB/s.
+fn measure(dir: &Path) -> Result<f64, String> {
+    let path = dir.join(FILE_NAME);
+    let access = DataDirAccess::unleased();
+    let result = access.with(&[&path], || write_test_file(&path));
+    let _ = access.with(&[&path], || std::fs::remove_file(&path));
+    let elapsed = result.map_err(|e| format!("write {}: {e}", path.display()))?;
+    Ok(mb_per_sec(TEST_SIZE, elapsed))
+}
+
+/// Measure in the data directory.  Errors when benchmarks aren't allowed.
+pub async fn run(app: &AppHandle) -> Result<f64, String> {
+    if !allowed() {
+        return Err(format!("Set {ALLOW_ENV}=1 to run the disk benchmark."));
+    }
+    let dir = paths::data_dir(app)?;
+    let speed = tauri::async_runtime::spawn_blocking(move || measure(&dir))
+        .await
+        .map_err(|e| e.to_string())??;
+    shell_log::log(
+        Level::Info,
+        "disk-bench",
+        format!("data directory write speed {speed:.1} MB/s"),
+    );
+    Ok(speed)
+}
+
+#[tauri::command]
+pub async fn test_disk_write_speed(app: AppHandle) -> Result<f64, String> {
+    run(&app).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measures_and_cleans_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let speed = measure(dir.path()).unwrap();
+        assert!(speed > 0.0 && speed.is_finite());
+        assert!(!dir.path().join(FILE_NAME).exists());
+    }
+}
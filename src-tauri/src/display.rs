@@ -0,0 +1,29 @@
+//! Display scale factor of app windows.
+//!
+//! Moving a window to a monitor with a different DPI changes its scale
+//! factor.  The window that moved gets `dpi-changed { factor }` so React can
+//! swap in images for the new density, and `window.__SCREEN_DPI__` is
+//! updated for code that reads it synchronously.
+
+use serde::Serialize;
+use tauri::{Emitter, Manager, WebviewWindow, Window};
+
+#[derive(Debug, Clone, Serialize)]
+struct DpiChanged {
+    factor: f64,
+}
+
+/// Called from `on_window_event` on `ScaleFactorChanged`.  Sent only to the
+/// window whose monitor changed, not broadcast.
+pub fn on_scale_factor_changed(window: &Window, factor: f64) {
+    let _ = window.emit_to(window.label(), "dpi-changed", DpiChanged { factor });
+    if let Some(webview) = window.app_handle().get_webview_window(window.label()) {
+        let _ = webview.eval(format!("window.__SCREEN_DPI__ = {factor};"));
+    }
+}
+
+/// Scale factor of the calling window.
+#[tauri::command]
+pub fn get_window_scale_factor(window: WebviewWindow) -> Result<f64, String> {
+    window.scale_factor().map_err(|e| e.to_string())
+}
@@ -0,0 +1,274 @@
+//! Event audiences: which windows may receive which shell events.
+//!
+//! Every window built by `WindowFactory` is registered here with a set of
+//! capabilities.  The main window holds all of them; a basic pop-out holds
+//! none.  Every broadcast goes through `emit`, which takes an `Audience`,
+//! so no event can reach every window by default:
+//!
+//! * `AllWindows` – generic UI events (theme, compact mode, backend port,
+//!   lifecycle stages – see `lifecycle`).
+//! * `MainOnly` – lifecycle detail and anything else only the main
+//!   workspace should see.
+//! * `Capability(c)` – windows registered with `c`, e.g. a log viewer with
+//!   `BackendLogs`.  Backend log lines can contain file paths.
+//!
+//! Commands that hand out similar data check `has` for the calling window
+//! instead (see `shell_log`).
+//!
+//! Calling `Emitter::emit` directly is rejected by clippy (see
+//! `clippy.toml`), so a new event has to pick an audience here.
+//!
+//! `get_event_listener_count` shows, per window and event, how many
+//! listeners the pages have registered – a count that only grows points at
+//! a listener nobody removes.  Tauri keeps the frontend's listeners in its
+//! IPC layer without a way to count them, so pages report their own:
+//! `listen` in `src/lib/tauri.ts` (and the same helper in the static splash
+//! and log viewer pages) calls `track_listener` on every add and remove.  A
+//! window's counts go with it when it is destroyed.  The shell itself
+//! listens for no events.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Mutex,
+};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, EventTarget, Manager, State, WebviewWindow};
+
+use crate::{
+    shell_log::{self, Level},
+    window_factory::MAIN_LABEL,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Capability {
+    /// `backend-log-line`.
+    BackendLogs,
+    /// Sidecar lifecycle detail: crashes, restarts, exit codes.
+    Lifecycle,
+    /// Diagnostics and storage reports.
+    Diagnostics,
+    /// `shell_log` backfill and subscriptions.
+    ShellLogs,
+    /// Security-relevant settings, such as enabling lifecycle hooks.
+    Admin,
+}
+
+impl Capability {
+    pub const ALL: &'static [Capability] = &[
+        Capability::BackendLogs,
+        Capability::Lifecycle,
+        Capability::Diagnostics,
+        Capability::ShellLogs,
+        Capability::Admin,
+    ];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Audience {
+    AllWindows,
+    MainOnly,
+    Capability(Capability),
+}
+
+/// Managed state: capabilities of every open window, by label.
+#[derive(Default)]
+pub struct WindowCapabilities(Mutex<BTreeMap<String, BTreeSet<Capability>>>);
+
+impl WindowCapabilities {
+    pub fn register(&self, label: &str, capabilities: &[Capability]) {
+        self.0
+            .lock()
+            .unwrap()
+            .insert(label.to_string(), capabilities.iter().copied().collect());
+    }
+
+    pub fn unregister(&self, label: &str) {
+        self.0.lock().unwrap().remove(label);
+    }
+
+    pub fn has(&self, label: &str, capability: Capability) -> bool {
+        self.0
+            .lock()
+            .unwrap()
+            .get(label)
+            .is_some_and(|caps| caps.contains(&capability))
+    }
+
+    /// Whether the window `label` may receive an event for `audience`.
+    /// Unregistered windows only get `AllWindows` events.
+    fn admits(&self, label: &str, audience: Audience) -> bool {
+        match audience {
+            Audience::AllWindows => true,
+            Audience::MainOnly => label == MAIN_LABEL,
+            Audience::Capability(c) => self.has(label, c),
+        }
+    }
+}
+
+/// Emit `event` to the windows in `audience`.
+#[allow(clippy::disallowed_methods)]
+pub fn emit<S: Serialize + Clone>(app: &AppHandle, audience: Audience, event: &str, payload: S) {
+    let result = match audience {
+        Audience::AllWindows => app.emit(event, payload),
+        _ => {
+            let windows = app.state::<WindowCapabilities>();
+            app.emit_filter(event, payload, |target| match target {
+                EventTarget::AnyLabel { label }
+                | EventTarget::Window { label }
+                | EventTarget::Webview { label }
+                | EventTarget::WebviewWindow { label } => windows.admits(label, audience),
+                _ => false,
+            })
+        }
+    };
+    if let Err(e) = result {
+        shell_log::log(Level::Warn, "events", format!("emit {event} failed: {e}"));
+    }
+}
+
+// ── Listeners ───────────────────────────────────────────────────────────────
+
+/// Managed state: the listeners each window reported through
+/// `track_listener`.
+#[derive(Default)]
+pub struct ListenerCounts {
+    windows: Mutex<BTreeMap<String, BTreeMap<String, u32>>>,
+}
+
+impl ListenerCounts {
+    /// Count a listener `window` added or removed.  `false` for a removal
+    /// that was never added.
+    fn track(&self, window: &str, event: &str, added: bool) -> bool {
+        let mut windows = self.windows.lock().unwrap();
+        let counts = windows.entry(window.to_string()).or_default();
+        if added {
+            *counts.entry(event.to_string()).or_default() += 1;
+            return true;
+        }
+        let Some(count) = counts.get_mut(event) else {
+            return false;
+        };
+        *count -= 1;
+        if *count == 0 {
+            counts.remove(event);
+        }
+        true
+    }
+
+    /// Called from `on_window_event` on `Destroyed`: the page's listeners
+    /// went with it.
+    pub fn forget_window(&self, label: &str) {
+        self.windows.lock().unwrap().remove(label);
+    }
+}
+
+/// Per window label, the listeners its page reported per event name, for
+/// leak hunting from the developer console.
+#[tauri::command]
+pub fn get_event_listener_count(
+    listeners: State<'_, ListenerCounts>,
+) -> BTreeMap<String, BTreeMap<String, u32>> {
+    listeners.windows.lock().unwrap().clone()
+}
+
+/// Called by the calling window's `listen` wrapper whenever it adds
+/// (`added`) or removes a listener for `event`.
+#[tauri::command]
+pub fn track_listener(
+    window: WebviewWindow,
+    listeners: State<'_, ListenerCounts>,
+    event: String,
+    added: bool,
+) {
+    if !listeners.track(window.label(), &event, added) {
+        shell_log::log(
+            Level::Warn,
+            "events",
+            format!(
+                "{}: removed a {event} listener that was never counted",
+                window.label()
+            ),
+        );
+    }
+}
+
+/// Every open window's capabilities, for debugging.
+#[tauri::command]
+pub fn get_window_capabilities(
+    windows: State<'_, WindowCapabilities>,
+) -> BTreeMap<String, BTreeSet<Capability>> {
+    windows.0.lock().unwrap().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn windows() -> WindowCapabilities {
+        let windows = WindowCapabilities::default();
+        windows.register(MAIN_LABEL, Capability::ALL);
+        windows.register("report-1", &[]);
+        windows.register("logs", &[Capability::BackendLogs]);
+        windows
+    }
+
+    #[test]
+    fn basic_pop_out_never_receives_backend_logs() {
+        let windows = windows();
+        let logs = Audience::Capability(Capability::BackendLogs);
+        assert!(windows.admits(MAIN_LABEL, logs));
+        assert!(windows.admits("logs", logs));
+        assert!(!windows.admits("report-1", logs));
+    }
+
+    #[test]
+    fn main_only_and_broadcast() {
+        let windows = windows();
+        assert!(windows.admits(MAIN_LABEL, Audience::MainOnly));
+        assert!(!windows.admits("logs", Audience::MainOnly));
+        assert!(!windows.admits("report-1", Audience::MainOnly));
+        assert!(windows.admits("report-1", Audience::AllWindows));
+    }
+
+    #[test]
+    fn unregistered_and_closed_windows_get_broadcasts_only() {
+        let windows = windows();
+        windows.unregister("logs");
+        let logs = Audience::Capability(Capability::BackendLogs);
+        assert!(!windows.admits("logs", logs));
+        assert!(!windows.admits("unknown", logs));
+        assert!(windows.admits("unknown", Audience::AllWindows));
+    }
+
+    #[test]
+    fn window_listeners_are_counted_until_removed_or_the_window_closes() {
+        let listeners = ListenerCounts::default();
+        assert!(listeners.track(MAIN_LABEL, "backend-port-changed", true));
+        assert!(listeners.track(MAIN_LABEL, "backend-port-changed", true));
+        assert!(listeners.track("splash", "splash-status", true));
+        assert!(listeners.track(MAIN_LABEL, "backend-port-changed", false));
+        assert!(!listeners.track(MAIN_LABEL, "zoom-changed", false));
+        assert_eq!(
+            *listeners.windows.lock().unwrap(),
+            BTreeMap::from([
+                (
+                    MAIN_LABEL.to_string(),
+                    BTreeMap::from([("backend-port-changed".into(), 1)])
+                ),
+                (
+                    "splash".to_string(),
+                    BTreeMap::from([("splash-status".into(), 1)])
+                ),
+            ])
+        );
+
+        assert!(listeners.track("splash", "splash-status", false));
+        listeners.forget_window(MAIN_LABEL);
+        assert_eq!(
+            *listeners.windows.lock().unwrap(),
+            BTreeMap::from([("splash".to_string(), BTreeMap::new())])
+        );
+    }
+}
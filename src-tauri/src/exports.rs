@@ -0,0 +1,349 @@
+//! Backend exports saved straight to disk.
+//!
+//! A blob download through the webview holds the whole export in memory
+//! and, on Windows, shows no save dialog.  `save_export` instead asks for a
+//! destination with the native save dialog and streams the backend's
+//! response into it.  The main window gets `export-started { id, url, path }`
+//! once a destination is chosen, then `export-progress { id, received,
+//! total }` every `PROGRESS_STEP` bytes; `cancel_export(id)` stops a running
+//! export at once, also while it waits on the backend.  An export that sends
+//! nothing for `http::EXPORT_READ_TIMEOUT` fails.
+//!
+//! The response is written to `{path}.part` and only renamed over the
+//! chosen path once complete, so a failed, cancelled or backend-refused
+//! export never leaves a truncated file (or clobbers an existing one).
+//!
+//! Only paths on our own backend are accepted (`/api/…`), so the command
+//! can't be used as a generic downloader.
+
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::DialogExt;
+use tokio::{io::AsyncWriteExt as _, sync::Notify};
+
+use crate::{
+    events::{self, Audience},
+    http,
+    reveal::RevealRoots,
+    shell_log::{self, Level},
+    BackendProcess,
+};
+
+/// Bytes between two `export-progress` events.
+const PROGRESS_STEP: u64 = 256 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum ExportOutcome {
+    Saved { path: PathBuf, bytes: u64 },
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Started {
+    id: u32,
+    url: String,
+    path: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Progress {
+    id: u32,
+    received: u64,
+    /// From `Content-Length`, when the backend sent one.
+    total: Option<u64>,
+}
+
+/// Running exports, each with the signal that stops it.
+#[derive(Default)]
+pub struct Exports {
+    next_id: AtomicU32,
+    running: Mutex<HashMap<u32, Arc<Notify>>>,
+}
+
+impl Exports {
+    fn start(&self) -> (u32, Arc<Notify>) {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        let cancel = Arc::new(Notify::new());
+        self.running.lock().unwrap().insert(id, cancel.clone());
+        (id, cancel)
+    }
+
+    fn cancel(&self, id: u32) {
+        if let Some(cancel) = self.running.lock().unwrap().get(&id) {
+            // Kept as a permit if the export is between two awaits.
+            cancel.notify_one();
+        }
+    }
+
+    fn finish(&self, id: u32) {
+        self.running.lock().unwrap().remove(&id);
+    }
+}
+
+/// Accept only an absolute path on the backend's API, e.g.
+/// `/api/results/{id}/export?format=xlsx`.
+fn backend_path(url: &str) -> Result<&str, String> {
+    let relative = url.starts_with("/api/") && !url.contains("://") && !url.contains('\\');
+    if relative {
+        Ok(url)
+    } else {
+        Err(format!("{url:?} is not a path on the ALMReady backend"))
+    }
+}
+
+fn part_path(path: &Path) -> PathBuf {
+    let mut part = OsString::from(path.as_os_str());
+    part.push(".part");
+    PathBuf::from(part)
+}
+
+async fn choose_destination(app: &AppHandle, suggested_name: &str) -> Option<PathBuf> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .set_title("Save Export")
+        .set_file_name(suggested_name)
+        .save_file(move |path| {
+            let _ = tx.send(path);
+        });
+    rx.await.ok()??.into_path().ok()
+}
+
+/// Stream the response body to `part`, calling `progress` with the bytes
+/// received every `PROGRESS_STEP`.  Fails when nothing arrives for
+/// `read_timeout`.
+async fn write_body(
+    mut response: reqwest::Response,
+    part: &Path,
+    read_timeout: Duration,
+    mut progress: impl FnMut(u64),
+) -> Result<u64, String> {
+    let mut file = tokio::fs::File::create(part)
+        .await
+        .map_err(|e| format!("create {}: {e}", part.display()))?;
+    let mut received = 0;
+    let mut next_report = PROGRESS_STEP;
+
+    loop {
+        let chunk = tokio::time::timeout(read_timeout, response.chunk())
+            .await
+            .map_err(|_| format!("download: no data for {read_timeout:?}"))?
+            .map_err(|e| format!("download: {e}"))?;
+        let Some(chunk) = chunk else {
+            break;
+        };
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| format!("write {}: {e}", part.display()))?;
+        received += chunk.len() as u64;
+        if received >= next_report {
+            next_report = received + PROGRESS_STEP;
+            progress(received);
+        }
+    }
+    file.flush()
+        .await
+        .map_err(|e| format!("write {}: {e}", part.display()))?;
+    Ok(received)
+}
+
+/// Run `transfer` unless `cancel` is notified first.  `Ok(None)` when
+/// cancelled; `transfer` is dropped wherever it was waiting.
+async fn until_cancelled(
+    cancel: &Notify,
+    transfer: impl std::future::Future<Output = Result<u64, String>>,
+) -> Result<Option<u64>, String> {
+    tokio::select! {
+        biased;
+        () = cancel.notified() => Ok(None),
+        result = transfer => result.map(Some),
+    }
+}
+
+/// Save the backend export at `url` (a `/api/…` path) to a file the user
+/// picks, `suggested_name` preselected.
+#[tauri::command]
+pub async fn save_export(
+    app: AppHandle,
+    url: String,
+    suggested_name: String,
+) -> Result<ExportOutcome, String> {
+    let path = backend_path(&url)?;
+    let backend = app.state::<BackendProcess>();
+    let port = backend
+        .port
+        .get()
+        .ok_or("The calculation engine is not running.")?;
+
+    let Some(destination) = choose_destination(&app, &suggested_name).await else {
+        return Ok(ExportOutcome::Cancelled);
+    };
+    if let Some(dir) = destination.parent() {
+        app.state::<RevealRoots>().allow(dir);
+    }
+    let exports = app.state::<Exports>();
+    let (id, cancel) = exports.start();
+    let started = Started {
+        id,
+        url: url.clone(),
+        path: destination.clone(),
+    };
+    events::emit(&app, Audience::MainOnly, "export-started", started);
+
+    let part = part_path(&destination);
+    let token = backend.token();
+    let transfer = async {
+        let response = http::get_export(port, token.as_deref(), path).await?;
+        let total = response.content_length();
+        write_body(response, &part, http::EXPORT_READ_TIMEOUT, |received| {
+            let progress = Progress {
+                id,
+                received,
+                total,
+            };
+            events::emit(&app, Audience::MainOnly, "export-progress", progress);
+        })
+        .await
+    };
+    let result = until_cancelled(&cancel, transfer).await;
+    exports.finish(id);
+
+    let outcome = match result {
+        Ok(Some(bytes)) => tokio::fs::rename(&part, &destination)
+            .await
+            .map(|()| ExportOutcome::Saved {
+                path: destination.clone(),
+                bytes,
+            })
+            .map_err(|e| format!("save {}: {e}", destination.display())),
+        Ok(None) => Ok(ExportOutcome::Cancelled),
+        Err(e) => Err(e),
+    };
+    if !matches!(outcome, Ok(ExportOutcome::Saved { .. })) {
+        let _ = tokio::fs::remove_file(&part).await;
+    }
+    match &outcome {
+        Ok(ExportOutcome::Saved { bytes, .. }) => shell_log::log(
+            Level::Info,
+            "exports",
+            format!("saved {bytes} bytes from {path}"),
+        ),
+        Ok(ExportOutcome::Cancelled) => {}
+        Err(e) => shell_log::log(Level::Warn, "exports", format!("{path}: {e}")),
+    }
+    outcome
+}
+
+/// Stop the running export `id`; its partial file is removed.
+#[tauri::command]
+pub fn cancel_export(app: AppHandle, id: u32) {
+    app.state::<Exports>().cancel(id);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use tokio::{
+        io::{AsyncReadExt as _, AsyncWriteExt as _},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    /// A backend that sends an export's headers and first bytes, then
+    /// stalls.  Returns the response.
+    async fn stalled_export() -> reqwest::Response {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut sock, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 4096];
+            let _ = sock.read(&mut buf).await;
+            let head = "HTTP/1.1 200 OK\r\nContent-Length: 1000\r\n\r\nfirst";
+            sock.write_all(head.as_bytes()).await.unwrap();
+            tokio::time::sleep(Duration::from_secs(60)).await;
+        });
+        http::get_export(port, None, "/api/export").await.unwrap()
+    }
+
+    #[test]
+    fn only_backend_api_paths_are_accepted() {
+        assert!(backend_path("/api/results/abc/export?format=xlsx").is_ok());
+        for url in [
+            "http://example.com/api/x",
+            "//example.com/api/x",
+            "api/results/abc/export",
+            "/api/redirect?to=https://example.com",
+            "/assets/x.csv",
+            "\\\\server\\share",
+        ] {
+            assert!(backend_path(url).is_err(), "{url}");
+        }
+    }
+
+    #[test]
+    fn partial_file_sits_next_to_the_destination() {
+        assert_eq!(
+            part_path(Path::new("/tmp/EVE Report.xlsx")),
+            Path::new("/tmp/EVE Report.xlsx.part")
+        );
+    }
+
+    #[tokio::test]
+    async fn cancellation_is_per_export() {
+        let exports = Exports::default();
+        let ((a, cancel_a), (b, cancel_b)) = (exports.start(), exports.start());
+        assert_ne!(a, b);
+        exports.cancel(a);
+        let never = std::future::pending::<Result<u64, String>>();
+        assert_eq!(until_cancelled(&cancel_a, never).await, Ok(None));
+        let done = async { Ok::<u64, String>(5) };
+        assert_eq!(until_cancelled(&cancel_b, done).await, Ok(Some(5)));
+        exports.finish(a);
+        // Cancelling a finished export leaves nothing behind.
+        exports.cancel(a);
+        assert!(exports.running.lock().unwrap().get(&a).is_none());
+    }
+
+    #[tokio::test]
+    async fn cancelling_stops_an_export_waiting_for_data() {
+        let tmp = tempfile::tempdir().unwrap();
+        let part = tmp.path().join("EVE Report.xlsx.part");
+        let response = stalled_export().await;
+        let cancel = Arc::new(Notify::new());
+        let canceller = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            canceller.notify_one();
+        });
+
+        let started = Instant::now();
+        let transfer = write_body(response, &part, http::EXPORT_READ_TIMEOUT, |_| {});
+        assert_eq!(until_cancelled(&cancel, transfer).await, Ok(None));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn an_export_that_stops_sending_times_out() {
+        let tmp = tempfile::tempdir().unwrap();
+        let part = tmp.path().join("EVE Report.xlsx.part");
+        let response = stalled_export().await;
+        let error = write_body(response, &part, Duration::from_millis(100), |_| {})
+            .await
+            .unwrap_err();
+        assert!(error.contains("no data"), "{error}");
+    }
+}
@@ -0,0 +1,322 @@
+//! Typed sidecar startup failures and their categories.
+//!
+//! Every way `launch_sidecar` can fail is a `BackendError`.  `category`
+//! buckets each one (exhaustively – no wildcard arm, so a new variant can't
+//! be added without deciding its category), and the category decides what
+//! the failure dialog suggests and whether a retry is offered:
+//!
+//! * `InstallationDamaged` – missing or unexecutable files: reinstall.  The
+//!   only category an update can plausibly cause, see `update_related`.
+//! * `Environmental` – the data directory is unreachable (e.g. on a
+//!   disconnected network share): reconnect it.
+//! * `DataIncompatibility` – the backend refused the data directory: restore
+//!   a backup or install the version that wrote it.
+//! * `ResourceExhaustion` – disk full or out of memory: free some up.
+//! * `Unknown` – everything else.
+//!
+//! The backend reports incompatible data by exiting with `EX_DATAERR` (65)
+//! before printing its port.
+
+use std::{fmt, io};
+
+use serde::{Deserialize, Serialize};
+
+/// Exit code (sysexits `EX_DATAERR`) the backend uses for a data directory
+/// it cannot read.
+const EXIT_DATA_INCOMPATIBLE: i32 = 65;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BackendError {
+    /// The app resource directory could not be resolved.
+    ResourcesUnavailable(String),
+    /// The backend executable is not where the bundle puts it.
+    BinaryMissing(String),
+    /// A bundle file doesn't match `manifest.sha256` (see `bundle_check`).
+    BundleDamaged {
+        file: String,
+        problem: crate::bundle_check::Problem,
+    },
+    /// The executable exists but could not be started.
+    SpawnFailed {
+        message: String,
+        kind: io::ErrorKind,
+        raw_os_error: Option<i32>,
+    },
+    /// The data directory could not be resolved or created.
+    DataDirUnavailable(String),
+    /// Exited before reporting its port.
+    ExitedBeforePort,
+    /// Still running, but never reported its port.
+    PortTimeout,
+    /// stdout could not be read, even after a respawn.
+    ReaderFailed,
+    /// Reported a port but never answered the health check.
+    HealthTimeout,
+    /// Healthy, but these components never became ready (see `readiness`).
+    ComponentsNotReady(Vec<&'static str>),
+    /// The `pre-backend-start` hook failed and its policy aborts startup
+    /// (see `hooks`).
+    HookFailed(String),
+}
+
+impl BackendError {
+    pub fn spawn(message: String, e: &io::Error) -> Self {
+        if e.kind() == io::ErrorKind::NotFound {
+            return BackendError::BinaryMissing(message);
+        }
+        BackendError::SpawnFailed {
+            message,
+            kind: e.kind(),
+            raw_os_error: e.raw_os_error(),
+        }
+    }
+}
+
+impl fmt::Display for BackendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackendError::ResourcesUnavailable(e)
+            | BackendError::BinaryMissing(e)
+            | BackendError::DataDirUnavailable(e)
+            | BackendError::HookFailed(e)
+            | BackendError::SpawnFailed { message: e, .. } => f.write_str(e),
+            BackendError::BundleDamaged { file, problem } => write!(
+                f,
+                "The engine file {file} {}. Antivirus software may have quarantined it.",
+                problem.describe()
+            ),
+            BackendError::ExitedBeforePort => f.write_str(crate::port_reader::EXITED_BEFORE_PORT),
+            BackendError::PortTimeout => write!(
+                f,
+                "The engine did not report which port it is listening on within {} seconds.",
+                crate::timeouts::current().port.as_secs()
+            ),
+            BackendError::ReaderFailed => f.write_str(crate::port_reader::READER_FAILED),
+            BackendError::HealthTimeout => write!(
+                f,
+                "The engine did not respond to its health check within {} seconds.",
+                crate::timeouts::current().startup.as_secs()
+            ),
+            BackendError::ComponentsNotReady(names) => write!(
+                f,
+                "The engine did not finish starting within {} seconds ({} not ready).",
+                crate::timeouts::current().readiness.as_secs(),
+                names.join(", ")
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FailureCategory {
+    InstallationDamaged,
+    Environmental,
+    DataIncompatibility,
+    ResourceExhaustion,
+    Unknown,
+}
+
+/// Disk full or out of memory, by OS error code.
+fn is_exhaustion(raw_os_error: Option<i32>) -> bool {
+    #[cfg(unix)]
+    let codes = [libc::ENOSPC, libc::ENOMEM, libc::EAGAIN];
+    // ERROR_NOT_ENOUGH_MEMORY, ERROR_OUTOFMEMORY, ERROR_DISK_FULL.
+    #[cfg(windows)]
+    let codes = [8, 14, 112];
+    raw_os_error.is_some_and(|code| codes.contains(&code))
+}
+
+/// `exit_code` is the sidecar's, when it had started and was stopped.
+pub fn category(error: &BackendError, exit_code: Option<i32>) -> FailureCategory {
+    match error {
+        BackendError::ResourcesUnavailable(_)
+        | BackendError::BinaryMissing(_)
+        | BackendError::BundleDamaged { .. } => FailureCategory::InstallationDamaged,
+        BackendError::SpawnFailed {
+            kind, raw_os_error, ..
+        } => {
+            if is_exhaustion(*raw_os_error) {
+                FailureCategory::ResourceExhaustion
+            } else if *kind == io::ErrorKind::PermissionDenied {
+                FailureCategory::InstallationDamaged
+            } else {
+                FailureCategory::Unknown
+            }
+        }
+        BackendError::DataDirUnavailable(_) => FailureCategory::Environmental,
+        BackendError::ExitedBeforePort => match exit_code {
+            Some(EXIT_DATA_INCOMPATIBLE) => FailureCategory::DataIncompatibility,
+            _ => FailureCategory::Unknown,
+        },
+        // Seen with a corrupted onedir extraction, but just as likely a
+        // machine too busy to start it in time, so a retry stays on offer.
+        BackendError::PortTimeout => FailureCategory::Unknown,
+        BackendError::ReaderFailed => FailureCategory::Unknown,
+        BackendError::HealthTimeout => FailureCategory::Unknown,
+        BackendError::ComponentsNotReady(_) => FailureCategory::Unknown,
+        // Site-specific; the hook's own message says what went wrong.
+        BackendError::HookFailed(_) => FailureCategory::Unknown,
+    }
+}
+
+/// Whether a launch that failed with `error` is worth another attempt
+/// straight away: the spawn itself failed, or the engine exited before its
+/// port – both seen when antivirus kills a first start – but not a missing
+/// executable (dev mode) or data the engine refused.
+pub fn retries_launch(error: &BackendError, exit_code: Option<i32>) -> bool {
+    matches!(
+        error,
+        BackendError::SpawnFailed { .. } | BackendError::ExitedBeforePort
+    ) && category(error, exit_code) != FailureCategory::DataIncompatibility
+}
+
+impl FailureCategory {
+    /// Failures an update could have caused; the only ones for which
+    /// reinstalling (or rolling back) is worth suggesting.
+    pub fn update_related(self) -> bool {
+        self == FailureCategory::InstallationDamaged
+    }
+
+    /// Whether simply trying again can help.
+    pub fn retryable(self) -> bool {
+        !matches!(
+            self,
+            FailureCategory::InstallationDamaged | FailureCategory::DataIncompatibility
+        )
+    }
+
+    /// What the failure dialog suggests, if anything.
+    pub fn guidance(self) -> Option<&'static str> {
+        match self {
+            FailureCategory::InstallationDamaged => Some(
+                "The ALMReady installation appears to be damaged or incomplete. \
+                 Reinstalling ALMReady should fix this.",
+            ),
+            FailureCategory::Environmental => Some(
+                "ALMReady's data folder is not reachable. If it is on a network drive, \
+                 check that the drive is connected.",
+            ),
+            FailureCategory::DataIncompatibility => Some(
+                "Your saved data can't be read by this version of ALMReady. Restore a \
+                 backup of your data or install the version that created it.",
+            ),
+            FailureCategory::ResourceExhaustion => Some(
+                "Your computer is low on disk space or memory. Free some up and try \
+                 again.",
+            ),
+            FailureCategory::Unknown => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spawn_error(kind: io::ErrorKind, raw_os_error: Option<i32>) -> BackendError {
+        BackendError::SpawnFailed {
+            message: "spawn".into(),
+            kind,
+            raw_os_error,
+        }
+    }
+
+    fn bundle_damaged() -> BackendError {
+        BackendError::BundleDamaged {
+            file: "_internal/python3.dll".into(),
+            problem: crate::bundle_check::Problem::Missing,
+        }
+    }
+
+    #[test]
+    fn every_simulated_failure_has_a_pinned_category() {
+        use FailureCategory::*;
+
+        #[cfg(unix)]
+        let disk_full = libc::ENOSPC;
+        #[cfg(windows)]
+        let disk_full = 112;
+
+        let cases = [
+            (
+                BackendError::ResourcesUnavailable("x".into()),
+                InstallationDamaged,
+            ),
+            (
+                BackendError::spawn("x".into(), &io::Error::from(io::ErrorKind::NotFound)),
+                InstallationDamaged,
+            ),
+            (
+                spawn_error(io::ErrorKind::PermissionDenied, None),
+                InstallationDamaged,
+            ),
+            (
+                spawn_error(io::ErrorKind::Other, Some(disk_full)),
+                ResourceExhaustion,
+            ),
+            (spawn_error(io::ErrorKind::Other, None), Unknown),
+            (bundle_damaged(), InstallationDamaged),
+            (BackendError::DataDirUnavailable("x".into()), Environmental),
+            (BackendError::ExitedBeforePort, Unknown),
+            (BackendError::PortTimeout, Unknown),
+            (BackendError::ReaderFailed, Unknown),
+            (BackendError::HealthTimeout, Unknown),
+            (BackendError::ComponentsNotReady(vec!["pool"]), Unknown),
+            (BackendError::HookFailed("x".into()), Unknown),
+        ];
+        for (error, expected) in cases {
+            assert_eq!(category(&error, None), expected, "{error:?}");
+        }
+
+        let exited = BackendError::ExitedBeforePort;
+        assert_eq!(category(&exited, Some(65)), DataIncompatibility);
+        assert_eq!(category(&exited, Some(1)), Unknown);
+        // Only an exit before the port means the data was refused.
+        for error in [BackendError::ReaderFailed, BackendError::HealthTimeout] {
+            assert_eq!(category(&error, Some(65)), Unknown);
+        }
+    }
+
+    #[test]
+    fn only_fast_crashes_and_failed_spawns_are_retried() {
+        assert!(retries_launch(&BackendError::ExitedBeforePort, None));
+        assert!(retries_launch(&BackendError::ExitedBeforePort, Some(1)));
+        assert!(retries_launch(
+            &spawn_error(io::ErrorKind::PermissionDenied, None),
+            None
+        ));
+
+        assert!(!retries_launch(
+            &BackendError::ExitedBeforePort,
+            Some(EXIT_DATA_INCOMPATIBLE)
+        ));
+        for error in [
+            BackendError::spawn("x".into(), &io::Error::from(io::ErrorKind::NotFound)),
+            BackendError::PortTimeout,
+            BackendError::HealthTimeout,
+            BackendError::ComponentsNotReady(vec!["pool"]),
+            BackendError::ReaderFailed,
+            BackendError::HookFailed("x".into()),
+            bundle_damaged(),
+        ] {
+            assert!(!retries_launch(&error, None), "{error:?}");
+        }
+    }
+
+    #[test]
+    fn only_installation_damage_is_update_related() {
+        use FailureCategory::*;
+        for c in [
+            Environmental,
+            DataIncompatibility,
+            ResourceExhaustion,
+            Unknown,
+        ] {
+            assert!(!c.update_related());
+        }
+        assert!(InstallationDamaged.update_related());
+        assert!(!InstallationDamaged.retryable());
+        assert!(ResourceExhaustion.retryable());
+    }
+}
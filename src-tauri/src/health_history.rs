@@ -0,0 +1,112 @@
+//! Rolling record of the watchdog's health probes.
+//!
+//! A single "alive" flag hides intermittent degradation; the last
+//! `CAPACITY` probe results, with their response times, let the frontend
+//! draw a sparkline and spot a backend that is slowing down before it
+//! fails outright.
+
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+use tauri::State;
+
+/// Number of results kept.
+const CAPACITY: usize = 100;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthCheckResult {
+    /// Unix time in milliseconds.
+    pub at_ms: u64,
+    pub ok: bool,
+    pub response_ms: u64,
+    pub port: u16,
+}
+
+/// Managed state, updated after every watchdog probe.
+#[derive(Default)]
+pub struct SidecarHealthHistory {
+    entries: Mutex<VecDeque<HealthCheckResult>>,
+}
+
+impl SidecarHealthHistory {
+    pub fn record(&self, port: u16, ok: bool, response_time: Duration) {
+        let at_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        self.push(HealthCheckResult {
+            at_ms,
+            ok,
+            response_ms: response_time.as_millis() as u64,
+            port,
+        });
+    }
+
+    fn push(&self, result: HealthCheckResult) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() == CAPACITY {
+            entries.pop_front();
+        }
+        entries.push_back(result);
+    }
+
+    /// The most recent `n` results, oldest first.
+    fn last(&self, n: usize) -> Vec<HealthCheckResult> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .iter()
+            .skip(entries.len().saturating_sub(n))
+            .cloned()
+            .collect()
+    }
+}
+
+/// The last `n` health probes (at most 100), oldest first.
+#[tauri::command]
+pub fn get_health_history(
+    history: State<'_, SidecarHealthHistory>,
+    n: u32,
+) -> Vec<HealthCheckResult> {
+    history.last(n as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(at_ms: u64) -> HealthCheckResult {
+        HealthCheckResult {
+            at_ms,
+            ok: true,
+            response_ms: 3,
+            port: 8000,
+        }
+    }
+
+    #[test]
+    fn keeps_only_the_most_recent_entries() {
+        let history = SidecarHealthHistory::default();
+        for i in 0..(CAPACITY as u64 + 20) {
+            history.push(result(i));
+        }
+        let all = history.last(usize::MAX);
+        assert_eq!(all.len(), CAPACITY);
+        assert_eq!(all.first().unwrap().at_ms, 20);
+        assert_eq!(all.last().unwrap().at_ms, CAPACITY as u64 + 19);
+    }
+
+    #[test]
+    fn last_n_is_oldest_first() {
+        let history = SidecarHealthHistory::default();
+        for i in 0..5 {
+            history.push(result(i));
+        }
+        let at: Vec<u64> = history.last(3).iter().map(|r| r.at_ms).collect();
+        assert_eq!(at, [2, 3, 4]);
+        assert!(history.last(0).is_empty());
+    }
+}
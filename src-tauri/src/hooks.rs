@@ -0,0 +1,376 @@
+//! Site-specific scripts run right before the backend starts and right
+//! after it stops – mounting a network drive, refreshing a Kerberos ticket –
+//! without customer code in the app.
+//!
+//! Hooks live in `{data_dir}/hooks/`:
+//!
+//! * `pre-backend-start` – before each sidecar launch (startup, restart,
+//!   watchdog respawn);
+//! * `post-backend-stop` – after the shell stopped or reaped a sidecar,
+//!   including when the app quits.
+//!
+//! On Unix the file itself is run and must be executable.  On Windows the
+//! first of `{name}.exe`, `.cmd`, `.bat` and `.ps1` is used, a `.ps1`
+//! through `powershell -NoProfile -ExecutionPolicy Bypass -File`.
+//!
+//! They run only with `Settings::hooks.enabled`, which is off by default;
+//! turning it on from the app needs a window with `Capability::Admin` and a
+//! confirmed security warning.  Every hook gets `HOOK_TIMEOUT` and is killed
+//! after it.  The environment is cleared except for `PASS_THROUGH_ENV` and:
+//!
+//! * `ALMREADY_HOOK_EVENT` – `pre-backend-start` or `post-backend-stop`;
+//! * `ALMREADY_DATA_DIR` – the data directory;
+//! * `ALMREADY_BACKEND_PORT` – the sidecar's port, when known (post-stop
+//!   of a sidecar that reported one).
+//!
+//! stdout and stderr go to the shell log, and every run is recorded in the
+//! lifecycle history (`lifecycle::hook_ran`).  A failed pre-start hook
+//! aborts the launch only with `HookSettings::pre_backend_start_failure`
+//! set to `abort-startup`; a failed post-stop hook is only logged.
+//!
+//! Nothing here runs for a backend the shell doesn't launch: conformance
+//! runs spawn the sidecar directly and `ALMREADY_BACKEND_PORT` skips the
+//! launch altogether.
+
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State, WebviewWindow};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::{
+    events::{Capability, WindowCapabilities},
+    lifecycle, paths,
+    settings::SettingsStore,
+    shell_log::{self, Level},
+};
+
+const HOOK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Inherited variables; everything else is removed.
+const PASS_THROUGH_ENV: &[&str] = &[
+    "PATH",
+    "HOME",
+    "USER",
+    "LOGNAME",
+    "TMPDIR",
+    "KRB5CCNAME",
+    "SYSTEMROOT",
+    "WINDIR",
+    "USERPROFILE",
+    "USERNAME",
+    "USERDOMAIN",
+    "TEMP",
+    "TMP",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Hook {
+    PreBackendStart,
+    PostBackendStop,
+}
+
+impl Hook {
+    fn name(self) -> &'static str {
+        match self {
+            Hook::PreBackendStart => "pre-backend-start",
+            Hook::PostBackendStop => "post-backend-stop",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FailurePolicy {
+    /// Log the failure and start the backend anyway.
+    #[default]
+    Continue,
+    /// Fail the launch, as if the backend itself had not started.
+    AbortStartup,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct HookSettings {
+    pub enabled: bool,
+    /// What a non-zero exit (or timeout) of `pre-backend-start` does.
+    pub pre_backend_start_failure: FailurePolicy,
+}
+
+/// One hook execution, as kept in the lifecycle history.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookRun {
+    pub hook: Hook,
+    pub script: PathBuf,
+    /// Unix time in milliseconds.
+    pub started_at_ms: u64,
+    pub duration_ms: u64,
+    /// `None` when it timed out, could not be started or was killed by a
+    /// signal.
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    /// Why it could not be started.
+    pub error: Option<String>,
+}
+
+impl HookRun {
+    pub fn succeeded(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+
+    fn describe(&self) -> String {
+        let name = self.hook.name();
+        match (&self.error, self.timed_out, self.exit_code) {
+            (Some(e), _, _) => format!("{name} hook could not be started: {e}"),
+            (None, true, _) => format!("{name} hook timed out after {HOOK_TIMEOUT:?}"),
+            (None, false, Some(code)) => format!("{name} hook exited with code {code}"),
+            (None, false, None) => format!("{name} hook was killed"),
+        }
+    }
+}
+
+/// Whether `run` stops the backend from being launched.
+fn blocks_startup(policy: FailurePolicy, run: &HookRun) -> bool {
+    run.hook == Hook::PreBackendStart && policy == FailurePolicy::AbortStartup && !run.succeeded()
+}
+
+// ── Execution ───────────────────────────────────────────────────────────────
+
+#[cfg(not(windows))]
+fn find_script(dir: &Path, hook: Hook) -> Option<PathBuf> {
+    Some(dir.join(hook.name())).filter(|path| path.is_file())
+}
+
+#[cfg(windows)]
+fn find_script(dir: &Path, hook: Hook) -> Option<PathBuf> {
+    ["exe", "cmd", "bat", "ps1"]
+        .iter()
+        .map(|ext| dir.join(hook.name()).with_extension(ext))
+        .find(|path| path.is_file())
+}
+
+fn command_for(script: &Path) -> tokio::process::Command {
+    if cfg!(windows) && script.extension().is_some_and(|e| e == "ps1") {
+        let mut command = tokio::process::Command::new("powershell");
+        command.args(["-NoProfile", "-ExecutionPolicy", "Bypass", "-File"]);
+        command.arg(script);
+        return command;
+    }
+    tokio::process::Command::new(script)
+}
+
+/// Run `script` with exactly `env` on top of `PASS_THROUGH_ENV`, killing it
+/// after `timeout`.  Returns the run and its combined output.
+async fn execute(
+    hook: Hook,
+    script: &Path,
+    env: &[(&str, String)],
+    timeout: Duration,
+) -> (HookRun, String) {
+    let mut command = command_for(script);
+    command.env_clear();
+    for name in PASS_THROUGH_ENV {
+        if let Some(value) = std::env::var_os(name) {
+            command.env(name, value);
+        }
+    }
+    command
+        .envs(env.iter().cloned())
+        .stdin(std::process::Stdio::null())
+        .kill_on_drop(true);
+    if let Some(dir) = script.parent() {
+        command.current_dir(dir);
+    }
+
+    let started_at_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64);
+    let started = Instant::now();
+    let result = tokio::time::timeout(timeout, command.output()).await;
+    let mut run = HookRun {
+        hook,
+        script: script.to_path_buf(),
+        started_at_ms,
+        duration_ms: started.elapsed().as_millis() as u64,
+        exit_code: None,
+        timed_out: false,
+        error: None,
+    };
+    let mut output = String::new();
+    match result {
+        // Dropping the future killed the child.
+        Err(_) => run.timed_out = true,
+        Ok(Err(e)) => run.error = Some(e.to_string()),
+        Ok(Ok(out)) => {
+            run.exit_code = out.status.code();
+            output.push_str(&String::from_utf8_lossy(&out.stdout));
+            output.push_str(&String::from_utf8_lossy(&out.stderr));
+        }
+    }
+    (run, output)
+}
+
+/// Run `hook` if hooks are enabled and it exists.  `Err` means the launch
+/// must not go ahead (pre-start hook failed under `AbortStartup`).
+pub async fn run(app: &AppHandle, hook: Hook, port: Option<u16>) -> Result<(), String> {
+    let settings = app.state::<SettingsStore>().get().hooks;
+    if !settings.enabled {
+        return Ok(());
+    }
+    let data_dir = match paths::data_dir(app) {
+        Ok(dir) => dir,
+        Err(e) => {
+            shell_log::log(Level::Warn, "hooks", format!("no data directory: {e}"));
+            return Ok(());
+        }
+    };
+    let Some(script) = find_script(&data_dir.join("hooks"), hook) else {
+        return Ok(());
+    };
+
+    let mut env = vec![
+        ("ALMREADY_HOOK_EVENT", hook.name().to_string()),
+        ("ALMREADY_DATA_DIR", data_dir.display().to_string()),
+    ];
+    if let Some(port) = port {
+        env.push(("ALMREADY_BACKEND_PORT", port.to_string()));
+    }
+    shell_log::log(
+        Level::Info,
+        "hooks",
+        format!("running {}", script.display()),
+    );
+    let (run, output) = execute(hook, &script, &env, HOOK_TIMEOUT).await;
+    for line in output.lines().filter(|l| !l.trim().is_empty()) {
+        shell_log::log(Level::Info, "hooks", format!("{}: {line}", hook.name()));
+    }
+    if run.succeeded() {
+        shell_log::log(
+            Level::Info,
+            "hooks",
+            format!("{} hook finished in {} ms", hook.name(), run.duration_ms),
+        );
+    } else {
+        shell_log::log(Level::Warn, "hooks", run.describe());
+    }
+    let blocked = blocks_startup(settings.pre_backend_start_failure, &run);
+    let description = run.describe();
+    lifecycle::hook_ran(app, run);
+    if blocked {
+        return Err(format!("The engine was not started: the {description}."));
+    }
+    Ok(())
+}
+
+// ── Commands ────────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub fn get_hook_settings(store: State<'_, SettingsStore>) -> HookSettings {
+    store.get().hooks
+}
+
+async fn confirm_enable(app: &AppHandle) -> bool {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .message(
+            "Lifecycle hooks run the scripts in the data folder's \"hooks\" folder \
+             with your permissions every time the calculation engine starts or \
+             stops.  Only enable them if your IT department asked you to and you \
+             trust those scripts.",
+        )
+        .title("Enable lifecycle hooks?")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Enable Hooks".into(),
+            "Cancel".into(),
+        ))
+        .show(move |ok| {
+            let _ = tx.send(ok);
+        });
+    rx.await.unwrap_or(false)
+}
+
+/// Save the hook settings.  Only windows with `Capability::Admin` may, and
+/// turning hooks on needs the user to confirm a security warning first.
+#[tauri::command]
+pub async fn set_hook_settings(window: WebviewWindow, hooks: HookSettings) -> Result<(), String> {
+    let app = window.app_handle();
+    if !app
+        .state::<WindowCapabilities>()
+        .has(window.label(), Capability::Admin)
+    {
+        return Err("This window may not change the hook settings.".into());
+    }
+    let store = app.state::<SettingsStore>();
+    let mut settings = store.get();
+    if hooks.enabled && !settings.hooks.enabled && !confirm_enable(app).await {
+        return Err("Hooks were not enabled.".into());
+    }
+    settings.hooks = hooks;
+    store.replace(settings)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::*;
+
+    fn script(dir: &Path, name: &str, body: &str) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, format!("#!/bin/sh\n{body}\n")).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn a_hook_that_outlives_its_timeout_is_killed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = script(dir.path(), "slow", "exec sleep 5");
+        let started = Instant::now();
+        let (run, _) = execute(
+            Hook::PreBackendStart,
+            &path,
+            &[],
+            Duration::from_millis(200),
+        )
+        .await;
+        assert!(run.timed_out);
+        assert_eq!(run.exit_code, None);
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert!(blocks_startup(FailurePolicy::AbortStartup, &run));
+    }
+
+    #[tokio::test]
+    async fn only_the_abort_policy_lets_a_failed_pre_start_hook_block_startup() {
+        let dir = tempfile::tempdir().unwrap();
+        let failing = script(
+            dir.path(),
+            "failing",
+            "echo \"$ALMREADY_HOOK_EVENT $ALMREADY_HOOK_TEST_SECRET\"; exit 3",
+        );
+        std::env::set_var("ALMREADY_HOOK_TEST_SECRET", "leaked");
+        let env = [("ALMREADY_HOOK_EVENT", "pre-backend-start".to_string())];
+        let (run, output) = execute(Hook::PreBackendStart, &failing, &env, HOOK_TIMEOUT).await;
+        assert_eq!(run.exit_code, Some(3));
+        assert_eq!(output.trim(), "pre-backend-start");
+        assert!(blocks_startup(FailurePolicy::AbortStartup, &run));
+        assert!(!blocks_startup(FailurePolicy::Continue, &run));
+
+        let post_stop = HookRun {
+            hook: Hook::PostBackendStop,
+            ..run.clone()
+        };
+        assert!(!blocks_startup(FailurePolicy::AbortStartup, &post_stop));
+
+        let ok = script(dir.path(), "ok", "exit 0");
+        let (run, _) = execute(Hook::PreBackendStart, &ok, &[], HOOK_TIMEOUT).await;
+        assert!(run.succeeded());
+        assert!(!blocks_startup(FailurePolicy::AbortStartup, &run));
+    }
+}
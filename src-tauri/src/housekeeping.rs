@@ -0,0 +1,474 @@
+//! Periodic maintenance tasks on one shared scheduler.
+//!
+//! Tasks register with an interval, a priority and two flags:
+//!
+//! * `io_heavy` – at most one such task runs at a time, so maintenance never
+//!   competes with itself for the disk.
+//! * `requires_idle` – deferred while the frontend reports a calculation in
+//!   progress (`set_job_active`), and run once it has finished.  This is
+//!   the frontend's view only: the backend is not asked whether it is idle,
+//!   and its file leases do not exist yet (see `data_dir_access`).
+//!
+//! First runs are staggered (`STAGGER` apart, plus up to half of that in
+//! random jitter) so nothing fires together in the first seconds after
+//! launch, when the user starts working.  Later runs follow each task's
+//! own interval from when it last started.
+//!
+//! `Schedule` is the pure bookkeeping and takes `now` explicitly, so the
+//! tests drive it with a mocked clock; `spawn` runs it on the async runtime.
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+
+use crate::{
+    data_dir_access::DataDirAccess,
+    logpipe, paths, printer,
+    settings::SettingsStore,
+    shell_log::{self, Level},
+    storage,
+};
+
+/// Delay before the first task's first run.
+const INITIAL_DELAY: Duration = Duration::from_secs(5);
+
+/// Spacing between first runs of successive tasks.
+const STAGGER: Duration = Duration::from_secs(20);
+
+/// How often the driver looks for due tasks.
+const TICK: Duration = Duration::from_millis(500);
+
+/// Rotated backend logs older than this are deleted.
+const LOG_RETENTION: Duration = Duration::from_secs(14 * 24 * 3600);
+
+// ── Tasks ───────────────────────────────────────────────────────────────────
+
+type TaskFn = fn(&AppHandle) -> Result<(), String>;
+
+#[derive(Clone, Copy)]
+pub struct TaskSpec {
+    pub name: &'static str,
+    pub interval: Duration,
+    /// Higher runs first when several tasks are due at once.
+    pub priority: u8,
+    pub io_heavy: bool,
+    pub requires_idle: bool,
+}
+
+/// Every maintenance task the shell runs, in registration order.
+fn tasks() -> Vec<(TaskSpec, TaskFn)> {
+    vec![
+        (
+            TaskSpec {
+                name: "settings-reload",
+                interval: Duration::from_secs(2),
+                priority: 10,
+                io_heavy: false,
+                requires_idle: false,
+            },
+            |app| {
+                app.state::<SettingsStore>().reload_if_modified();
+                Ok(())
+            },
+        ),
+        (
+            TaskSpec {
+                name: "log-retention",
+                interval: Duration::from_secs(6 * 3600),
+                priority: 1,
+                io_heavy: true,
+                requires_idle: true,
+            },
+            |app| {
+                let data_dir = paths::data_dir(app)?;
+                prune_rotated_log(&logpipe::backend_log_path(&data_dir))
+            },
+        ),
+        (
+            TaskSpec {
+                name: "indexeddb-usage",
+                interval: Duration::from_secs(3600),
+                priority: 2,
+                io_heavy: true,
+                requires_idle: false,
+            },
+            |app| storage::check_usage(app).map(drop),
+        ),
+        (
+            TaskSpec {
+                name: "log-size-cap",
+                interval: Duration::from_secs(300),
+                priority: 3,
+                io_heavy: true,
+                requires_idle: false,
+            },
+            logpipe::cap_backend_log,
+        ),
+        (
+            TaskSpec {
+                name: "default-printer",
+                interval: printer::CHECK_INTERVAL,
+                priority: 4,
+                io_heavy: false,
+                requires_idle: false,
+            },
+            |app| printer::check(app).map(drop),
+        ),
+    ]
+}
+
+fn prune_rotated_log(log_path: &Path) -> Result<(), String> {
+    let rotated = logpipe::rotated_path(log_path);
+    let Ok(modified) = std::fs::metadata(&rotated).and_then(|m| m.modified()) else {
+        return Ok(());
+    };
+    if modified.elapsed().unwrap_or_default() < LOG_RETENTION {
+        return Ok(());
+    }
+    DataDirAccess::unleased()
+        .with(&[&rotated], || std::fs::remove_file(&rotated))
+        .map_err(|e| format!("remove {rotated:?}: {e}"))
+}
+
+// ── Schedule ────────────────────────────────────────────────────────────────
+
+struct Slot {
+    spec: TaskSpec,
+    next_run: Instant,
+    running_since: Option<Instant>,
+    last_run: Option<Instant>,
+    last_duration: Option<Duration>,
+    last_error: Option<String>,
+}
+
+pub struct Schedule {
+    slots: Vec<Slot>,
+}
+
+impl Schedule {
+    /// `jitter` is called once per task and should return a value below
+    /// `STAGGER / 2`, which keeps first runs at least half a stagger apart.
+    pub fn new(
+        specs: impl IntoIterator<Item = TaskSpec>,
+        now: Instant,
+        mut jitter: impl FnMut() -> Duration,
+    ) -> Self {
+        let slots = specs
+            .into_iter()
+            .enumerate()
+            .map(|(i, spec)| Slot {
+                spec,
+                next_run: now + INITIAL_DELAY + STAGGER * i as u32 + jitter().min(STAGGER / 2),
+                running_since: None,
+                last_run: None,
+                last_duration: None,
+                last_error: None,
+            })
+            .collect();
+        Schedule { slots }
+    }
+
+    /// The task to start now, if any: due, not already running, not held
+    /// back by another I/O-heavy task or by `job_active`.  Highest priority
+    /// wins, then whichever has been due longest.
+    pub fn next_due(&self, now: Instant, job_active: bool) -> Option<usize> {
+        let io_busy = self
+            .slots
+            .iter()
+            .any(|s| s.spec.io_heavy && s.running_since.is_some());
+        self.slots
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.running_since.is_none() && s.next_run <= now)
+            .filter(|(_, s)| !(s.spec.io_heavy && io_busy))
+            .filter(|(_, s)| !(s.spec.requires_idle && job_active))
+            .max_by(|(_, a), (_, b)| {
+                a.spec
+                    .priority
+                    .cmp(&b.spec.priority)
+                    .then(b.next_run.cmp(&a.next_run))
+            })
+            .map(|(i, _)| i)
+    }
+
+    pub fn start(&mut self, task: usize, now: Instant) {
+        self.slots[task].running_since = Some(now);
+    }
+
+    pub fn finish(&mut self, task: usize, now: Instant, result: Result<(), String>) {
+        let slot = &mut self.slots[task];
+        let started = slot.running_since.take().unwrap_or(now);
+        slot.last_run = Some(started);
+        slot.last_duration = Some(now.saturating_duration_since(started));
+        slot.last_error = result.err();
+        slot.next_run = (started + slot.spec.interval).max(now);
+    }
+}
+
+// ── Runtime ─────────────────────────────────────────────────────────────────
+
+/// Managed state: the live schedule and the job-active flag.
+pub struct Housekeeping {
+    schedule: Mutex<Schedule>,
+    job_active: AtomicBool,
+}
+
+/// Uniform in `[0, max)`, seeded per process by std's random hasher keys.
+fn random_below(max: Duration) -> Duration {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(0);
+    let nanos = max.as_nanos().max(1) as u64;
+    Duration::from_nanos(hasher.finish() % nanos)
+}
+
+/// Register every task and start the driver loop.
+pub fn spawn(app: AppHandle) {
+    let registry = tasks();
+    let schedule = Schedule::new(
+        registry.iter().map(|(spec, _)| *spec),
+        Instant::now(),
+        || random_below(STAGGER / 2),
+    );
+    app.manage(Housekeeping {
+        schedule: Mutex::new(schedule),
+        job_active: AtomicBool::new(false),
+    });
+
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<Housekeeping>();
+        loop {
+            tokio::time::sleep(TICK).await;
+            let now = Instant::now();
+            let job_active = state.job_active.load(Ordering::Relaxed);
+            let mut schedule = state.schedule.lock().unwrap();
+            while let Some(task) = schedule.next_due(now, job_active) {
+                schedule.start(task, now);
+                let (spec, run) = registry[task];
+                let app = app.clone();
+                tauri::async_runtime::spawn_blocking(move || {
+                    let result = run(&app);
+                    if let Err(e) = &result {
+                        shell_log::log(
+                            Level::Warn,
+                            "housekeeping",
+                            format!("{} failed: {e}", spec.name),
+                        );
+                    }
+                    let state = app.state::<Housekeeping>();
+                    state
+                        .schedule
+                        .lock()
+                        .unwrap()
+                        .finish(task, Instant::now(), result);
+                });
+            }
+        }
+    });
+}
+
+// ── Commands ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskInfo {
+    pub name: &'static str,
+    pub interval_secs: u64,
+    pub priority: u8,
+    pub io_heavy: bool,
+    pub requires_idle: bool,
+    pub running: bool,
+    /// Unix time in milliseconds.
+    pub last_run_ms: Option<u64>,
+    pub last_duration_ms: Option<u64>,
+    pub last_error: Option<String>,
+    /// Unix time in milliseconds; may be in the past for a deferred task.
+    pub next_run_ms: u64,
+}
+
+/// Scheduler state for the diagnostics page.
+#[tauri::command]
+pub fn list_housekeeping_tasks(state: State<'_, Housekeeping>) -> Vec<TaskInfo> {
+    let (now, wall) = (Instant::now(), SystemTime::now());
+    let unix_ms = |t: Instant| {
+        let at = if t <= now {
+            wall - (now - t)
+        } else {
+            wall + (t - now)
+        };
+        at.duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0)
+    };
+
+    let schedule = state.schedule.lock().unwrap();
+    schedule
+        .slots
+        .iter()
+        .map(|slot| {
+            let spec = slot.spec;
+            TaskInfo {
+                name: spec.name,
+                interval_secs: spec.interval.as_secs(),
+                priority: spec.priority,
+                io_heavy: spec.io_heavy,
+                requires_idle: spec.requires_idle,
+                running: slot.running_since.is_some(),
+                last_run_ms: slot.last_run.map(unix_ms),
+                last_duration_ms: slot.last_duration.map(|d| d.as_millis() as u64),
+                last_error: slot.last_error.clone(),
+                next_run_ms: unix_ms(slot.next_run),
+            }
+        })
+        .collect()
+}
+
+/// The frontend reports when a calculation starts and finishes; tasks that
+/// require an idle backend wait until it has.
+#[tauri::command]
+pub fn set_job_active(state: State<'_, Housekeeping>, active: bool) {
+    state.job_active.store(active, Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(name: &'static str, priority: u8, io_heavy: bool, requires_idle: bool) -> TaskSpec {
+        TaskSpec {
+            name,
+            interval: Duration::from_secs(60),
+            priority,
+            io_heavy,
+            requires_idle,
+        }
+    }
+
+    /// Start and finish every task that is due at `now`, returning names in
+    /// the order they started.
+    fn run_due(schedule: &mut Schedule, now: Instant, job_active: bool) -> Vec<&'static str> {
+        let mut started = Vec::new();
+        while let Some(i) = schedule.next_due(now, job_active) {
+            schedule.start(i, now);
+            started.push(i);
+        }
+        for &i in &started {
+            schedule.finish(i, now, Ok(()));
+        }
+        started
+            .into_iter()
+            .map(|i| schedule.slots[i].spec.name)
+            .collect()
+    }
+
+    #[test]
+    fn first_runs_are_staggered() {
+        let t0 = Instant::now();
+        let specs = [
+            spec("a", 1, false, false),
+            spec("b", 1, false, false),
+            spec("c", 1, false, false),
+        ];
+        // Worst case for spacing: the earlier task gets maximum jitter and
+        // the later one none.
+        let mut jitters = [STAGGER, Duration::ZERO, Duration::ZERO].into_iter();
+        let mut schedule = Schedule::new(specs, t0, || jitters.next().unwrap());
+
+        assert!(run_due(
+            &mut schedule,
+            t0 + INITIAL_DELAY - Duration::from_secs(1),
+            false
+        )
+        .is_empty());
+
+        let mut first_runs = Vec::new();
+        let mut t = t0;
+        while first_runs.len() < 3 {
+            for name in run_due(&mut schedule, t, false) {
+                first_runs.push((name, t));
+            }
+            t += Duration::from_millis(100);
+        }
+        assert_eq!(
+            first_runs.iter().map(|(n, _)| *n).collect::<Vec<_>>(),
+            ["a", "b", "c"]
+        );
+        for pair in first_runs.windows(2) {
+            assert!(pair[1].1 - pair[0].1 >= STAGGER / 2);
+        }
+    }
+
+    #[test]
+    fn only_one_io_heavy_task_runs_at_a_time() {
+        let t0 = Instant::now();
+        let specs = [
+            spec("backup", 1, true, false),
+            spec("prune", 2, true, false),
+            spec("light", 0, false, false),
+        ];
+        let mut schedule = Schedule::new(specs, t0, || Duration::ZERO);
+        let now = t0 + INITIAL_DELAY + STAGGER * 3;
+
+        // The higher-priority heavy task starts; the other heavy one waits,
+        // the light one may run alongside.
+        let first = schedule.next_due(now, false).unwrap();
+        assert_eq!(schedule.slots[first].spec.name, "prune");
+        schedule.start(first, now);
+        let second = schedule.next_due(now, false).unwrap();
+        assert_eq!(schedule.slots[second].spec.name, "light");
+        schedule.start(second, now);
+        assert_eq!(schedule.next_due(now, false), None);
+
+        let later = now + Duration::from_secs(3);
+        schedule.finish(first, later, Ok(()));
+        let third = schedule.next_due(later, false).unwrap();
+        assert_eq!(schedule.slots[third].spec.name, "backup");
+    }
+
+    #[test]
+    fn idle_tasks_are_deferred_while_a_job_is_active() {
+        let t0 = Instant::now();
+        let specs = [
+            spec("retention", 1, true, true),
+            spec("reload", 1, false, false),
+        ];
+        let mut schedule = Schedule::new(specs, t0, || Duration::ZERO);
+        let now = t0 + INITIAL_DELAY + STAGGER * 2;
+
+        assert_eq!(run_due(&mut schedule, now, true), ["reload"]);
+        let later = now + Duration::from_secs(600);
+        assert_eq!(run_due(&mut schedule, later, true), ["reload"]);
+        assert_eq!(run_due(&mut schedule, later, false), ["retention"]);
+    }
+
+    #[test]
+    fn next_run_follows_interval_from_start() {
+        let t0 = Instant::now();
+        let mut schedule = Schedule::new([spec("a", 1, false, false)], t0, || Duration::ZERO);
+        let start = t0 + INITIAL_DELAY;
+        schedule.start(0, start);
+        schedule.finish(0, start + Duration::from_secs(5), Err("disk full".into()));
+
+        assert_eq!(
+            schedule.slots[0].last_duration,
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(schedule.slots[0].last_error.as_deref(), Some("disk full"));
+        assert_eq!(
+            schedule.next_due(start + Duration::from_secs(59), false),
+            None
+        );
+        assert_eq!(
+            schedule.next_due(start + Duration::from_secs(60), false),
+            Some(0)
+        );
+    }
+}
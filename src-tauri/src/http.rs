@@ -0,0 +1,508 @@
+//! Loopback HTTP helpers for talking to the sidecar.
+//!
+//! Every request the shell makes to the backend goes to 127.0.0.1 on the
+//! port the sidecar printed at startup.  URLs are built here with
+//! `reqwest::Url` rather than `format!` so paths, query strings and
+//! percent-encoding are handled in one place.
+//!
+//! All requests go through the single `client()` below, which never uses a
+//! proxy.  On machines with a mandatory system proxy some HTTP stacks route
+//! even 127.0.0.1 through it and the proxy rejects the request, so the
+//! health check fails while the backend is fine.  We don't rely on NO_PROXY
+//! or the library's loopback heuristics: proxies are switched off outright,
+//! which is safe because this client only ever talks to the local sidecar.
+//!
+//! Requests carry the sidecar's session token (see `session_token`) when it
+//! has one; a backend started outside the app has none.
+
+use std::{sync::OnceLock, time::Duration};
+
+use reqwest::{Client, ClientBuilder, RequestBuilder, StatusCode, Url};
+use serde::Deserialize;
+use tokio::{
+    io::{AsyncReadExt as _, AsyncWriteExt as _},
+    net::TcpListener,
+};
+
+/// Loopback address the sidecar binds to (see sidecar_main.py).
+pub(crate) const BACKEND_HOST: &str = "127.0.0.1";
+
+/// Build `http://127.0.0.1:{port}{path}` for a backend endpoint.
+///
+/// `path` may be empty, start with or without a leading slash, and carry a
+/// query string.  Anything that would resolve to a different host or port
+/// (an absolute URL, a `//host` reference) is rejected so callers can pass
+/// frontend-supplied paths through without turning the shell into a generic
+/// HTTP client.
+pub(crate) fn port_to_url(port: u16, path: &str) -> Result<Url, String> {
+    let base = Url::parse(&format!("http://{BACKEND_HOST}:{port}/"))
+        .map_err(|e| format!("backend base url for port {port}: {e}"))?;
+
+    // Strip leading slashes so `join` always resolves relative to the root
+    // and `//evil.example/x` can't be read as a network-path reference.
+    let url = base
+        .join(path.trim_start_matches('/'))
+        .map_err(|e| format!("backend url for {path:?}: {e}"))?;
+
+    if url.host_str() != Some(BACKEND_HOST) || url.port_or_known_default() != Some(port) {
+        return Err(format!("{path:?} does not resolve to the local backend"));
+    }
+
+    Ok(url)
+}
+
+/// The loopback client's settings on top of `builder`.  `no_proxy` also
+/// drops any proxy set on `builder` before it.
+fn configure(builder: ClientBuilder) -> ClientBuilder {
+    builder.no_proxy()
+}
+
+fn build_client() -> reqwest::Result<Client> {
+    configure(Client::builder()).build()
+}
+
+/// Shared HTTP client for every request to the sidecar (health checks,
+/// watchdog, forwarding, exports).
+pub(crate) fn client() -> &'static Client {
+    static CLIENT: OnceLock<Client> = OnceLock::new();
+    CLIENT.get_or_init(|| build_client().expect("failed to build loopback HTTP client"))
+}
+
+/// Attach the session token, if the backend was given one.
+fn with_token(request: RequestBuilder, token: Option<&str>) -> RequestBuilder {
+    match token {
+        Some(token) => request.header(crate::session_token::HEADER, token),
+        None => request,
+    }
+}
+
+/// Per-probe timeout; a hung request must not eat the whole startup budget.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Deserialize)]
+struct HealthBody {
+    status: String,
+}
+
+/// One `GET /api/health` probe.  Only a 200 with `{"status":"ok"}` counts;
+/// connection errors, 5xx and malformed bodies all mean "not ready yet".
+pub(crate) async fn probe_health(port: u16, token: Option<&str>) -> bool {
+    let Ok(url) = port_to_url(port, "/api/health") else {
+        return false;
+    };
+    let request = with_token(client().get(url), token);
+    let Ok(resp) = request.timeout(PROBE_TIMEOUT).send().await else {
+        return false;
+    };
+    if resp.status() != StatusCode::OK {
+        return false;
+    }
+    matches!(resp.json::<HealthBody>().await, Ok(body) if body.status == "ok")
+}
+
+/// One `GET /api/health`, reported as is for a support bundle: the status
+/// line and body, or why there was no reply.
+pub(crate) async fn health_report(port: u16, token: Option<&str>) -> String {
+    let url = match port_to_url(port, "/api/health") {
+        Ok(url) => url,
+        Err(e) => return e,
+    };
+    let request = with_token(client().get(url), token);
+    match request.timeout(PROBE_TIMEOUT).send().await {
+        Ok(resp) => {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_else(|e| format!("<{e}>"));
+            format!("{status}\n{body}")
+        }
+        Err(e) => format!("no reply: {e}"),
+    }
+}
+
+/// Result of one `GET /api/ready/{component}` probe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ComponentProbe {
+    Ready,
+    NotReady,
+    /// 404: the backend does not know this component.
+    NotReported,
+}
+
+/// One readiness probe for a backend component (see `readiness`).  Only a
+/// 200 is ready; a 404 is reported apart for the conformance suite.
+pub(crate) async fn probe_component(
+    port: u16,
+    token: Option<&str>,
+    component: &str,
+) -> ComponentProbe {
+    let Ok(url) = port_to_url(port, &format!("/api/ready/{component}")) else {
+        return ComponentProbe::NotReady;
+    };
+    let request = with_token(client().get(url), token);
+    match request.timeout(PROBE_TIMEOUT).send().await {
+        Ok(resp) if resp.status() == StatusCode::OK => ComponentProbe::Ready,
+        Ok(resp) if resp.status() == StatusCode::NOT_FOUND => ComponentProbe::NotReported,
+        _ => ComponentProbe::NotReady,
+    }
+}
+
+/// Probe up to `attempts` times, `interval` apart.
+pub(crate) async fn wait_for_health(
+    port: u16,
+    token: Option<&str>,
+    attempts: u32,
+    interval: Duration,
+) -> bool {
+    for attempt in 0..attempts {
+        if probe_health(port, token).await {
+            return true;
+        }
+        if attempt + 1 < attempts {
+            tokio::time::sleep(interval).await;
+        }
+    }
+    false
+}
+
+/// Ask the sidecar to exit gracefully via `POST /api/shutdown`.  Returns
+/// true if it accepted (202); the process then exits on its own once the
+/// lifespan shutdown has run.
+pub(crate) async fn request_shutdown(port: u16, token: Option<&str>) -> bool {
+    let Ok(url) = port_to_url(port, "/api/shutdown") else {
+        return false;
+    };
+    with_token(client().post(url), token)
+        // Required by the backend: it keeps the endpoint out of reach of
+        // simple cross-origin POSTs from web pages.
+        .header("X-ALMReady-Shell", "shutdown")
+        .timeout(PROBE_TIMEOUT)
+        .send()
+        .await
+        .is_ok_and(|resp| resp.status() == StatusCode::ACCEPTED)
+}
+
+#[derive(Deserialize)]
+struct VersionBody {
+    version: String,
+}
+
+/// The running backend's version from `GET /api/version`; `None` when the
+/// backend predates the endpoint (404).
+pub(crate) async fn fetch_version(
+    port: u16,
+    token: Option<&str>,
+) -> Result<Option<String>, String> {
+    let url = port_to_url(port, "/api/version")?;
+    let resp = with_token(client().get(url), token)
+        .timeout(PROBE_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("GET /api/version: {e}"))?;
+    match resp.status() {
+        StatusCode::OK => {}
+        StatusCode::NOT_FOUND => return Ok(None),
+        status => return Err(format!("GET /api/version: {status}")),
+    }
+    resp.json::<VersionBody>()
+        .await
+        .map(|body| Some(body.version))
+        .map_err(|e| format!("GET /api/version: {e}"))
+}
+
+/// Open a session file in the backend via `POST /api/session/open` and
+/// return its JSON reply.
+pub(crate) async fn open_session(
+    port: u16,
+    token: Option<&str>,
+    path: &std::path::Path,
+) -> Result<serde_json::Value, String> {
+    let url = port_to_url(port, "/api/session/open")?;
+    let resp = with_token(client().post(url), token)
+        .json(&serde_json::json!({ "path": path }))
+        .send()
+        .await
+        .map_err(|e| format!("POST /api/session/open: {e}"))?;
+    let status = resp.status();
+    if !status.is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("POST /api/session/open: {status} {}", body.trim()));
+    }
+    resp.json()
+        .await
+        .map_err(|e| format!("POST /api/session/open: {e}"))
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    detail: String,
+}
+
+/// How long an export may go without sending anything: before its headers,
+/// or between two chunks of its body.
+pub(crate) const EXPORT_READ_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// `GET path` for an export, returned unread so the body can be streamed.
+/// A non-2xx reply becomes the backend's FastAPI `detail` message, or the
+/// raw body.
+pub(crate) async fn get_export(
+    port: u16,
+    token: Option<&str>,
+    path: &str,
+) -> Result<reqwest::Response, String> {
+    let url = port_to_url(port, path)?;
+    let send = with_token(client().get(url), token).send();
+    let resp = tokio::time::timeout(EXPORT_READ_TIMEOUT, send)
+        .await
+        .map_err(|_| format!("GET {path}: no reply within {EXPORT_READ_TIMEOUT:?}"))?
+        .map_err(|e| format!("GET {path}: {e}"))?;
+    let status = resp.status();
+    if status.is_success() {
+        return Ok(resp);
+    }
+    let body = resp.text().await.unwrap_or_default();
+    Err(match serde_json::from_str::<ErrorBody>(&body) {
+        Ok(error) => error.detail,
+        Err(_) => format!("GET {path}: {status} {}", body.trim()),
+    })
+}
+
+/// Ask the sidecar to keep off `paths` (relative to the data directory) for
+/// `duration` via `POST /api/files/lease`.  False when it refused, or has no
+/// such endpoint – which, for now, the bundled backend never has; callers
+/// then fall back to retrying (see `data_dir_access`).
+pub(crate) async fn request_lease(
+    port: u16,
+    token: Option<&str>,
+    paths: &[String],
+    duration: Duration,
+) -> bool {
+    let Ok(url) = port_to_url(port, "/api/files/lease") else {
+        return false;
+    };
+    with_token(client().post(url), token)
+        .json(&serde_json::json!({
+            "paths": paths,
+            "duration": duration.as_secs_f64(),
+        }))
+        .timeout(PROBE_TIMEOUT)
+        .send()
+        .await
+        .is_ok_and(|resp| resp.status().is_success())
+}
+
+/// Send one request from `client` to a throwaway loopback listener and
+/// report whether it arrived directly.  If a proxy were in the path the
+/// listener would never see the connection.
+async fn reaches_loopback_directly(client: &Client) -> bool {
+    let Ok(listener) = TcpListener::bind((BACKEND_HOST, 0)).await else {
+        return false;
+    };
+    let Ok(port) = listener.local_addr().map(|a| a.port()) else {
+        return false;
+    };
+    let Ok(url) = port_to_url(port, "/") else {
+        return false;
+    };
+
+    let server = async {
+        let (mut sock, _) = listener.accept().await?;
+        let mut buf = [0u8; 1024];
+        let _ = sock.read(&mut buf).await?;
+        sock.write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n")
+            .await
+    };
+    let request = client.get(url).send();
+
+    match tokio::time::timeout(Duration::from_secs(3), async {
+        tokio::join!(server, request)
+    })
+    .await
+    {
+        Ok((Ok(()), Ok(resp))) => resp.status() == StatusCode::NO_CONTENT,
+        _ => false,
+    }
+}
+
+/// Diagnostics self-check: does the shared client bypass proxies for
+/// loopback traffic?
+pub(crate) async fn loopback_bypasses_proxy() -> bool {
+    reaches_loopback_directly(client()).await
+}
+
+/// A loopback HTTP server for tests.
+#[cfg(test)]
+pub(crate) mod test_server {
+    use tokio::{
+        io::{AsyncReadExt as _, AsyncWriteExt as _},
+        net::TcpListener,
+    };
+
+    use super::BACKEND_HOST;
+
+    /// Serve requests until the test ends, each answered with the status
+    /// line and JSON body `reply` returns for it.  `reply` gets the
+    /// request's head (request line and headers), lower-cased, and how many
+    /// requests came before it.  Returns the port.
+    pub(crate) async fn serve<F>(mut reply: F) -> u16
+    where
+        F: FnMut(&str, usize) -> (&'static str, String) + Send + 'static,
+    {
+        let listener = TcpListener::bind((BACKEND_HOST, 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            for served in 0.. {
+                let Ok((mut sock, _)) = listener.accept().await else {
+                    return;
+                };
+                let mut buf = [0u8; 4096];
+                let n = sock.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]).to_ascii_lowercase();
+                let head = request.split("\r\n\r\n").next().unwrap_or_default();
+                let (status, body) = reply(head, served);
+                let response = format!(
+                    "HTTP/1.1 {status}\r\nContent-Type: application/json\r\n\
+                     Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                let _ = sock.write_all(response.as_bytes()).await;
+            }
+        });
+        port
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{test_server::serve, *};
+
+    #[test]
+    fn empty_path_is_root() {
+        let url = port_to_url(8000, "").unwrap();
+        assert_eq!(url.as_str(), "http://127.0.0.1:8000/");
+    }
+
+    #[test]
+    fn leading_slash_is_optional() {
+        let with = port_to_url(51234, "/api/health").unwrap();
+        let without = port_to_url(51234, "api/health").unwrap();
+        assert_eq!(with.as_str(), "http://127.0.0.1:51234/api/health");
+        assert_eq!(with, without);
+    }
+
+    #[test]
+    fn query_parameters_are_preserved() {
+        let url = port_to_url(8000, "/api/results/abc/export?format=xlsx&sheet=EVE").unwrap();
+        assert_eq!(url.path(), "/api/results/abc/export");
+        assert_eq!(url.query(), Some("format=xlsx&sheet=EVE"));
+    }
+
+    #[test]
+    fn default_http_port_still_matches() {
+        // Url normalises :80 away for http; the host/port check must not trip on it.
+        let url = port_to_url(80, "/api/health").unwrap();
+        assert_eq!(url.as_str(), "http://127.0.0.1/api/health");
+    }
+
+    #[test]
+    fn paths_never_leave_the_backend() {
+        assert!(port_to_url(8000, "http://example.com/api/health").is_err());
+        assert!(port_to_url(8000, "//example.com/api/health")
+            .is_ok_and(|u| u.host_str() == Some(BACKEND_HOST)));
+    }
+
+    #[tokio::test]
+    async fn loopback_requests_ignore_any_proxy() {
+        // A "proxy" that records whether anyone connected to it, set the
+        // way a system proxy would be, without touching the process env.
+        let proxy = TcpListener::bind((BACKEND_HOST, 0)).await.unwrap();
+        let proxy_url = format!("http://{}", proxy.local_addr().unwrap());
+        let builder = Client::builder().proxy(reqwest::Proxy::all(&proxy_url).unwrap());
+
+        let client = configure(builder).build().unwrap();
+        assert!(reaches_loopback_directly(&client).await);
+
+        let proxied = tokio::time::timeout(Duration::from_millis(200), proxy.accept()).await;
+        assert!(proxied.is_err(), "request was routed through the proxy");
+    }
+
+    /// Answers with 503 for the first `failures` requests, then with
+    /// `ok_body`.
+    async fn flaky_health_server(failures: usize, ok_body: &'static str) -> u16 {
+        serve(move |_, served| {
+            if served < failures {
+                ("503 Service Unavailable", r#"{"detail":"starting"}"#.into())
+            } else {
+                ("200 OK", ok_body.into())
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn health_is_not_ready_while_backend_returns_503() {
+        let port = flaky_health_server(2, r#"{"status":"ok"}"#).await;
+        assert!(!probe_health(port, None).await);
+        assert!(!probe_health(port, None).await);
+        assert!(probe_health(port, None).await);
+    }
+
+    #[tokio::test]
+    async fn wait_for_health_succeeds_after_503s() {
+        let port = flaky_health_server(3, r#"{"status":"ok"}"#).await;
+        assert!(wait_for_health(port, None, 5, Duration::from_millis(10)).await);
+    }
+
+    #[tokio::test]
+    async fn wait_for_health_gives_up_after_attempts() {
+        let port = flaky_health_server(10, r#"{"status":"ok"}"#).await;
+        assert!(!wait_for_health(port, None, 3, Duration::from_millis(10)).await);
+    }
+
+    #[tokio::test]
+    async fn malformed_or_unexpected_body_is_not_ready() {
+        let port = flaky_health_server(0, "not json").await;
+        assert!(!probe_health(port, None).await);
+        let port = flaky_health_server(0, r#"{"status":"degraded"}"#).await;
+        assert!(!probe_health(port, None).await);
+    }
+
+    #[tokio::test]
+    async fn health_probe_sends_the_session_token() {
+        // Answers 200 only when the request carries `X-ALMReady-Token: s3cret`.
+        let port = serve(|head, _| {
+            if head.lines().any(|line| line == "x-almready-token: s3cret") {
+                ("200 OK", r#"{"status":"ok"}"#.into())
+            } else {
+                ("401 Unauthorized", r#"{"detail":"missing token"}"#.into())
+            }
+        })
+        .await;
+        assert!(!probe_health(port, None).await);
+        assert!(!probe_health(port, Some("wrong")).await);
+        assert!(probe_health(port, Some("s3cret")).await);
+    }
+
+    /// Answers every request with `status` and `body`.
+    async fn fixed_server(status: &'static str, body: &'static str) -> u16 {
+        serve(move |_, _| (status, body.into())).await
+    }
+
+    #[tokio::test]
+    async fn a_backend_without_the_version_endpoint_reports_none() {
+        let port = fixed_server("200 OK", r#"{"version":"1.4.0"}"#).await;
+        assert_eq!(fetch_version(port, None).await, Ok(Some("1.4.0".into())));
+        let port = fixed_server("404 Not Found", r#"{"detail":"Not Found"}"#).await;
+        assert_eq!(fetch_version(port, None).await, Ok(None));
+        let port = fixed_server("503 Service Unavailable", r#"{"detail":"version unknown"}"#).await;
+        assert!(fetch_version(port, None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn connection_refused_is_not_ready() {
+        // Bind and drop to get a port nothing is listening on.
+        let port = {
+            let l = TcpListener::bind((BACKEND_HOST, 0)).await.unwrap();
+            l.local_addr().unwrap().port()
+        };
+        assert!(!probe_health(port, None).await);
+    }
+}
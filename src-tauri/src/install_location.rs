@@ -0,0 +1,280 @@
+//! Detecting a read-only or translocated install.
+//!
+//! Users sometimes run the app straight from the mounted DMG, from a
+//! quarantined download (which macOS "translocates" to a random read-only
+//! mount), or on Windows from a read-only network share.  Resources still
+//! resolve, but anything that assumes the install directory is writable
+//! fails with confusing errors.
+//!
+//! The shell already keeps every writable path (settings, logs, sessions,
+//! webview data) under the per-user app data directories, never next to the
+//! executable, so a read-only install only needs to be detected, reported in
+//! diagnostics and – on macOS – fixed by offering to copy the app into
+//! /Applications and relaunch it from there.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LocationKind {
+    Writable,
+    /// macOS App Translocation: a quarantined app launched from where it
+    /// was downloaded runs from a randomised read-only mount.
+    Translocated,
+    /// macOS: running from a mounted disk image under /Volumes.
+    DiskImage,
+    /// The directory holding the app could not be written to.
+    ReadOnly,
+    /// Installed in a location only administrators can write to (Program
+    /// Files, /Applications, /usr, /opt, an AppImage mount).  Read-only by
+    /// design, so not a problem.
+    SystemInstall,
+}
+
+/// Managed state: where the running app lives, detected once at startup.
+#[derive(Debug, Clone, Serialize)]
+pub struct InstallLocation {
+    pub kind: LocationKind,
+    /// The `.app` bundle on macOS, the executable's directory elsewhere.
+    pub path: PathBuf,
+}
+
+impl InstallLocation {
+    /// True for the unexpected read-only cases that need user attention.
+    pub fn is_read_only(&self) -> bool {
+        matches!(
+            self.kind,
+            LocationKind::Translocated | LocationKind::DiskImage | LocationKind::ReadOnly
+        )
+    }
+
+    pub fn detect() -> Self {
+        let exe = std::env::current_exe().unwrap_or_default();
+        let path = bundle_path(&exe)
+            .or_else(|| exe.parent().map(Path::to_path_buf))
+            .unwrap_or_default();
+
+        let kind = if path.to_string_lossy().contains("/AppTranslocation/") {
+            LocationKind::Translocated
+        } else if cfg!(target_os = "macos") && path.starts_with("/Volumes") {
+            LocationKind::DiskImage
+        } else if can_write_next_to(&path) {
+            LocationKind::Writable
+        } else if is_system_location(&path) {
+            LocationKind::SystemInstall
+        } else {
+            LocationKind::ReadOnly
+        };
+        InstallLocation { kind, path }
+    }
+}
+
+/// The enclosing `.app` bundle, if any.
+fn bundle_path(exe: &Path) -> Option<PathBuf> {
+    exe.ancestors()
+        .find(|p| p.extension().is_some_and(|ext| ext == "app"))
+        .map(Path::to_path_buf)
+}
+
+fn is_system_location(path: &Path) -> bool {
+    if std::env::var_os("APPIMAGE").is_some() {
+        return true;
+    }
+    let mut roots: Vec<PathBuf> = ["ProgramFiles", "ProgramFiles(x86)", "ProgramW6432"]
+        .iter()
+        .filter_map(std::env::var_os)
+        .map(PathBuf::from)
+        .collect();
+    if cfg!(target_os = "macos") {
+        roots.push("/Applications".into());
+    } else if cfg!(unix) {
+        roots.extend(["/usr".into(), "/opt".into()]);
+    }
+    roots.iter().any(|root| path.starts_with(root))
+}
+
+/// Probe by creating and removing a file in the directory that holds the
+/// install (the bundle's parent on macOS, the exe directory elsewhere).
+fn can_write_next_to(path: &Path) -> bool {
+    let dir = if path.extension().is_some_and(|ext| ext == "app") {
+        path.parent().unwrap_or(path)
+    } else {
+        path
+    };
+    let probe = dir.join(format!(".almready-write-probe-{}", std::process::id()));
+    let ok = std::fs::write(&probe, b"").is_ok();
+    let _ = std::fs::remove_file(&probe);
+    ok
+}
+
+// ── Move to /Applications (macOS) ───────────────────────────────────────────
+
+#[cfg(target_os = "macos")]
+pub use macos::offer_move_to_applications;
+
+#[cfg(not(target_os = "macos"))]
+pub async fn offer_move_to_applications(_app: tauri::AppHandle) {}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use std::{
+        path::{Path, PathBuf},
+        process::Command,
+    };
+
+    use tauri::{AppHandle, Manager};
+    use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+    use super::InstallLocation;
+    use crate::{
+        settings::SettingsStore,
+        shell_log::{self, Level},
+    };
+
+    async fn ask(app: &AppHandle, message: String, ok: &str, cancel: &str) -> bool {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        app.dialog()
+            .message(message)
+            .title("ALMReady")
+            .kind(MessageDialogKind::Info)
+            .buttons(MessageDialogButtons::OkCancelCustom(
+                ok.into(),
+                cancel.into(),
+            ))
+            .show(move |ok| {
+                let _ = tx.send(ok);
+            });
+        rx.await.unwrap_or(false)
+    }
+
+    /// `CFBundleShortVersionString` from a bundle's Info.plist (XML form).
+    fn bundle_version(bundle: &Path) -> Option<String> {
+        let plist = std::fs::read_to_string(bundle.join("Contents/Info.plist")).ok()?;
+        let after_key = plist
+            .split("<key>CFBundleShortVersionString</key>")
+            .nth(1)?;
+        let value = after_key
+            .split("<string>")
+            .nth(1)?
+            .split("</string>")
+            .next()?;
+        Some(value.trim().to_string())
+    }
+
+    /// Copy `source` to `target` with ditto (keeps signatures and xattrs but
+    /// drops the quarantine flag so the copy isn't translocated again), staging
+    /// it next to the target so an existing copy is only replaced once the new
+    /// one is complete.
+    fn install_copy(source: &Path, target: &Path) -> Result<(), String> {
+        let staged = target.with_extension("app.new");
+        let old = target.with_extension("app.old");
+        let _ = std::fs::remove_dir_all(&staged);
+
+        let status = Command::new("ditto")
+            .arg("--noqtn")
+            .arg(source)
+            .arg(&staged)
+            .status()
+            .map_err(|e| format!("ditto: {e}"))?;
+        if !status.success() {
+            let _ = std::fs::remove_dir_all(&staged);
+            return Err(format!("ditto exited with {status}"));
+        }
+
+        if target.exists() {
+            let _ = std::fs::remove_dir_all(&old);
+            std::fs::rename(target, &old).map_err(|e| format!("move aside {target:?}: {e}"))?;
+        }
+        if let Err(e) = std::fs::rename(&staged, target) {
+            let _ = std::fs::rename(&old, target);
+            return Err(format!("install {target:?}: {e}"));
+        }
+        let _ = std::fs::remove_dir_all(&old);
+        Ok(())
+    }
+
+    /// Offer, once, to copy the app into /Applications and relaunch it from
+    /// there.  Does nothing for a writable install or if already offered.
+    pub async fn offer_move_to_applications(app: AppHandle) {
+        let location = app.state::<InstallLocation>().inner().clone();
+        if !location.is_read_only() || !location.path.extension().is_some_and(|e| e == "app") {
+            return;
+        }
+        let store = app.state::<SettingsStore>();
+        let mut settings = store.get();
+        if settings.move_to_applications_offered {
+            return;
+        }
+        settings.move_to_applications_offered = true;
+        let _ = store.replace(settings);
+
+        let accepted = ask(
+            &app,
+            "ALMReady is running from a disk image or download location where it \
+             can't work reliably.\n\nCopy it to your Applications folder and \
+             relaunch from there?"
+                .into(),
+            "Copy and Relaunch",
+            "Not Now",
+        )
+        .await;
+        if !accepted {
+            return;
+        }
+
+        let target =
+            PathBuf::from("/Applications").join(location.path.file_name().unwrap_or_default());
+        if target.exists() {
+            let existing = bundle_version(&target).unwrap_or_else(|| "unknown version".into());
+            let current = app.package_info().version.to_string();
+            let replace = ask(
+                &app,
+                format!(
+                    "A copy of ALMReady ({existing}) is already in Applications.\n\n\
+                     Replace it with this version ({current})?"
+                ),
+                "Replace",
+                "Cancel",
+            )
+            .await;
+            if !replace {
+                return;
+            }
+        }
+
+        let copied = tauri::async_runtime::spawn_blocking({
+            let (source, target) = (location.path.clone(), target.clone());
+            move || install_copy(&source, &target)
+        })
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r);
+        if let Err(e) = copied {
+            shell_log::log(
+                Level::Error,
+                "install-location",
+                format!("copy to Applications failed: {e}"),
+            );
+            app.dialog()
+                .message(format!(
+                    "ALMReady could not be copied to Applications.\n\n{e}"
+                ))
+                .title("ALMReady")
+                .kind(MessageDialogKind::Error)
+                .show(|_| {});
+            return;
+        }
+
+        // -n: a new instance, since this one is still running while it exits.
+        match Command::new("open").arg("-n").arg(&target).spawn() {
+            Ok(_) => app.exit(0),
+            Err(e) => shell_log::log(
+                Level::Error,
+                "install-location",
+                format!("relaunch from {target:?} failed: {e}"),
+            ),
+        }
+    }
+}
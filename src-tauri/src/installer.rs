@@ -0,0 +1,138 @@
+//! Waiting for an update installer that is still running.
+//!
+//! On Windows users sometimes start the new installer and, while it is
+//! still replacing files, launch the app from the old shortcut.  The
+//! half-replaced bundle then spawns a mismatched or incomplete backend.
+//!
+//! Before the sidecar is spawned the shell probes two named mutexes: the
+//! one our installer holds while it runs (`INSTALLER_MUTEX`) and
+//! `Global\_MSIExecute`, which Windows Installer holds during any MSI
+//! installation.  While either exists the splash says so and the probe is
+//! repeated every `POLL_INTERVAL`; every `PROMPT_INTERVAL` the user is asked
+//! whether to keep waiting or quit.  Other platforms never wait.
+
+use std::time::{Duration, Instant};
+
+use tauri::AppHandle;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::{
+    shell_log::{self, Level},
+    splash,
+};
+
+/// Named mutex created by the ALMReady installer for as long as it runs.
+#[cfg_attr(not(windows), allow(dead_code))]
+const INSTALLER_MUTEX: &str = "Global\\ALMReadyInstaller";
+#[cfg_attr(not(windows), allow(dead_code))]
+const MSI_MUTEX: &str = "Global\\_MSIExecute";
+
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+const PROMPT_INTERVAL: Duration = Duration::from_secs(60);
+
+pub const WAITING_STATUS: &str = "An update is being installed – please wait…";
+
+#[cfg(windows)]
+fn mutex_exists(name: &str) -> bool {
+    use windows::{
+        core::HSTRING,
+        Win32::{
+            Foundation::{CloseHandle, ERROR_ACCESS_DENIED},
+            System::Threading::{OpenMutexW, SYNCHRONIZATION_SYNCHRONIZE},
+        },
+    };
+
+    // SAFETY: the name is a valid null-terminated wide string and the
+    // returned handle is closed right away.
+    match unsafe {
+        OpenMutexW(
+            SYNCHRONIZATION_SYNCHRONIZE,
+            false.into(),
+            &HSTRING::from(name),
+        )
+    } {
+        Ok(handle) => {
+            let _ = unsafe { CloseHandle(handle) };
+            true
+        }
+        // Held by an elevated installer we may not open.
+        Err(e) => e.code() == ERROR_ACCESS_DENIED.to_hresult(),
+    }
+}
+
+/// The installer currently running, if any.
+fn running_installer() -> Option<&'static str> {
+    #[cfg(windows)]
+    {
+        if mutex_exists(INSTALLER_MUTEX) {
+            return Some("ALMReady installer");
+        }
+        if mutex_exists(MSI_MUTEX) {
+            return Some("Windows Installer");
+        }
+    }
+    None
+}
+
+async fn keep_waiting(app: &AppHandle) -> bool {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .message(
+            "An update is still being installed.  ALMReady will start as soon \
+             as the installer has finished.",
+        )
+        .title("ALMReady")
+        .kind(MessageDialogKind::Info)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Keep Waiting".into(),
+            "Quit".into(),
+        ))
+        .show(move |ok| {
+            let _ = tx.send(ok);
+        });
+    rx.await.unwrap_or(false)
+}
+
+/// Wait until no installer is running.  Returns false if the user chose to
+/// quit instead.
+pub async fn wait_until_finished(app: &AppHandle) -> bool {
+    let Some(installer) = running_installer() else {
+        return true;
+    };
+    let started = Instant::now();
+    shell_log::log(
+        Level::Info,
+        "installer",
+        format!("{installer} is running, waiting before starting the engine"),
+    );
+    splash::show(app);
+    splash::set_status(app, WAITING_STATUS);
+
+    let mut next_prompt = started + PROMPT_INTERVAL;
+    while running_installer().is_some() {
+        if Instant::now() >= next_prompt {
+            if !keep_waiting(app).await {
+                shell_log::log(
+                    Level::Info,
+                    "installer",
+                    format!(
+                        "quit while waiting for {installer} ({}s)",
+                        started.elapsed().as_secs()
+                    ),
+                );
+                return false;
+            }
+            next_prompt = Instant::now() + PROMPT_INTERVAL;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+    shell_log::log(
+        Level::Info,
+        "installer",
+        format!(
+            "{installer} finished after {}s, starting the engine",
+            started.elapsed().as_secs()
+        ),
+    );
+    true
+}
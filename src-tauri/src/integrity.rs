@@ -0,0 +1,107 @@
+//! SHA-256 check for files downloaded into the data directory, e.g. a
+//! session template from the marketplace before it is imported.
+//!
+//! Only files under the data directory (what the sidecar gets as
+//! `ALMREADY_DATA_DIR`) can be checked, after resolving symlinks, so the
+//! command can't be used to probe the rest of the disk.
+
+use std::{
+    fs::File,
+    io::{BufReader, Read},
+    path::{Path, PathBuf},
+};
+
+use sha2::{Digest, Sha256};
+use tauri::AppHandle;
+
+use crate::{data_dir_access::DataDirAccess, paths};
+
+const CHUNK: usize = 64 * 1024;
+
+/// Hex SHA-256 of everything `reader` yields.
+pub(crate) fn sha256_hex(mut reader: impl Read) -> std::io::Result<String> {
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; CHUNK];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// `expected` as lower-case hex, if it is a SHA-256 digest.
+fn normalize_digest(expected: &str) -> Result<String, String> {
+    let expected = expected.trim().to_ascii_lowercase();
+    if expected.len() != 64 || !expected.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err("The expected checksum must be 64 hexadecimal characters.".into());
+    }
+    Ok(expected)
+}
+
+/// `path` resolved, if it lies under `data_dir`.
+fn resolve_in(data_dir: &Path, path: &str) -> Result<PathBuf, String> {
+    let data_dir = data_dir
+        .canonicalize()
+        .map_err(|e| format!("data directory: {e}"))?;
+    let resolved = Path::new(path)
+        .canonicalize()
+        .map_err(|e| format!("{path}: {e}"))?;
+    if !resolved.starts_with(&data_dir) {
+        return Err(format!("{path} is outside the data directory."));
+    }
+    Ok(resolved)
+}
+
+/// Whether the SHA-256 of the file at `path` is `expected_sha256` (hex,
+/// either case).  Errors if the file is outside the data directory or
+/// can't be read.
+#[tauri::command]
+pub async fn verify_file_integrity(
+    app: AppHandle,
+    path: String,
+    expected_sha256: String,
+) -> Result<bool, String> {
+    let expected = normalize_digest(&expected_sha256)?;
+    let resolved = resolve_in(&paths::data_dir(&app)?, &path)?;
+    let actual = tauri::async_runtime::spawn_blocking(move || {
+        DataDirAccess::unleased()
+            .with(&[&resolved], || {
+                File::open(&resolved)
+                    .and_then(|file| sha256_hex(BufReader::with_capacity(CHUNK, file)))
+            })
+            .map_err(|e| format!("{}: {e}", resolved.display()))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    Ok(actual == expected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ABC: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    #[test]
+    fn digest_is_compared_in_any_case() {
+        assert_eq!(sha256_hex(&b"abc"[..]).unwrap(), ABC);
+        assert_eq!(normalize_digest(&ABC.to_uppercase()), Ok(ABC.to_string()));
+        assert!(normalize_digest("abc").is_err());
+        assert!(normalize_digest(&ABC.replace('a', "g")).is_err());
+    }
+
+    #[test]
+    fn only_files_in_the_data_dir_resolve() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let file = dir.join("template.json");
+        std::fs::write(&file, "{}").unwrap();
+
+        assert!(resolve_in(dir, file.to_str().unwrap()).is_ok());
+        let escape = dir.join("..").join("..");
+        assert!(resolve_in(dir, escape.to_str().unwrap()).is_err());
+    }
+}
@@ -0,0 +1,393 @@
+//! Activity journal and "while you were away" summaries.
+//!
+//! Long projection runs are often left going overnight.  The journal keeps a
+//! short record of notable shell events (engine restarts, crashes, finished
+//! runs, autosaves) and, while the main window is unfocused, collects them
+//! into an away period.  When the window regains focus after at least
+//! `AWAY_THRESHOLD` the period is turned into an `AwaySummary`, emitted to
+//! the webview as `away-summary` and – if anything noteworthy happened –
+//! shown as a single native notification.
+//!
+//! Until then the events waiting are counted in the main window's title
+//! ("ALMReady – 3 new events", visible in the taskbar while the window is
+//! in the background) and in the tray tooltip (see `tray`), for a window
+//! hidden to the tray.
+
+use std::{
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::{
+    alerts::{self, Alert},
+    events::{self, Audience},
+    shell_log::{self, Level},
+    tray,
+    window_factory::MAIN_LABEL,
+};
+
+/// Minimum unfocused time before a period counts as "away".
+const AWAY_THRESHOLD: Duration = Duration::from_secs(30 * 60);
+
+/// Maximum number of events kept in a single away summary.
+const MAX_SUMMARY_EVENTS: usize = 50;
+
+// ── Entries ─────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ActivityKind {
+    RunCompleted,
+    RunFailed,
+    ExportCompleted,
+    Autosave,
+    EngineRestart,
+    EngineCrash,
+    /// A single failed health probe that recovered on its own.  Recorded for
+    /// completeness but never surfaced in away summaries.
+    HealthFlap,
+}
+
+/// The kinds the frontend may report through `record_activity`.  Engine
+/// restarts, crashes and health flaps are only ever recorded by the shell,
+/// so a page can't put them in the away summary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FrontendActivity {
+    RunCompleted,
+    RunFailed,
+    ExportCompleted,
+    Autosave,
+}
+
+impl From<FrontendActivity> for ActivityKind {
+    fn from(kind: FrontendActivity) -> Self {
+        match kind {
+            FrontendActivity::RunCompleted => ActivityKind::RunCompleted,
+            FrontendActivity::RunFailed => ActivityKind::RunFailed,
+            FrontendActivity::ExportCompleted => ActivityKind::ExportCompleted,
+            FrontendActivity::Autosave => ActivityKind::Autosave,
+        }
+    }
+}
+
+impl ActivityKind {
+    fn is_noteworthy(self) -> bool {
+        !matches!(self, ActivityKind::HealthFlap)
+    }
+
+    /// Singular / plural phrase used in the notification body.
+    fn phrase(self) -> (&'static str, &'static str) {
+        match self {
+            ActivityKind::RunCompleted => ("run completed", "runs completed"),
+            ActivityKind::RunFailed => ("run failed", "runs failed"),
+            ActivityKind::ExportCompleted => ("export completed", "exports completed"),
+            ActivityKind::Autosave => ("autosave", "autosaves"),
+            ActivityKind::EngineRestart => ("engine restart", "engine restarts"),
+            ActivityKind::EngineCrash => ("engine crash", "engine crashes"),
+            ActivityKind::HealthFlap => ("health flap", "health flaps"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ActivityEntry {
+    pub kind: ActivityKind,
+    pub message: String,
+    /// Unix time in milliseconds.
+    pub at_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AwaySummary {
+    pub away_since_ms: u64,
+    pub returned_at_ms: u64,
+    pub events: Vec<ActivityEntry>,
+    /// Events that happened but didn't fit under `MAX_SUMMARY_EVENTS`.
+    pub omitted: u32,
+}
+
+impl AwaySummary {
+    /// "While you were away: 2 runs completed, 1 engine restart".
+    fn notification_body(&self) -> String {
+        let mut counts: Vec<(ActivityKind, u32)> = Vec::new();
+        for event in &self.events {
+            match counts.iter_mut().find(|(k, _)| *k == event.kind) {
+                Some((_, n)) => *n += 1,
+                None => counts.push((event.kind, 1)),
+            }
+        }
+        let parts: Vec<String> = counts
+            .into_iter()
+            .map(|(kind, n)| {
+                let (one, many) = kind.phrase();
+                format!("{n} {}", if n == 1 { one } else { many })
+            })
+            .collect();
+        format!("While you were away: {}", parts.join(", "))
+    }
+}
+
+// ── State ───────────────────────────────────────────────────────────────────
+
+struct AwayPeriod {
+    since: SystemTime,
+    events: Vec<ActivityEntry>,
+    omitted: u32,
+}
+
+#[derive(Default)]
+struct JournalInner {
+    away: Option<AwayPeriod>,
+    last_summary: Option<AwaySummary>,
+}
+
+impl JournalInner {
+    /// Once the current unfocused period has lasted long enough to count as
+    /// a new away period, the previous summary is no longer "the last one".
+    fn roll_over(&mut self, now: SystemTime) {
+        if let Some(away) = &self.away {
+            if elapsed(away.since, now) >= AWAY_THRESHOLD {
+                self.last_summary = None;
+            }
+        }
+    }
+}
+
+/// Managed state: the activity journal for the running shell.
+#[derive(Default)]
+pub struct ActivityJournal(Mutex<JournalInner>);
+
+impl ActivityJournal {
+    /// Record a notable event.  Only kept while an away period is open.
+    /// Prefer `record`, which also updates the title bar and tray.
+    pub fn record(&self, kind: ActivityKind, message: impl Into<String>) {
+        self.record_at(kind, message, SystemTime::now());
+    }
+
+    fn record_at(&self, kind: ActivityKind, message: impl Into<String>, now: SystemTime) {
+        let mut inner = self.0.lock().unwrap();
+        inner.roll_over(now);
+        let Some(away) = inner.away.as_mut() else {
+            return;
+        };
+        if !kind.is_noteworthy() {
+            return;
+        }
+        if away.events.len() < MAX_SUMMARY_EVENTS {
+            away.events.push(ActivityEntry {
+                kind,
+                message: message.into(),
+                at_ms: unix_ms(now),
+            });
+        } else {
+            away.omitted += 1;
+        }
+    }
+
+    /// Noteworthy events of the open away period, including omitted ones.
+    pub fn pending(&self) -> u32 {
+        let inner = self.0.lock().unwrap();
+        inner
+            .away
+            .as_ref()
+            .map_or(0, |away| away.events.len() as u32 + away.omitted)
+    }
+
+    fn window_blurred(&self, now: SystemTime) {
+        let mut inner = self.0.lock().unwrap();
+        if inner.away.is_none() {
+            inner.away = Some(AwayPeriod {
+                since: now,
+                events: Vec::new(),
+                omitted: 0,
+            });
+        }
+    }
+
+    /// Close the open away period.  Returns a summary when the window was
+    /// unfocused for at least `AWAY_THRESHOLD`.
+    fn window_focused(&self, now: SystemTime) -> Option<AwaySummary> {
+        let mut inner = self.0.lock().unwrap();
+        let away = inner.away.take()?;
+        if elapsed(away.since, now) < AWAY_THRESHOLD {
+            return None;
+        }
+        let summary = AwaySummary {
+            away_since_ms: unix_ms(away.since),
+            returned_at_ms: unix_ms(now),
+            events: away.events,
+            omitted: away.omitted,
+        };
+        inner.last_summary = Some(summary.clone());
+        Some(summary)
+    }
+}
+
+/// "ALMReady", or "ALMReady – 3 new events" while events are waiting.
+fn title(pending: u32) -> String {
+    match pending {
+        0 => "ALMReady".to_string(),
+        1 => "ALMReady – 1 new event".to_string(),
+        n => format!("ALMReady – {n} new events"),
+    }
+}
+
+/// Show the number of waiting events in the title bar and the tray.
+fn surface(app: &AppHandle) {
+    let pending = app.state::<ActivityJournal>().pending();
+    if let Some(window) = app.get_webview_window(MAIN_LABEL) {
+        let _ = window.set_title(&title(pending));
+    }
+    tray::refresh(app);
+}
+
+/// Record a notable event (see `ActivityJournal::record`) and surface it.
+pub fn record(app: &AppHandle, kind: ActivityKind, message: impl Into<String>) {
+    app.state::<ActivityJournal>().record(kind, message);
+    surface(app);
+}
+
+/// Called from `on_window_event` for the main window's `Focused` events.
+pub fn on_main_window_focus(app: &AppHandle, focused: bool) {
+    let journal = app.state::<ActivityJournal>();
+    if !focused {
+        journal.window_blurred(SystemTime::now());
+        return;
+    }
+    let summary = journal.window_focused(SystemTime::now());
+    surface(app);
+    let Some(summary) = summary else {
+        return;
+    };
+
+    events::emit(app, Audience::MainOnly, "away-summary", &summary);
+
+    if !summary.events.is_empty() {
+        let _ = app
+            .notification()
+            .builder()
+            .title("ALMReady")
+            .body(summary.notification_body())
+            .show()
+            .inspect_err(|e| {
+                shell_log::log(
+                    Level::Warn,
+                    "journal",
+                    format!("away notification failed: {e}"),
+                )
+            });
+    }
+}
+
+// ── Commands ────────────────────────────────────────────────────────────────
+
+/// The most recent away summary, until the next away period begins.
+#[tauri::command]
+pub fn get_last_away_summary(journal: tauri::State<'_, ActivityJournal>) -> Option<AwaySummary> {
+    let mut inner = journal.0.lock().unwrap();
+    inner.roll_over(SystemTime::now());
+    inner.last_summary.clone()
+}
+
+/// Lets the frontend report events only it knows about (finished runs,
+/// exports, autosave results).  Runs and exports also raise the alert the
+/// user chose (see `alerts`).
+#[tauri::command]
+pub fn record_activity(app: AppHandle, kind: FrontendActivity, message: String) {
+    record(&app, kind.into(), message);
+    let alert = match kind {
+        FrontendActivity::RunCompleted => Alert::JobFinished,
+        FrontendActivity::RunFailed => Alert::JobFailed,
+        FrontendActivity::ExportCompleted => Alert::ExportComplete,
+        FrontendActivity::Autosave => return,
+    };
+    alerts::raise(&app, alert);
+}
+
+// ── Helpers ─────────────────────────────────────────────────────────────────
+
+fn elapsed(since: SystemTime, now: SystemTime) -> Duration {
+    now.duration_since(since).unwrap_or_default()
+}
+
+fn unix_ms(t: SystemTime) -> u64 {
+    t.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    const AWAY: u64 = AWAY_THRESHOLD.as_secs();
+
+    #[test]
+    fn records_only_noteworthy_events_while_away_and_caps_them() {
+        let journal = ActivityJournal::default();
+        journal.record_at(ActivityKind::RunCompleted, "before", at(0));
+        assert_eq!(journal.pending(), 0, "nothing is kept while focused");
+
+        journal.window_blurred(at(10));
+        journal.record_at(ActivityKind::HealthFlap, "flap", at(20));
+        assert_eq!(journal.pending(), 0);
+        for i in 0..MAX_SUMMARY_EVENTS + 3 {
+            journal.record_at(ActivityKind::Autosave, format!("save {i}"), at(30));
+        }
+        assert_eq!(journal.pending(), MAX_SUMMARY_EVENTS as u32 + 3);
+
+        let summary = journal.window_focused(at(10 + AWAY)).unwrap();
+        assert_eq!(summary.events.len(), MAX_SUMMARY_EVENTS);
+        assert_eq!(summary.omitted, 3);
+        assert_eq!(summary.away_since_ms, 10_000);
+        assert_eq!(journal.pending(), 0);
+    }
+
+    #[test]
+    fn short_absences_produce_no_summary_and_keep_the_last_one() {
+        let journal = ActivityJournal::default();
+        journal.window_blurred(at(0));
+        journal.record_at(ActivityKind::EngineCrash, "crash", at(1));
+        journal.record_at(ActivityKind::EngineRestart, "restart", at(2));
+        let summary = journal.window_focused(at(AWAY)).unwrap();
+        assert_eq!(
+            summary.notification_body(),
+            "While you were away: 1 engine crash, 1 engine restart"
+        );
+
+        journal.window_blurred(at(AWAY + 10));
+        assert!(journal.window_focused(at(AWAY + 20)).is_none());
+        assert!(journal.0.lock().unwrap().last_summary.is_some());
+
+        // A new absence long enough to count replaces the last summary.
+        journal.window_blurred(at(AWAY + 30));
+        journal.record_at(ActivityKind::RunFailed, "run", at(2 * AWAY + 30));
+        assert!(journal.0.lock().unwrap().last_summary.is_none());
+    }
+
+    #[test]
+    fn title_counts_waiting_events() {
+        assert_eq!(title(0), "ALMReady");
+        assert_eq!(title(1), "ALMReady – 1 new event");
+        assert_eq!(title(4), "ALMReady – 4 new events");
+    }
+
+    #[test]
+    fn the_frontend_cannot_report_engine_events() {
+        let kind: FrontendActivity = serde_json::from_str(r#""run-completed""#).unwrap();
+        assert_eq!(ActivityKind::from(kind), ActivityKind::RunCompleted);
+        for shell_only in ["engine-crash", "engine-restart", "health-flap"] {
+            let json = format!("{shell_only:?}");
+            assert!(serde_json::from_str::<FrontendActivity>(&json).is_err());
+        }
+    }
+}
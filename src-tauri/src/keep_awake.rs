@@ -0,0 +1,283 @@
+//! Keeping the computer awake during long calculations.
+//!
+//! An ALM projection run takes 20–40 minutes; a laptop that sleeps
+//! meanwhile suspends the backend's worker processes and the run is lost.
+//! The frontend calls `set_keep_awake(true)` when a calculation starts and
+//! `set_keep_awake(false)` when it ends.  Calls nest: sleep is inhibited
+//! from the first enable until as many disables have followed.
+//!
+//! Windows → `SetThreadExecutionState(ES_SYSTEM_REQUIRED)` on a thread of
+//!           its own, as the state belongs to the calling thread
+//! macOS   → an IOKit `PreventUserIdleSystemSleep` power assertion
+//! Linux   → a logind inhibitor lock held by `systemd-inhibit` (over D-Bus)
+//!           for as long as its stdin stays open
+//!
+//! The shell also lets go when the main window is destroyed, when the
+//! backend crashes and when the app exits, in case the frontend never gets
+//! to disable it.  While sleep is inhibited the tray tooltip says so.
+
+use std::sync::Mutex;
+
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    shell_log::{self, Level},
+    tray,
+};
+
+/// Shown by the OS where it lists what is keeping the computer awake.
+const REASON: &str = "An ALMReady calculation is running";
+
+// ── Platform inhibitors ─────────────────────────────────────────────────────
+
+/// Sleep is inhibited until this is dropped.
+#[cfg(target_os = "windows")]
+pub struct Inhibitor {
+    /// Dropping it ends the thread holding the execution state.
+    _release: std::sync::mpsc::Sender<()>,
+}
+
+#[cfg(target_os = "windows")]
+fn acquire() -> Result<Inhibitor, String> {
+    use std::sync::mpsc;
+    use windows::Win32::System::Power::{
+        SetThreadExecutionState, ES_CONTINUOUS, ES_SYSTEM_REQUIRED,
+    };
+
+    let (release, released) = mpsc::channel::<()>();
+    let (ready_tx, ready) = mpsc::channel();
+    std::thread::Builder::new()
+        .name("keep-awake".into())
+        .spawn(move || {
+            // SAFETY: plain flag calls; the state belongs to this thread,
+            // which lives exactly as long as the inhibitor.
+            let previous = unsafe { SetThreadExecutionState(ES_CONTINUOUS | ES_SYSTEM_REQUIRED) };
+            let _ = ready_tx.send(previous.0 != 0);
+            let _ = released.recv();
+            unsafe { SetThreadExecutionState(ES_CONTINUOUS) };
+        })
+        .map_err(|e| format!("keep-awake thread: {e}"))?;
+    match ready.recv() {
+        Ok(true) => Ok(Inhibitor { _release: release }),
+        _ => Err("SetThreadExecutionState failed".into()),
+    }
+}
+
+/// Sleep is inhibited until this is dropped.
+#[cfg(target_os = "macos")]
+pub struct Inhibitor(u32);
+
+#[cfg(target_os = "macos")]
+#[link(name = "IOKit", kind = "framework")]
+extern "C" {
+    fn IOPMAssertionCreateWithName(
+        assertion_type: *const std::ffi::c_void,
+        level: u32,
+        name: *const std::ffi::c_void,
+        id: *mut u32,
+    ) -> i32;
+    fn IOPMAssertionRelease(id: u32) -> i32;
+}
+
+#[cfg(target_os = "macos")]
+fn acquire() -> Result<Inhibitor, String> {
+    use objc2_foundation::NSString;
+
+    const ASSERTION_LEVEL_ON: u32 = 255;
+    let kind = NSString::from_str("PreventUserIdleSystemSleep");
+    let name = NSString::from_str(REASON);
+    let mut id = 0;
+    // SAFETY: NSString is toll-free bridged to CFStringRef; both strings
+    // outlive the call.
+    let status = unsafe {
+        IOPMAssertionCreateWithName(
+            (&*kind as *const NSString).cast(),
+            ASSERTION_LEVEL_ON,
+            (&*name as *const NSString).cast(),
+            &mut id,
+        )
+    };
+    if status == 0 {
+        Ok(Inhibitor(id))
+    } else {
+        Err(format!("IOPMAssertionCreateWithName: {status:#x}"))
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl Drop for Inhibitor {
+    fn drop(&mut self) {
+        // SAFETY: the id came from IOPMAssertionCreateWithName and is
+        // released once.
+        unsafe { IOPMAssertionRelease(self.0) };
+    }
+}
+
+/// Sleep is inhibited until this is dropped.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub struct Inhibitor(std::process::Child);
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn acquire() -> Result<Inhibitor, String> {
+    use std::process::{Command, Stdio};
+
+    // `cat` exits when its stdin closes – when the inhibitor is dropped, or
+    // when the shell dies – and systemd-inhibit releases the lock with it.
+    Command::new("systemd-inhibit")
+        .args([
+            "--what=idle:sleep",
+            "--who=ALMReady",
+            &format!("--why={REASON}"),
+            "--mode=block",
+            "cat",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(Inhibitor)
+        .map_err(|e| format!("systemd-inhibit: {e}"))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+impl Drop for Inhibitor {
+    fn drop(&mut self) {
+        drop(self.0.stdin.take());
+        let _ = self.0.wait();
+    }
+}
+
+// ── Reference count ─────────────────────────────────────────────────────────
+
+/// Nested enables and the inhibitor held while there are any.
+struct Holds<T> {
+    count: u32,
+    inhibitor: Option<T>,
+}
+
+impl<T> Holds<T> {
+    fn enable(&mut self, acquire: impl FnOnce() -> Result<T, String>) -> Result<(), String> {
+        if self.inhibitor.is_none() {
+            self.inhibitor = Some(acquire()?);
+        }
+        self.count += 1;
+        Ok(())
+    }
+
+    fn disable(&mut self) {
+        self.count = self.count.saturating_sub(1);
+        if self.count == 0 {
+            self.inhibitor = None;
+        }
+    }
+
+    /// Drop every hold; whether there were any.
+    fn clear(&mut self) -> bool {
+        self.count = 0;
+        self.inhibitor.take().is_some()
+    }
+}
+
+/// Managed state.
+pub struct KeepAwake(Mutex<Holds<Inhibitor>>);
+
+impl Default for KeepAwake {
+    fn default() -> Self {
+        KeepAwake(Mutex::new(Holds {
+            count: 0,
+            inhibitor: None,
+        }))
+    }
+}
+
+impl KeepAwake {
+    /// Whether sleep is being inhibited.
+    pub fn is_active(&self) -> bool {
+        self.0.lock().unwrap().inhibitor.is_some()
+    }
+}
+
+/// Stop inhibiting sleep, whatever the frontend enabled.  Called when the
+/// main window is destroyed, the backend crashes and the app exits.
+pub fn release_all(app: &AppHandle, why: &str) {
+    if app.state::<KeepAwake>().0.lock().unwrap().clear() {
+        shell_log::log(
+            Level::Info,
+            "keep-awake",
+            format!("sleep allowed again ({why})"),
+        );
+        tray::refresh(app);
+    }
+}
+
+/// Count one calculation in (`true`) or out (`false`).  Returns whether
+/// sleep is inhibited afterwards.
+#[tauri::command]
+pub fn set_keep_awake(app: AppHandle, enabled: bool) -> Result<bool, String> {
+    let (was_active, active) = {
+        let keep_awake = app.state::<KeepAwake>();
+        let mut holds = keep_awake.0.lock().unwrap();
+        let was_active = holds.inhibitor.is_some();
+        if enabled {
+            holds.enable(acquire)?;
+        } else {
+            holds.disable();
+        }
+        (was_active, holds.inhibitor.is_some())
+    };
+    if active != was_active {
+        let message = if active {
+            "inhibiting sleep"
+        } else {
+            "sleep allowed again"
+        };
+        shell_log::log(Level::Info, "keep-awake", message);
+        tray::refresh(&app);
+    }
+    Ok(active)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_enables_hold_until_the_last_disable() {
+        let mut holds = Holds {
+            count: 0,
+            inhibitor: None,
+        };
+        let mut acquired = 0;
+        for _ in 0..2 {
+            holds
+                .enable(|| {
+                    acquired += 1;
+                    Ok(())
+                })
+                .unwrap();
+        }
+        assert_eq!(acquired, 1);
+        holds.disable();
+        assert!(holds.inhibitor.is_some());
+        holds.disable();
+        assert!(holds.inhibitor.is_none());
+        // An unmatched disable doesn't underflow into a later enable.
+        holds.disable();
+        holds.enable(|| Ok(())).unwrap();
+        holds.disable();
+        assert!(holds.inhibitor.is_none());
+    }
+
+    #[test]
+    fn a_failed_acquire_is_not_counted() {
+        let mut holds: Holds<()> = Holds {
+            count: 0,
+            inhibitor: None,
+        };
+        assert!(holds.enable(|| Err("no logind".into())).is_err());
+        assert_eq!(holds.count, 0);
+        holds.enable(|| Ok(())).unwrap();
+        assert!(holds.clear());
+        assert!(!holds.clear());
+    }
+}
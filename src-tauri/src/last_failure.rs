@@ -0,0 +1,294 @@
+//! The last failure dialog, kept so it can be shown again on a support
+//! call long after the user dismissed it.
+//!
+//! The startup failure dialog and the "engine stopped unexpectedly" dialog
+//! both save what they showed, plus context support needs, to
+//! `{data_dir}/last-failure.json`:
+//!
+//! * the failure category, exit code and dialog text;
+//! * the last `LOG_EXCERPT_LINES` of `backend.log`;
+//! * the diagnostics self-checks at that moment;
+//! * when it happened and the app version.
+//!
+//! Each failure overwrites the previous one.  Everything is redacted before
+//! it is written: the home directory becomes `~` and the session token
+//! `<redacted>`, so the file can be sent on as is.
+//!
+//! Help → Show Last Failure, or starting the app with `--show-last-failure`
+//! (also when it is already running), shows the dialog again, labelled
+//! "Recorded at …".  Its Restart Engine button acts on the live engine;
+//! `get_last_failure` gives the frontend the whole snapshot.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::{
+    data_dir_access::DataDirAccess,
+    diagnostics,
+    failure::FailureCategory,
+    logpipe, menu, paths,
+    shell_log::{self, Level},
+    BackendProcess,
+};
+
+pub const SHOW_FLAG: &str = "--show-last-failure";
+
+const FILE_NAME: &str = "last-failure.json";
+const LOG_EXCERPT_LINES: usize = 40;
+const REDACTED: &str = "<redacted>";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FailureKind {
+    /// The engine did not start (`startup_failed_dialog`).
+    StartupFailed,
+    /// The engine stopped and the watchdog could not restart it.
+    EngineCrashed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FailureSnapshot {
+    pub kind: FailureKind,
+    /// Local time, RFC 3339.
+    pub recorded_at: String,
+    pub app_version: String,
+    pub category: FailureCategory,
+    pub exit_code: Option<i32>,
+    /// The dialog text as shown.
+    pub message: String,
+    /// Whether the dialog offered a retry.
+    pub retry_offered: bool,
+    pub log_excerpt: Vec<String>,
+    /// `run_self_checks` output.
+    pub self_checks: serde_json::Value,
+}
+
+fn file_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(paths::data_dir(app)?.join(FILE_NAME))
+}
+
+// ── Redaction ───────────────────────────────────────────────────────────────
+
+/// What must not leave the machine: the home directory (user name) and
+/// secrets such as the session token.
+pub(crate) struct Redactor {
+    home: Option<String>,
+    secrets: Vec<String>,
+}
+
+impl Redactor {
+    /// The user's home directory and the live session token.
+    pub(crate) fn for_app(app: &AppHandle) -> Self {
+        Redactor {
+            home: app.path().home_dir().ok().map(|h| h.display().to_string()),
+            secrets: app.state::<BackendProcess>().token().into_iter().collect(),
+        }
+    }
+
+    pub(crate) fn text(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for secret in self.secrets.iter().filter(|s| !s.is_empty()) {
+            text = text.replace(secret.as_str(), REDACTED);
+        }
+        if let Some(home) = self.home.as_deref().filter(|h| h.len() > 1) {
+            text = text.replace(home, "~");
+        }
+        text
+    }
+
+    pub(crate) fn value(&self, value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::String(s) => *s = self.text(s),
+            serde_json::Value::Array(items) => items.iter_mut().for_each(|v| self.value(v)),
+            serde_json::Value::Object(map) => map.values_mut().for_each(|v| self.value(v)),
+            _ => {}
+        }
+    }
+
+    fn snapshot(&self, snapshot: &mut FailureSnapshot) {
+        snapshot.message = self.text(&snapshot.message);
+        for line in &mut snapshot.log_excerpt {
+            *line = self.text(line);
+        }
+        self.value(&mut snapshot.self_checks);
+    }
+}
+
+// ── Recording ───────────────────────────────────────────────────────────────
+
+/// The last `n` lines of the file at `path`.
+fn tail(path: &Path, n: usize) -> Vec<String> {
+    let Ok(text) = DataDirAccess::unleased().with(&[path], || std::fs::read_to_string(path)) else {
+        return Vec::new();
+    };
+    let lines: Vec<&str> = text.lines().collect();
+    lines[lines.len().saturating_sub(n)..]
+        .iter()
+        .map(|l| l.to_string())
+        .collect()
+}
+
+/// Save the failure a dialog is showing.  Errors are logged, never shown:
+/// the dialog itself matters more.
+pub async fn record(
+    app: AppHandle,
+    kind: FailureKind,
+    category: FailureCategory,
+    exit_code: Option<i32>,
+    message: String,
+    retry_offered: bool,
+) {
+    let path = match file_path(&app) {
+        Ok(path) => path,
+        Err(e) => {
+            shell_log::log(Level::Warn, "last-failure", format!("not recorded: {e}"));
+            return;
+        }
+    };
+    let log_excerpt = path
+        .parent()
+        .map(|dir| tail(&logpipe::backend_log_path(dir), LOG_EXCERPT_LINES))
+        .unwrap_or_default();
+    let self_checks =
+        serde_json::to_value(diagnostics::run_self_checks(app.clone()).await).unwrap_or_default();
+
+    let mut snapshot = FailureSnapshot {
+        kind,
+        recorded_at: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+        app_version: app.package_info().version.to_string(),
+        category,
+        exit_code,
+        message,
+        retry_offered,
+        log_excerpt,
+        self_checks,
+    };
+    Redactor::for_app(&app).snapshot(&mut snapshot);
+
+    let written = serde_json::to_string_pretty(&snapshot)
+        .map_err(|e| e.to_string())
+        .and_then(|json| {
+            DataDirAccess::unleased()
+                .with(&[&path], || std::fs::write(&path, &json))
+                .map_err(|e| e.to_string())
+        });
+    if let Err(e) = written {
+        shell_log::log(
+            Level::Warn,
+            "last-failure",
+            format!("cannot write {}: {e}", path.display()),
+        );
+    }
+}
+
+fn load(app: &AppHandle) -> Result<Option<FailureSnapshot>, String> {
+    let path = file_path(app)?;
+    match DataDirAccess::unleased().with(&[&path], || std::fs::read_to_string(&path)) {
+        Ok(json) => serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|e| format!("{}: {e}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("{}: {e}", path.display())),
+    }
+}
+
+// ── Replay ──────────────────────────────────────────────────────────────────
+
+fn replay_text(snapshot: &FailureSnapshot) -> String {
+    format!(
+        "Recorded at {} (ALMReady {}).\n\n{}",
+        snapshot.recorded_at, snapshot.app_version, snapshot.message
+    )
+}
+
+/// Show the recorded failure dialog again.
+pub fn show(app: &AppHandle) {
+    let (text, kind) = match load(app) {
+        Ok(Some(snapshot)) => (replay_text(&snapshot), MessageDialogKind::Error),
+        Ok(None) => (
+            "No failure has been recorded.".to_string(),
+            MessageDialogKind::Info,
+        ),
+        Err(e) => (
+            format!("The last failure could not be read: {e}"),
+            MessageDialogKind::Warning,
+        ),
+    };
+    let app_handle = app.clone();
+    app.dialog()
+        .message(text)
+        .title("ALMReady – Last Failure")
+        .kind(kind)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Restart Engine".into(),
+            "Close".into(),
+        ))
+        .show(move |restart| {
+            if restart {
+                menu::restart_engine(&app_handle);
+            }
+        });
+}
+
+/// The recorded failure, if any.
+#[tauri::command]
+pub fn get_last_failure(app: AppHandle) -> Result<Option<FailureSnapshot>, String> {
+    load(&app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot() -> FailureSnapshot {
+        FailureSnapshot {
+            kind: FailureKind::StartupFailed,
+            recorded_at: "2026-10-16T09:30:00+02:00".into(),
+            app_version: "1.0.0".into(),
+            category: FailureCategory::Environmental,
+            exit_code: Some(1),
+            message: "cannot open /home/ana/ALMReady/data".into(),
+            retry_offered: true,
+            log_excerpt: vec!["token=0123abcd".into(), "ok".into()],
+            self_checks: serde_json::json!([
+                { "name": "install", "ok": true, "detail": "/home/ana/Apps" },
+            ]),
+        }
+    }
+
+    #[test]
+    fn snapshot_is_redacted_and_round_trips() {
+        let mut snapshot = snapshot();
+        Redactor {
+            home: Some("/home/ana".into()),
+            secrets: vec!["0123abcd".into()],
+        }
+        .snapshot(&mut snapshot);
+        assert_eq!(snapshot.message, "cannot open ~/ALMReady/data");
+        assert_eq!(snapshot.log_excerpt[0], "token=<redacted>");
+        assert_eq!(snapshot.self_checks[0]["detail"], "~/Apps");
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert!(json.contains("\"kind\":\"startup-failed\""));
+        assert_eq!(
+            serde_json::from_str::<FailureSnapshot>(&json).unwrap(),
+            snapshot
+        );
+        assert!(replay_text(&snapshot).starts_with("Recorded at 2026-10-16T09:30:00+02:00"));
+    }
+
+    #[test]
+    fn tail_keeps_the_last_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("backend.log");
+        std::fs::write(&path, "a\nb\nc\n").unwrap();
+        assert_eq!(tail(&path, 2), ["b", "c"]);
+        assert_eq!(tail(&path, 10), ["a", "b", "c"]);
+        std::fs::remove_file(&path).unwrap();
+        assert!(tail(&path, 2).is_empty());
+    }
+}
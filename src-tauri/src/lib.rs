@@ -3,242 +3,1573 @@
 /// Startup sequence
 /// ────────────────
 /// 1.  Resolve the PyInstaller one-directory bundle from the app resource dir.
-/// 2.  Set ALMREADY_DATA_DIR (OS user-data dir) and ALMREADY_CORS_ORIGINS env
-///     vars, then spawn the sidecar as a child process with stdout captured.
+/// 2.  Set ALMREADY_DATA_DIR (OS user-data dir, or `{exe_dir}/data` in
+///     portable mode – see `paths`), ALMREADY_CORS_ORIGINS and a
+///     fresh ALMREADY_AUTH_TOKEN (see `session_token`) env vars, then spawn
+///     the sidecar as a child process with stdout captured.
+///     stderr is filtered (see `logpipe`) into `{data_dir}/logs/backend.log`
+///     and forwarded to the webview as `backend-log-line` events.
 /// 3.  A blocking-reader task scans stdout for the "PORT:{n}" line printed by
-///     sidecar_main.py and delivers the port over a oneshot channel.
+///     sidecar_main.py and delivers the port over a oneshot channel.  If the
+///     reader itself fails, the port file the sidecar also writes is used,
+///     or the sidecar is respawned once (see `port_reader`).
 /// 4.  A second async task waits for the port, polls
-///     `GET http://127.0.0.1:{port}/api/health` (TCP connect) until 200 OK,
+///     `GET http://127.0.0.1:{port}/api/health` until 200 `{"status":"ok"}`,
 ///     then creates the main WebviewWindow with an initialization_script that
-///     injects `window.__BACKEND_PORT__ = {port}` **before** React modules
-///     load – guaranteeing the value is synchronously available in api.ts.
-/// 5.  On CloseRequested: the child process is killed so no zombie Python
-///     processes remain after the native window closes.
+///     injects `window.__BACKEND_PORT__ = {port}` and the token as
+///     `window.__BACKEND_TOKEN__` **before** React modules load –
+///     guaranteeing both are synchronously available in api.ts.
+///     If the sidecar exits before printing its port or the health check
+///     times out, the child is killed and reaped and a native error dialog
+///     offers one retry before the app exits.
+///     Once the window is open a watchdog keeps checking the sidecar and
+///     restarts it once if it crashes (see `watchdog`).  Every stage – spawn,
+///     port, ready, termination, restart – is also sent to all windows as an
+///     event (see `lifecycle`).
+/// 5.  On CloseRequested, ExitRequested (Cmd+Q) or Exit: the sidecar is
+///     asked to exit via `POST /api/shutdown` and given 5 s to finish
+///     in-flight session writes, then SIGTERM (TerminateProcess on Windows)
+///     and 3 s more.  Then its whole process tree (process group on Unix,
+///     Job Object on Windows) is killed so no orphaned Python workers
+///     remain; a child that still won't go is abandoned after 5 s.
 ///
 /// Development note
 /// ────────────────
 /// When running via `cargo tauri dev`, `beforeDevCommand` starts Vite and
-/// uvicorn via `npm run dev:all`.  In that case Tauri does NOT use the
-/// sidecar path – it points the webview at the Vite dev server
-/// (http://localhost:8080) and the backend is already running on :8000 from
-/// the dev command.  The sidecar spawn code still executes, but the binary
-/// won't exist in the dev tree, so the error is caught and logged, and the
-/// app continues to work via the Vite dev server + dev uvicorn instance.
-
+/// the webview loads the dev server (http://localhost:8080).  The bundled
+/// sidecar doesn't exist in the dev tree, so the shell runs the backend from
+/// source instead (see `dev_backend`) and goes through the usual port, health
+/// and shutdown handling.  If that fails it is logged and the app relies on a
+/// separately started uvicorn, as before.
+///
+/// Debugging against a manually started backend
+/// ────────────────────────────────────────────
+/// Set `ALMREADY_BACKEND_PORT={port}` (and optionally
+/// `ALMREADY_BACKEND_HOST=127.0.0.1`, the only host supported) to skip the
+/// sidecar: the packaged app waits for that backend's health check and opens
+/// the main window with the port injected, e.g. for a backend running under
+/// a debugger.  An invalid value shows an error dialog.  The shell never
+/// stops a backend it didn't spawn (see `backend_override`).
+///
+/// Updates are checked for and installed from the frontend; the sidecar is
+/// stopped before the installer replaces its files (see `updater`).
+///
+/// The Help menu opens the data folder and the backend log and restarts the
+/// engine, for when something goes wrong (see `menu`).  A tray icon shows
+/// the engine's health and can hide the window on close (see `tray`).
+///
+/// Administrators can have site scripts run right before the sidecar starts
+/// and after it stops, e.g. to mount a network drive (see `hooks`).
+///
+/// `almready conformance --sidecar <path>` runs a backend build through the
+/// protocol above and exits; see `conformance`.  A protocol change needs a
+/// matching check there in the same change.
+///
+/// `--dump-window-config` prints the main window's configuration (scripts,
+/// sizes, capabilities, feature flags) as it would be built, then exits
+/// without starting the sidecar.  Close a running instance first: the
+/// single-instance lock hands a second launch to it.  On Windows release
+/// builds run it with stdout redirected, as there is no console.
 use std::{
-    io::{BufRead as _, BufReader},
-    sync::Mutex,
-    time::Duration,
+    collections::BTreeMap,
+    io::BufReader,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{
+    DialogExt, MessageDialogButtons, MessageDialogKind, MessageDialogResult,
 };
 
-use tauri::{AppHandle, Manager, WebviewWindowBuilder, WebviewUrl};
-use tokio::{net::TcpStream, time::sleep};
+mod alerts;
+mod api_protocol;
+mod backend_override;
+mod backend_status;
+mod browser;
+mod build_info;
+mod bundle_check;
+mod clipboard;
+mod conformance;
+mod cookies;
+mod cursor;
+mod data_dir_access;
+mod degradations;
+mod detached_windows;
+mod dev_backend;
+mod diagnostics;
+mod disk_bench;
+mod display;
+mod events;
+mod exports;
+mod failure;
+mod health_history;
+mod hooks;
+mod housekeeping;
+mod http;
+mod install_location;
+mod installer;
+mod integrity;
+mod journal;
+mod keep_awake;
+mod last_failure;
+mod license;
+mod lifecycle;
+mod locales;
+mod log_viewer;
+mod logpipe;
+mod menu;
+mod migration;
+mod network;
+mod paths;
+mod pid_file;
+mod port_broadcast;
+mod port_reader;
+mod printer;
+mod process_tree;
+mod profiler;
+mod readiness;
+mod resource_usage;
+mod reveal;
+mod screen_lock;
+mod session_files;
+mod session_token;
+mod settings;
+mod shell_log;
+mod shortcuts;
+mod sidecar_version;
+mod splash;
+mod storage;
+mod support_bundle;
+mod taskbar_progress;
+mod theme;
+mod time_zone;
+mod timeouts;
+mod tray;
+mod updater;
+mod user_scope;
+mod watchdog;
+mod webview_prefs;
+mod window_factory;
+mod window_handle;
+mod window_opacity;
+mod window_position;
+mod window_size;
+mod window_state;
+mod zoom;
+
+use backend_status::BackendStatus;
+use degradations::{Code as DegradationCode, Degradation, Severity};
+use events::{Capability, WindowCapabilities};
+use failure::BackendError;
+use health_history::SidecarHealthHistory;
+use hooks::Hook;
+use install_location::InstallLocation;
+use journal::ActivityJournal;
+use last_failure::FailureKind;
+use license::LicenseStatus;
+use port_broadcast::SidecarPortBroadcaster;
+use port_reader::{PortOutcome, PortStep};
+use process_tree::ProcessTree;
+use settings::SettingsStore;
+use shell_log::Level;
+use timeouts::Timeouts;
+use webview_prefs::BrowserArgs;
+use window_factory::{
+    WindowConfig, WindowConfigs, WindowFactory, WindowPreset, WindowSpec, MAIN_LABEL,
+};
 
 // ── App state ───────────────────────────────────────────────────────────────
 
-/// Holds the sidecar child process handle so we can kill it on exit.
-struct BackendProcess(Mutex<Option<std::process::Child>>);
+/// How long a sidecar asked to shut down gets to exit on its own (finishing
+/// in-flight session writes) before its process tree is killed, by default
+/// (see `timeouts`).
+const SHUTDOWN_GRACE: Duration = Timeouts::DEFAULT.shutdown_grace;
 
-// ── Health check ────────────────────────────────────────────────────────────
+/// How long each stage of `BackendProcess::terminate` waits for the
+/// sidecar to exit before escalating to the next.
+#[derive(Debug, Clone, Copy)]
+struct Escalation {
+    /// After `POST /api/shutdown` was accepted.
+    http: Duration,
+    /// After SIGTERM (Unix) or `TerminateProcess` of the child (Windows).
+    terminate: Duration,
+    /// After killing the whole tree, for the child to be reaped; past this
+    /// it is abandoned rather than waited for.
+    kill: Duration,
+}
+
+/// The stages with the configured shutdown grace.
+fn escalation() -> Escalation {
+    Escalation {
+        http: timeouts::current().shutdown_grace,
+        terminate: Duration::from_secs(3),
+        kill: Duration::from_secs(5),
+    }
+}
+
+/// Holds the sidecar process tree and, once it has reported it, its port, so
+/// we can shut it down on exit.
+#[derive(Default)]
+struct BackendProcess {
+    child: Mutex<Option<ProcessTree>>,
+    port: SidecarPortBroadcaster,
+    /// Lifecycle as last recorded by startup, the watchdog and shutdown;
+    /// read through `status()`.
+    status: Mutex<BackendStatus>,
+    /// Set once the app is closing, so the startup task stops quietly
+    /// instead of reporting the sidecar we just stopped as a failure.
+    shutting_down: AtomicBool,
+    /// The running watchdog task, cancelled before shutdown stops the child.
+    watchdog: Mutex<Option<tauri::async_runtime::JoinHandle<()>>>,
+    /// Panic message of the last stdout reader that failed, for diagnostics.
+    reader_failure: Mutex<Option<String>>,
+    /// Set while `restart_backend` runs, so concurrent calls are refused.
+    restarting: AtomicBool,
+    /// `backend.pid` of the stored child, removed once it is stopped.
+    pid_file: Mutex<Option<PathBuf>>,
+    /// Using a backend from `ALMREADY_BACKEND_PORT`, not our own sidecar.
+    external: AtomicBool,
+    /// Session token of the stored child; a new one for every spawn.
+    token: Mutex<Option<String>>,
+}
+
+impl BackendProcess {
+    fn is_running(&self) -> bool {
+        self.child.lock().unwrap().is_some()
+    }
+
+    /// Exit status of the stored child, if it has exited on its own.
+    fn exit_status(&self) -> Option<process_tree::ExitStatus> {
+        self.child.lock().unwrap().as_mut()?.try_wait()
+    }
+
+    /// Resolves once the stored child has exited on its own; pending while
+    /// there is none.
+    async fn exited(&self) -> process_tree::ExitStatus {
+        process_tree::exited(self.pid(), || self.exit_status()).await
+    }
+
+    /// Whether the stored child has exited on its own.
+    fn has_exited(&self) -> bool {
+        self.exit_status().is_some()
+    }
+
+    /// Pid of the stored child, if there is one.
+    fn pid(&self) -> Option<u32> {
+        Some(self.child.lock().unwrap().as_mut()?.child_mut().id())
+    }
+
+    fn token(&self) -> Option<String> {
+        self.token.lock().unwrap().clone()
+    }
+
+    fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+
+    fn set_status(&self, status: BackendStatus) {
+        *self.status.lock().unwrap() = status;
+    }
+
+    /// The recorded status, except that a "ready" child which has since
+    /// exited is reported as crashed before the watchdog notices.
+    fn status(&self) -> BackendStatus {
+        let status = *self.status.lock().unwrap();
+        if status == BackendStatus::Ready && self.has_exited() {
+            BackendStatus::Crashed
+        } else {
+            status
+        }
+    }
+
+    /// Mark the app as closing and cancel the watchdog; `shutdown_backend`
+    /// then stops the child.
+    fn begin_shutdown(&self) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+        // A cancelled watchdog drops any child it held, which kills it.
+        if let Some(watchdog) = self.watchdog.lock().unwrap().take() {
+            watchdog.abort();
+        }
+    }
+
+    /// Take the sidecar out of state and stop it in stages (`escalation`):
+    /// HTTP shutdown when we know its port, then SIGTERM/`TerminateProcess`,
+    /// then a kill of its whole process tree.  Nothing waits without a
+    /// deadline, so a child wedged in an uninterruptible wait can't hang the
+    /// app's exit.  Returns the child's exit code when it had already
+    /// terminated on its own before we asked.
+    async fn terminate(&self) -> Option<i32> {
+        self.terminate_with(escalation()).await
+    }
+
+    async fn terminate_with(&self, stages: Escalation) -> Option<i32> {
+        let mut tree = self.child.lock().unwrap().take()?;
+        let port = self.port.take();
+        let token = self.token.lock().unwrap().take();
+        let pid_path = self.pid_file.lock().unwrap().take();
+        let forget_pid = || {
+            if let Some(path) = &pid_path {
+                pid_file::remove(path);
+            }
+        };
+
+        if let Some(status) = tree.try_wait() {
+            tree.kill(); // the workers may have outlived it
+            forget_pid();
+            self.set_status(BackendStatus::Crashed);
+            return status.code;
+        }
+
+        let accepted = match port {
+            Some(port) => http::request_shutdown(port, token.as_deref()).await,
+            None => false,
+        };
+        let mut exited = accepted && wait_for_exit(&mut tree, stages.http).await;
+        if !exited {
+            if accepted {
+                shell_log::log(
+                    Level::Warn,
+                    "shutdown",
+                    format!(
+                        "sidecar did not exit within {:?} of the HTTP shutdown, terminating it",
+                        stages.http
+                    ),
+                );
+            }
+            tree.terminate_child();
+            exited = wait_for_exit(&mut tree, stages.terminate).await;
+        }
+        if !exited {
+            shell_log::log(
+                Level::Warn,
+                "shutdown",
+                format!(
+                    "sidecar did not exit within {:?} of being terminated, killing its process tree",
+                    stages.terminate
+                ),
+            );
+        }
+        // Even a child that exited may leave workers behind.
+        tree.send_kill();
+        if !wait_for_exit(&mut tree, stages.kill).await {
+            shell_log::log(
+                Level::Error,
+                "shutdown",
+                format!(
+                    "sidecar (pid {}) still running {:?} after its process tree was killed, \
+                     abandoning it",
+                    tree.child_mut().id(),
+                    stages.kill
+                ),
+            );
+        }
+        tree.release();
+        forget_pid();
+        self.set_status(BackendStatus::Stopped);
+        None
+    }
+}
+
+/// Stop the stored sidecar (see `BackendProcess::terminate`), report it to
+/// the windows and run the `post-backend-stop` hook.  Returns the exit code
+/// of a child that had already exited on its own.
+async fn terminate_backend(app: &AppHandle) -> Option<i32> {
+    let backend = app.state::<BackendProcess>();
+    if !backend.is_running() {
+        return None;
+    }
+    let port = backend.port.get();
+    let exit_code = backend.terminate().await;
+    lifecycle::terminated(app, exit_code);
+    let _ = hooks::run(app, Hook::PostBackendStop, port).await;
+    exit_code
+}
+
+/// Stop the sidecar because the app is closing.  Shared by window close,
+/// Cmd+Q and the final `RunEvent::Exit`; safe to call repeatedly, later
+/// calls find no child and return at once.
+async fn shutdown_backend(app: &AppHandle) {
+    app.state::<BackendProcess>().begin_shutdown();
+    terminate_backend(app).await;
+}
+
+/// Let a workspace window (main or detached) close.  The last one stops the
+/// sidecar and its workers so no orphaned Python processes remain.  That
+/// runs through `escalation`'s stages on the async runtime, so hide the window
+/// at once and destroy it afterwards.
+fn close_workspace_window(window: &tauri::Window, api: &tauri::CloseRequestApi) {
+    let app = window.app_handle();
+    if detached_windows::others_open(app, window.label())
+        || !app.state::<BackendProcess>().is_running()
+    {
+        return;
+    }
+    api.prevent_close();
+    let _ = window.hide();
+    let window = window.clone();
+    tauri::async_runtime::spawn(async move {
+        shutdown_backend(window.app_handle()).await;
+        let _ = window.destroy();
+    });
+}
+
+/// How a running sidecar responded to `ask_to_exit`.
+struct ExitRequest {
+    /// `POST /api/shutdown` was accepted; otherwise SIGTERM was sent.
+    accepted: bool,
+    /// It exited within `SHUTDOWN_GRACE`.
+    exited: bool,
+}
+
+/// Ask a running sidecar to exit – HTTP shutdown when `port` is known,
+/// SIGTERM on Unix otherwise or if that is refused – and wait up to
+/// `SHUTDOWN_GRACE` for it to do so.
+async fn ask_to_exit(
+    tree: &mut ProcessTree,
+    port: Option<u16>,
+    token: Option<&str>,
+) -> ExitRequest {
+    let accepted = match port {
+        Some(port) => http::request_shutdown(port, token).await,
+        None => false,
+    };
+    if !accepted {
+        tree.request_stop();
+    }
+
+    ExitRequest {
+        accepted,
+        exited: wait_for_exit(tree, SHUTDOWN_GRACE).await,
+    }
+}
 
-/// Poll port until a TCP connection succeeds (server is accepting) or we time out.
-/// Returns true if the backend became ready within the timeout.
-async fn wait_for_backend(port: u16) -> bool {
-    // 60 attempts × 500 ms = 30 s maximum wait.
-    // The ProcessPoolExecutor warm-up in the FastAPI lifespan is the slowest
-    // part (~3-8 s depending on CPU count); 30 s is a comfortable upper bound.
-    for _ in 0..60u32 {
-        if TcpStream::connect(format!("127.0.0.1:{port}")).await.is_ok() {
-            return true;
+/// Whether `tree`'s child exits (and is reaped) within `timeout`.
+async fn wait_for_exit(tree: &mut ProcessTree, timeout: Duration) -> bool {
+    let deadline = tokio::time::Instant::now() + timeout;
+    while tree.try_wait().is_none() {
+        if tokio::time::Instant::now() >= deadline {
+            return false;
         }
-        sleep(Duration::from_millis(500)).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
     }
-    false
+    true
+}
+
+// ── Health check ────────────────────────────────────────────────────────────
+
+/// Poll `GET /api/health` until it answers 200 `{"status":"ok"}` or we time
+/// out.  Returns true if the backend became ready within the timeout.
+///
+/// uvicorn accepts TCP connections before the FastAPI lifespan has finished
+/// warming the process pool, so a bare connect isn't enough: the first API
+/// calls would still 503.
+///
+/// The ProcessPoolExecutor warm-up in the FastAPI lifespan is the slowest
+/// part (~3-8 s depending on CPU count, 50 s+ on old 2-core machines), so
+/// `timeout` comes from `timeouts` (30 s by default).
+async fn wait_for_backend(
+    port: u16,
+    token: Option<&str>,
+    timeout: Duration,
+    interval: Duration,
+) -> bool {
+    http::wait_for_health(port, token, timeouts::attempts(timeout, interval), interval).await
 }
 
 // ── Sidecar spawn ───────────────────────────────────────────────────────────
 
-fn spawn_sidecar(
-    app: &AppHandle,
-) -> Result<(std::process::Child, tokio::sync::oneshot::Receiver<u16>), String> {
-    // Locate the PyInstaller bundle within the app's resource directory.
-    // tauri.conf.json maps  ../backend/dist/almready-backend  →  almready-backend
-    // so it lands at  {resource_dir}/almready-backend/almready-backend[.exe].
+/// A freshly spawned sidecar and the ways to learn its port.
+struct Spawned {
+    tree: ProcessTree,
+    port_rx: tokio::sync::oneshot::Receiver<PortOutcome>,
+    port_file: PathBuf,
+    pid_file: PathBuf,
+    token: String,
+}
+
+/// The command line and environment a sidecar is started with.
+fn sidecar_command(
+    exe_path: &Path,
+    data_dir: &Path,
+    backend_env: &BTreeMap<String, String>,
+    port_file: &Path,
+    token: &str,
+) -> std::process::Command {
+    // Tauri webview origins – one per platform, both listed for safety.
+    let cors_origins = "tauri://localhost,https://tauri.localhost";
+
+    let mut command = std::process::Command::new(exe_path);
+    command
+        // User/migration overrides first so the shell's own vars win.
+        .envs(backend_env)
+        .env("ALMREADY_DATA_DIR", data_dir)
+        .env("ALMREADY_CORS_ORIGINS", cors_origins)
+        .env(port_reader::PORT_FILE_ENV, port_file)
+        .env(session_token::ENV, token)
+        // Capture stdout so we can read the PORT:{n} line.
+        .stdout(std::process::Stdio::piped())
+        // Capture stderr (uvicorn + app logging) into backend.log.
+        .stderr(std::process::Stdio::piped());
+    command
+}
+
+/// The PyInstaller bundle within the app's resource directory.
+/// tauri.conf.json maps  ../backend/dist/almready-backend  →  almready-backend
+/// so it lands at  {resource_dir}/almready-backend/almready-backend[.exe].
+pub(crate) fn sidecar_exe_path(app: &AppHandle) -> Result<PathBuf, BackendError> {
     let resource_dir = app
         .path()
         .resource_dir()
-        .map_err(|e| format!("resource_dir: {e}"))?;
+        .map_err(|e| BackendError::ResourcesUnavailable(format!("resource_dir: {e}")))?;
 
     #[cfg(target_os = "windows")]
     let exe_name = "almready-backend.exe";
     #[cfg(not(target_os = "windows"))]
     let exe_name = "almready-backend";
 
-    let exe_path = resource_dir.join("almready-backend").join(exe_name);
+    Ok(resource_dir.join("almready-backend").join(exe_name))
+}
+
+fn spawn_sidecar(app: &AppHandle) -> Result<Spawned, BackendError> {
+    let mut exe_path = sidecar_exe_path(app)?;
+    // Debug builds without a bundled sidecar run the backend from source.
+    let mut dev_args = None;
+    if dev_backend::applies(&exe_path) {
+        let (program, args) = dev_backend::command();
+        shell_log::log(
+            Level::Info,
+            "sidecar",
+            format!("no bundled sidecar, starting the dev backend: {program:?} {args:?}"),
+        );
+        exe_path = program;
+        dev_args = Some(args);
+    } else {
+        bundle_check::check(app, &exe_path)?;
+    }
 
     // OS user-data directory for session persistence.
     // macOS → ~/Library/Application Support/ALMReady
     // Windows → %APPDATA%\ALMReady
-    let data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("app_data_dir: {e}"))?;
+    let data_dir = paths::data_dir(app).map_err(BackendError::DataDirUnavailable)?;
 
-    // Tauri webview origins – one per platform, both listed for safety.
-    let cors_origins = "tauri://localhost,https://tauri.localhost";
+    let backend_env = app.state::<SettingsStore>().get().backend_env;
 
-    let mut child = std::process::Command::new(&exe_path)
-        .env("ALMREADY_DATA_DIR", &data_dir)
-        .env("ALMREADY_CORS_ORIGINS", cors_origins)
-        // Capture stdout so we can read the PORT:{n} line.
-        .stdout(std::process::Stdio::piped())
-        // Discard stderr from the sidecar (uvicorn noise).
-        .stderr(std::process::Stdio::null())
-        .spawn()
-        .map_err(|e| format!("spawn {exe_path:?}: {e}"))?;
+    let token = session_token::generate().map_err(|e| BackendError::SpawnFailed {
+        message: format!("cannot generate session token: {e}"),
+        kind: std::io::ErrorKind::Other,
+        raw_os_error: e.raw_os_error(),
+    })?;
+
+    // A sidecar orphaned by a crashed shell still holds a port and the
+    // session files.
+    let pid_path = pid_file::path(&data_dir);
+    pid_file::kill_leftover(&pid_path, &exe_path);
+
+    // A port file left by a previous sidecar would point at a dead port.
+    let port_file = port_reader::port_file_path(&data_dir);
+    let _ = std::fs::remove_file(&port_file);
+
+    let mut command = sidecar_command(&exe_path, &data_dir, &backend_env, &port_file, &token);
+    if let Some(args) = dev_args {
+        command.args(args).current_dir(dev_backend::backend_dir());
+    }
+    let mut tree = ProcessTree::spawn(&mut command)
+        .map_err(|e| BackendError::spawn(format!("spawn {exe_path:?}: {e}"), &e))?;
+    let child = tree.child_mut();
+    if let Err(e) = pid_file::write(&pid_path, child.id()) {
+        shell_log::log(Level::Warn, "sidecar", e);
+    }
+
+    if let Some(stderr) = child.stderr.take() {
+        logpipe::spawn_stderr_pump(app.clone(), stderr, logpipe::backend_log_path(&data_dir));
+    }
 
     let stdout = child
         .stdout
         .take()
-        .ok_or_else(|| "stdout pipe not available".to_string())?;
+        .ok_or_else(|| BackendError::SpawnFailed {
+            message: "stdout pipe not available".into(),
+            kind: std::io::ErrorKind::BrokenPipe,
+            raw_os_error: None,
+        })?;
 
-    // Channel: the stdout-reader task sends the port; the health-check task
-    // receives it.
-    let (tx, rx) = tokio::sync::oneshot::channel::<u16>();
+    // A blocking task reads stdout line-by-line (BufReader::lines() blocks)
+    // and reports the port, or why there is none, over a oneshot channel.
+    let port_rx = port_reader::spawn(move || port_reader::read_port(BufReader::new(stdout)));
 
-    // Spawn a blocking task to read the sidecar's stdout line-by-line.
-    // We use spawn_blocking because std::io::BufReader::lines() blocks.
-    tauri::async_runtime::spawn(async move {
-        let port = tauri::async_runtime::spawn_blocking(move || {
-            let reader = BufReader::new(stdout);
-            for line in reader.lines().flatten() {
-                if let Some(port_str) = line.strip_prefix("PORT:") {
-                    if let Ok(p) = port_str.trim().parse::<u16>() {
-                        return p;
+    Ok(Spawned {
+        tree,
+        port_rx,
+        port_file,
+        pid_file: pid_path,
+        token,
+    })
+}
+
+// ── Main window creation ─────────────────────────────────────────────────────
+
+fn main_window_spec(compact: bool) -> WindowSpec<'static> {
+    WindowSpec {
+        label: MAIN_LABEL,
+        page: "index.html",
+        title: "ALMReady",
+        size: (1440.0, 900.0),
+        min_size: None,
+        center: true,
+        preset: WindowPreset::for_mode(compact),
+        capabilities: Capability::ALL,
+    }
+}
+
+async fn create_main_window(app: &AppHandle, port: u16) {
+    let compact = app.state::<SettingsStore>().get().compact_mode;
+    let mut config = WindowConfig::for_app(app, &main_window_spec(compact), port);
+    window_state::restore_into(app, &mut config);
+    let window = WindowFactory::build(app, config).inspect_err(|e| {
+        shell_log::log(
+            Level::Error,
+            "window",
+            format!("failed to create main window: {e}"),
+        )
+    });
+
+    if window.is_ok() {
+        license::notify_if_invalid(app);
+    }
+}
+
+/// `--dump-window-config`: print the main window's config as it would be
+/// built (the port is not known yet and shows as 0) and exit.
+fn dump_main_window_config(app: &AppHandle) {
+    let compact = app.state::<SettingsStore>().get().compact_mode;
+    let mut config = WindowConfig::for_app(app, &main_window_spec(compact), 0);
+    window_state::restore_into(app, &mut config);
+    let config = config.redacted();
+    match serde_json::to_string_pretty(&config) {
+        Ok(json) => println!("{json}"),
+        Err(e) => eprintln!("[ALMReady] cannot serialize window config: {e}"),
+    }
+    app.exit(0);
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct SecondInstance {
+    args: Vec<String>,
+    cwd: String,
+}
+
+/// Called in the running instance when ALMReady is launched again.  The
+/// second process has already exited (before spawning a sidecar); bring
+/// the existing window forward and pass on its arguments as
+/// `second-instance`.
+fn on_second_instance(app: &AppHandle, args: Vec<String>, cwd: String) {
+    shell_log::log(
+        Level::Info,
+        "window",
+        format!("second launch with {args:?}, focusing the running instance"),
+    );
+    if app.state::<BackendProcess>().is_shutting_down() {
+        return;
+    }
+    if args.iter().any(|arg| arg == last_failure::SHOW_FLAG) {
+        last_failure::show(app);
+    }
+    session_files::open(
+        app,
+        session_files::paths_from_args(args.iter().skip(1), Path::new(&cwd)),
+    );
+    // Before the main window exists the splash is what's on screen.
+    let window = app
+        .get_webview_window(MAIN_LABEL)
+        .or_else(|| app.get_webview_window(splash::LABEL));
+    if let Some(window) = window {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+    events::emit(
+        app,
+        events::Audience::MainOnly,
+        "second-instance",
+        SecondInstance { args, cwd },
+    );
+}
+
+// ── Startup ──────────────────────────────────────────────────────────────────
+
+/// Show the "engine failed to start" dialog and wait for the user to dismiss
+/// it.  Returns true if they chose Retry (only offered when `can_retry` and
+/// the failure's category is one a retry can fix).
+async fn startup_failed_dialog(
+    app: &AppHandle,
+    error: &BackendError,
+    exit_code: Option<i32>,
+    can_retry: bool,
+) -> bool {
+    let category = failure::category(error, exit_code);
+    shell_log::log(
+        Level::Error,
+        "startup",
+        format!(
+            "failure category: {category:?} (update related: {})",
+            category.update_related()
+        ),
+    );
+    let can_retry = can_retry && category.retryable();
+
+    let mut message = format!("The ALMReady calculation engine failed to start.\n\n{error}");
+    if let Some(code) = exit_code {
+        message.push_str(&format!("\n\nThe engine process exited with code {code}."));
+    }
+    if let Some(guidance) = category.guidance() {
+        message.push_str(&format!("\n\n{guidance}"));
+    }
+
+    let buttons = || {
+        if can_retry {
+            MessageDialogButtons::YesNoCancelCustom(
+                "Retry".into(),
+                support_bundle::SAVE_LABEL.into(),
+                "Quit".into(),
+            )
+        } else {
+            MessageDialogButtons::OkCancelCustom(support_bundle::SAVE_LABEL.into(), "Quit".into())
+        }
+    };
+    let recording = tauri::async_runtime::spawn(last_failure::record(
+        app.clone(),
+        FailureKind::StartupFailed,
+        category,
+        exit_code,
+        message.clone(),
+        can_retry,
+    ));
+
+    // Saving diagnostics brings the dialog back, so the user still decides
+    // between retrying and quitting afterwards.
+    let retry = loop {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        app.dialog()
+            .message(message.clone())
+            .title("ALMReady")
+            .kind(MessageDialogKind::Error)
+            .buttons(buttons())
+            .show_with_result(move |result| {
+                let _ = tx.send(result);
+            });
+
+        // Custom buttons come back by label on some platforms and by
+        // position (Yes/No/Ok) on others.
+        match rx.await {
+            Ok(MessageDialogResult::Custom(label)) if label == support_bundle::SAVE_LABEL => {}
+            Ok(MessageDialogResult::No) if can_retry => {}
+            Ok(MessageDialogResult::Ok) if !can_retry => {}
+            Ok(MessageDialogResult::Custom(label)) => break can_retry && label == "Retry",
+            Ok(MessageDialogResult::Yes) => break can_retry,
+            _ => break false,
+        }
+        if let Err(e) = support_bundle::save(app).await {
+            shell_log::log(Level::Warn, "support-bundle", e.clone());
+            let (tx, rx) = tokio::sync::oneshot::channel();
+            app.dialog()
+                .message(format!("The diagnostics could not be saved.\n\n{e}"))
+                .title("ALMReady")
+                .kind(MessageDialogKind::Warning)
+                .show(move |_| {
+                    let _ = tx.send(());
+                });
+            let _ = rx.await;
+        }
+    };
+    // Written before the app exits.
+    let _ = recording.await;
+    retry
+}
+
+/// Outcome of one attempt to bring the sidecar up.
+enum Launch {
+    /// Healthy on this port; child and port are stored in `BackendProcess`.
+    Ready(u16),
+    /// The binary could not be started at all.
+    SpawnFailed(BackendError),
+    /// It started but exited or never became healthy, and has been stopped.
+    Failed {
+        error: BackendError,
+        exit_code: Option<i32>,
+    },
+    /// The app began shutting down while we were waiting.
+    Cancelled,
+}
+
+/// How long a running sidecar gets to print its `PORT:` line, by default
+/// (see `timeouts`).
+const PORT_TIMEOUT: Duration = Timeouts::DEFAULT.port;
+
+/// How long to wait for the port file after the stdout reader failed while
+/// the sidecar is still running.
+const PORT_FILE_WAIT: Duration = Duration::from_secs(15);
+
+/// Poll the port-file fallback until it names a port, the sidecar exits or
+/// `PORT_FILE_WAIT` runs out.
+async fn wait_for_port_file(backend: &BackendProcess, path: &Path) -> Option<u16> {
+    let deadline = tokio::time::Instant::now() + PORT_FILE_WAIT;
+    loop {
+        if let Some(port) = port_reader::read_port_file(path) {
+            return Some(port);
+        }
+        if backend.has_exited() || tokio::time::Instant::now() >= deadline {
+            return None;
+        }
+        tokio::time::sleep(Duration::from_millis(250)).await;
+    }
+}
+
+/// Backoff before each new attempt after a launch failed right away.
+const LAUNCH_RETRY_DELAYS: [Duration; 3] = [
+    Duration::from_secs(1),
+    Duration::from_secs(2),
+    Duration::from_secs(4),
+];
+
+/// Spawn the sidecar, store it, and wait for its port and health check.
+/// Shared by the initial start (with the splash window), `restart_backend`
+/// and the watchdog.
+///
+/// A launch that fails right away – the spawn itself, or the engine exiting
+/// before its port – is retried after each of `LAUNCH_RETRY_DELAYS`: on some
+/// Windows machines antivirus kills the first start of the PyInstaller exe
+/// while scanning it, and the next one works.  A missing executable (dev
+/// mode) and refused data are not retried (see `failure::retries_launch`).
+async fn launch_sidecar(app: &AppHandle, with_splash: bool) -> Launch {
+    if let Err(e) = hooks::run(app, Hook::PreBackendStart, None).await {
+        return Launch::SpawnFailed(BackendError::HookFailed(e));
+    }
+
+    let mut delays = LAUNCH_RETRY_DELAYS.iter();
+    loop {
+        let launch = launch_once(app, with_splash).await;
+        let (error, exit_code) = match &launch {
+            Launch::SpawnFailed(error) => (error, None),
+            Launch::Failed { error, exit_code } => (error, *exit_code),
+            Launch::Ready(_) | Launch::Cancelled => return launch,
+        };
+        if !failure::retries_launch(error, exit_code) {
+            return launch;
+        }
+        let Some(delay) = delays.next() else {
+            return launch;
+        };
+        shell_log::log(
+            Level::Warn,
+            "startup",
+            format!("launch failed ({error}, exit code {exit_code:?}), retrying in {delay:?}"),
+        );
+        tokio::time::sleep(*delay).await;
+        if app.state::<BackendProcess>().is_shutting_down() {
+            return Launch::Cancelled;
+        }
+        lifecycle::restarting(app);
+    }
+}
+
+/// One launch attempt.  If the stdout reader fails (rather than the engine
+/// exiting) the sidecar is respawned once.
+async fn launch_once(app: &AppHandle, with_splash: bool) -> Launch {
+    let backend = app.state::<BackendProcess>();
+    let started = Instant::now();
+    let mut respawned = false;
+
+    let port = loop {
+        let mut spawned = match spawn_sidecar(app) {
+            Ok(spawned) => spawned,
+            Err(e) => return Launch::SpawnFailed(e),
+        };
+        let pid = spawned.tree.child_mut().id();
+        shell_log::log(
+            Level::Info,
+            "sidecar",
+            format!(
+                "spawned pid {pid} after {} ms",
+                started.elapsed().as_millis()
+            ),
+        );
+        lifecycle::spawned(app, pid);
+
+        // Store the process tree for cleanup on close.
+        *backend.child.lock().unwrap() = Some(spawned.tree);
+        *backend.pid_file.lock().unwrap() = Some(spawned.pid_file);
+        *backend.token.lock().unwrap() = Some(spawned.token);
+        backend.set_status(BackendStatus::Starting);
+        tray::refresh(app);
+
+        if with_splash {
+            splash::show(app);
+            splash::set_status(app, splash::STARTING_ENGINE);
+        }
+
+        // Wait for the sidecar to print its port.  A bundle that hangs
+        // before printing it (seen with a corrupted onedir extraction) must
+        // not stall startup with no window and no error.
+        let port_timeout = timeouts::current().port;
+        let outcome = tokio::time::timeout(port_timeout, spawned.port_rx).await;
+        if backend.is_shutting_down() {
+            return Launch::Cancelled;
+        }
+        let outcome = match outcome {
+            Ok(outcome) => outcome.unwrap_or(PortOutcome::Exited),
+            Err(_) => {
+                let status = backend
+                    .exit_status()
+                    .map_or_else(|| "still running".to_string(), |s| s.to_string());
+                shell_log::log(
+                    Level::Error,
+                    "sidecar",
+                    format!("never printed PORT within {port_timeout:?} (child: {status})"),
+                );
+                break Err(BackendError::PortTimeout);
+            }
+        };
+
+        let from_file = match &outcome {
+            PortOutcome::Port(_) => None,
+            PortOutcome::Exited => port_reader::read_port_file(&spawned.port_file),
+            PortOutcome::ReaderPanicked(message) => {
+                shell_log::log(
+                    Level::Error,
+                    "sidecar",
+                    format!("stdout reader panicked: {message}"),
+                );
+                *backend.reader_failure.lock().unwrap() = Some(message.clone());
+                degradations::set(
+                    app,
+                    Degradation::new(
+                        DegradationCode::EngineOutputUnreadable,
+                        Severity::Warning,
+                        serde_json::json!({ "message": message }),
+                    ),
+                );
+                wait_for_port_file(&backend, &spawned.port_file).await
+            }
+        };
+        if backend.is_shutting_down() {
+            return Launch::Cancelled;
+        }
+
+        match port_reader::resolve(&outcome, from_file, respawned) {
+            PortStep::Ready(port) => break Ok(port),
+            PortStep::Fail(reason) => break Err(reason),
+            PortStep::Respawn => {
+                shell_log::log(
+                    Level::Warn,
+                    "sidecar",
+                    "engine output unreadable, respawning sidecar",
+                );
+                let exit_code = backend.terminate().await;
+                lifecycle::terminated(app, exit_code);
+                lifecycle::restarting(app);
+                respawned = true;
+            }
+        }
+    };
+
+    let error = match port {
+        Err(error) => {
+            shell_log::log(Level::Error, "sidecar", format!("FATAL: {error}"));
+            error
+        }
+        Ok(port) => {
+            shell_log::log(
+                Level::Info,
+                "sidecar",
+                format!(
+                    "reported port {port} after {} ms, polling health...",
+                    started.elapsed().as_millis()
+                ),
+            );
+            backend.port.set(Some(port));
+            lifecycle::port(app, port);
+            if with_splash {
+                splash::set_status(app, splash::WAITING_FOR_HEALTH);
+            }
+            let limits = timeouts::current();
+            let healthy = wait_for_backend(
+                port,
+                backend.token().as_deref(),
+                limits.startup,
+                limits.health_interval,
+            )
+            .await;
+            if backend.is_shutting_down() {
+                // Quit while we were waiting; the exit handler owns cleanup.
+                return Launch::Cancelled;
+            }
+            if !healthy {
+                shell_log::log(
+                    Level::Error,
+                    "sidecar",
+                    format!("FATAL: health check timed out after {:?}", limits.startup),
+                );
+                BackendError::HealthTimeout
+            } else {
+                shell_log::log(
+                    Level::Info,
+                    "sidecar",
+                    format!("healthy after {} ms", started.elapsed().as_millis()),
+                );
+                if with_splash {
+                    splash::set_status(app, splash::WAITING_FOR_COMPONENTS);
+                }
+                let token = backend.token();
+                let components =
+                    readiness::wait_for_components(app, port, token.as_deref(), started).await;
+                if backend.is_shutting_down() {
+                    return Launch::Cancelled;
+                }
+                match components {
+                    Ok(()) => {
+                        shell_log::log(
+                            Level::Info,
+                            "sidecar",
+                            format!("ready after {} ms", started.elapsed().as_millis()),
+                        );
+                        backend.set_status(BackendStatus::Ready);
+                        tray::refresh(app);
+                        lifecycle::ready(app, port);
+                        return Launch::Ready(port);
+                    }
+                    Err(names) => {
+                        shell_log::log(
+                            Level::Error,
+                            "sidecar",
+                            format!(
+                                "FATAL: components not ready after {:?}: {names:?}",
+                                timeouts::current().readiness
+                            ),
+                        );
+                        BackendError::ComponentsNotReady(names)
                     }
                 }
             }
-            // Sidecar exited without printing a port – return 0 as sentinel.
-            0u16
-        })
-        .await
-        .unwrap_or(0);
+        }
+    };
 
-        let _ = tx.send(port);
-    });
+    // Make sure the Python process isn't left running behind whatever
+    // reports the failure.
+    let exit_code = terminate_backend(app).await;
+    backend.set_status(BackendStatus::Crashed);
+    tray::refresh(app);
+    Launch::Failed { error, exit_code }
+}
+
+/// Spawn the sidecar, wait for its port and health check, then open the
+/// main window and start the watchdog.  A failure shows an error dialog
+/// offering one retry; the app only exits after the user has dismissed it.
+/// A running update installer is waited for first (see `installer`).
+async fn start_backend_and_open(app: AppHandle) {
+    match backend_override::from_env() {
+        Ok(Some(port)) => return open_external_backend(app, port).await,
+        Ok(None) => {}
+        Err(e) => {
+            shell_log::log(Level::Error, "startup", format!("FATAL: {e}"));
+            backend_override::error_dialog(&app, &e).await;
+            app.exit(1);
+            return;
+        }
+    }
+    if !installer::wait_until_finished(&app).await {
+        app.exit(0);
+        return;
+    }
+    let mut retried = false;
+    // Debug build without a bundled sidecar: `spawn_sidecar` runs the dev
+    // backend, and if that fails we fall back to what dev mode did before.
+    let dev_fallback = sidecar_exe_path(&app).is_ok_and(|p| dev_backend::applies(&p));
 
-    Ok((child, rx))
+    loop {
+        match launch_sidecar(&app, true).await {
+            Launch::Ready(port) => {
+                shell_log::log(
+                    Level::Info,
+                    "startup",
+                    format!("backend ready on port {port}, opening window"),
+                );
+                if !sidecar_version::check_compatible(&app, port).await {
+                    app.exit(1);
+                    return;
+                }
+                splash::set_status(&app, splash::OPENING_WORKSPACE);
+                create_main_window(&app, port).await;
+                splash::close(&app);
+                watchdog::spawn(&app);
+                updater::announce_installed(&app);
+                session_files::deliver_pending(&app).await;
+                return;
+            }
+            Launch::SpawnFailed(BackendError::BinaryMissing(e)) if !retried && !dev_fallback => {
+                // A release build missing its sidecar outside the bundle
+                // (e.g. `cargo run --release`): nothing to start.
+                shell_log::log(
+                    Level::Info,
+                    "startup",
+                    format!("sidecar not available ({e}), assuming dev mode"),
+                );
+                return;
+            }
+            Launch::SpawnFailed(e) | Launch::Failed { error: e, .. } if dev_fallback => {
+                // The dev backend didn't come up (no venv, missing
+                // dependencies, ...).  Leave it to a separately started
+                // uvicorn, as before.
+                splash::close(&app);
+                shell_log::log(
+                    Level::Warn,
+                    "startup",
+                    format!(
+                        "dev backend failed ({e}); start it yourself or set {}",
+                        dev_backend::CMD_ENV
+                    ),
+                );
+                return;
+            }
+            Launch::SpawnFailed(e) => {
+                shell_log::log(
+                    Level::Error,
+                    "startup",
+                    format!("FATAL: sidecar spawn failed: {e}"),
+                );
+                startup_failed_dialog(&app, &e, None, false).await;
+                app.exit(1);
+                return;
+            }
+            Launch::Cancelled => return,
+            Launch::Failed { error, exit_code } => {
+                // No window exists yet; don't leave the splash behind the
+                // dialog.
+                splash::close(&app);
+                if startup_failed_dialog(&app, &error, exit_code, !retried).await {
+                    shell_log::log(Level::Info, "startup", "retrying sidecar startup");
+                    retried = true;
+                    continue;
+                }
+                app.exit(1);
+                return;
+            }
+        }
+    }
 }
 
-// ── Main window creation ─────────────────────────────────────────────────────
+/// Use the backend the developer started on `port` instead of spawning the
+/// sidecar.  Only the port is stored, so shutdown finds no child to stop.
+async fn open_external_backend(app: AppHandle, port: u16) {
+    shell_log::log(
+        Level::Info,
+        "startup",
+        format!(
+            "{}={port}: using an external backend, not spawning the sidecar",
+            backend_override::PORT_ENV
+        ),
+    );
+    let backend = app.state::<BackendProcess>();
+    backend.external.store(true, Ordering::SeqCst);
+    splash::show(&app);
+    splash::set_status(&app, splash::WAITING_FOR_HEALTH);
 
-async fn create_main_window(app: &AppHandle, port: u16) {
-    // initialization_script runs BEFORE any page scripts (React, Vite bundle).
-    // This guarantees window.__BACKEND_PORT__ is synchronously available when
-    // api.ts evaluates its module-level API_BASE constant.
-    let init_script = format!("window.__BACKEND_PORT__ = {port};");
+    let limits = timeouts::current();
+    if !wait_for_backend(port, None, limits.startup, limits.health_interval).await {
+        splash::close(&app);
+        let message = format!(
+            "No ALMReady backend answered on {}:{port} within {} seconds.\n\n\
+             Start it first, or unset {} to use the bundled engine.",
+            http::BACKEND_HOST,
+            limits.startup.as_secs(),
+            backend_override::PORT_ENV
+        );
+        shell_log::log(
+            Level::Error,
+            "startup",
+            format!("FATAL: external backend on port {port} not healthy"),
+        );
+        backend_override::error_dialog(&app, &message).await;
+        app.exit(1);
+        return;
+    }
 
-    let _ = WebviewWindowBuilder::new(
-        app,
-        "main",
-        WebviewUrl::App("index.html".into()),
-    )
-    .initialization_script(&init_script)
-    .title("ALMReady")
-    .inner_size(1440.0, 900.0)
-    .min_inner_size(1024.0, 768.0)
-    .center()
-    .build()
-    .inspect_err(|e| eprintln!("[ALMReady] failed to create main window: {e}"));
+    backend.port.set(Some(port));
+    lifecycle::port(&app, port);
+    degradations::set(
+        &app,
+        Degradation::new(
+            DegradationCode::ExternalBackend,
+            Severity::Info,
+            serde_json::json!({ "port": port }),
+        ),
+    );
+    backend.set_status(BackendStatus::Ready);
+    tray::refresh(&app);
+    lifecycle::ready(&app, port);
+    splash::set_status(&app, splash::OPENING_WORKSPACE);
+    create_main_window(&app, port).await;
+    splash::close(&app);
+    updater::announce_installed(&app);
+    session_files::deliver_pending(&app).await;
 }
 
 // ── Entry point ──────────────────────────────────────────────────────────────
 
+/// If the command line is `conformance …`, run the backend protocol
+/// conformance suite and return its exit code; otherwise `None`.
+pub fn run_conformance() -> Option<i32> {
+    conformance::main(&std::env::args().collect::<Vec<_>>())
+}
+
 pub fn run() {
     tauri::Builder::default()
-        .manage(BackendProcess(Mutex::new(None)))
+        // First, so a second launch exits before anything else starts.  The
+        // lock is an OS object (named mutex, D-Bus name, socket) that goes
+        // away with the process, so a crashed instance can't leave it stale.
+        .plugin(tauri_plugin_single_instance::init(|app, args, cwd| {
+            on_second_instance(app, args, cwd)
+        }))
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_updater::Builder::new().build())
+        .menu(menu::build)
+        .on_menu_event(menu::on_menu_event)
+        .register_asynchronous_uri_scheme_protocol(api_protocol::SCHEME, api_protocol::handle)
+        .manage(BackendProcess::default())
+        .manage(ActivityJournal::default())
+        .manage(screen_lock::ScreenLock::default())
+        .manage(WindowCapabilities::default())
+        .manage(events::ListenerCounts::default())
+        .manage(WindowConfigs::default())
+        .manage(window_state::GeometrySaver::default())
+        .manage(tray::TrayStatus::default())
+        .manage(lifecycle::Lifecycle::default())
+        .manage(degradations::Degradations::default())
+        .manage(SidecarHealthHistory::default())
+        .manage(session_files::PendingSessions::default())
+        .manage(exports::Exports::default())
+        .manage(reveal::RevealRoots::default())
+        .manage(keep_awake::KeepAwake::default())
+        .manage(printer::DefaultPrinter::default())
+        .manage(resource_usage::ResourceMonitor::default())
+        .manage(bundle_check::BundleIntegrity::default())
+        .manage(updater::Updates::default())
+        .invoke_handler({
+            let handler = tauri::generate_handler![
+                backend_status::get_backend_port,
+                backend_status::wait_for_backend_port,
+                backend_status::get_backend_status,
+                backend_status::restart_backend,
+                browser::get_default_browser,
+                build_info::get_build_info,
+                clipboard::get_clipboard_format,
+                cookies::get_webview_cookies,
+                cookies::get_session_cookie,
+                cookies::clear_webview_cookies,
+                cursor::set_cursor,
+                taskbar_progress::set_progress_window_style,
+                shortcuts::get_keyboard_shortcuts,
+                keep_awake::set_keep_awake,
+                journal::get_last_away_summary,
+                journal::record_activity,
+                alerts::get_alert_style,
+                alerts::set_alert_style,
+                alerts::preview_alert,
+                screen_lock::set_lock_passcode,
+                screen_lock::lock_screen,
+                screen_lock::unlock_screen,
+                screen_lock::is_screen_locked,
+                theme::get_theme_colors,
+                network::get_online_status,
+                paths::get_app_data_dir,
+                paths::get_app_cache_dir,
+                paths::get_app_log_dir,
+                diagnostics::run_self_checks,
+                disk_bench::test_disk_write_speed,
+                display::get_window_scale_factor,
+                exports::save_export,
+                exports::cancel_export,
+                reveal::reveal_in_file_manager,
+                support_bundle::collect_diagnostics,
+                degradations::get_degradations,
+                window_opacity::set_window_opacity,
+                window_position::get_window_position,
+                window_position::set_window_position,
+                window_size::get_window_size,
+                window_size::set_window_size,
+                detached_windows::open_window,
+                integrity::verify_file_integrity,
+                resource_usage::get_backend_resource_usage,
+                updater::check_for_updates,
+                updater::install_update,
+                zoom::get_zoom,
+                zoom::set_zoom,
+                resource_usage::set_resource_monitoring,
+                window_handle::get_window_handle,
+                profiler::capture_performance_profile,
+                sidecar_version::check_sidecar_update_available,
+                time_zone::get_time_zone,
+                locales::get_available_locales,
+                printer::get_default_printer,
+                tray::get_close_to_tray,
+                tray::set_close_to_tray,
+                events::get_window_capabilities,
+                events::get_event_listener_count,
+                events::track_listener,
+                health_history::get_health_history,
+                housekeeping::list_housekeeping_tasks,
+                housekeeping::set_job_active,
+                settings::import_preferences,
+                settings::export_preferences,
+                storage::get_indexed_db_usage,
+                webview_prefs::get_webview_prefs,
+                webview_prefs::set_webview_prefs,
+                window_factory::set_compact_mode,
+                window_factory::get_compact_mode,
+                window_factory::dump_window_config,
+                window_factory::get_active_window_label,
+                lifecycle::get_lifecycle_snapshot,
+                last_failure::get_last_failure,
+                session_files::get_opened_sessions,
+                hooks::get_hook_settings,
+                hooks::set_hook_settings,
+                license::get_license_status,
+                log_viewer::open_log_viewer_window,
+                logpipe::get_stderr_size,
+                logpipe::truncate_backend_log,
+                migration::detect_legacy_install,
+                migration::plan_legacy_migration,
+                migration::run_legacy_migration,
+                shell_log::subscribe_shell_log,
+                shell_log::unsubscribe_shell_log,
+                shell_log::read_shell_log,
+            ];
+            move |invoke: tauri::ipc::Invoke| {
+                let command = invoke.message.command();
+                shell_log::log(Level::Debug, "command", format!("invoke {command}"));
+                handler(invoke)
+            }
+        })
         .setup(|app| {
-            let app_handle = app.handle().clone();
+            let data_dir = paths::data_dir(app.handle())?;
+            shell_log::init(&paths::log_dir_in(&data_dir));
+            let settings = SettingsStore::load(data_dir.join("settings.json"));
+            app.manage(BrowserArgs::new(&settings.get().webview));
+            app.manage(settings);
+            housekeeping::spawn(app.handle().clone());
+            let today = chrono::Local::now().date_naive();
+            let license = LicenseStatus::check(&data_dir, today);
+            if let LicenseStatus::Invalid { reason } = &license {
+                degradations::set(
+                    app.handle(),
+                    Degradation::new(
+                        DegradationCode::LicenseInvalid,
+                        Severity::Error,
+                        serde_json::json!({ "reason": reason }),
+                    ),
+                );
+            }
+            app.manage(license);
+            if std::env::args().any(|arg| arg == "--dump-window-config") {
+                dump_main_window_config(app.handle());
+                return Ok(());
+            }
 
-            tauri::async_runtime::spawn(async move {
-                // Attempt to spawn the sidecar.
-                match spawn_sidecar(&app_handle) {
-                    Err(e) => {
-                        // In `cargo tauri dev` the sidecar binary doesn't
-                        // exist – dev mode uses the Vite dev server + a
-                        // separately-running uvicorn.  Log and create the
-                        // window pointing at the dev server (port from Vite).
-                        eprintln!("[ALMReady] sidecar not available ({e}), assuming dev mode");
-                        // In dev mode Tauri uses devUrl from config; the window
-                        // is created by Tauri automatically when devUrl is set.
-                        // Nothing to do here.
-                    }
+            if std::env::args().any(|arg| arg == last_failure::SHOW_FLAG) {
+                last_failure::show(app.handle());
+            }
+            if let Ok(cwd) = std::env::current_dir() {
+                let args = std::env::args_os().skip(1);
+                session_files::open(app.handle(), session_files::paths_from_args(args, &cwd));
+            }
+            if let Err(e) = tray::create(app.handle()) {
+                shell_log::log(Level::Warn, "tray", format!("no tray icon: {e}"));
+            }
+            let location = InstallLocation::detect();
+            if location.is_read_only() {
+                degradations::set(
+                    app.handle(),
+                    Degradation::new(
+                        DegradationCode::ReadOnlyInstall,
+                        Severity::Warning,
+                        serde_json::json!({ "kind": location.kind, "path": location.path }),
+                    ),
+                );
+            }
+            app.manage(location);
+            if let Some((dir, error)) = paths::portable_fallback(app.handle()) {
+                degradations::set(
+                    app.handle(),
+                    Degradation::new(
+                        DegradationCode::PortableDataUnwritable,
+                        Severity::Warning,
+                        serde_json::json!({ "path": dir, "error": error }),
+                    ),
+                );
+            }
+            timeouts::init(app.handle());
+            updater::take_installed(app.handle());
+            network::check(app.handle());
+            tauri::async_runtime::spawn(install_location::offer_move_to_applications(
+                app.handle().clone(),
+            ));
 
-                    Ok((child, rx)) => {
-                        // Store child handle for cleanup on close.
-                        *app_handle.state::<BackendProcess>().0.lock().unwrap() = Some(child);
-
-                        // Wait for the sidecar to print its port.
-                        let port = rx.await.unwrap_or(0);
-
-                        if port == 0 {
-                            eprintln!("[ALMReady] FATAL: sidecar exited before printing port");
-                            // Kill child and exit – no window was created yet.
-                            if let Some(mut c) = app_handle
-                                .state::<BackendProcess>()
-                                .0
-                                .lock()
-                                .unwrap()
-                                .take()
-                            {
-                                let _ = c.kill();
-                                let _ = c.wait();
-                            }
-                            std::process::exit(1);
-                        }
-
-                        eprintln!("[ALMReady] sidecar reported port {port}, polling health...");
-
-                        // Poll /api/health until ready.
-                        if !wait_for_backend(port).await {
-                            eprintln!("[ALMReady] FATAL: health check timed out after 30 s");
-                            if let Some(mut c) = app_handle
-                                .state::<BackendProcess>()
-                                .0
-                                .lock()
-                                .unwrap()
-                                .take()
-                            {
-                                let _ = c.kill();
-                                let _ = c.wait();
-                            }
-                            std::process::exit(1);
-                        }
-
-                        eprintln!("[ALMReady] backend ready on port {port}, opening window");
-                        create_main_window(&app_handle, port).await;
-                    }
-                }
-            });
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move { start_backend_and_open(app_handle).await });
 
             Ok(())
         })
-        .on_window_event(|window, event| {
-            if let tauri::WindowEvent::CloseRequested { .. } = event {
-                // Kill the sidecar so no zombie Python processes remain.
-                if let Some(mut child) = window
+        .on_window_event(|window, event| match event {
+            tauri::WindowEvent::CloseRequested { api, .. } if window.label() == MAIN_LABEL => {
+                window_state::save(window);
+                // The engine keeps running; the tray's Quit stops it.
+                if tray::hides_on_close(window.app_handle()) {
+                    api.prevent_close();
+                    let _ = window.hide();
+                    return;
+                }
+                close_workspace_window(window, api);
+            }
+            tauri::WindowEvent::CloseRequested { api, .. }
+                if detached_windows::is_detached(window.label()) =>
+            {
+                window_state::save(window);
+                close_workspace_window(window, api);
+            }
+            tauri::WindowEvent::Moved(_) | tauri::WindowEvent::Resized(_)
+                if window.label() == MAIN_LABEL
+                    || detached_windows::is_detached(window.label()) =>
+            {
+                window_state::schedule_save(window);
+            }
+            tauri::WindowEvent::Focused(focused) if window.label() == MAIN_LABEL => {
+                journal::on_main_window_focus(window.app_handle(), *focused);
+            }
+            tauri::WindowEvent::ThemeChanged(_) if window.label() == MAIN_LABEL => {
+                theme::on_theme_changed(window.app_handle());
+            }
+            tauri::WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                display::on_scale_factor_changed(window, *scale_factor);
+            }
+            tauri::WindowEvent::Destroyed => {
+                shell_log::log(
+                    Level::Info,
+                    "window",
+                    format!("window {} closed", window.label()),
+                );
+                window
+                    .app_handle()
+                    .state::<WindowCapabilities>()
+                    .unregister(window.label());
+                shell_log::on_window_destroyed(window.label());
+                window
                     .app_handle()
-                    .state::<BackendProcess>()
-                    .0
-                    .lock()
-                    .unwrap()
-                    .take()
-                {
-                    let _ = child.kill();
-                    let _ = child.wait(); // reap the zombie
+                    .state::<events::ListenerCounts>()
+                    .forget_window(window.label());
+                if window.label() == MAIN_LABEL {
+                    keep_awake::release_all(window.app_handle(), "main window closed");
                 }
+                window
+                    .app_handle()
+                    .state::<WindowConfigs>()
+                    .remove(window.label());
             }
+            _ => {}
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| match event {
+            // Cmd+Q / dock Quit on macOS end the run loop without a window
+            // CloseRequested, so stop the sidecar the same way here.  Exit is
+            // deferred until it is down, then requested again; by then there
+            // is no child and it goes through.
+            tauri::RunEvent::ExitRequested { api, code, .. } => {
+                if app.state::<BackendProcess>().is_running() {
+                    api.prevent_exit();
+                    let app = app.clone();
+                    tauri::async_runtime::spawn(async move {
+                        shutdown_backend(&app).await;
+                        app.exit(code.unwrap_or(0));
+                    });
+                }
+            }
+            // Last chance for exits that skipped ExitRequested.  Blocks so
+            // the child is reaped before we return and the process ends.
+            tauri::RunEvent::Exit => {
+                keep_awake::release_all(app, "exiting");
+                tauri::async_runtime::block_on(shutdown_backend(app));
+            }
+            // Finder hands over double-clicked session files here rather
+            // than on the command line.
+            #[cfg(target_os = "macos")]
+            tauri::RunEvent::Opened { urls } => {
+                let paths = urls.iter().filter_map(|url| url.to_file_path().ok());
+                session_files::open(app, paths.collect());
+            }
+            _ => {}
+        });
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::{
+        io::{BufRead, BufReader},
+        process::{Command, Stdio},
+    };
+
+    const QUICK: Escalation = Escalation {
+        http: Duration::from_millis(200),
+        terminate: Duration::from_millis(500),
+        kill: Duration::from_secs(2),
+    };
+
+    /// A backend holding `sh -c script`, once the script has printed a line.
+    fn backend_running(script: &str) -> (BackendProcess, u32) {
+        let mut tree = ProcessTree::spawn(
+            Command::new("sh")
+                .args(["-c", script])
+                .stdout(Stdio::piped()),
+        )
+        .unwrap();
+        let stdout = tree.child_mut().stdout.take().unwrap();
+        BufReader::new(stdout)
+            .read_line(&mut String::new())
+            .unwrap();
+        let pid = tree.child_mut().id();
+        let backend = BackendProcess::default();
+        *backend.child.lock().unwrap() = Some(tree);
+        (backend, pid)
+    }
+
+    fn gone(pid: u32) -> bool {
+        // SAFETY: signal 0 only checks that the pid exists.
+        unsafe { libc::kill(pid as libc::pid_t, 0) != 0 }
+    }
+
+    #[tokio::test]
+    async fn terminate_stops_at_sigterm_when_it_is_honoured() {
+        let (backend, pid) = backend_running("echo ready; exec sleep 60");
+        let started = std::time::Instant::now();
+        assert_eq!(backend.terminate_with(QUICK).await, None);
+        assert!(started.elapsed() < QUICK.terminate);
+        assert!(gone(pid));
+        assert_eq!(backend.status(), BackendStatus::Stopped);
+    }
+
+    #[tokio::test]
+    async fn terminate_kills_the_tree_when_sigterm_is_ignored() {
+        let (backend, pid) = backend_running("trap '' TERM; echo ready; sleep 60");
+        let started = std::time::Instant::now();
+        assert_eq!(backend.terminate_with(QUICK).await, None);
+        assert!(started.elapsed() >= QUICK.terminate);
+        assert!(started.elapsed() < QUICK.terminate + QUICK.kill);
+        assert!(gone(pid));
+        assert!(!backend.is_running());
+    }
 }
@@ -0,0 +1,262 @@
+//! `license.key` validation.
+//!
+//! The license file lives in ALMREADY_DATA_DIR and is a small JSON document:
+//!
+//! ```json
+//! { "license_key": "ACME-0001", "expires_at": "2027-03-31",
+//!   "seat_count": 25, "tier": "pro", "signature": "<hex>" }
+//! ```
+//!
+//! `signature` is HMAC-SHA256 over `license_key|expires_at|seat_count|tier`
+//! with the vendor secret baked in at build time via
+//! `ALMREADY_LICENSE_SECRET`.  Packaging a release sets `ALMREADY_RELEASE`,
+//! and `build.rs` then refuses to build without the secret; any other build
+//! without one reports `LicenseStatus::Unverifiable` and checks nothing.  A
+//! valid license injects `window.__LICENSE_TIER__`; an invalid one emits
+//! `license-invalid { reason }` once the main window exists.
+
+use std::path::Path;
+
+use chrono::NaiveDate;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    data_dir_access::DataDirAccess,
+    events::{self, Audience},
+    shell_log::{self, Level},
+};
+
+const LICENSE_FILE: &str = "license.key";
+
+const SECRET: Option<&str> = option_env!("ALMREADY_LICENSE_SECRET");
+
+#[derive(Debug, Clone, Deserialize)]
+struct LicenseFile {
+    license_key: String,
+    expires_at: NaiveDate,
+    seat_count: u32,
+    tier: String,
+    signature: String,
+}
+
+/// A license whose signature and expiry have been checked.
+#[derive(Debug, Clone, Serialize)]
+pub struct LicenseValidator {
+    pub license_key: String,
+    pub expires_at: NaiveDate,
+    pub seat_count: u32,
+    pub tier: String,
+}
+
+impl LicenseValidator {
+    /// Read and verify `{data_dir}/license.key`.  The error is the
+    /// user-facing reason sent with `license-invalid`.
+    fn load(data_dir: &Path, secret: &str, today: NaiveDate) -> Result<Self, String> {
+        let path = data_dir.join(LICENSE_FILE);
+        let read = DataDirAccess::unleased().with(&[&path], || std::fs::read_to_string(&path));
+        let text = match read {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err("No license file found.".into())
+            }
+            Err(e) => return Err(format!("The license file could not be read: {e}")),
+        };
+        Self::parse(&text, secret, today)
+    }
+
+    /// Check the contents of a license file against `secret`.
+    fn parse(text: &str, secret: &str, today: NaiveDate) -> Result<Self, String> {
+        let file: LicenseFile = serde_json::from_str(text)
+            .map_err(|e| format!("The license file is malformed: {e}"))?;
+
+        verify_signature(secret, &file)?;
+
+        if file.expires_at < today {
+            return Err(format!("The license expired on {}.", file.expires_at));
+        }
+
+        Ok(LicenseValidator {
+            license_key: file.license_key,
+            expires_at: file.expires_at,
+            seat_count: file.seat_count,
+            tier: file.tier,
+        })
+    }
+}
+
+fn signed_payload(file: &LicenseFile) -> String {
+    format!(
+        "{}|{}|{}|{}",
+        file.license_key, file.expires_at, file.seat_count, file.tier
+    )
+}
+
+fn verify_signature(secret: &str, file: &LicenseFile) -> Result<(), String> {
+    let signature =
+        hex::decode(file.signature.trim()).map_err(|_| "The license signature is malformed.")?;
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).map_err(|e| e.to_string())?;
+    mac.update(signed_payload(file).as_bytes());
+    // verify_slice compares in constant time.
+    mac.verify_slice(&signature)
+        .map_err(|_| "The license signature is invalid.".to_string())
+}
+
+// ── State & events ──────────────────────────────────────────────────────────
+
+/// Managed state: outcome of the startup license check.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "kebab-case")]
+pub enum LicenseStatus {
+    Valid(LicenseValidator),
+    Invalid {
+        reason: String,
+    },
+    /// This build has no `ALMREADY_LICENSE_SECRET` to check signatures
+    /// with (a development or CI build).
+    Unverifiable,
+}
+
+impl LicenseStatus {
+    /// Check `{data_dir}/license.key` with the secret this build carries.
+    pub fn check(data_dir: &Path, today: NaiveDate) -> Self {
+        Self::check_with(data_dir, SECRET, today)
+    }
+
+    fn check_with(data_dir: &Path, secret: Option<&str>, today: NaiveDate) -> Self {
+        let Some(secret) = secret else {
+            return LicenseStatus::Unverifiable;
+        };
+        match LicenseValidator::load(data_dir, secret, today) {
+            Ok(license) => LicenseStatus::Valid(license),
+            Err(reason) => LicenseStatus::Invalid { reason },
+        }
+    }
+
+    pub fn tier(&self) -> Option<&str> {
+        match self {
+            LicenseStatus::Valid(license) => Some(&license.tier),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+struct LicenseInvalid<'a> {
+    reason: &'a str,
+}
+
+/// Emit `license-invalid` to the main window if the check failed.
+pub fn notify_if_invalid(app: &AppHandle) {
+    match &*app.state::<LicenseStatus>() {
+        LicenseStatus::Valid(_) => {}
+        LicenseStatus::Invalid { reason } => {
+            shell_log::log(Level::Warn, "license", format!("license invalid: {reason}"));
+            events::emit(
+                app,
+                Audience::MainOnly,
+                "license-invalid",
+                LicenseInvalid { reason },
+            );
+        }
+        LicenseStatus::Unverifiable => shell_log::log(
+            Level::Warn,
+            "license",
+            "built without ALMREADY_LICENSE_SECRET, licenses are not checked",
+        ),
+    }
+}
+
+/// For late listeners that missed the `license-invalid` event.
+#[tauri::command]
+pub fn get_license_status(status: tauri::State<'_, LicenseStatus>) -> LicenseStatus {
+    status.inner().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_SECRET: &str = "test-secret";
+
+    fn day(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    fn license(expires_at: &str, tier: &str, secret: &str) -> String {
+        let mut file = LicenseFile {
+            license_key: "ACME-0001".into(),
+            expires_at: day(expires_at),
+            seat_count: 25,
+            tier: tier.into(),
+            signature: String::new(),
+        };
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(signed_payload(&file).as_bytes());
+        file.signature = hex::encode(mac.finalize().into_bytes());
+        serde_json::json!({
+            "license_key": file.license_key,
+            "expires_at": file.expires_at,
+            "seat_count": file.seat_count,
+            "tier": file.tier,
+            "signature": file.signature,
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn accepts_a_signed_license_until_it_expires() {
+        let text = license("2027-03-31", "pro", TEST_SECRET);
+        let valid = LicenseValidator::parse(&text, TEST_SECRET, day("2027-03-31")).unwrap();
+        assert_eq!(valid.tier, "pro");
+        assert_eq!(valid.seat_count, 25);
+
+        let expired = LicenseValidator::parse(&text, TEST_SECRET, day("2027-04-01"));
+        assert_eq!(expired.unwrap_err(), "The license expired on 2027-03-31.");
+    }
+
+    #[test]
+    fn rejects_tampered_and_foreign_signatures() {
+        let today = day("2026-01-01");
+        let tampered = license("2027-03-31", "basic", TEST_SECRET).replace("basic", "pro");
+        assert_eq!(
+            LicenseValidator::parse(&tampered, TEST_SECRET, today).unwrap_err(),
+            "The license signature is invalid."
+        );
+        let foreign = license("2027-03-31", "pro", "another-secret");
+        assert!(LicenseValidator::parse(&foreign, TEST_SECRET, today).is_err());
+
+        let unsigned = license("2027-03-31", "pro", TEST_SECRET)
+            .replace(r#""signature":""#, r#""signature":"zz"#);
+        assert_eq!(
+            LicenseValidator::parse(&unsigned, TEST_SECRET, today).unwrap_err(),
+            "The license signature is malformed."
+        );
+    }
+
+    #[test]
+    fn a_build_without_a_secret_reports_licenses_as_unverifiable() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let today = day("2026-01-01");
+        std::fs::write(
+            dir.join(LICENSE_FILE),
+            license("2027-03-31", "pro", TEST_SECRET),
+        )
+        .unwrap();
+
+        let unverifiable = LicenseStatus::check_with(dir, None, today);
+        assert!(matches!(unverifiable, LicenseStatus::Unverifiable));
+        assert_eq!(unverifiable.tier(), None);
+        assert_eq!(
+            serde_json::to_value(&unverifiable).unwrap(),
+            serde_json::json!({ "status": "unverifiable" })
+        );
+        assert_eq!(
+            LicenseStatus::check_with(dir, Some(TEST_SECRET), today).tier(),
+            Some("pro")
+        );
+    }
+}
@@ -0,0 +1,218 @@
+//! Sidecar lifecycle events for every window, so the frontend learns what
+//! the engine is doing instead of inferring it from failing fetches.
+//!
+//! | event                     | payload                     | when                              |
+//! |---------------------------|-----------------------------|-----------------------------------|
+//! | `sidecar-spawned`         | `{pid}`                     | a sidecar process was started     |
+//! | `backend-port`            | `{port}`                    | it reported its port              |
+//! | `backend-component-ready` | `{component, readyAfterMs}` | a component is up (see `readiness`) |
+//! | `backend-ready`           | `{port}`                    | health and all components are up  |
+//! | `backend-terminated`      | `{exitCode}` (may be null)  | it crashed, failed or was stopped |
+//! | `backend-restarting`      | `{}`                        | a replacement is being started    |
+//!
+//! They go to all windows, the splash included.  The main window only
+//! exists once `backend-ready` has fired, so every event also updates a
+//! snapshot that late subscribers read with `get_lifecycle_snapshot`.  The
+//! snapshot also keeps the recent lifecycle hook runs (see `hooks`).
+
+use std::{collections::BTreeMap, sync::Mutex, time::Duration};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+
+use crate::{
+    backend_status::BackendStatus,
+    events::{self, Audience},
+    hooks::HookRun,
+    BackendProcess,
+};
+
+const MAX_HOOK_RUNS: usize = 20;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Spawned {
+    pub pid: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Port {
+    pub port: u16,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Terminated {
+    pub exit_code: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Restarting {}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComponentReady {
+    pub component: &'static str,
+    pub ready_after_ms: u64,
+}
+
+/// Everything the lifecycle events have reported so far.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LifecycleSnapshot {
+    pub status: BackendStatus,
+    /// Pid of the current sidecar, if one is running.
+    pub pid: Option<u32>,
+    pub port: Option<u16>,
+    /// Exit code of the last sidecar that terminated, when known.
+    pub last_exit_code: Option<i32>,
+    /// Sidecars started so far in this session; above 1 after a restart.
+    pub spawn_count: u32,
+    /// Milliseconds from the start of the current sidecar's launch to each
+    /// component becoming ready.
+    pub component_ready_ms: BTreeMap<String, u64>,
+    /// The last `MAX_HOOK_RUNS` lifecycle hook runs, oldest first.
+    pub hook_runs: Vec<HookRun>,
+}
+
+/// Managed state behind `get_lifecycle_snapshot`.
+#[derive(Default)]
+pub struct Lifecycle(Mutex<LifecycleSnapshot>);
+
+impl Lifecycle {
+    pub fn snapshot(&self) -> LifecycleSnapshot {
+        self.0.lock().unwrap().clone()
+    }
+
+    fn update(&self, f: impl FnOnce(&mut LifecycleSnapshot)) {
+        f(&mut self.0.lock().unwrap());
+    }
+}
+
+pub fn spawned(app: &AppHandle, pid: u32) {
+    app.state::<Lifecycle>().update(|s| {
+        s.pid = Some(pid);
+        s.port = None;
+        s.spawn_count += 1;
+        s.component_ready_ms.clear();
+    });
+    events::emit(
+        app,
+        Audience::AllWindows,
+        "sidecar-spawned",
+        Spawned { pid },
+    );
+}
+
+pub fn port(app: &AppHandle, port: u16) {
+    app.state::<Lifecycle>().update(|s| s.port = Some(port));
+    events::emit(app, Audience::AllWindows, "backend-port", Port { port });
+}
+
+pub fn ready(app: &AppHandle, port: u16) {
+    app.state::<Lifecycle>().update(|s| s.port = Some(port));
+    events::emit(app, Audience::AllWindows, "backend-ready", Port { port });
+}
+
+pub fn component_ready(app: &AppHandle, component: &'static str, after: Duration) {
+    let ready_after_ms = after.as_millis() as u64;
+    app.state::<Lifecycle>().update(|s| {
+        s.component_ready_ms
+            .insert(component.to_string(), ready_after_ms);
+    });
+    events::emit(
+        app,
+        Audience::AllWindows,
+        "backend-component-ready",
+        ComponentReady {
+            component,
+            ready_after_ms,
+        },
+    );
+}
+
+pub fn terminated(app: &AppHandle, exit_code: Option<i32>) {
+    app.state::<Lifecycle>().update(|s| {
+        s.pid = None;
+        s.port = None;
+        s.last_exit_code = exit_code;
+    });
+    events::emit(
+        app,
+        Audience::AllWindows,
+        "backend-terminated",
+        Terminated { exit_code },
+    );
+}
+
+pub fn restarting(app: &AppHandle) {
+    events::emit(
+        app,
+        Audience::AllWindows,
+        "backend-restarting",
+        Restarting {},
+    );
+}
+
+/// Record a lifecycle hook run (see `hooks`).
+pub fn hook_ran(app: &AppHandle, run: HookRun) {
+    app.state::<Lifecycle>().update(|s| {
+        if s.hook_runs.len() == MAX_HOOK_RUNS {
+            s.hook_runs.remove(0);
+        }
+        s.hook_runs.push(run);
+    });
+}
+
+/// The lifecycle so far, for windows created after the events fired.
+#[tauri::command]
+pub fn get_lifecycle_snapshot(
+    lifecycle: State<'_, Lifecycle>,
+    backend: State<'_, BackendProcess>,
+) -> LifecycleSnapshot {
+    LifecycleSnapshot {
+        status: backend.status(),
+        ..lifecycle.snapshot()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_and_payloads_serialize_in_camel_case() {
+        let lifecycle = Lifecycle::default();
+        lifecycle.update(|s| {
+            s.pid = Some(42);
+            s.spawn_count += 1;
+        });
+        lifecycle.update(|s| s.last_exit_code = Some(3));
+        let json = serde_json::to_value(lifecycle.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "status": "stopped",
+                "pid": 42,
+                "port": null,
+                "lastExitCode": 3,
+                "spawnCount": 1,
+                "componentReadyMs": {},
+                "hookRuns": [],
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(Terminated { exit_code: None }).unwrap(),
+            serde_json::json!({ "exitCode": null })
+        );
+        assert_eq!(
+            serde_json::to_value(ComponentReady {
+                component: "pool",
+                ready_after_ms: 4200,
+            })
+            .unwrap(),
+            serde_json::json!({ "component": "pool", "readyAfterMs": 4200 })
+        );
+    }
+}
@@ -0,0 +1,122 @@
+//! Locales the OS fully supports, for the language picker in Settings.
+//!
+//! Windows → `EnumSystemLocalesEx(LOCALE_WINDOWS)`
+//! macOS   → `NSLocale.availableLocaleIdentifiers`
+//! Linux   → `locale -a` (only what has been generated on this machine)
+//!
+//! Every platform's names are turned into BCP 47 tags (`en-US`, `zh-Hans-CN`)
+//! as the frontend's `Intl` APIs expect, then sorted and deduplicated.  The
+//! `C`/`POSIX` and invariant locales are left out.
+
+use crate::shell_log::{self, Level};
+
+/// `raw` as a BCP 47 tag: `en_US.UTF-8` → `en-US`, `zh_Hans_CN` →
+/// `zh-Hans-CN`.  `None` for the C, POSIX and invariant locales.
+fn bcp47(raw: &str) -> Option<String> {
+    // Drop the encoding and modifier: `de_DE.utf8@euro`.
+    let name = raw.split(['.', '@']).next()?.trim();
+    if name.is_empty() || name == "C" || name == "POSIX" {
+        return None;
+    }
+    Some(name.replace('_', "-"))
+}
+
+#[cfg(target_os = "windows")]
+fn system_locales() -> Result<Vec<String>, String> {
+    use windows::{
+        core::{BOOL, PWSTR},
+        Win32::{
+            Foundation::LPARAM,
+            Globalization::{EnumSystemLocalesEx, LOCALE_WINDOWS},
+        },
+    };
+
+    unsafe extern "system" fn collect(name: PWSTR, _flags: u32, names: LPARAM) -> BOOL {
+        // SAFETY: `names` is the Vec passed below, alive for the whole
+        // enumeration; `name` is a NUL-terminated string Windows owns.
+        let names = &mut *(names.0 as *mut Vec<String>);
+        if let Ok(name) = name.to_string() {
+            names.push(name);
+        }
+        true.into()
+    }
+
+    let mut names: Vec<String> = Vec::new();
+    // SAFETY: the callback only runs during this call.
+    unsafe {
+        EnumSystemLocalesEx(
+            Some(collect),
+            LOCALE_WINDOWS,
+            LPARAM(&mut names as *mut Vec<String> as isize),
+            None,
+        )
+    }
+    .map_err(|e| format!("EnumSystemLocalesEx: {e}"))?;
+    Ok(names)
+}
+
+#[cfg(target_os = "macos")]
+fn system_locales() -> Result<Vec<String>, String> {
+    use objc2_foundation::NSLocale;
+
+    let identifiers = NSLocale::availableLocaleIdentifiers();
+    Ok(identifiers.iter().map(|id| id.to_string()).collect())
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn system_locales() -> Result<Vec<String>, String> {
+    let output = std::process::Command::new("locale")
+        .arg("-a")
+        .output()
+        .map_err(|e| format!("locale -a: {e}"))?;
+    if !output.status.success() {
+        return Err(format!("locale -a: {}", output.status));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
+
+fn normalize(raw: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut locales: Vec<String> = raw.into_iter().filter_map(|l| bcp47(&l)).collect();
+    locales.sort();
+    locales.dedup();
+    locales
+}
+
+/// Sorted BCP 47 tags; empty if the OS can't be asked.
+#[tauri::command]
+pub fn get_available_locales() -> Vec<String> {
+    match system_locales() {
+        Ok(raw) => normalize(raw),
+        Err(e) => {
+            shell_log::log(Level::Warn, "locales", e);
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn platform_names_become_sorted_unique_tags() {
+        let raw = [
+            "C",
+            "C.UTF-8",
+            "POSIX",
+            "en_US.utf8",
+            "de_DE@euro",
+            "de_DE.utf8",
+            "zh_Hans_CN",
+            "es-ES",
+            "",
+        ];
+        assert_eq!(
+            normalize(raw.map(String::from)),
+            ["de-DE", "en-US", "es-ES", "zh-Hans-CN"]
+        );
+    }
+}
@@ -0,0 +1,50 @@
+//! Stand-alone log viewer window.
+//!
+//! Loads the static `logs.html` (from `public/`), which live-tails the
+//! `backend-log-line` events and, on its second tab, the shell log (see
+//! `shell_log`).  The window has no parent, so closing it never touches the
+//! main window, and it is the only window besides main that is registered
+//! for `Capability::BackendLogs` and `Capability::ShellLogs`.  There is at
+//! most one: opening it again focuses the existing window.  Compact mode
+//! doesn't resize it.
+
+use tauri::{AppHandle, Manager, State};
+
+use crate::{
+    events::Capability,
+    window_factory::{WindowConfig, WindowFactory, WindowPreset, WindowSpec},
+    BackendProcess,
+};
+
+pub const LABEL: &str = "logs";
+
+/// Open the log viewer, or focus it if it is already open.
+///
+/// Async so the window is built off the main thread (a sync command that
+/// creates a window deadlocks on Windows).
+#[tauri::command]
+pub async fn open_log_viewer_window(
+    app: AppHandle,
+    backend: State<'_, BackendProcess>,
+) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(LABEL) {
+        let _ = window.unminimize();
+        return window.set_focus().map_err(|e| e.to_string());
+    }
+
+    let port = backend.port.get().ok_or("The engine is not running.")?;
+    let spec = WindowSpec {
+        label: LABEL,
+        page: "logs.html",
+        title: "ALMReady – Logs",
+        size: (800.0, 600.0),
+        // The presets are sized for the workspace; a log view can be smaller.
+        min_size: Some((480.0, 320.0)),
+        center: false,
+        preset: WindowPreset::STANDARD,
+        capabilities: &[Capability::BackendLogs, Capability::ShellLogs],
+    };
+    WindowFactory::build(&app, WindowConfig::for_app(&app, &spec, port))
+        .map_err(|e| format!("failed to create log viewer: {e}"))?;
+    Ok(())
+}
@@ -0,0 +1,594 @@
+//! Capture of the sidecar's stderr into `backend.log`.
+//!
+//! uvicorn and the FastAPI request middleware write one line per API call,
+//! which bloats the log during heavy UI use and records every path the user
+//! touches.  Each captured line therefore goes through a `LogFilter` before
+//! it is written to disk or forwarded to the webview as `backend-log-line`:
+//!
+//! * warnings, errors, tracebacks and 5xx responses are always kept;
+//! * access-log lines can be kept, dropped, or sampled (1 in N);
+//! * path segments that look like identifiers can be replaced by a short
+//!   stable hash;
+//! * dropped lines are counted and flushed as a single summary record at
+//!   most once per `summary_interval_secs`.
+//!
+//! The filter rules live in `Settings::backend_log` and are picked up from
+//! the settings watcher without restarting the sidecar.
+//!
+//! Besides the 10 MB rotation, the combined size of `backend.log` and
+//! `backend.log.1` is capped at `plugins.almready.max_log_size_mb` (from
+//! `tauri.conf.json`) by a housekeeping task.  The current log is truncated
+//! rather than deleted so the writer's handle stays valid.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::Write as _,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncBufReadExt as _, BufReader};
+
+use crate::{
+    data_dir_access::DataDirAccess,
+    events::{self, Audience, Capability},
+    paths,
+    settings::SettingsStore,
+    shell_log::{self, Level},
+};
+
+/// A log file is rotated to `{name}.1` once it exceeds this size.
+const MAX_LOG_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Combined cap on `backend.log` and its rotated copy when
+/// `plugins.almready.max_log_size_mb` is not configured.
+const DEFAULT_MAX_LOG_SIZE_MB: u64 = 50;
+
+// ── Settings ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessLogMode {
+    Keep,
+    Drop,
+    Sample,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogFilterSettings {
+    pub access_log: AccessLogMode,
+    /// With `Sample`, keep one access-log line out of this many.
+    pub sample_every: u32,
+    /// Replace identifier-like path segments with a short hash.
+    pub hash_ids: bool,
+    /// Minimum spacing between "dropped N lines" summary records.
+    pub summary_interval_secs: u64,
+}
+
+impl Default for LogFilterSettings {
+    fn default() -> Self {
+        LogFilterSettings {
+            access_log: AccessLogMode::Keep,
+            sample_every: 10,
+            hash_ids: false,
+            summary_interval_secs: 60,
+        }
+    }
+}
+
+// ── Classification ──────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineClass {
+    /// One line per HTTP request (uvicorn access log or request middleware).
+    Access,
+    /// Warning, error, or a failed request – never filtered.
+    Problem,
+    Other,
+}
+
+const METHODS: [&str; 7] = ["GET", "POST", "PUT", "PATCH", "DELETE", "HEAD", "OPTIONS"];
+
+/// Level name for the two formats the backend produces:
+/// uvicorn's `"ERROR:    message"` and the app's
+/// `"%(asctime)s %(levelname)-5s %(message)s"`.
+fn level(line: &str) -> Option<&str> {
+    if let Some((head, _)) = line.split_once(':') {
+        if !head.is_empty() && head.chars().all(|c| c.is_ascii_uppercase()) {
+            return Some(head);
+        }
+    }
+    let mut tokens = line.split_whitespace();
+    let time = tokens.next()?;
+    if time.len() == 8 && time.as_bytes()[2] == b':' {
+        return tokens.next();
+    }
+    None
+}
+
+/// HTTP status of an access-log line, if it is one.
+fn access_status(line: &str) -> Option<u16> {
+    // uvicorn: 127.0.0.1:52341 - "GET /api/health HTTP/1.1" 200 OK
+    if let Some(idx) = line.find(" HTTP/") {
+        let rest = &line[idx..];
+        let after_quote = rest.split_once('"')?.1;
+        return after_quote.split_whitespace().next()?.parse().ok();
+    }
+    // middleware: 12:01:02 INFO  GET /api/sessions/abc → 200 (12 ms)
+    let (request, rest) = line.split_once(" → ")?;
+    let mut words = request.split_whitespace().rev();
+    let path = words.next()?;
+    let method = words.next()?;
+    if !path.starts_with('/') || !METHODS.contains(&method) {
+        return None;
+    }
+    rest.split_whitespace().next()?.parse().ok()
+}
+
+fn classify(line: &str) -> LineClass {
+    if matches!(level(line), Some("WARNING" | "WARN" | "ERROR" | "CRITICAL")) {
+        return LineClass::Problem;
+    }
+    match access_status(line) {
+        Some(status) if status >= 500 => LineClass::Problem,
+        Some(_) => LineClass::Access,
+        None => LineClass::Other,
+    }
+}
+
+// ── Identifier hashing ──────────────────────────────────────────────────────
+
+fn looks_like_id(segment: &str) -> bool {
+    let hex_digits = segment.chars().filter(|c| c.is_ascii_hexdigit()).count();
+    let hexish = segment.chars().all(|c| c.is_ascii_hexdigit() || c == '-');
+    let numeric = segment.len() >= 4 && segment.chars().all(|c| c.is_ascii_digit());
+    (hexish && hex_digits >= 12) || numeric
+}
+
+/// FNV-1a, truncated to 32 bits: stable across runs so the same session id
+/// always hashes to the same token in the log.
+fn short_hash(s: &str) -> String {
+    let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+    for b in s.bytes() {
+        h ^= u64::from(b);
+        h = h.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("~{:08x}", h as u32)
+}
+
+fn hash_path(path: &str) -> String {
+    let (path, query) = match path.split_once('?') {
+        Some((p, q)) => (p, Some(q)),
+        None => (path, None),
+    };
+    let mut out: String = path
+        .split('/')
+        .map(|seg| {
+            if looks_like_id(seg) {
+                short_hash(seg)
+            } else {
+                seg.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+    if let Some(q) = query {
+        out.push('?');
+        out.push_str(q);
+    }
+    out
+}
+
+fn hash_ids_in_line(line: &str) -> String {
+    line.split(' ')
+        .map(|token| {
+            if token.starts_with('/') {
+                hash_path(token)
+            } else {
+                token.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// ── Filter ──────────────────────────────────────────────────────────────────
+
+/// Stateful line filter: sampling position and dropped-line counters.
+pub struct LogFilter {
+    settings: LogFilterSettings,
+    seen_access: u64,
+    dropped: u64,
+    last_summary: Instant,
+}
+
+impl LogFilter {
+    pub fn new(settings: LogFilterSettings, now: Instant) -> Self {
+        LogFilter {
+            settings,
+            seen_access: 0,
+            dropped: 0,
+            last_summary: now,
+        }
+    }
+
+    pub fn set_settings(&mut self, settings: LogFilterSettings) {
+        self.settings = settings;
+    }
+
+    /// Returns the line to record, or `None` if it was filtered out.
+    pub fn apply(&mut self, line: &str) -> Option<String> {
+        let class = classify(line);
+        if class == LineClass::Access {
+            self.seen_access += 1;
+            let keep = match self.settings.access_log {
+                AccessLogMode::Keep => true,
+                AccessLogMode::Drop => false,
+                AccessLogMode::Sample => {
+                    let n = u64::from(self.settings.sample_every.max(1));
+                    (self.seen_access - 1) % n == 0
+                }
+            };
+            if !keep {
+                self.dropped += 1;
+                return None;
+            }
+        }
+        if self.settings.hash_ids && class != LineClass::Other {
+            return Some(hash_ids_in_line(line));
+        }
+        Some(line.to_string())
+    }
+
+    /// A summary record for lines dropped since the last one, once the
+    /// summary interval has elapsed (or unconditionally when `force`).
+    pub fn take_summary(&mut self, now: Instant, force: bool) -> Option<String> {
+        let interval = Duration::from_secs(self.settings.summary_interval_secs);
+        if self.dropped == 0 || (!force && now.duration_since(self.last_summary) < interval) {
+            return None;
+        }
+        let secs = now.duration_since(self.last_summary).as_secs();
+        let summary = format!(
+            "[ALMReady] log filter dropped {} access-log line(s) in the last {secs} s",
+            self.dropped
+        );
+        self.dropped = 0;
+        self.last_summary = now;
+        Some(summary)
+    }
+}
+
+// ── Writer ──────────────────────────────────────────────────────────────────
+
+/// Append-only log file (`backend.log`, `shell.log`) with single-generation
+/// rotation.
+pub struct LogFile {
+    path: PathBuf,
+    file: Option<File>,
+}
+
+impl LogFile {
+    pub fn open(path: PathBuf) -> Self {
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let file = open_append(&path);
+        LogFile { path, file }
+    }
+
+    pub fn write_line(&mut self, line: &str) {
+        self.rotate_if_needed();
+        if let Some(file) = self.file.as_mut() {
+            let _ = writeln!(file, "{line}");
+        }
+    }
+
+    fn rotate_if_needed(&mut self) {
+        let size = std::fs::metadata(&self.path).map(|m| m.len()).unwrap_or(0);
+        if size < MAX_LOG_BYTES {
+            return;
+        }
+        self.file = None;
+        let _ = std::fs::rename(&self.path, rotated_path(&self.path));
+        self.file = open_append(&self.path);
+    }
+}
+
+fn open_append(path: &Path) -> Option<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .inspect_err(|e| {
+            shell_log::log(Level::Warn, "logpipe", format!("cannot open {path:?}: {e}"))
+        })
+        .ok()
+}
+
+/// `backend.log` → `backend.log.1`
+pub fn rotated_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".1");
+    PathBuf::from(name)
+}
+
+/// Location of the captured backend log.
+pub fn backend_log_path(data_dir: &Path) -> PathBuf {
+    paths::log_dir_in(data_dir).join("backend.log")
+}
+
+/// Combined size of `log_path` and its rotated copy; missing files count as
+/// empty.
+pub fn log_size(log_path: &Path) -> u64 {
+    [log_path.to_path_buf(), rotated_path(log_path)]
+        .iter()
+        .filter_map(|path| std::fs::metadata(path).ok())
+        .map(|m| m.len())
+        .sum()
+}
+
+/// Truncate `path` to zero bytes in place.  The writer opened it in append
+/// mode, so its next line lands at the new end.
+pub fn truncate(path: &Path) -> Result<(), String> {
+    let access = DataDirAccess::unleased();
+    match access.with(&[path], || OpenOptions::new().write(true).open(path)) {
+        Ok(file) => access
+            .with(&[path], || file.set_len(0))
+            .map_err(|e| format!("truncate {path:?}: {e}")),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("open {path:?}: {e}")),
+    }
+}
+
+/// `plugins.almready.max_log_size_mb`, in bytes.
+fn max_log_size(app: &AppHandle) -> u64 {
+    let mb = app
+        .config()
+        .plugins
+        .0
+        .get("almready")
+        .and_then(|config| config.get("max_log_size_mb"))
+        .and_then(|mb| mb.as_u64())
+        .unwrap_or(DEFAULT_MAX_LOG_SIZE_MB);
+    mb * 1024 * 1024
+}
+
+/// Bring the backend logs back under `max_bytes`: truncate the current log
+/// and, if the rotated copy alone is still too big, delete that too.
+/// Returns whether anything was done.
+fn enforce_size_cap(log_path: &Path, max_bytes: u64) -> Result<bool, String> {
+    let size = log_size(log_path);
+    if size <= max_bytes {
+        return Ok(false);
+    }
+    truncate(log_path)?;
+    if log_size(log_path) > max_bytes {
+        let rotated = rotated_path(log_path);
+        DataDirAccess::unleased()
+            .with(&[&rotated], || std::fs::remove_file(&rotated))
+            .map_err(|e| format!("remove {rotated:?}: {e}"))?;
+    }
+    shell_log::log(
+        Level::Info,
+        "logpipe",
+        format!("backend logs reached {size} bytes, truncated"),
+    );
+    Ok(true)
+}
+
+/// Housekeeping task: apply the configured size cap.
+pub fn cap_backend_log(app: &AppHandle) -> Result<(), String> {
+    let log_path = backend_log_path(&paths::data_dir(app)?);
+    enforce_size_cap(&log_path, max_log_size(app)).map(drop)
+}
+
+/// Read the sidecar's stderr asynchronously, filter each line, append it to
+/// `backend.log` and forward it to the webview.  The pump lives as long as
+/// the sidecar, so it must not hold a blocking-pool thread.
+pub fn spawn_stderr_pump(app: AppHandle, stderr: std::process::ChildStderr, log_path: PathBuf) {
+    let mut settings_rx = app.state::<SettingsStore>().subscribe();
+    tauri::async_runtime::spawn(async move {
+        let stderr = match tokio::process::ChildStderr::from_std(stderr) {
+            Ok(stderr) => stderr,
+            Err(e) => {
+                return shell_log::log(
+                    Level::Error,
+                    "logpipe",
+                    format!("cannot read backend stderr: {e}"),
+                )
+            }
+        };
+        let mut filter = LogFilter::new(
+            settings_rx.borrow_and_update().backend_log.clone(),
+            Instant::now(),
+        );
+        let mut log = LogFile::open(log_path);
+
+        let record = |log: &mut LogFile, line: &str| {
+            log.write_line(line);
+            events::emit(
+                &app,
+                Audience::Capability(Capability::BackendLogs),
+                "backend-log-line",
+                line,
+            );
+        };
+
+        let mut lines = BufReader::new(stderr).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if settings_rx.has_changed().unwrap_or(false) {
+                filter.set_settings(settings_rx.borrow_and_update().backend_log.clone());
+            }
+            if let Some(summary) = filter.take_summary(Instant::now(), false) {
+                record(&mut log, &summary);
+            }
+            if let Some(kept) = filter.apply(&line) {
+                record(&mut log, &kept);
+            }
+        }
+
+        if let Some(summary) = filter.take_summary(Instant::now(), true) {
+            record(&mut log, &summary);
+        }
+    });
+}
+
+// ── Commands ────────────────────────────────────────────────────────────────
+
+/// Combined size in bytes of `backend.log` and `backend.log.1`.
+#[tauri::command]
+pub fn get_stderr_size(app: AppHandle) -> u64 {
+    paths::data_dir(&app)
+        .map(|dir| log_size(&backend_log_path(&dir)))
+        .unwrap_or(0)
+}
+
+/// Empty `backend.log`; the running sidecar keeps writing to it.
+#[tauri::command]
+pub fn truncate_backend_log(app: AppHandle) -> Result<(), String> {
+    truncate(&backend_log_path(&paths::data_dir(&app)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UVICORN_ACCESS: &str = r#"INFO:     127.0.0.1:52341 - "GET /api/sessions/3f2b9c1e-8a4d-4e21-9b7a-0c5d6e7f8a9b/balance HTTP/1.1" 200 OK"#;
+    const UVICORN_ACCESS_500: &str = r#"INFO:     127.0.0.1:52341 - "POST /api/sessions/3f2b9c1e-8a4d-4e21-9b7a-0c5d6e7f8a9b/calculate HTTP/1.1" 500 Internal Server Error"#;
+    const MIDDLEWARE_ACCESS: &str = "12:01:02 INFO  GET /api/sessions/3f2b9c1e-8a4d-4e21-9b7a-0c5d6e7f8a9b/curves → 200 (12 ms)";
+    const UVICORN_STARTUP: &str =
+        "INFO:     Uvicorn running on http://127.0.0.1:52341 (Press CTRL+C to quit)";
+    const UVICORN_ERROR: &str = "ERROR:    Exception in ASGI application";
+    const APP_WARNING: &str = "12:01:03 WARNING Periodic session cleanup failed";
+    const TRACEBACK: &str = "Traceback (most recent call last):";
+
+    fn filter(settings: LogFilterSettings) -> LogFilter {
+        LogFilter::new(settings, Instant::now())
+    }
+
+    #[test]
+    fn classifies_representative_lines() {
+        assert_eq!(classify(UVICORN_ACCESS), LineClass::Access);
+        assert_eq!(classify(MIDDLEWARE_ACCESS), LineClass::Access);
+        assert_eq!(classify(UVICORN_ACCESS_500), LineClass::Problem);
+        assert_eq!(classify(UVICORN_ERROR), LineClass::Problem);
+        assert_eq!(classify(APP_WARNING), LineClass::Problem);
+        assert_eq!(classify(UVICORN_STARTUP), LineClass::Other);
+        assert_eq!(classify(TRACEBACK), LineClass::Other);
+    }
+
+    #[test]
+    fn default_keeps_everything_unchanged() {
+        let mut f = filter(LogFilterSettings::default());
+        for line in [UVICORN_ACCESS, MIDDLEWARE_ACCESS, UVICORN_ERROR, TRACEBACK] {
+            assert_eq!(f.apply(line).as_deref(), Some(line));
+        }
+    }
+
+    #[test]
+    fn drop_mode_keeps_problems_and_other_lines() {
+        let mut f = filter(LogFilterSettings {
+            access_log: AccessLogMode::Drop,
+            ..Default::default()
+        });
+        assert_eq!(f.apply(UVICORN_ACCESS), None);
+        assert_eq!(f.apply(MIDDLEWARE_ACCESS), None);
+        assert!(f.apply(UVICORN_ACCESS_500).is_some());
+        assert!(f.apply(UVICORN_ERROR).is_some());
+        assert!(f.apply(APP_WARNING).is_some());
+        assert!(f.apply(UVICORN_STARTUP).is_some());
+        assert!(f.apply(TRACEBACK).is_some());
+    }
+
+    #[test]
+    fn sample_mode_keeps_one_in_n() {
+        let mut f = filter(LogFilterSettings {
+            access_log: AccessLogMode::Sample,
+            sample_every: 3,
+            ..Default::default()
+        });
+        let kept: Vec<bool> = (0..7).map(|_| f.apply(UVICORN_ACCESS).is_some()).collect();
+        assert_eq!(kept, [true, false, false, true, false, false, true]);
+    }
+
+    #[test]
+    fn dropped_lines_are_summarised_once_per_interval() {
+        let start = Instant::now();
+        let mut f = LogFilter::new(
+            LogFilterSettings {
+                access_log: AccessLogMode::Drop,
+                summary_interval_secs: 60,
+                ..Default::default()
+            },
+            start,
+        );
+        for _ in 0..5 {
+            f.apply(MIDDLEWARE_ACCESS);
+        }
+        assert_eq!(f.take_summary(start + Duration::from_secs(10), false), None);
+
+        let summary = f
+            .take_summary(start + Duration::from_secs(61), false)
+            .unwrap();
+        assert!(
+            summary.contains("dropped 5 access-log line(s)"),
+            "{summary}"
+        );
+
+        // Counter was reset; nothing to report until more lines are dropped.
+        assert_eq!(f.take_summary(start + Duration::from_secs(200), true), None);
+        f.apply(UVICORN_ACCESS);
+        assert!(f
+            .take_summary(start + Duration::from_secs(201), true)
+            .is_some());
+    }
+
+    #[test]
+    fn identifier_segments_are_hashed_stably() {
+        let mut f = filter(LogFilterSettings {
+            hash_ids: true,
+            ..Default::default()
+        });
+        let once = f.apply(MIDDLEWARE_ACCESS).unwrap();
+        let twice = f.apply(MIDDLEWARE_ACCESS).unwrap();
+        assert_eq!(once, twice);
+        assert!(!once.contains("3f2b9c1e-8a4d"), "{once}");
+        assert!(once.contains("/api/sessions/~"), "{once}");
+        assert!(once.ends_with("/curves → 200 (12 ms)"), "{once}");
+
+        // Non-identifier segments such as curve names survive.
+        assert_eq!(
+            hash_path("/api/curves/EUR_ESTR_OIS"),
+            "/api/curves/EUR_ESTR_OIS"
+        );
+        assert_eq!(
+            hash_path("/api/items/123456?x=1"),
+            format!("/api/items/{}?x=1", short_hash("123456"))
+        );
+    }
+
+    #[test]
+    fn size_cap_truncates_in_place_then_drops_the_rotated_copy() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = backend_log_path(dir.path());
+        let mut log = LogFile::open(path.clone());
+        log.write_line(&"x".repeat(99));
+        std::fs::write(rotated_path(&path), "y".repeat(50)).unwrap();
+        assert_eq!(log_size(&path), 150);
+
+        assert!(!enforce_size_cap(&path, 200).unwrap());
+        assert!(enforce_size_cap(&path, 100).unwrap());
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 0);
+        assert_eq!(log_size(&path), 50);
+
+        // The writer's handle still works and appends from the start.
+        log.write_line("after");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "after\n");
+
+        assert!(enforce_size_cap(&path, 10).unwrap());
+        assert!(!rotated_path(&path).exists());
+    }
+}
@@ -3,5 +3,8 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    if let Some(code) = almready_lib::run_conformance() {
+        std::process::exit(code);
+    }
     almready_lib::run()
 }
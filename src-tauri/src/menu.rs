@@ -0,0 +1,200 @@
+//! Native application menu.
+//!
+//! Besides the standard items (on macOS the app, Edit and Window menus, so
+//! copy and paste keep working in the webview), the Help menu gives users a
+//! way to the things support asks for when something goes wrong:
+//!
+//! * Open Data Folder – the data directory in Finder / Explorer / the file
+//!   manager;
+//! * View Backend Logs – `logs/backend.log` in the default text viewer;
+//! * Show Last Failure – the last failure dialog again, for support calls
+//!   (see `last_failure`);
+//! * Restart Calculation Engine – `restart_backend`.
+//!
+//! The View menu zooms the focused window (see `zoom`).
+//!
+//! Menu items can be clicked at any moment, including while the backend is
+//! still starting.  Whatever can't be done right then is a no-op with a
+//! `shell-toast { message }` event to the main window saying why.
+
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use serde::Serialize;
+use tauri::{
+    menu::{Menu, MenuBuilder, MenuEvent, MenuItem, SubmenuBuilder},
+    AppHandle, Manager, Wry,
+};
+
+use crate::{
+    backend_status::{self, BackendStatus},
+    events::{self, Audience},
+    last_failure, logpipe, paths,
+    shell_log::{self, Level},
+    zoom, BackendProcess,
+};
+
+const OPEN_DATA_FOLDER: &str = "open-data-folder";
+const VIEW_BACKEND_LOGS: &str = "view-backend-logs";
+const RESTART_ENGINE: &str = "restart-engine";
+const SHOW_LAST_FAILURE: &str = "show-last-failure";
+
+/// Passed to `tauri::Builder::menu`.
+pub fn build(app: &AppHandle) -> tauri::Result<Menu<Wry>> {
+    let help = SubmenuBuilder::new(app, "Help")
+        .text(OPEN_DATA_FOLDER, "Open Data Folder")
+        .text(VIEW_BACKEND_LOGS, "View Backend Logs")
+        .text(SHOW_LAST_FAILURE, "Show Last Failure")
+        .separator()
+        .text(RESTART_ENGINE, "Restart Calculation Engine")
+        .build()?;
+    let view = SubmenuBuilder::new(app, "View")
+        .item(&MenuItem::with_id(
+            app,
+            zoom::ZOOM_IN,
+            "Zoom In",
+            true,
+            Some("CmdOrCtrl+="),
+        )?)
+        .item(&MenuItem::with_id(
+            app,
+            zoom::ZOOM_OUT,
+            "Zoom Out",
+            true,
+            Some("CmdOrCtrl+-"),
+        )?)
+        .item(&MenuItem::with_id(
+            app,
+            zoom::ZOOM_RESET,
+            "Actual Size",
+            true,
+            Some("CmdOrCtrl+0"),
+        )?)
+        .build()?;
+
+    #[cfg(target_os = "macos")]
+    {
+        let app_menu = SubmenuBuilder::new(app, "ALMReady")
+            .about(None)
+            .separator()
+            .services()
+            .separator()
+            .hide()
+            .hide_others()
+            .show_all()
+            .separator()
+            .quit()
+            .build()?;
+        let edit = SubmenuBuilder::new(app, "Edit")
+            .undo()
+            .redo()
+            .separator()
+            .cut()
+            .copy()
+            .paste()
+            .select_all()
+            .build()?;
+        let window = SubmenuBuilder::new(app, "Window")
+            .minimize()
+            .maximize()
+            .separator()
+            .close_window()
+            .build()?;
+        MenuBuilder::new(app)
+            .items(&[&app_menu, &edit, &view, &window, &help])
+            .build()
+    }
+    #[cfg(not(target_os = "macos"))]
+    MenuBuilder::new(app).items(&[&view, &help]).build()
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Toast {
+    message: String,
+}
+
+fn toast(app: &AppHandle, message: impl Into<String>) {
+    let message = message.into();
+    shell_log::log(Level::Info, "menu", message.clone());
+    events::emit(app, Audience::MainOnly, "shell-toast", Toast { message });
+}
+
+/// Open `path` with the platform's default handler.
+pub fn open_path(path: &Path) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    let program = "open";
+    #[cfg(target_os = "windows")]
+    let program = "explorer";
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let program = "xdg-open";
+
+    let mut child = Command::new(program)
+        .arg(path)
+        .spawn()
+        .map_err(|e| format!("{program}: {e}"))?;
+    // Reap it; explorer's exit code means nothing, so it isn't checked.
+    let _ = child.wait();
+    Ok(())
+}
+
+fn open_in_background(app: &AppHandle, path: PathBuf) {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(e) = open_path(&path) {
+            toast(&app, format!("Could not open {}: {e}", path.display()));
+        }
+    });
+}
+
+fn open_data_folder(app: &AppHandle) {
+    match paths::data_dir(app) {
+        Ok(dir) => {
+            let _ = std::fs::create_dir_all(&dir);
+            open_in_background(app, dir);
+        }
+        Err(e) => toast(app, e),
+    }
+}
+
+fn view_backend_logs(app: &AppHandle) {
+    let log = match paths::data_dir(app) {
+        Ok(dir) => logpipe::backend_log_path(&dir),
+        Err(e) => return toast(app, e),
+    };
+    if !log.exists() {
+        return toast(app, "The calculation engine hasn't written a log yet.");
+    }
+    open_in_background(app, log);
+}
+
+/// Restart the engine if it is up or crashed; shared with the tray menu.
+pub fn restart_engine(app: &AppHandle) {
+    // Before the first spawn the status is still `Stopped`; restarting then
+    // would race startup.
+    match app.state::<BackendProcess>().status() {
+        BackendStatus::Ready | BackendStatus::Crashed => {}
+        BackendStatus::Starting => return toast(app, "The calculation engine is still starting."),
+        BackendStatus::Stopped => return toast(app, "The calculation engine isn't running yet."),
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        match backend_status::restart_backend(app.clone()).await {
+            Ok(_) => toast(&app, "The calculation engine was restarted."),
+            Err(e) => toast(&app, format!("Could not restart the engine: {e}")),
+        }
+    });
+}
+
+/// Passed to `tauri::Builder::on_menu_event`.
+pub fn on_menu_event(app: &AppHandle, event: MenuEvent) {
+    match event.id().as_ref() {
+        OPEN_DATA_FOLDER => open_data_folder(app),
+        VIEW_BACKEND_LOGS => view_backend_logs(app),
+        RESTART_ENGINE => restart_engine(app),
+        SHOW_LAST_FAILURE => last_failure::show(app),
+        id @ (zoom::ZOOM_IN | zoom::ZOOM_OUT | zoom::ZOOM_RESET) => zoom::on_menu(app, id),
+        _ => {}
+    }
+}
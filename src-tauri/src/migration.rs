@@ -0,0 +1,532 @@
+//! Migration from a standalone backend install to the bundled sidecar.
+//!
+//! Early adopters ran `uvicorn app.main:app` as a hand-installed service,
+//! with sessions under `{backend}/data/sessions` and an optional flat
+//! `config.yaml`.  The assistant works in three steps, each a command:
+//!
+//! 1. `detect_legacy_install` – look for known footprints (backend dirs,
+//!    `config.yaml`, systemd/launchd/Windows service definitions).
+//! 2. `plan_legacy_migration` – a dry run listing every change that would
+//!    be made, for the user to confirm.
+//! 3. `run_legacy_migration` – execute that plan.  Sessions are copied into
+//!    our data dir, recognised config keys become backend env overrides in
+//!    settings, and the legacy service is stopped only if the user ticked
+//!    the consent box.  If any step fails, the earlier ones are rolled back.
+//!
+//! Every run writes `{data_dir}/migration-report.json`.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::{data_dir_access::DataDirAccess, paths, settings::SettingsStore};
+
+/// Legacy `config.yaml` keys we know how to carry over, and the backend env
+/// var each one becomes.
+const RECOGNISED_KEYS: [(&str, &str); 1] = [("session_ttl_days", "SESSION_TTL_DAYS")];
+
+// ── Footprints ──────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LegacyService {
+    SystemdUser { unit: String },
+    Launchd { plist: PathBuf },
+    Windows { name: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LegacyInstall {
+    pub backend_dir: PathBuf,
+    pub sessions_dir: Option<PathBuf>,
+    pub session_count: usize,
+    pub config_file: Option<PathBuf>,
+    pub service: Option<LegacyService>,
+}
+
+fn home() -> Option<PathBuf> {
+    std::env::var_os(if cfg!(windows) { "USERPROFILE" } else { "HOME" }).map(PathBuf::from)
+}
+
+/// Where the standalone backend was usually checked out.
+fn candidate_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(dir) = std::env::var_os("ALMREADY_LEGACY_BACKEND_DIR") {
+        dirs.push(PathBuf::from(dir));
+    }
+    if let Some(home) = home() {
+        for name in ["ALMReady", "almready", "ALMReady-backend"] {
+            dirs.push(home.join(name).join("backend"));
+        }
+    }
+    if cfg!(windows) {
+        dirs.push(PathBuf::from(r"C:\ALMReady\backend"));
+    } else {
+        dirs.push(PathBuf::from("/opt/almready/backend"));
+    }
+    dirs
+}
+
+fn detect_service() -> Option<LegacyService> {
+    if cfg!(target_os = "macos") {
+        let plist = home()?.join("Library/LaunchAgents/com.almready.backend.plist");
+        return plist.exists().then_some(LegacyService::Launchd { plist });
+    }
+    if cfg!(windows) {
+        let name = "ALMReadyBackend";
+        let found = Command::new("sc")
+            .args(["query", name])
+            .output()
+            .is_ok_and(|o| o.status.success());
+        return found.then(|| LegacyService::Windows { name: name.into() });
+    }
+    let unit = "almready-backend.service";
+    let path = home()?.join(".config/systemd/user").join(unit);
+    path.exists()
+        .then(|| LegacyService::SystemdUser { unit: unit.into() })
+}
+
+/// Flat `key: value` pairs from a legacy config.yaml.  The file never used
+/// nesting, so a line parser avoids pulling in a YAML dependency.
+fn read_legacy_config(path: &Path) -> BTreeMap<String, String> {
+    let Ok(text) = std::fs::read_to_string(path) else {
+        return BTreeMap::new();
+    };
+    text.lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim())
+        .filter_map(|line| line.split_once(':'))
+        .map(|(k, v)| {
+            let v = v.trim().trim_matches(|c| c == '"' || c == '\'');
+            (k.trim().to_string(), v.to_string())
+        })
+        .filter(|(k, v)| !k.is_empty() && !v.is_empty())
+        .collect()
+}
+
+fn session_dirs(sessions: &Path) -> Vec<PathBuf> {
+    std::fs::read_dir(sessions)
+        .map(|rd| {
+            rd.filter_map(Result::ok)
+                .map(|e| e.path())
+                .filter(|p| p.is_dir())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn inspect(backend_dir: &Path) -> Option<LegacyInstall> {
+    let config_file = Some(backend_dir.join("config.yaml")).filter(|p| p.is_file());
+    let config = config_file
+        .as_deref()
+        .map(read_legacy_config)
+        .unwrap_or_default();
+
+    let sessions_dir = config
+        .get("data_dir")
+        .map(|d| PathBuf::from(d).join("sessions"))
+        .unwrap_or_else(|| backend_dir.join("data").join("sessions"));
+    let sessions_dir = Some(sessions_dir).filter(|p| p.is_dir());
+
+    if sessions_dir.is_none() && config_file.is_none() {
+        return None;
+    }
+    Some(LegacyInstall {
+        backend_dir: backend_dir.to_path_buf(),
+        session_count: sessions_dir.as_deref().map_or(0, |d| session_dirs(d).len()),
+        sessions_dir,
+        config_file,
+        service: None,
+    })
+}
+
+// ── Plan ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum MigrationStep {
+    CopySession {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    SkipSession {
+        from: PathBuf,
+        reason: String,
+    },
+    SetBackendEnv {
+        key: String,
+        value: String,
+        from_config_key: String,
+    },
+    IgnoreConfigKey {
+        key: String,
+    },
+    StopService {
+        service: LegacyService,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationPlan {
+    pub source: PathBuf,
+    pub steps: Vec<MigrationStep>,
+}
+
+fn build_plan(install: &LegacyInstall, data_dir: &Path, stop_service: bool) -> MigrationPlan {
+    let mut steps = Vec::new();
+
+    if let Some(sessions) = &install.sessions_dir {
+        let target_root = data_dir.join("sessions");
+        for from in session_dirs(sessions) {
+            let Some(name) = from.file_name() else {
+                continue;
+            };
+            let to = target_root.join(name);
+            if to.exists() {
+                steps.push(MigrationStep::SkipSession {
+                    from,
+                    reason: "a session with this id already exists".into(),
+                });
+            } else {
+                steps.push(MigrationStep::CopySession { from, to });
+            }
+        }
+    }
+
+    if let Some(config) = &install.config_file {
+        for (key, value) in read_legacy_config(config) {
+            match RECOGNISED_KEYS.iter().find(|(k, _)| *k == key) {
+                Some((_, env)) => steps.push(MigrationStep::SetBackendEnv {
+                    key: (*env).into(),
+                    value,
+                    from_config_key: key,
+                }),
+                // data_dir is consumed by detection itself.
+                None if key == "data_dir" => {}
+                None => steps.push(MigrationStep::IgnoreConfigKey { key }),
+            }
+        }
+    }
+
+    if stop_service {
+        if let Some(service) = &install.service {
+            steps.push(MigrationStep::StopService {
+                service: service.clone(),
+            });
+        }
+    }
+
+    MigrationPlan {
+        source: install.backend_dir.clone(),
+        steps,
+    }
+}
+
+// ── Execution ───────────────────────────────────────────────────────────────
+
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+fn stop_service(service: &LegacyService) -> Result<(), String> {
+    let mut cmds: Vec<Vec<String>> = Vec::new();
+    match service {
+        LegacyService::SystemdUser { unit } => cmds.push(
+            ["systemctl", "--user", "disable", "--now", unit.as_str()]
+                .map(String::from)
+                .to_vec(),
+        ),
+        LegacyService::Launchd { plist } => cmds.push(vec![
+            "launchctl".into(),
+            "unload".into(),
+            "-w".into(),
+            plist.to_string_lossy().into_owned(),
+        ]),
+        LegacyService::Windows { name } => {
+            cmds.push(["sc", "stop", name.as_str()].map(String::from).to_vec());
+            cmds.push(
+                ["sc", "config", name.as_str(), "start=", "disabled"]
+                    .map(String::from)
+                    .to_vec(),
+            );
+        }
+    }
+    for cmd in cmds {
+        let status = Command::new(&cmd[0])
+            .args(&cmd[1..])
+            .status()
+            .map_err(|e| format!("{}: {e}", cmd.join(" ")))?;
+        if !status.success() {
+            return Err(format!("{} exited with {status}", cmd.join(" ")));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+pub struct MigrationReport {
+    pub plan: MigrationPlan,
+    pub completed: usize,
+    pub error: Option<String>,
+    pub rolled_back: bool,
+}
+
+/// Session copies land in the backend's own directory, so they go through
+/// `access`, which leases them from a running backend.
+fn execute(
+    plan: &MigrationPlan,
+    store: &SettingsStore,
+    access: &DataDirAccess,
+) -> Result<usize, (usize, String)> {
+    let mut copied: Vec<PathBuf> = Vec::new();
+    let previous_settings = store.get();
+    let mut settings = store.get();
+    let mut completed = 0;
+
+    let rollback = |copied: &[PathBuf]| {
+        for dir in copied {
+            let _ = access.with(&[dir], || std::fs::remove_dir_all(dir));
+        }
+        let _ = store.replace(previous_settings.clone());
+    };
+
+    for step in &plan.steps {
+        let result = match step {
+            MigrationStep::CopySession { from, to } => {
+                let r = access
+                    .with(&[to], || copy_dir(from, to))
+                    .map_err(|e| format!("copy {from:?}: {e}"));
+                // Record even on failure: a half-copied dir must be removed.
+                copied.push(to.clone());
+                r
+            }
+            MigrationStep::SetBackendEnv { key, value, .. } => {
+                settings.backend_env.insert(key.clone(), value.clone());
+                store.replace(settings.clone())
+            }
+            MigrationStep::StopService { service } => stop_service(service),
+            MigrationStep::SkipSession { .. } | MigrationStep::IgnoreConfigKey { .. } => Ok(()),
+        };
+        if let Err(e) = result {
+            rollback(&copied);
+            return Err((completed, e));
+        }
+        completed += 1;
+    }
+    Ok(completed)
+}
+
+// ── Commands ────────────────────────────────────────────────────────────────
+
+fn find_install(backend_dir: &Path) -> Result<LegacyInstall, String> {
+    let mut install = inspect(backend_dir)
+        .ok_or_else(|| format!("no legacy installation found in {backend_dir:?}"))?;
+    install.service = detect_service();
+    Ok(install)
+}
+
+fn detect() -> Vec<LegacyInstall> {
+    let service = detect_service();
+    candidate_dirs()
+        .iter()
+        .filter_map(|dir| inspect(dir))
+        .map(|mut install| {
+            install.service = service.clone();
+            install
+        })
+        .collect()
+}
+
+fn migrate(
+    app: &AppHandle,
+    backend_dir: &Path,
+    stop_service: bool,
+) -> Result<MigrationReport, String> {
+    let data_dir = paths::data_dir(app)?;
+    let install = find_install(backend_dir)?;
+    let plan = build_plan(&install, &data_dir, stop_service);
+
+    let access = DataDirAccess::for_app(app)?;
+    let report = match execute(&plan, &app.state::<SettingsStore>(), &access) {
+        Ok(completed) => MigrationReport {
+            plan,
+            completed,
+            error: None,
+            rolled_back: false,
+        },
+        Err((completed, error)) => MigrationReport {
+            plan,
+            completed,
+            error: Some(error),
+            rolled_back: true,
+        },
+    };
+
+    let text = serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?;
+    let _ = std::fs::create_dir_all(&data_dir);
+    let report_path = data_dir.join("migration-report.json");
+    access
+        .with(&[&report_path], || std::fs::write(&report_path, &text))
+        .map_err(|e| format!("write migration report: {e}"))?;
+    Ok(report)
+}
+
+// Scanning, copying sessions and stopping a service all block, some for
+// seconds, so each command runs on the blocking pool.
+
+#[tauri::command]
+pub async fn detect_legacy_install() -> Vec<LegacyInstall> {
+    tauri::async_runtime::spawn_blocking(detect)
+        .await
+        .unwrap_or_default()
+}
+
+/// Dry run: the exact list of changes `run_legacy_migration` would make.
+#[tauri::command]
+pub async fn plan_legacy_migration(
+    app: AppHandle,
+    backend_dir: PathBuf,
+    stop_service: bool,
+) -> Result<MigrationPlan, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let install = find_install(&backend_dir)?;
+        Ok(build_plan(&install, &paths::data_dir(&app)?, stop_service))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Execute the migration.  `stop_service` is the user's explicit consent to
+/// stop and disable the legacy service; without it the service is left
+/// alone.
+#[tauri::command]
+pub async fn run_legacy_migration(
+    app: AppHandle,
+    backend_dir: PathBuf,
+    stop_service: bool,
+) -> Result<MigrationReport, String> {
+    tauri::async_runtime::spawn_blocking(move || migrate(&app, &backend_dir, stop_service))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, text: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, text).unwrap();
+    }
+
+    #[test]
+    fn legacy_config_is_read_as_flat_pairs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let path = dir.join("config.yaml");
+        write(
+            &path,
+            "# standalone backend\n\
+             session_ttl_days: 30  # a month\n\
+             data_dir: \"/srv/almready\"\n\
+             log_level: 'debug'\n\
+             empty:\n\
+             not a pair\n",
+        );
+        let config = read_legacy_config(&path);
+        assert_eq!(
+            config.into_iter().collect::<Vec<_>>(),
+            [
+                ("data_dir".to_string(), "/srv/almready".to_string()),
+                ("log_level".to_string(), "debug".to_string()),
+                ("session_ttl_days".to_string(), "30".to_string()),
+            ]
+        );
+        assert!(read_legacy_config(&dir.join("missing.yaml")).is_empty());
+    }
+
+    #[test]
+    fn plan_copies_new_sessions_and_maps_recognised_keys() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let sessions = dir.join("backend/data/sessions");
+        write(&sessions.join("a/meta.json"), "{}");
+        write(&sessions.join("b/meta.json"), "{}");
+        let data_dir = dir.join("data");
+        write(&data_dir.join("sessions/b/meta.json"), "{}");
+        let config = dir.join("backend/config.yaml");
+        write(
+            &config,
+            "session_ttl_days: 30\ndata_dir: /elsewhere\nport: 8000\n",
+        );
+
+        let mut install = LegacyInstall {
+            backend_dir: dir.join("backend"),
+            sessions_dir: Some(sessions.clone()),
+            session_count: 2,
+            config_file: Some(config),
+            service: Some(LegacyService::SystemdUser {
+                unit: "almready.service".into(),
+            }),
+        };
+        let plan = build_plan(&install, &data_dir, false);
+        let mut actions: Vec<String> = plan
+            .steps
+            .iter()
+            .map(|step| match step {
+                MigrationStep::CopySession { from, to } => {
+                    assert_eq!(
+                        to,
+                        &data_dir.join("sessions").join(from.file_name().unwrap())
+                    );
+                    format!("copy {}", from.file_name().unwrap().to_string_lossy())
+                }
+                MigrationStep::SkipSession { from, .. } => {
+                    format!("skip {}", from.file_name().unwrap().to_string_lossy())
+                }
+                MigrationStep::SetBackendEnv {
+                    key,
+                    value,
+                    from_config_key,
+                } => format!("env {key}={value} from {from_config_key}"),
+                MigrationStep::IgnoreConfigKey { key } => format!("ignore {key}"),
+                MigrationStep::StopService { .. } => "stop".into(),
+            })
+            .collect();
+        actions.sort();
+        assert_eq!(
+            actions,
+            [
+                "copy a",
+                "env SESSION_TTL_DAYS=30 from session_ttl_days",
+                "ignore port",
+                "skip b",
+            ]
+        );
+
+        let with_consent = build_plan(&install, &data_dir, true);
+        assert!(matches!(
+            with_consent.steps.last(),
+            Some(MigrationStep::StopService { .. })
+        ));
+        install.service = None;
+        assert!(!build_plan(&install, &data_dir, true)
+            .steps
+            .iter()
+            .any(|step| matches!(step, MigrationStep::StopService { .. })));
+    }
+}
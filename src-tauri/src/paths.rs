@@ -0,0 +1,240 @@
+//! App directories, resolved in one place.
+//!
+//! macOS   → data `~/Library/Application Support/com.almready.desktop`,
+//!           cache `~/Library/Caches/com.almready.desktop`
+//! Windows → data `%APPDATA%\com.almready.desktop`,
+//!           cache `%LOCALAPPDATA%\com.almready.desktop`
+//! Linux   → `$XDG_DATA_HOME` / `$XDG_CACHE_HOME` equivalents
+//!
+//! Portable mode: when a `portable.flag` file or a `data/` directory sits
+//! next to the executable (a copy run from a shared drive, where a roaming
+//! `%APPDATA%` would grow with every session), the data directory is
+//! `{exe_dir}/data` instead, created on first use.  On a read-only or
+//! translocated install (`InstallLocation::is_read_only`) portable mode is
+//! off whatever the marker says – a translocated copy would otherwise write
+//! to a random mount that vanishes on quit – and, as when `data/` can't be
+//! written, the OS directory is used and the `portable-data-unwritable`
+//! degradation says why.  The choice is made once per run, so the sidecar, logs, window
+//! state, the pid file and everything else agree on it.
+//!
+//! Logs live under the data directory (`{data_dir}/logs`, next to the
+//! sessions they describe) rather than in Tauri's `app_log_dir`, so support
+//! only ever has to look in one place.
+//!
+//! The commands hand the frontend the same paths the shell uses, instead of
+//! it guessing them.  Each creates its directory and checks that it can be
+//! written to before returning it.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use tauri::{AppHandle, Manager};
+
+use crate::{data_dir_access::DataDirAccess, install_location::InstallLocation};
+
+pub const PORTABLE_FLAG: &str = "portable.flag";
+pub const PORTABLE_DATA: &str = "data";
+
+/// Where the data directory is, and why.
+#[derive(Debug, Clone, PartialEq)]
+enum DataDir {
+    Os(Result<PathBuf, String>),
+    Portable(PathBuf),
+    /// Portable mode was asked for but the install is read-only or `dir`
+    /// isn't writable.
+    PortableUnwritable {
+        os: Result<PathBuf, String>,
+        dir: PathBuf,
+        error: String,
+    },
+}
+
+fn choose(
+    location: &InstallLocation,
+    exe_dir: Option<&Path>,
+    os_dir: impl FnOnce() -> Result<PathBuf, String>,
+    writable: impl FnOnce(PathBuf) -> Result<PathBuf, String>,
+) -> DataDir {
+    let portable =
+        exe_dir.filter(|dir| dir.join(PORTABLE_FLAG).is_file() || dir.join(PORTABLE_DATA).is_dir());
+    let Some(exe_dir) = portable else {
+        return DataDir::Os(os_dir());
+    };
+    let dir = exe_dir.join(PORTABLE_DATA);
+    if location.is_read_only() {
+        return DataDir::PortableUnwritable {
+            os: os_dir(),
+            dir,
+            error: format!(
+                "portable mode is off for a {:?} install at {:?}",
+                location.kind, location.path
+            ),
+        };
+    }
+    match writable(dir.clone()) {
+        Ok(dir) => DataDir::Portable(dir),
+        Err(error) => DataDir::PortableUnwritable {
+            os: os_dir(),
+            dir,
+            error,
+        },
+    }
+}
+
+fn resolved(app: &AppHandle) -> &'static DataDir {
+    static DATA_DIR: OnceLock<DataDir> = OnceLock::new();
+    DATA_DIR.get_or_init(|| {
+        let exe = std::env::current_exe().ok();
+        let chosen = choose(
+            &InstallLocation::detect(),
+            exe.as_deref().and_then(Path::parent),
+            || {
+                app.path()
+                    .app_data_dir()
+                    .map_err(|e| format!("app_data_dir: {e}"))
+            },
+            ensure_writable,
+        );
+        // Not through shell_log, whose file lives in this directory.
+        match &chosen {
+            DataDir::Os(_) => {}
+            DataDir::Portable(dir) => eprintln!("[ALMReady] portable mode, data in {dir:?}"),
+            DataDir::PortableUnwritable { error, .. } => {
+                eprintln!("[ALMReady] portable data directory unusable, using the OS one: {error}")
+            }
+        }
+        chosen
+    })
+}
+
+/// The data directory: `{exe_dir}/data` in portable mode, otherwise the OS
+/// app-data directory.
+pub fn data_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    match resolved(app) {
+        DataDir::Portable(dir) => Ok(dir.clone()),
+        DataDir::Os(os) | DataDir::PortableUnwritable { os, .. } => os.clone(),
+    }
+}
+
+/// The portable directory and why it isn't used, when portable mode fell
+/// back to the OS directory.
+pub fn portable_fallback(app: &AppHandle) -> Option<(PathBuf, String)> {
+    match resolved(app) {
+        DataDir::PortableUnwritable { dir, error, .. } => Some((dir.clone(), error.clone())),
+        _ => None,
+    }
+}
+
+pub fn cache_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_cache_dir()
+        .map_err(|e| format!("app_cache_dir: {e}"))
+}
+
+/// `{data_dir}/logs`.
+pub fn log_dir_in(data_dir: &Path) -> PathBuf {
+    data_dir.join("logs")
+}
+
+pub fn log_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    data_dir(app).map(|dir| log_dir_in(&dir))
+}
+
+/// Create `dir` if needed and check that a file can be written in it.
+fn ensure_writable(dir: PathBuf) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(&dir).map_err(|e| format!("create {dir:?}: {e}"))?;
+    let probe = dir.join(format!(".almready-write-probe-{}", std::process::id()));
+    let access = DataDirAccess::unleased();
+    let written = access.with(&[&probe], || std::fs::write(&probe, b""));
+    let _ = access.with(&[&probe], || std::fs::remove_file(&probe));
+    written.map_err(|e| format!("{dir:?} is not writable: {e}"))?;
+    Ok(dir)
+}
+
+// ── Commands ────────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub fn get_app_data_dir(app: AppHandle) -> Result<PathBuf, String> {
+    ensure_writable(data_dir(&app)?)
+}
+
+#[tauri::command]
+pub fn get_app_cache_dir(app: AppHandle) -> Result<PathBuf, String> {
+    ensure_writable(cache_dir(&app)?)
+}
+
+/// Directory holding `backend.log` and its rotated copy.
+#[tauri::command]
+pub fn get_app_log_dir(app: AppHandle) -> Result<PathBuf, String> {
+    ensure_writable(log_dir(&app)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::install_location::LocationKind;
+
+    fn installed(kind: LocationKind, path: &Path) -> InstallLocation {
+        InstallLocation {
+            kind,
+            path: path.to_path_buf(),
+        }
+    }
+
+    #[test]
+    fn portable_mode_follows_the_marker_and_falls_back_when_unwritable() {
+        let tmp = tempfile::tempdir().unwrap();
+        let exe_dir = tmp.path();
+        let location = installed(LocationKind::Writable, exe_dir);
+        let os = || Ok(PathBuf::from("/os/data"));
+        let writable = |dir: PathBuf| Ok(dir);
+
+        assert_eq!(
+            choose(&location, Some(exe_dir), os, writable),
+            DataDir::Os(Ok("/os/data".into()))
+        );
+
+        std::fs::write(exe_dir.join(PORTABLE_FLAG), "").unwrap();
+        let portable = exe_dir.join(PORTABLE_DATA);
+        assert_eq!(
+            choose(&location, Some(exe_dir), os, writable),
+            DataDir::Portable(portable.clone())
+        );
+        assert_eq!(
+            choose(&location, Some(exe_dir), os, |_| Err("read-only".into())),
+            DataDir::PortableUnwritable {
+                os: Ok("/os/data".into()),
+                dir: portable,
+                error: "read-only".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn read_only_and_translocated_installs_are_never_portable() {
+        let tmp = tempfile::tempdir().unwrap();
+        let exe_dir = tmp.path();
+        std::fs::write(exe_dir.join(PORTABLE_FLAG), "").unwrap();
+
+        for kind in [
+            LocationKind::Translocated,
+            LocationKind::DiskImage,
+            LocationKind::ReadOnly,
+        ] {
+            let chosen = choose(
+                &installed(kind, exe_dir),
+                Some(exe_dir),
+                || Ok(PathBuf::from("/os/data")),
+                |_| panic!("a {kind:?} install was probed for portable data"),
+            );
+            let DataDir::PortableUnwritable { os, dir, error } = chosen else {
+                panic!("{kind:?}: {chosen:?}");
+            };
+            assert_eq!(os, Ok("/os/data".into()));
+            assert_eq!(dir, exe_dir.join(PORTABLE_DATA));
+            assert!(error.contains(&format!("{kind:?}")), "{error}");
+        }
+    }
+}
@@ -0,0 +1,99 @@
+//! `{data_dir}/backend.pid`: the sidecar the shell last started.
+//!
+//! If the shell is killed (Task Manager, a crash) its close handlers never
+//! run and the backend keeps running, holding a port and the session files.
+//! The pid file is written after every spawn and removed whenever the shell
+//! stops the sidecar itself, so one that is still present at the next spawn
+//! names a leftover.  It is killed only if that pid still runs our backend
+//! executable (see `process_tree::kill_if_running`).
+
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    data_dir_access::DataDirAccess,
+    process_tree,
+    shell_log::{self, Level},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct PidRecord {
+    pid: u32,
+    /// Unix time in milliseconds.
+    spawned_at_ms: u64,
+}
+
+pub fn path(data_dir: &Path) -> PathBuf {
+    data_dir.join("backend.pid")
+}
+
+pub fn write(path: &Path, pid: u32) -> Result<(), String> {
+    let record = PidRecord {
+        pid,
+        spawned_at_ms: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+    };
+    let text = serde_json::to_string(&record).map_err(|e| e.to_string())?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("create {dir:?}: {e}"))?;
+    }
+    DataDirAccess::unleased()
+        .with(&[path], || std::fs::write(path, &text))
+        .map_err(|e| format!("write {path:?}: {e}"))
+}
+
+pub fn remove(path: &Path) {
+    let _ = DataDirAccess::unleased().with(&[path], || std::fs::remove_file(path));
+}
+
+fn read(path: &Path) -> Option<PidRecord> {
+    let text = DataDirAccess::unleased()
+        .with(&[path], || std::fs::read_to_string(path))
+        .ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+/// Kill the sidecar named by a leftover pid file, if it is still running
+/// `exe`, and remove the file.
+pub fn kill_leftover(path: &Path, exe: &Path) {
+    let Some(record) = read(path) else {
+        remove(path);
+        return;
+    };
+    if process_tree::kill_if_running(record.pid, exe) {
+        shell_log::log(
+            Level::Warn,
+            "backend",
+            format!(
+                "killed leftover sidecar pid {} (spawned at {} ms)",
+                record.pid, record.spawned_at_ms
+            ),
+        );
+    }
+    remove(path);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_round_trips_and_garbage_is_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = path(dir.path());
+
+        write(&path, 4242).unwrap();
+        assert_eq!(read(&path).map(|r| r.pid), Some(4242));
+
+        std::fs::write(&path, "not json").unwrap();
+        assert_eq!(read(&path), None);
+        kill_leftover(&path, Path::new("/nonexistent"));
+        assert!(!path.exists());
+    }
+}
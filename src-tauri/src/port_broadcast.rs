@@ -0,0 +1,75 @@
+//! The sidecar's port for anyone who needs it, whenever they start.
+//!
+//! `port_reader` delivers the port once, to the launch that spawned the
+//! sidecar.  Everything else reads it from `SidecarPortBroadcaster` (held
+//! in `BackendProcess::port`), a `watch` channel: `get` for the current
+//! value, or `wait` / `subscribe` to be woken when a port is published –
+//! also when that happened before the subscriber existed.  `None` means no
+//! backend is running.
+
+use tokio::sync::watch;
+
+pub struct SidecarPortBroadcaster(watch::Sender<Option<u16>>);
+
+impl Default for SidecarPortBroadcaster {
+    fn default() -> Self {
+        SidecarPortBroadcaster(watch::channel(None).0)
+    }
+}
+
+impl SidecarPortBroadcaster {
+    /// The current port, without waiting.
+    pub fn get(&self) -> Option<u16> {
+        *self.0.borrow()
+    }
+
+    /// Publish the port of a backend that reported it, or `None` once it
+    /// is gone.
+    pub fn set(&self, port: Option<u16>) {
+        self.0.send_replace(port);
+    }
+
+    /// Clear the port and return what it was.
+    pub fn take(&self) -> Option<u16> {
+        self.0.send_replace(None)
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<Option<u16>> {
+        self.0.subscribe()
+    }
+
+    /// The port, as soon as a backend has one.
+    pub async fn wait(&self) -> u16 {
+        let mut rx = self.subscribe();
+        let port = *rx
+            .wait_for(Option::is_some)
+            .await
+            .expect("the sender lives as long as self");
+        port.expect("waited for Some")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{sync::Arc, time::Duration};
+
+    #[tokio::test]
+    async fn early_and_late_subscribers_both_see_the_port() {
+        let ports = Arc::new(SidecarPortBroadcaster::default());
+        assert_eq!(ports.get(), None);
+
+        let early = tokio::spawn({
+            let ports = ports.clone();
+            async move { ports.wait().await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        ports.set(Some(51234));
+
+        assert_eq!(early.await.unwrap(), 51234);
+        // Subscribed after the port was sent: no wait, no new probe.
+        assert_eq!(ports.wait().await, 51234);
+        assert_eq!(ports.take(), Some(51234));
+        assert_eq!(ports.get(), None);
+    }
+}
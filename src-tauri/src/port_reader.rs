@@ -0,0 +1,240 @@
+//! Learning the sidecar's port.
+//!
+//! sidecar_main.py prints `PORT:{n}` on stdout, which a blocking reader task
+//! picks up.  It also writes the port to the file named by
+//! `ALMREADY_PORT_FILE`, a fallback for when stdout can't be read.
+//!
+//! Besides `PORT:` the handshake reserves three prefixes, whose syntax
+//! `parse_line` fixes and `conformance` checks, though the shell acts on
+//! none of them yet: `STAGE:{name}` (startup progress, only before
+//! `PORT:`), `ERROR:{message}` (why startup failed) and `CTRL:{word}`.
+//! Names and words are lower-case ASCII letters, digits, `-` and `_`.  Any
+//! other stdout line is log output.
+//!
+//! The reader reports a `PortOutcome` instead of a bare port so a panicking
+//! reader is not mistaken for an engine that exited: in that case the child
+//! may be perfectly healthy, just unobservable.  `resolve` turns the outcome
+//! (plus whatever the port file says) into the next step, allowing one
+//! respawn after a reader failure.
+//!
+//! The release profile builds with `panic = "abort"`, where a reader panic
+//! ends the process before it can be classified; unexpected pipe errors are
+//! therefore treated as end of output rather than panicking.
+
+use std::{
+    any::Any,
+    io::BufRead,
+    path::{Path, PathBuf},
+};
+
+use tokio::sync::oneshot;
+
+use crate::{data_dir_access::DataDirAccess, failure::BackendError};
+
+/// Environment variable telling the sidecar where to write its port.
+pub const PORT_FILE_ENV: &str = "ALMREADY_PORT_FILE";
+
+pub const EXITED_BEFORE_PORT: &str =
+    "The engine exited before reporting which port it is listening on.";
+pub const READER_FAILED: &str = "Internal error reading engine output.";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PortOutcome {
+    Port(u16),
+    /// stdout closed without a `PORT:` line.
+    Exited,
+    /// The reader task panicked, with the panic message.
+    ReaderPanicked(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PortStep {
+    Ready(u16),
+    /// Stop this sidecar and start a new one.
+    Respawn,
+    Fail(BackendError),
+}
+
+pub fn port_file_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("backend.port")
+}
+
+/// A kind of handshake line, by its prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineKind {
+    Port,
+    Stage,
+    Error,
+    Ctrl,
+}
+
+impl LineKind {
+    const ALL: [LineKind; 4] = [
+        LineKind::Port,
+        LineKind::Stage,
+        LineKind::Error,
+        LineKind::Ctrl,
+    ];
+
+    pub fn prefix(self) -> &'static str {
+        match self {
+            LineKind::Port => "PORT:",
+            LineKind::Stage => "STAGE:",
+            LineKind::Error => "ERROR:",
+            LineKind::Ctrl => "CTRL:",
+        }
+    }
+}
+
+fn is_word(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
+
+/// `None` for a line that isn't part of the handshake; otherwise its kind
+/// and value, or why the value is malformed.
+pub fn parse_line(line: &str) -> Option<(LineKind, Result<String, String>)> {
+    let (kind, value) = LineKind::ALL
+        .iter()
+        .find_map(|&kind| Some((kind, line.strip_prefix(kind.prefix())?.trim())))?;
+    let valid = match kind {
+        LineKind::Port => value.parse::<u16>().is_ok_and(|port| port != 0),
+        LineKind::Stage | LineKind::Ctrl => is_word(value),
+        LineKind::Error => !value.is_empty(),
+    };
+    let value = if valid {
+        Ok(value.to_string())
+    } else {
+        Err(format!("malformed {} line {line:?}", kind.prefix()))
+    };
+    Some((kind, value))
+}
+
+pub fn parse_port_line(line: &str) -> Option<u16> {
+    match parse_line(line)? {
+        (LineKind::Port, Ok(port)) => port.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Scan `reader` for the `PORT:` line.  A read error ends the scan like EOF.
+pub fn read_port(reader: impl BufRead) -> Option<u16> {
+    reader
+        .lines()
+        .map_while(Result::ok)
+        .find_map(|line| parse_port_line(&line))
+}
+
+pub fn read_port_file(path: &Path) -> Option<u16> {
+    DataDirAccess::unleased()
+        .with(&[path], || std::fs::read_to_string(path))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".into())
+}
+
+/// Run `read` on a blocking thread and deliver its outcome.  The receiver
+/// always gets a value, including when `read` panics.
+pub fn spawn<F>(read: F) -> oneshot::Receiver<PortOutcome>
+where
+    F: FnOnce() -> Option<u16> + Send + 'static,
+{
+    let (tx, rx) = oneshot::channel();
+    tauri::async_runtime::spawn(async move {
+        let outcome = match tokio::task::spawn_blocking(read).await {
+            Ok(Some(port)) => PortOutcome::Port(port),
+            Ok(None) => PortOutcome::Exited,
+            Err(e) if e.is_panic() => PortOutcome::ReaderPanicked(panic_message(e.into_panic())),
+            Err(e) => PortOutcome::ReaderPanicked(e.to_string()),
+        };
+        let _ = tx.send(outcome);
+    });
+    rx
+}
+
+/// What to do after the reader finished.  `from_file` is the port-file
+/// fallback, consulted whenever stdout didn't yield a port.
+pub fn resolve(outcome: &PortOutcome, from_file: Option<u16>, respawned: bool) -> PortStep {
+    match (outcome, from_file.as_ref()) {
+        (PortOutcome::Port(port), _) | (_, Some(port)) => PortStep::Ready(*port),
+        (PortOutcome::Exited, None) => PortStep::Fail(BackendError::ExitedBeforePort),
+        (PortOutcome::ReaderPanicked(_), None) if !respawned => PortStep::Respawn,
+        (PortOutcome::ReaderPanicked(_), None) => PortStep::Fail(BackendError::ReaderFailed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_port_line() {
+        let out = "loading\nPORT: 51234\nPORT:1\n".as_bytes();
+        assert_eq!(read_port(out), Some(51234));
+        assert_eq!(read_port("no port\n".as_bytes()), None);
+    }
+
+    #[test]
+    fn handshake_lines_are_parsed_by_prefix() {
+        assert_eq!(parse_line("INFO: started"), None);
+        assert_eq!(
+            parse_line("STAGE: warm-pool"),
+            Some((LineKind::Stage, Ok("warm-pool".into())))
+        );
+        assert_eq!(
+            parse_line("ERROR:database is locked"),
+            Some((LineKind::Error, Ok("database is locked".into())))
+        );
+        assert_eq!(
+            parse_line("CTRL:ready_2"),
+            Some((LineKind::Ctrl, Ok("ready_2".into())))
+        );
+        for malformed in [
+            "PORT:0",
+            "PORT:70000",
+            "STAGE:Warm Pool",
+            "ERROR: ",
+            "CTRL:",
+        ] {
+            assert!(
+                matches!(parse_line(malformed), Some((_, Err(_)))),
+                "{malformed}"
+            );
+        }
+        assert_eq!(parse_port_line("PORT:abc"), None);
+    }
+
+    #[tokio::test]
+    async fn panicking_reader_is_classified_and_retried_once() {
+        let outcome = spawn(|| panic!("pipe error")).await.unwrap();
+        assert_eq!(outcome, PortOutcome::ReaderPanicked("pipe error".into()));
+
+        assert_eq!(resolve(&outcome, None, false), PortStep::Respawn);
+        assert_eq!(
+            resolve(&outcome, None, true),
+            PortStep::Fail(BackendError::ReaderFailed)
+        );
+        assert_eq!(resolve(&outcome, Some(8123), true), PortStep::Ready(8123));
+    }
+
+    #[tokio::test]
+    async fn exited_engine_is_not_retried() {
+        let outcome = spawn(|| None).await.unwrap();
+        assert_eq!(outcome, PortOutcome::Exited);
+        assert_eq!(
+            resolve(&outcome, None, false),
+            PortStep::Fail(BackendError::ExitedBeforePort)
+        );
+    }
+}
@@ -0,0 +1,135 @@
+//! The OS default printer, shown before `print_window` prints.
+//!
+//! Windows → `GetDefaultPrinterW`
+//! macOS   → `NSPrintInfo.defaultPrinter.name`
+//! Linux   → `lpstat -d` (CUPS)
+//!
+//! `None` means no printer is configured (or, on Linux, CUPS isn't
+//! installed).  A housekeeping task looks again every `CHECK_INTERVAL` and
+//! emits `default-printer-changed` with the new name (or `null`) to every
+//! window when it differs from the last one seen.
+
+use std::{sync::Mutex, time::Duration};
+
+use tauri::{AppHandle, Manager};
+
+use crate::events::{self, Audience};
+
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+#[cfg(target_os = "windows")]
+fn default_printer() -> Result<Option<String>, String> {
+    use windows::{core::PWSTR, Win32::Graphics::Printing::GetDefaultPrinterW};
+
+    let mut len = 0u32;
+    // SAFETY: with no buffer, only the needed length is written to `len`.
+    // It stays 0 when there is no default printer.
+    let _ = unsafe { GetDefaultPrinterW(None, &mut len) };
+    if len == 0 {
+        return Ok(None);
+    }
+    let mut buffer = vec![0u16; len as usize];
+    // SAFETY: `buffer` holds `len` UTF-16 units, as `len` says.
+    if !unsafe { GetDefaultPrinterW(Some(PWSTR(buffer.as_mut_ptr())), &mut len) }.as_bool() {
+        return Err(format!(
+            "GetDefaultPrinterW: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+    // `len` now counts the terminating NUL.
+    let name = &buffer[..(len as usize).saturating_sub(1)];
+    Ok(Some(String::from_utf16_lossy(name)))
+}
+
+#[cfg(target_os = "macos")]
+fn default_printer() -> Result<Option<String>, String> {
+    use objc2_app_kit::NSPrintInfo;
+
+    #[allow(unused_unsafe)]
+    let printer = unsafe { NSPrintInfo::defaultPrinter() };
+    Ok(printer.map(|printer| unsafe { printer.name() }.to_string()))
+}
+
+/// The destination in `lpstat -d` output (C locale):
+/// `system default destination: Office_Laser`, or
+/// `no system default destination`.
+#[cfg(any(all(unix, not(target_os = "macos")), test))]
+fn parse_lpstat(output: &str) -> Option<String> {
+    output
+        .lines()
+        .find_map(|line| line.strip_prefix("system default destination:"))
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn default_printer() -> Result<Option<String>, String> {
+    let output = match std::process::Command::new("lpstat")
+        .arg("-d")
+        .env("LC_ALL", "C")
+        .output()
+    {
+        Ok(output) => output,
+        // No CUPS client: nothing to print to.
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("lpstat -d: {e}")),
+    };
+    Ok(parse_lpstat(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Managed state: the default printer as last seen, once looked up.
+#[derive(Default)]
+pub struct DefaultPrinter(Mutex<Option<Option<String>>>);
+
+impl DefaultPrinter {
+    /// Remember `current`; whether it differs from a previously seen value.
+    fn update(&self, current: &Option<String>) -> bool {
+        let previous = self.0.lock().unwrap().replace(current.clone());
+        previous.is_some_and(|previous| previous != *current)
+    }
+}
+
+/// Look up the default printer and emit `default-printer-changed` if it
+/// changed.  Also the housekeeping task.
+pub fn check(app: &AppHandle) -> Result<Option<String>, String> {
+    let current = default_printer()?;
+    if app.state::<DefaultPrinter>().update(&current) {
+        events::emit(
+            app,
+            Audience::AllWindows,
+            "default-printer-changed",
+            current.clone(),
+        );
+    }
+    Ok(current)
+}
+
+#[tauri::command]
+pub fn get_default_printer(app: AppHandle) -> Result<Option<String>, String> {
+    check(&app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lpstat_destination_or_none() {
+        assert_eq!(
+            parse_lpstat("system default destination: Office_Laser\n"),
+            Some("Office_Laser".into())
+        );
+        assert_eq!(parse_lpstat("no system default destination\n"), None);
+        assert_eq!(parse_lpstat(""), None);
+    }
+
+    #[test]
+    fn only_a_change_from_a_known_printer_counts() {
+        let printer = DefaultPrinter::default();
+        assert!(!printer.update(&Some("Office_Laser".into())));
+        assert!(!printer.update(&Some("Office_Laser".into())));
+        assert!(printer.update(&None));
+        assert!(printer.update(&Some("Home_Inkjet".into())));
+    }
+}
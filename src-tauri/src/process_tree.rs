@@ -0,0 +1,532 @@
+//! Spawning and killing the sidecar together with everything it forks.
+//!
+//! The PyInstaller backend starts ProcessPoolExecutor workers, so killing
+//! only the direct child leaves them orphaned.
+//!
+//! Unix    → the child leads its own process group and SIGKILL is sent to
+//!           the negative PGID, reaching every worker in the group.
+//! Windows → the child is assigned to a Job Object with
+//!           `JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE`; workers inherit the job,
+//!           and terminating it (or the shell dying and the handle closing)
+//!           kills all of them.  The child is assigned right after spawn,
+//!           before the backend's lifespan has had a chance to fork.
+//!
+//! `exited` resolves when a child exits without parking a thread in
+//! `wait()`: on Linux through a pidfd registered with the runtime, elsewhere
+//! by polling `try_wait` on the runtime's timer, since the child stays
+//! shared with the code that stops it.
+//!
+//! `kill_if_running` handles a sidecar left over from a shell that crashed:
+//! it is only killed if the pid still runs our backend executable as the
+//! current user, so an unrelated process that reused the pid – or another
+//! user's backend on a shared machine – is never touched.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+    process::{Child, Command},
+    time::Duration,
+};
+
+use serde::Serialize;
+
+use crate::user_scope;
+
+/// How a child ended: its exit code, or on Unix the signal that killed it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ExitStatus {
+    pub code: Option<i32>,
+    pub signal: Option<i32>,
+}
+
+impl From<std::process::ExitStatus> for ExitStatus {
+    fn from(status: std::process::ExitStatus) -> Self {
+        #[cfg(unix)]
+        let signal = std::os::unix::process::ExitStatusExt::signal(&status);
+        #[cfg(not(unix))]
+        let signal = None;
+        ExitStatus {
+            code: status.code(),
+            signal,
+        }
+    }
+}
+
+impl std::fmt::Display for ExitStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.code, self.signal) {
+            (Some(code), _) => write!(f, "exit code {code}"),
+            (None, Some(signal)) => write!(f, "signal {signal}"),
+            (None, None) => f.write_str("unknown exit status"),
+        }
+    }
+}
+
+/// Killed (tree and all) when dropped, so a handle lost on an error path or
+/// in a cancelled task can't leave the backend running.
+pub struct ProcessTree {
+    child: Child,
+    reaped: bool,
+    #[cfg(windows)]
+    job: job::Job,
+}
+
+impl ProcessTree {
+    pub fn spawn(command: &mut Command) -> io::Result<Self> {
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(command, 0);
+
+        let child = command.spawn()?;
+
+        #[cfg(windows)]
+        let job = match job::Job::assign(&child) {
+            Ok(job) => job,
+            Err(e) => {
+                let mut child = child;
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(e);
+            }
+        };
+
+        Ok(ProcessTree {
+            child,
+            reaped: false,
+            #[cfg(windows)]
+            job,
+        })
+    }
+
+    /// The direct child, e.g. to take its stdout/stderr pipes.
+    pub fn child_mut(&mut self) -> &mut Child {
+        &mut self.child
+    }
+
+    /// The child's exit status if it has terminated.
+    pub fn try_wait(&mut self) -> Option<ExitStatus> {
+        self.child.try_wait().ok().flatten().map(ExitStatus::from)
+    }
+
+    /// Ask the child alone to exit: SIGTERM on Unix, which uvicorn handles
+    /// as a graceful shutdown.  Windows has no equivalent; callers there
+    /// rely on the backend's HTTP shutdown endpoint.
+    pub fn request_stop(&self) {
+        #[cfg(unix)]
+        // SAFETY: plain syscall on our own child's pid.
+        unsafe {
+            libc::kill(self.child.id() as libc::pid_t, libc::SIGTERM);
+        }
+    }
+
+    /// Force the child alone to exit: SIGTERM on Unix, `TerminateProcess`
+    /// on Windows.  Its workers are left to `send_kill`.
+    pub fn terminate_child(&mut self) {
+        #[cfg(unix)]
+        self.request_stop();
+        #[cfg(windows)]
+        let _ = self.child.kill();
+    }
+
+    /// Kill the child and all of its descendants, then reap the child.
+    /// Returns the child's exit code when it had already terminated on its
+    /// own (its workers are killed regardless).
+    pub fn kill(mut self) -> Option<i32> {
+        self.kill_tree()
+    }
+
+    /// Kill the child and all of its descendants without waiting for them;
+    /// `try_wait` reaps the child once it is gone.
+    pub fn send_kill(&mut self) {
+        #[cfg(unix)]
+        {
+            let pgid = self.child.id() as libc::pid_t;
+            // SAFETY: plain syscall; a negative pid addresses the group.
+            unsafe { libc::kill(-pgid, libc::SIGKILL) };
+        }
+        #[cfg(windows)]
+        self.job.terminate();
+
+        let _ = self.child.kill(); // no-op if the group/job kill got it
+    }
+
+    /// Drop without the kill and blocking reap in `Drop`: for a child that
+    /// `try_wait` has already reaped, or one that `send_kill` could not take
+    /// down (stuck in an uninterruptible wait) and is given up on.
+    pub fn release(mut self) {
+        self.reaped = true;
+    }
+
+    fn kill_tree(&mut self) -> Option<i32> {
+        let code = match self.child.try_wait() {
+            Ok(Some(status)) => status.code(),
+            _ => None,
+        };
+        self.send_kill();
+        let _ = self.child.wait(); // reap the zombie
+        self.reaped = true;
+        code
+    }
+}
+
+impl Drop for ProcessTree {
+    fn drop(&mut self) {
+        if !self.reaped {
+            self.kill_tree();
+        }
+    }
+}
+
+// ── Exit notification ───────────────────────────────────────────────────────
+
+/// Interval at which `exited` polls where no exit notification is available.
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Resolve once `pid` has exited (it may still be a zombie awaiting reap).
+/// Returns at once if no pidfd could be opened; the caller then polls.
+#[cfg(target_os = "linux")]
+async fn pidfd_exited(pid: u32) {
+    use std::os::fd::{FromRawFd, OwnedFd};
+
+    // SAFETY: plain syscall; a non-negative result is a new fd we own.
+    let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid as libc::pid_t, 0) };
+    if fd < 0 {
+        return; // kernel older than 5.3
+    }
+    // SAFETY: `fd` was just opened and is not owned elsewhere.
+    let fd = unsafe { OwnedFd::from_raw_fd(fd as libc::c_int) };
+    if let Ok(fd) = tokio::io::unix::AsyncFd::new(fd) {
+        // A pidfd becomes readable when the process terminates.
+        let _ = fd.readable().await;
+    }
+}
+
+/// Resolve with the exit status of the child `poll` reports on, once it has
+/// one.  `pid` is the child's pid, used on Linux to wait for the exit
+/// instead of polling.
+pub async fn exited(pid: Option<u32>, mut poll: impl FnMut() -> Option<ExitStatus>) -> ExitStatus {
+    #[cfg(target_os = "linux")]
+    if let Some(pid) = pid {
+        pidfd_exited(pid).await;
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = pid;
+    loop {
+        if let Some(status) = poll() {
+            return status;
+        }
+        tokio::time::sleep(EXIT_POLL_INTERVAL).await;
+    }
+}
+
+// ── Leftovers from a crashed shell ──────────────────────────────────────────
+
+/// How long to wait for a killed leftover to disappear.
+const LEFTOVER_EXIT_WAIT: Duration = Duration::from_secs(2);
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// What is known about the process behind a leftover pid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ProcessInfo {
+    exe: Option<PathBuf>,
+    owner: Option<String>,
+}
+
+impl ProcessInfo {
+    /// Running `exe` as `user`.  Anything that can't be determined counts
+    /// as someone else's.
+    fn is_our_sidecar(&self, exe: &Path, user: Option<&str>) -> bool {
+        self.exe
+            .as_deref()
+            .is_some_and(|actual| same_file(actual, exe))
+            && self.owner.is_some()
+            && self.owner.as_deref() == user
+    }
+}
+
+/// Executable of a running process, if it can be determined.
+#[cfg(target_os = "linux")]
+fn exe_path(pid: u32) -> Option<PathBuf> {
+    let link = std::fs::read_link(format!("/proc/{pid}/exe")).ok()?;
+    // An executable replaced by an update reads as "… (deleted)".
+    let link = link.to_string_lossy();
+    Some(PathBuf::from(
+        link.strip_suffix(" (deleted)").unwrap_or(&link),
+    ))
+}
+
+#[cfg(target_os = "macos")]
+fn exe_path(pid: u32) -> Option<PathBuf> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut buf = vec![0u8; libc::PROC_PIDPATHINFO_MAXSIZE as usize];
+    // SAFETY: the buffer is writable for the length passed.
+    let len = unsafe {
+        libc::proc_pidpath(
+            pid as libc::c_int,
+            buf.as_mut_ptr().cast(),
+            buf.len() as u32,
+        )
+    };
+    if len <= 0 {
+        return None;
+    }
+    Some(PathBuf::from(std::ffi::OsStr::from_bytes(
+        &buf[..len as usize],
+    )))
+}
+
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+fn exe_path(_pid: u32) -> Option<PathBuf> {
+    None
+}
+
+/// Kill process `pid` and its process group if it is running `exe` as the
+/// current user.  Returns whether it was.
+#[cfg(unix)]
+pub fn kill_if_running(pid: u32, exe: &Path) -> bool {
+    let info = ProcessInfo {
+        exe: exe_path(pid),
+        owner: user_scope::process_owner(pid),
+    };
+    if !info.is_our_sidecar(exe, user_scope::current_user().as_deref()) {
+        return false;
+    }
+    // SAFETY: plain syscalls.  The sidecar led its own process group, so
+    // the negative pid reaches its workers too.
+    unsafe {
+        libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+        libc::kill(pid as libc::pid_t, libc::SIGKILL);
+    }
+    let start = std::time::Instant::now();
+    while exe_path(pid).is_some() && start.elapsed() < LEFTOVER_EXIT_WAIT {
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    true
+}
+
+/// Kill process `pid` if it is running `exe` as the current user.  Returns
+/// whether it was.  Its workers were in the crashed shell's job and died with it.
+#[cfg(windows)]
+pub fn kill_if_running(pid: u32, exe: &Path) -> bool {
+    use windows::{
+        core::PWSTR,
+        Win32::{
+            Foundation::CloseHandle,
+            System::Threading::{
+                OpenProcess, QueryFullProcessImageNameW, TerminateProcess, WaitForSingleObject,
+                PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_SYNCHRONIZE,
+                PROCESS_TERMINATE,
+            },
+        },
+    };
+
+    // SAFETY: the handle is closed before returning, and the name buffer is
+    // writable for the length passed.
+    unsafe {
+        let Ok(handle) = OpenProcess(
+            PROCESS_QUERY_LIMITED_INFORMATION | PROCESS_TERMINATE | PROCESS_SYNCHRONIZE,
+            false.into(),
+            pid,
+        ) else {
+            return false;
+        };
+        let mut buf = [0u16; 1024];
+        let mut len = buf.len() as u32;
+        let info = ProcessInfo {
+            exe: QueryFullProcessImageNameW(
+                handle,
+                PROCESS_NAME_WIN32,
+                PWSTR(buf.as_mut_ptr()),
+                &mut len,
+            )
+            .is_ok()
+            .then(|| PathBuf::from(String::from_utf16_lossy(&buf[..len as usize]))),
+            owner: user_scope::process_owner(pid),
+        };
+        let matches = info.is_our_sidecar(exe, user_scope::current_user().as_deref());
+        if matches {
+            let _ = TerminateProcess(handle, 1);
+            let _ = WaitForSingleObject(handle, LEFTOVER_EXIT_WAIT.as_millis() as u32);
+        }
+        let _ = CloseHandle(handle);
+        matches
+    }
+}
+
+#[cfg(windows)]
+mod job {
+    use std::{ffi::c_void, io, os::windows::io::AsRawHandle, process::Child};
+
+    use windows::{
+        core::PCWSTR,
+        Win32::{
+            Foundation::{CloseHandle, HANDLE},
+            System::JobObjects::{
+                AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation,
+                SetInformationJobObject, TerminateJobObject, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+                JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+            },
+        },
+    };
+
+    pub struct Job(HANDLE);
+
+    // SAFETY: a job handle is a kernel object handle, usable from any thread.
+    unsafe impl Send for Job {}
+
+    impl Job {
+        pub fn assign(child: &Child) -> io::Result<Job> {
+            // SAFETY: every pointer passed below outlives its call, and the
+            // handle is owned by `Job`, which closes it exactly once.
+            unsafe {
+                let job = Job(CreateJobObjectW(None, PCWSTR::null())?);
+
+                let mut info = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+                info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+                SetInformationJobObject(
+                    job.0,
+                    JobObjectExtendedLimitInformation,
+                    &info as *const _ as *const c_void,
+                    std::mem::size_of_val(&info) as u32,
+                )?;
+
+                AssignProcessToJobObject(job.0, HANDLE(child.as_raw_handle()))?;
+                Ok(job)
+            }
+        }
+
+        pub fn terminate(&self) {
+            // SAFETY: `self.0` is a valid job handle until drop.
+            let _ = unsafe { TerminateJobObject(self.0, 1) };
+        }
+    }
+
+    impl Drop for Job {
+        fn drop(&mut self) {
+            // SAFETY: closed once, here.
+            let _ = unsafe { CloseHandle(self.0) };
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::{
+        io::{BufRead, BufReader},
+        process::Stdio,
+        time::{Duration, Instant},
+    };
+
+    #[test]
+    fn leftovers_must_run_our_exe_as_us() {
+        let exe = Path::new("/opt/almready/almready-backend");
+        let info = |exe: Option<&str>, owner: Option<&str>| ProcessInfo {
+            exe: exe.map(PathBuf::from),
+            owner: owner.map(str::to_string),
+        };
+        let ours = info(Some("/opt/almready/almready-backend"), Some("1001"));
+        assert!(ours.is_our_sidecar(exe, Some("1001")));
+        // Another user's backend from the same shared install.
+        assert!(!ours.is_our_sidecar(exe, Some("1002")));
+        assert!(!ours.is_our_sidecar(exe, None));
+        assert!(!info(Some("/usr/bin/python3"), Some("1001")).is_our_sidecar(exe, Some("1001")));
+        assert!(!info(None, Some("1001")).is_our_sidecar(exe, Some("1001")));
+        assert!(!info(Some("/opt/almready/almready-backend"), None).is_our_sidecar(exe, None));
+    }
+
+    /// Running (not gone, not a zombie awaiting its new parent's reap).
+    fn alive(pid: u32) -> bool {
+        let out = Command::new("ps")
+            .args(["-o", "stat=", "-p", &pid.to_string()])
+            .output()
+            .unwrap();
+        let stat = String::from_utf8_lossy(&out.stdout);
+        !stat.trim().is_empty() && !stat.trim_start().starts_with('Z')
+    }
+
+    fn gone_within(pid: u32, timeout: Duration) -> bool {
+        let start = Instant::now();
+        while start.elapsed() < timeout {
+            if !alive(pid) {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        false
+    }
+
+    #[test]
+    fn kill_takes_down_grandchildren() {
+        let mut tree = ProcessTree::spawn(
+            Command::new("sh")
+                .args(["-c", "sleep 60 & echo $!; wait"])
+                .stdout(Stdio::piped()),
+        )
+        .unwrap();
+        let child_pid = tree.child_mut().id();
+
+        let stdout = tree.child_mut().stdout.take().unwrap();
+        let mut line = String::new();
+        BufReader::new(stdout).read_line(&mut line).unwrap();
+        let grandchild_pid: u32 = line.trim().parse().unwrap();
+        assert!(alive(grandchild_pid));
+
+        assert_eq!(tree.kill(), None);
+        assert!(gone_within(child_pid, Duration::from_secs(2)));
+        assert!(gone_within(grandchild_pid, Duration::from_secs(2)));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn leftover_is_only_killed_if_the_executable_matches() {
+        let mut tree = ProcessTree::spawn(Command::new("sleep").arg("60")).unwrap();
+        let pid = tree.child_mut().id();
+        let exe = exe_path(pid).unwrap();
+
+        assert!(!kill_if_running(pid, Path::new("/usr/bin/not-the-backend")));
+        assert!(alive(pid));
+
+        assert!(kill_if_running(pid, &exe));
+        assert!(gone_within(pid, Duration::from_secs(2)));
+        assert!(tree.try_wait().is_some());
+    }
+
+    #[tokio::test]
+    async fn exited_reports_code_or_signal() {
+        let mut tree = ProcessTree::spawn(Command::new("sh").args(["-c", "exit 3"])).unwrap();
+        let pid = Some(tree.child_mut().id());
+        let status = tokio::time::timeout(Duration::from_secs(5), exited(pid, || tree.try_wait()))
+            .await
+            .unwrap();
+        assert_eq!(
+            status,
+            ExitStatus {
+                code: Some(3),
+                signal: None
+            }
+        );
+        assert_eq!(status.to_string(), "exit code 3");
+
+        let mut tree = ProcessTree::spawn(Command::new("sleep").arg("60")).unwrap();
+        // SAFETY: plain syscall on our own child's pid.
+        unsafe { libc::kill(tree.child_mut().id() as libc::pid_t, libc::SIGKILL) };
+        let status = exited(None, || tree.try_wait()).await;
+        assert_eq!(status.code, None);
+        assert_eq!(status.signal, Some(libc::SIGKILL));
+        assert_eq!(status.to_string(), format!("signal {}", libc::SIGKILL));
+    }
+
+    #[test]
+    fn kill_reports_exit_code_of_finished_child() {
+        let tree = ProcessTree::spawn(Command::new("sh").args(["-c", "exit 3"])).unwrap();
+        std::thread::sleep(Duration::from_millis(300));
+        assert_eq!(tree.kill(), Some(3));
+    }
+}
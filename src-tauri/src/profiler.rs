@@ -0,0 +1,118 @@
+//! Flame graphs of the running engine for performance debugging.
+//!
+//! `capture_performance_profile` runs `py-spy record` against the sidecar
+//! (and its worker processes) for the requested number of seconds, writes
+//! `{data_dir}/profile.svg` and opens it with the default handler, usually
+//! the browser.  py-spy is not bundled: it has to be on `PATH`, and on macOS
+//! and some Linux setups it also needs the right to attach to another
+//! process (root, or `ptrace_scope` 0).
+
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    menu, paths,
+    shell_log::{self, Level},
+    BackendProcess,
+};
+
+const MAX_DURATION_SECS: u32 = 600;
+
+#[cfg(windows)]
+const PY_SPY: &str = "py-spy.exe";
+#[cfg(not(windows))]
+const PY_SPY: &str = "py-spy";
+
+/// First `name` in the directories of `path` (a `PATH` value).
+fn find_in_path(name: &str, path: &OsStr) -> Option<PathBuf> {
+    std::env::split_paths(path)
+        .map(|dir| dir.join(name))
+        .find(|candidate| candidate.is_file())
+}
+
+fn record(py_spy: &Path, pid: u32, output: &Path, duration_secs: u32) -> Result<(), String> {
+    let result = Command::new(py_spy)
+        .arg("record")
+        .args(["--pid", &pid.to_string()])
+        .arg("--output")
+        .arg(output)
+        .args(["--duration", &duration_secs.to_string()])
+        .args(["--format", "flamegraph"])
+        // Calculations run in the ProcessPoolExecutor workers.
+        .arg("--subprocesses")
+        .output()
+        .map_err(|e| format!("py-spy: {e}"))?;
+    if !result.status.success() {
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        let reason = stderr.lines().rev().find(|l| !l.trim().is_empty());
+        return Err(format!(
+            "py-spy exited with {}: {}",
+            result.status,
+            reason.unwrap_or("no output")
+        ));
+    }
+    Ok(())
+}
+
+/// Profile the running engine for `duration_secs` and open the flame graph.
+/// Returns the path of the SVG.
+#[tauri::command]
+pub async fn capture_performance_profile(
+    app: AppHandle,
+    duration_secs: u32,
+) -> Result<PathBuf, String> {
+    if !(1..=MAX_DURATION_SECS).contains(&duration_secs) {
+        return Err(format!(
+            "The duration must be between 1 and {MAX_DURATION_SECS} seconds."
+        ));
+    }
+    let py_spy = find_in_path(PY_SPY, &std::env::var_os("PATH").unwrap_or_default()).ok_or(
+        "py-spy was not found on PATH. Install it with `pip install py-spy` and restart \
+             ALMReady.",
+    )?;
+    let pid = app
+        .state::<BackendProcess>()
+        .pid()
+        .ok_or("The engine is not running.")?;
+    let output = paths::data_dir(&app)?.join("profile.svg");
+
+    shell_log::log(
+        Level::Info,
+        "profiler",
+        format!("recording pid {pid} for {duration_secs} s"),
+    );
+    let svg = output.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        record(&py_spy, pid, &svg, duration_secs)?;
+        menu::open_path(&svg)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn py_spy_is_found_in_the_first_path_entry_that_has_it() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let (empty, first, second) = (root.join("a"), root.join("b"), root.join("c"));
+        for dir in [&empty, &first, &second] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        std::fs::write(first.join(PY_SPY), b"").unwrap();
+        std::fs::write(second.join(PY_SPY), b"").unwrap();
+
+        let path = std::env::join_paths([&empty, &first, &second]).unwrap();
+        assert_eq!(find_in_path(PY_SPY, &path), Some(first.join(PY_SPY)));
+        assert_eq!(find_in_path(PY_SPY, OsStr::new("")), None);
+    }
+}
@@ -0,0 +1,219 @@
+//! Readiness of the backend's components, checked after `/api/health`.
+//!
+//! A healthy backend can still be warming its process pool or purging
+//! stale sessions, and the two overlap.  Each component in `COMPONENTS` is
+//! therefore probed on its own (`GET /api/ready/{name}`) with its own
+//! backoff, all at once but never more than `MAX_CONCURRENT_PROBES`
+//! requests in flight, over the shared loopback client.  The backend is
+//! ready when every component is; one that never gets there does not stop
+//! the others from being probed and reported.
+//!
+//! Each component that flips to ready is announced as
+//! `backend-component-ready { component, readyAfterMs }` (the splash ticks
+//! it off) and its time since the launch began is kept in the lifecycle
+//! snapshot, so startup reports show which component dominates.
+//!
+//! Only a 200 counts: a 404 means the backend does not know the component
+//! and is retried like any other answer until the deadline.
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use tauri::AppHandle;
+use tokio::{sync::Semaphore, task::JoinSet};
+
+use crate::{
+    http::{self, ComponentProbe},
+    lifecycle,
+    shell_log::{self, Level},
+    timeouts,
+};
+
+/// Requests in flight at once, across all components.
+const MAX_CONCURRENT_PROBES: usize = 2;
+
+/// A backend component and its probe schedule: the first retry after
+/// `first_retry`, doubling up to `max_retry`.
+#[derive(Debug, Clone, Copy)]
+pub struct Component {
+    pub name: &'static str,
+    first_retry: Duration,
+    max_retry: Duration,
+}
+
+pub const COMPONENTS: [Component; 2] = [
+    // ProcessPoolExecutor warm-up: 3–8 s, polled closely.
+    Component {
+        name: "pool",
+        first_retry: Duration::from_millis(100),
+        max_retry: Duration::from_secs(1),
+    },
+    // Purging stale sessions depends on how many there are.
+    Component {
+        name: "session",
+        first_retry: Duration::from_millis(250),
+        max_retry: Duration::from_secs(2),
+    },
+];
+
+/// The retry delay after `delay`.
+fn next_delay(delay: Duration, component: &Component) -> Duration {
+    (delay * 2).min(component.max_retry)
+}
+
+/// Probe `component` until it is ready or `deadline` passes.  Returns how
+/// long after `started` it became ready.
+async fn wait_for(
+    component: Component,
+    port: u16,
+    token: Option<String>,
+    permits: Arc<Semaphore>,
+    started: Instant,
+    deadline: tokio::time::Instant,
+) -> Option<Duration> {
+    let mut delay = component.first_retry;
+    loop {
+        let probe = {
+            let _permit = permits.acquire().await.ok()?;
+            http::probe_component(port, token.as_deref(), component.name).await
+        };
+        if probe == ComponentProbe::Ready {
+            return Some(started.elapsed());
+        }
+        if tokio::time::Instant::now() + delay > deadline {
+            return None;
+        }
+        tokio::time::sleep(delay).await;
+        delay = next_delay(delay, &component);
+    }
+}
+
+/// Probe every component concurrently; returns the names of those that
+/// were not ready within the `readiness` timeout.  `started` is when the launch
+/// began, the reference for the reported ready times.
+pub async fn wait_for_components(
+    app: &AppHandle,
+    port: u16,
+    token: Option<&str>,
+    started: Instant,
+) -> Result<(), Vec<&'static str>> {
+    let permits = Arc::new(Semaphore::new(MAX_CONCURRENT_PROBES));
+    let deadline = tokio::time::Instant::now() + timeouts::current().readiness;
+    let mut probes = JoinSet::new();
+    for component in COMPONENTS {
+        let probe = wait_for(
+            component,
+            port,
+            token.map(str::to_string),
+            permits.clone(),
+            started,
+            deadline,
+        );
+        probes.spawn(async move { (component.name, probe.await) });
+    }
+
+    let mut not_ready: Vec<&'static str> = COMPONENTS.iter().map(|c| c.name).collect();
+    while let Some(result) = probes.join_next().await {
+        let Ok((name, Some(after))) = result else {
+            continue;
+        };
+        shell_log::log(
+            Level::Info,
+            "readiness",
+            format!("{name} ready after {} ms", after.as_millis()),
+        );
+        lifecycle::component_ready(app, name, after);
+        not_ready.retain(|n| *n != name);
+    }
+    if not_ready.is_empty() {
+        Ok(())
+    } else {
+        Err(not_ready)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::http::test_server::serve;
+
+    /// A backend answering `status` for its first `not_ready` probes and 200
+    /// after that, and how many probes it has seen.
+    async fn backend(not_ready: usize, status: &'static str) -> (u16, Arc<AtomicUsize>) {
+        let probes = Arc::new(AtomicUsize::new(0));
+        let seen = probes.clone();
+        let port = serve(move |_, served| {
+            seen.fetch_add(1, Ordering::SeqCst);
+            if served < not_ready {
+                (status, r#"{"status":"starting"}"#.into())
+            } else {
+                ("200 OK", r#"{"status":"ready"}"#.into())
+            }
+        })
+        .await;
+        (port, probes)
+    }
+
+    fn probe(port: u16, within: Duration) -> impl std::future::Future<Output = Option<Duration>> {
+        let deadline = tokio::time::Instant::now() + within;
+        let permits = Arc::new(Semaphore::new(MAX_CONCURRENT_PROBES));
+        wait_for(COMPONENTS[0], port, None, permits, Instant::now(), deadline)
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let pool = COMPONENTS[0];
+        let mut delay = pool.first_retry;
+        let mut schedule = Vec::new();
+        for _ in 0..6 {
+            schedule.push(delay.as_millis());
+            delay = next_delay(delay, &pool);
+        }
+        assert_eq!(schedule, [100, 200, 400, 800, 1000, 1000]);
+    }
+
+    #[tokio::test]
+    async fn a_component_is_ready_once_a_probe_after_the_backoff_answers_200() {
+        let (port, probes) = backend(2, "503 Service Unavailable").await;
+        let after = probe(port, Duration::from_secs(10)).await.unwrap();
+        assert_eq!(probes.load(Ordering::SeqCst), 3);
+        // Two retries: 100 ms, then 200 ms.
+        assert!(after >= Duration::from_millis(300), "{after:?}");
+    }
+
+    #[tokio::test]
+    async fn probing_stops_when_the_next_retry_would_pass_the_deadline() {
+        // Probes at 0, 100 and 300 ms; the next would be at 700 ms.
+        let started = Instant::now();
+        let (port, probes) = backend(usize::MAX, "503 Service Unavailable").await;
+        assert_eq!(probe(port, Duration::from_millis(600)).await, None);
+        assert_eq!(probes.load(Ordering::SeqCst), 3);
+        assert!(started.elapsed() < Duration::from_millis(600));
+    }
+
+    #[tokio::test]
+    async fn an_unknown_component_is_not_ready() {
+        let (port, probes) = backend(usize::MAX, "404 Not Found").await;
+        assert_eq!(probe(port, Duration::from_millis(250)).await, None);
+        assert_eq!(probes.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn probing_gives_up_at_the_deadline_and_keeps_the_others_going() {
+        // Nothing listens on port 1: every probe fails.
+        let started = Instant::now();
+        let deadline = tokio::time::Instant::now() + Duration::from_millis(300);
+        let permits = Arc::new(Semaphore::new(MAX_CONCURRENT_PROBES));
+        let (pool, session) = tokio::join!(
+            wait_for(COMPONENTS[0], 1, None, permits.clone(), started, deadline),
+            wait_for(COMPONENTS[1], 1, None, permits.clone(), started, deadline),
+        );
+        assert_eq!((pool, session), (None, None));
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert_eq!(permits.available_permits(), MAX_CONCURRENT_PROBES);
+    }
+}
@@ -0,0 +1,175 @@
+//! CPU and memory of the sidecar's process tree, so the UI can show that a
+//! long projection run is busy rather than hung.
+//!
+//! `get_backend_resource_usage` covers the sidecar (the pid stored in
+//! `BackendProcess`) and all of its descendants – the ProcessPoolExecutor
+//! workers.  A stopped backend reports zeros.  Processes are looked up by
+//! pid on every sample, so one that exits in between is simply left out.
+//!
+//! CPU percentages are per core (a busy worker on a 4-core machine shows
+//! up to 400 %) and measured since the previous sample; the first sample
+//! waits `MINIMUM_CPU_UPDATE_INTERVAL` to have something to compare with.
+//!
+//! `set_resource_monitoring(true)` also emits `backend-metrics` every
+//! `SAMPLE_INTERVAL` to windows with `Capability::Diagnostics`, for a live
+//! chart, until it is switched off.
+
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use serde::Serialize;
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    events::{self, Audience, Capability},
+    BackendProcess,
+};
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessUsage {
+    pub pid: u32,
+    pub name: String,
+    pub rss_bytes: u64,
+    pub cpu_percent: f32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceUsage {
+    pub total_rss_bytes: u64,
+    pub cpu_percent: f32,
+    /// The sidecar first, then its descendants.
+    pub processes: Vec<ProcessUsage>,
+}
+
+/// Managed state: the process table kept between samples (CPU usage is a
+/// difference between two of them) and the running sampler, if any.
+#[derive(Default)]
+pub struct ResourceMonitor {
+    system: Mutex<Option<System>>,
+    sampler: Mutex<Option<tauri::async_runtime::JoinHandle<()>>>,
+}
+
+/// `root` and every process descended from it, given each process's
+/// parent; `root` first.
+fn tree_of(root: u32, parents: &HashMap<u32, u32>) -> Vec<u32> {
+    let mut tree = vec![root];
+    let mut i = 0;
+    while i < tree.len() {
+        let parent = tree[i];
+        let mut children: Vec<u32> = parents
+            .iter()
+            .filter(|(_, p)| **p == parent)
+            .map(|(child, _)| *child)
+            .filter(|child| !tree.contains(child))
+            .collect();
+        children.sort_unstable();
+        tree.extend(children);
+        i += 1;
+    }
+    tree
+}
+
+fn refresh(system: &mut System) {
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing().with_memory().with_cpu(),
+    );
+}
+
+/// Usage of `root`'s tree.  Blocks for the first sample's CPU baseline.
+fn measure(monitor: &ResourceMonitor, root: u32) -> ResourceUsage {
+    let mut system = monitor.system.lock().unwrap();
+    let system = system.get_or_insert_with(|| {
+        let mut system = System::new();
+        refresh(&mut system);
+        std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+        system
+    });
+    refresh(system);
+
+    let parents: HashMap<u32, u32> = system
+        .processes()
+        .iter()
+        .filter_map(|(pid, process)| Some((pid.as_u32(), process.parent()?.as_u32())))
+        .collect();
+    let processes: Vec<ProcessUsage> = tree_of(root, &parents)
+        .into_iter()
+        .filter_map(|pid| {
+            let process = system.process(Pid::from_u32(pid))?;
+            Some(ProcessUsage {
+                pid,
+                name: process.name().to_string_lossy().into_owned(),
+                rss_bytes: process.memory(),
+                cpu_percent: process.cpu_usage(),
+            })
+        })
+        .collect();
+    ResourceUsage {
+        total_rss_bytes: processes.iter().map(|p| p.rss_bytes).sum(),
+        cpu_percent: processes.iter().map(|p| p.cpu_percent).sum(),
+        processes,
+    }
+}
+
+async fn sample(app: &AppHandle) -> ResourceUsage {
+    let Some(pid) = app.state::<BackendProcess>().pid() else {
+        return ResourceUsage::default();
+    };
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || measure(&app.state::<ResourceMonitor>(), pid))
+        .await
+        .unwrap_or_default()
+}
+
+/// Memory and CPU of the sidecar and its workers; zeros when it is not
+/// running.
+#[tauri::command]
+pub async fn get_backend_resource_usage(app: AppHandle) -> ResourceUsage {
+    sample(&app).await
+}
+
+/// Start or stop emitting `backend-metrics` every `SAMPLE_INTERVAL`.
+#[tauri::command]
+pub fn set_resource_monitoring(app: AppHandle, enabled: bool) {
+    let monitor = app.state::<ResourceMonitor>();
+    let mut sampler = monitor.sampler.lock().unwrap();
+    match (enabled, sampler.take()) {
+        (true, Some(running)) => *sampler = Some(running),
+        (true, None) => {
+            let app = app.clone();
+            *sampler = Some(tauri::async_runtime::spawn(async move {
+                loop {
+                    let usage = sample(&app).await;
+                    events::emit(
+                        &app,
+                        Audience::Capability(Capability::Diagnostics),
+                        "backend-metrics",
+                        usage,
+                    );
+                    tokio::time::sleep(SAMPLE_INTERVAL).await;
+                }
+            }));
+        }
+        (false, Some(running)) => running.abort(),
+        (false, None) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tree_is_the_root_and_all_its_descendants() {
+        // 10 → 11, 12; 12 → 13; 20 is unrelated; 1 is the root's parent.
+        let parents = HashMap::from([(10, 1), (11, 10), (12, 10), (13, 12), (20, 1)]);
+        assert_eq!(tree_of(10, &parents), vec![10, 11, 12, 13]);
+        // An exited root still yields itself; `measure` then skips it.
+        assert_eq!(tree_of(99, &parents), vec![99]);
+    }
+}
@@ -0,0 +1,229 @@
+//! "Show in folder" for exported files and the data directory.
+//!
+//! `reveal_in_file_manager` opens the folder containing a file with the
+//! file selected:
+//!
+//! Windows → `explorer /select,"…"`
+//! macOS   → `open -R`
+//! Linux   → `xdg-open` on the parent directory (file managers have no
+//!           common way to select a file)
+//!
+//! So the webview can't use it to probe the filesystem, the path must lie
+//! inside the data directory or a directory the user picked in a save
+//! dialog during this session (`RevealRoots`, filled by `exports`).  That
+//! is checked on the lexically normalized path before the filesystem is
+//! touched, so anything outside is `NotAllowed` whether or not it exists,
+//! and again on the canonical path, so a symlink can't lead out.
+
+use std::{
+    path::{Component, Path, PathBuf},
+    process::Command,
+    sync::Mutex,
+};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::paths;
+
+/// Directories chosen in save dialogs this session.
+#[derive(Default)]
+pub struct RevealRoots(Mutex<Vec<PathBuf>>);
+
+impl RevealRoots {
+    /// Allow revealing files under `dir`.
+    pub fn allow(&self, dir: &Path) {
+        let mut roots = self.0.lock().unwrap();
+        for dir in forms(dir) {
+            if !roots.contains(&dir) {
+                roots.push(dir);
+            }
+        }
+    }
+}
+
+/// `dir` as it may be written (normalized) and as it resolves (canonical);
+/// the two differ for `\\?\` paths on Windows or a symlinked `/var` on
+/// macOS.
+fn forms(dir: &Path) -> Vec<PathBuf> {
+    let mut forms: Vec<PathBuf> = normalize(dir).into_iter().collect();
+    if let Ok(canonical) = dir.canonicalize() {
+        if !forms.contains(&canonical) {
+            forms.push(canonical);
+        }
+    }
+    forms
+}
+
+/// Absolute `path` with `.` and `..` resolved without the filesystem;
+/// `None` when relative or climbing above the root.
+fn normalize(path: &Path) -> Option<PathBuf> {
+    if !path.is_absolute() {
+        return None;
+    }
+    let mut normal = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normal.parent()?;
+                normal.pop();
+            }
+            other => normal.push(other),
+        }
+    }
+    Some(normal)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum RevealError {
+    /// The file no longer exists (deleted or moved since the export).
+    NotFound { path: String },
+    /// Outside the data directory and every save-dialog directory.
+    NotAllowed { path: String },
+    /// The file manager could not be started.
+    Failed { message: String },
+}
+
+/// Whether `path` lies inside one of `roots`.
+fn is_allowed(path: &Path, roots: &[PathBuf]) -> bool {
+    roots.iter().any(|root| path.starts_with(root))
+}
+
+/// The canonical form of `path` if it may be revealed.  Only a path that
+/// lies inside `roots` as written is looked up on disk.
+fn resolve(path: &str, roots: &[PathBuf]) -> Result<PathBuf, RevealError> {
+    let not_allowed = || RevealError::NotAllowed {
+        path: path.to_string(),
+    };
+    let normal = normalize(Path::new(path)).ok_or_else(not_allowed)?;
+    if !is_allowed(&normal, roots) {
+        return Err(not_allowed());
+    }
+    let canonical = normal.canonicalize().map_err(|_| RevealError::NotFound {
+        path: path.to_string(),
+    })?;
+    if !is_allowed(&canonical, roots) {
+        return Err(not_allowed());
+    }
+    Ok(canonical)
+}
+
+/// `path` without Windows' `\\?\` prefix, which Explorer does not accept.
+#[cfg(windows)]
+fn plain(path: &Path) -> String {
+    let path = path.display().to_string();
+    match path.strip_prefix(r"\\?\") {
+        Some(rest) if !rest.starts_with("UNC\\") => rest.to_string(),
+        _ => path,
+    }
+}
+
+fn reveal(path: &Path) -> std::io::Result<()> {
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        use std::os::windows::process::CommandExt;
+        let mut command = Command::new("explorer");
+        command.raw_arg(format!("/select,\"{}\"", plain(path)));
+        command
+    };
+    #[cfg(target_os = "macos")]
+    let mut command = {
+        let mut command = Command::new("open");
+        command.arg("-R").arg(path);
+        command
+    };
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let mut command = {
+        let mut command = Command::new("xdg-open");
+        command.arg(path.parent().unwrap_or(path));
+        command
+    };
+    // Reap it; explorer's exit code means nothing, so it isn't checked.
+    command.spawn()?.wait().map(drop)
+}
+
+/// Show `path` selected in the platform file manager.
+#[tauri::command]
+pub async fn reveal_in_file_manager(app: AppHandle, path: String) -> Result<(), RevealError> {
+    let mut roots = app.state::<RevealRoots>().0.lock().unwrap().clone();
+    if let Ok(data_dir) = paths::data_dir(&app) {
+        roots.extend(forms(&data_dir));
+    }
+    let canonical = resolve(&path, &roots)?;
+
+    tauri::async_runtime::spawn_blocking(move || reveal(&canonical))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r.map_err(|e| e.to_string()))
+        .map_err(|message| RevealError::Failed { message })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_files_under_known_roots_are_revealed() {
+        let roots = [
+            PathBuf::from("/home/ana/.local/share/com.almready.desktop"),
+            PathBuf::from("/home/ana/Reports"),
+        ];
+        assert!(is_allowed(Path::new("/home/ana/Reports/EVE.xlsx"), &roots));
+        assert!(is_allowed(
+            Path::new("/home/ana/.local/share/com.almready.desktop/logs/backend.log"),
+            &roots
+        ));
+        assert!(!is_allowed(Path::new("/home/ana/.ssh/id_ed25519"), &roots));
+        // Component-wise, not a string prefix.
+        assert!(!is_allowed(Path::new("/home/ana/Reports2/x.csv"), &roots));
+    }
+
+    #[test]
+    fn outside_the_roots_is_not_allowed_whether_or_not_it_exists() {
+        let tmp = tempfile::tempdir().unwrap();
+        let reports = tmp.path().join("Reports");
+        std::fs::create_dir(&reports).unwrap();
+        std::fs::write(reports.join("EVE.xlsx"), "").unwrap();
+        std::fs::write(tmp.path().join("secret.txt"), "").unwrap();
+        let roots = forms(&reports);
+        let at = |path: PathBuf| path.to_str().unwrap().to_string();
+        let kind = |path: PathBuf| match resolve(&at(path), &roots) {
+            Ok(_) => "ok",
+            Err(RevealError::NotFound { .. }) => "not-found",
+            Err(RevealError::NotAllowed { .. }) => "not-allowed",
+            Err(RevealError::Failed { .. }) => "failed",
+        };
+
+        assert_eq!(kind(reports.join("EVE.xlsx")), "ok");
+        assert_eq!(kind(reports.join("gone.xlsx")), "not-found");
+        assert_eq!(kind(tmp.path().join("secret.txt")), "not-allowed");
+        assert_eq!(kind(tmp.path().join("missing.txt")), "not-allowed");
+        assert_eq!(kind(reports.join("../secret.txt")), "not-allowed");
+        assert_eq!(kind(reports.join("../missing.txt")), "not-allowed");
+        assert_eq!(
+            resolve("Reports/EVE.xlsx", &roots),
+            Err(RevealError::NotAllowed {
+                path: "Reports/EVE.xlsx".into()
+            })
+        );
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(tmp.path().join("secret.txt"), reports.join("link"))
+                .unwrap();
+            assert_eq!(kind(reports.join("link")), "not-allowed");
+        }
+    }
+
+    #[test]
+    fn errors_serialize_with_a_kind() {
+        let error = RevealError::NotFound {
+            path: "/tmp/gone.csv".into(),
+        };
+        assert_eq!(
+            serde_json::to_value(error).unwrap(),
+            serde_json::json!({ "kind": "not-found", "path": "/tmp/gone.csv" })
+        );
+    }
+}
@@ -0,0 +1,254 @@
+//! Screen lock: hide every window's contents until the user re-enters their
+//! lock passcode.
+//!
+//! `lock_screen` marks the app locked, calls the frontend's
+//! `showLockScreen()` in every app window (covering the UI with its lock
+//! overlay) and emits `screen-lock-changed`.  `unlock_screen` checks the
+//! passcode and undoes that with `hideLockScreen()`.  A window that loads
+//! while the app is locked should ask `is_screen_locked` before showing
+//! anything.
+//!
+//! The passcode is chosen with `set_lock_passcode` and kept only as a
+//! salted PBKDF2-SHA256 hash in `{data_dir}/screen-lock.json` – not in
+//! settings.json, so importing preferences can't replace it.  A wrong
+//! passcode is only answered after `FAILED_ATTEMPT_DELAY`.
+//!
+//! On Windows `lock_screen` with `os_lock` locks the workstation instead
+//! (`LockWorkStation`) and Windows handles re-authentication.
+//!
+//! This guards an unattended desk; it is not a boundary against someone
+//! who can run code in the user's account.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tauri::{AppHandle, Manager, State};
+
+use crate::{
+    data_dir_access::DataDirAccess,
+    events::{self, Audience},
+    paths, splash,
+};
+
+const LOCK_FILE: &str = "screen-lock.json";
+
+/// PBKDF2-HMAC-SHA256 work factor for new passcodes (OWASP 2023).
+const PBKDF2_ROUNDS: u32 = 600_000;
+
+const MIN_PASSCODE_LEN: usize = 4;
+
+const FAILED_ATTEMPT_DELAY: Duration = Duration::from_secs(1);
+
+// ── Passcode ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct PasscodeHash {
+    salt: String,
+    hash: String,
+    rounds: u32,
+}
+
+fn derive(passcode: &str, salt: &[u8], rounds: u32) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passcode.as_bytes(), salt, rounds, &mut out);
+    out
+}
+
+impl PasscodeHash {
+    fn new(passcode: &str) -> Result<Self, String> {
+        let mut salt = [0u8; 16];
+        getrandom::fill(&mut salt).map_err(|e| format!("cannot generate salt: {e}"))?;
+        Ok(Self::with_salt(passcode, &salt, PBKDF2_ROUNDS))
+    }
+
+    fn with_salt(passcode: &str, salt: &[u8], rounds: u32) -> Self {
+        PasscodeHash {
+            salt: hex::encode(salt),
+            hash: hex::encode(derive(passcode, salt, rounds)),
+            rounds,
+        }
+    }
+
+    /// Constant-time comparison against the stored hash.
+    fn matches(&self, passcode: &str) -> bool {
+        let (Ok(salt), Ok(expected)) = (hex::decode(&self.salt), hex::decode(&self.hash)) else {
+            return false;
+        };
+        let actual = derive(passcode, &salt, self.rounds);
+        actual.len() == expected.len()
+            && actual
+                .iter()
+                .zip(&expected)
+                .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+                == 0
+    }
+}
+
+fn lock_file(app: &AppHandle) -> Result<PathBuf, String> {
+    paths::data_dir(app).map(|dir| dir.join(LOCK_FILE))
+}
+
+fn read_passcode(path: &Path) -> Result<Option<PasscodeHash>, String> {
+    let text = match DataDirAccess::unleased().with(&[path], || std::fs::read_to_string(path)) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("read {path:?}: {e}")),
+    };
+    serde_json::from_str(&text)
+        .map(Some)
+        .map_err(|e| format!("parse {path:?}: {e}"))
+}
+
+fn write_passcode(path: &Path, hash: &PasscodeHash) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("create {dir:?}: {e}"))?;
+    }
+    let text = serde_json::to_string_pretty(hash).map_err(|e| e.to_string())?;
+    DataDirAccess::unleased()
+        .with(&[path], || std::fs::write(path, &text))
+        .map_err(|e| format!("write {path:?}: {e}"))
+}
+
+// ── Lock state ──────────────────────────────────────────────────────────────
+
+/// Managed state: whether the app is locked.
+#[derive(Default)]
+pub struct ScreenLock {
+    locked: AtomicBool,
+}
+
+impl ScreenLock {
+    fn is_locked(&self) -> bool {
+        self.locked.load(Ordering::SeqCst)
+    }
+}
+
+/// Show or hide the lock overlay in every app window.
+fn set_locked(app: &AppHandle, locked: bool) {
+    app.state::<ScreenLock>()
+        .locked
+        .store(locked, Ordering::SeqCst);
+    let script = if locked {
+        "window.showLockScreen?.();"
+    } else {
+        "window.hideLockScreen?.();"
+    };
+    for window in app.webview_windows().values() {
+        if window.label() != splash::LABEL {
+            let _ = window.eval(script);
+        }
+    }
+    events::emit(app, Audience::AllWindows, "screen-lock-changed", locked);
+}
+
+#[cfg(target_os = "windows")]
+fn lock_workstation() -> Result<(), String> {
+    // SAFETY: no arguments; the lock happens asynchronously.
+    unsafe { windows::Win32::System::Shutdown::LockWorkStation() }
+        .map_err(|e| format!("LockWorkStation: {e}"))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn lock_workstation() -> Result<(), String> {
+    Err("Locking the workstation is only supported on Windows.".into())
+}
+
+// ── Commands ────────────────────────────────────────────────────────────────
+
+/// Set or change the lock passcode.  Changing it needs the current one.
+#[tauri::command]
+pub async fn set_lock_passcode(
+    app: AppHandle,
+    current: Option<String>,
+    passcode: String,
+) -> Result<(), String> {
+    if app.state::<ScreenLock>().is_locked() {
+        return Err("Unlock the app first.".into());
+    }
+    if passcode.chars().count() < MIN_PASSCODE_LEN {
+        return Err(format!(
+            "The passcode must have at least {MIN_PASSCODE_LEN} characters."
+        ));
+    }
+    let path = lock_file(&app)?;
+    // Hashing takes a noticeable fraction of a second; keep it off the
+    // async runtime.
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Some(existing) = read_passcode(&path)? {
+            if !current.is_some_and(|current| existing.matches(&current)) {
+                return Err("The current passcode is incorrect.".to_string());
+            }
+        }
+        write_passcode(&path, &PasscodeHash::new(&passcode)?)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Lock the app.  With `os_lock` on Windows, lock the workstation instead.
+#[tauri::command]
+pub fn lock_screen(app: AppHandle, os_lock: Option<bool>) -> Result<(), String> {
+    if os_lock == Some(true) {
+        return lock_workstation();
+    }
+    if read_passcode(&lock_file(&app)?)?.is_none() {
+        return Err("Set a lock passcode first.".into());
+    }
+    set_locked(&app, true);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn unlock_screen(app: AppHandle, passcode: String) -> Result<(), String> {
+    if !app.state::<ScreenLock>().is_locked() {
+        return Ok(());
+    }
+    let path = lock_file(&app)?;
+    let matches = tauri::async_runtime::spawn_blocking(move || {
+        read_passcode(&path).map(|hash| hash.is_some_and(|hash| hash.matches(&passcode)))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    if !matches {
+        tokio::time::sleep(FAILED_ATTEMPT_DELAY).await;
+        return Err("Incorrect passcode.".into());
+    }
+    set_locked(&app, false);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn is_screen_locked(lock: State<'_, ScreenLock>) -> bool {
+    lock.is_locked()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passcode_hash_matches_only_the_passcode() {
+        let hash = PasscodeHash::with_salt("1234", b"0123456789abcdef", 1000);
+        assert!(hash.matches("1234"));
+        assert!(!hash.matches("12345"));
+        assert!(!hash.matches(""));
+
+        let json = serde_json::to_string(&hash).unwrap();
+        let parsed: PasscodeHash = serde_json::from_str(&json).unwrap();
+        assert!(parsed.matches("1234"));
+
+        let other_salt = PasscodeHash::with_salt("1234", b"fedcba9876543210", 1000);
+        assert_ne!(other_salt.hash, hash.hash);
+
+        let corrupt = PasscodeHash {
+            salt: "not hex".into(),
+            ..hash
+        };
+        assert!(!corrupt.matches("1234"));
+    }
+}
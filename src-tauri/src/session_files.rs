@@ -0,0 +1,228 @@
+//! `.almready` session files opened from the desktop.
+//!
+//! Double-clicking a session file (the bundle registers the extension)
+//! starts ALMReady with its path on the command line; macOS delivers it as
+//! `RunEvent::Opened` instead, and a launch while ALMReady is already
+//! running forwards its arguments through `on_second_instance`.  All three
+//! end up in `open`.
+//!
+//! Paths are queued in `PendingSessions` until the backend is healthy and
+//! the main window exists, then handed to the backend one by one with
+//! `POST /api/session/open {"path": …}`, which imports the file as a new
+//! session.  The main window gets
+//!
+//! * `session-opened { status: "opened", path, response }` with the new
+//!   session's `SessionMeta`, or
+//! * `session-open-failed { status: "failed", path, error }` when the file
+//!   does not exist or the backend refused it.
+//!
+//! Each outcome is also kept for `get_opened_sessions`, for a frontend that
+//! subscribes after the first one was emitted.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    backend_status::BackendStatus,
+    events::{self, Audience},
+    http,
+    shell_log::{self, Level},
+    window_factory::MAIN_LABEL,
+    BackendProcess,
+};
+
+pub const EXTENSION: &str = "almready";
+
+/// Outcomes kept for `get_opened_sessions`; older ones are dropped.
+const MAX_OUTCOMES: usize = 20;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum SessionOutcome {
+    Opened {
+        path: PathBuf,
+        response: serde_json::Value,
+    },
+    Failed {
+        path: PathBuf,
+        error: String,
+    },
+}
+
+/// Session files waiting for the backend, and what became of those
+/// already delivered.
+#[derive(Default)]
+pub struct PendingSessions {
+    queue: Mutex<Vec<PathBuf>>,
+    outcomes: Mutex<Vec<SessionOutcome>>,
+}
+
+impl PendingSessions {
+    fn push(&self, paths: Vec<PathBuf>) {
+        let mut queue = self.queue.lock().unwrap();
+        for path in paths {
+            if !queue.contains(&path) {
+                queue.push(path);
+            }
+        }
+    }
+
+    fn take(&self) -> Vec<PathBuf> {
+        std::mem::take(&mut *self.queue.lock().unwrap())
+    }
+
+    fn record(&self, outcome: SessionOutcome) {
+        let mut outcomes = self.outcomes.lock().unwrap();
+        outcomes.push(outcome);
+        let excess = outcomes.len().saturating_sub(MAX_OUTCOMES);
+        outcomes.drain(..excess);
+    }
+}
+
+fn is_session_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(EXTENSION))
+}
+
+/// Session files among command-line `args` (without the program name),
+/// made absolute against `cwd`, the launching process's directory.
+pub fn paths_from_args<I, S>(args: I, cwd: &Path) -> Vec<PathBuf>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<PathBuf>,
+{
+    args.into_iter()
+        .map(Into::into)
+        .filter(|path| !path.as_os_str().to_string_lossy().starts_with("--"))
+        .filter(|path| is_session_file(path))
+        .map(|path| {
+            if path.is_absolute() {
+                path
+            } else {
+                cwd.join(path)
+            }
+        })
+        .collect()
+}
+
+/// Queue `paths` and deliver them now if the backend is ready.
+pub fn open(app: &AppHandle, paths: Vec<PathBuf>) {
+    if paths.is_empty() {
+        return;
+    }
+    shell_log::log(
+        Level::Info,
+        "session-files",
+        format!("queued {} session file(s)", paths.len()),
+    );
+    app.state::<PendingSessions>().push(paths);
+    let ready = app.state::<BackendProcess>().status() == BackendStatus::Ready
+        && app.get_webview_window(MAIN_LABEL).is_some();
+    if ready {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move { deliver_pending(&app).await });
+    }
+}
+
+/// Hand every queued session file to the backend.  Called once the main
+/// window is open, and by `open` after that.
+pub async fn deliver_pending(app: &AppHandle) {
+    let paths = app.state::<PendingSessions>().take();
+    if paths.is_empty() {
+        return;
+    }
+    let backend = app.state::<BackendProcess>();
+    let port = backend.port.get();
+    let token = backend.token();
+
+    for path in paths {
+        let result = if !path.is_file() {
+            Err("The session file does not exist.".to_string())
+        } else if let Some(port) = port {
+            http::open_session(port, token.as_deref(), &path).await
+        } else {
+            Err("The calculation engine is not running.".to_string())
+        };
+        let (event, outcome) = match result {
+            Ok(response) => (
+                "session-opened",
+                SessionOutcome::Opened {
+                    path: path.clone(),
+                    response,
+                },
+            ),
+            Err(error) => {
+                shell_log::log(
+                    Level::Warn,
+                    "session-files",
+                    format!("cannot open {}: {error}", path.display()),
+                );
+                (
+                    "session-open-failed",
+                    SessionOutcome::Failed { path, error },
+                )
+            }
+        };
+        app.state::<PendingSessions>().record(outcome.clone());
+        events::emit(app, Audience::MainOnly, event, outcome);
+    }
+}
+
+/// Session files handed to the backend so far, oldest first.
+#[tauri::command]
+pub fn get_opened_sessions(app: AppHandle) -> Vec<SessionOutcome> {
+    app.state::<PendingSessions>()
+        .outcomes
+        .lock()
+        .unwrap()
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn session_files_are_picked_from_args() {
+        let cwd = Path::new("/home/ana/Documents");
+        let args = [
+            "--show-last-failure",
+            "Q3 Stress Test.almready",
+            "/srv/shared/Bilanz Übersicht.ALMREADY",
+            "notes.txt",
+        ];
+        assert_eq!(
+            paths_from_args(args, cwd),
+            [
+                PathBuf::from("/home/ana/Documents/Q3 Stress Test.almready"),
+                PathBuf::from("/srv/shared/Bilanz Übersicht.ALMREADY"),
+            ]
+        );
+    }
+
+    #[test]
+    fn queue_drops_duplicates_and_outcomes_are_capped() {
+        let sessions = PendingSessions::default();
+        let path = PathBuf::from("/tmp/a.almready");
+        sessions.push(vec![path.clone(), path.clone()]);
+        assert_eq!(sessions.take(), [path.clone()]);
+        assert!(sessions.take().is_empty());
+
+        for _ in 0..MAX_OUTCOMES + 5 {
+            sessions.record(SessionOutcome::Failed {
+                path: path.clone(),
+                error: "missing".into(),
+            });
+        }
+        assert_eq!(sessions.outcomes.lock().unwrap().len(), MAX_OUTCOMES);
+        let json = serde_json::to_value(&sessions.outcomes.lock().unwrap()[0]).unwrap();
+        assert_eq!(json["status"], "failed");
+        assert_eq!(json["path"], "/tmp/a.almready");
+    }
+}
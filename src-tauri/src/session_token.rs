@@ -0,0 +1,55 @@
+//! Per-session secret shared by the shell, the sidecar and the webviews.
+//!
+//! The backend listens on loopback with CORS open to the Tauri origins (and
+//! is reachable through `almready://api/`, see `api_protocol`), so
+//! without a secret any local process, or a web page using DNS rebinding,
+//! could call its API.  Every spawn of the sidecar gets a fresh random token
+//! in `ALMREADY_AUTH_TOKEN`; the backend then refuses `/api/*` requests
+//! without it in the `X-ALMReady-Token` header.  The shell sends it with its
+//! own requests (`http`) and injects it into each window as
+//! `window.__BACKEND_TOKEN__` (`window_factory`).
+//!
+//! The token lives only in `BackendProcess` and the child's environment.  It
+//! is never logged, and `dump_window_config` shows it redacted.
+
+pub const ENV: &str = "ALMREADY_AUTH_TOKEN";
+pub const HEADER: &str = "X-ALMReady-Token";
+
+/// Stands in for the token wherever a config is shown.
+pub const REDACTED: &str = "<redacted>";
+
+/// 32 random bytes from the OS, hex-encoded.
+pub fn generate() -> Result<String, getrandom::Error> {
+    let mut bytes = [0u8; 32];
+    getrandom::fill(&mut bytes)?;
+    Ok(hex::encode(bytes))
+}
+
+/// `text` with every occurrence of `token` replaced by `REDACTED`.
+pub fn redact(text: &str, token: &str) -> String {
+    if token.is_empty() {
+        return text.to_string();
+    }
+    text.replace(token, REDACTED)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_are_fresh_hex_and_redactable() {
+        let a = generate().unwrap();
+        let b = generate().unwrap();
+        assert_eq!(a.len(), 64);
+        assert!(a.bytes().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(a, b);
+
+        let script = format!("window.__BACKEND_TOKEN__ = \"{a}\";");
+        assert_eq!(
+            redact(&script, &a),
+            "window.__BACKEND_TOKEN__ = \"<redacted>\";"
+        );
+        assert_eq!(redact(&script, ""), script);
+    }
+}
@@ -0,0 +1,232 @@
+//! Persistent shell settings (`{app_data_dir}/settings.json`).
+//!
+//! The file is read once at startup into a `tokio::sync::watch` channel so
+//! any part of the shell can either read the current value or subscribe to
+//! changes.  A housekeeping task polls the file's modification time and
+//! reloads it when it is edited outside the app, which makes every setting
+//! hot-reloadable without a restart.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::SystemTime,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::watch;
+
+use crate::{
+    alerts::AlertStyle,
+    data_dir_access::DataDirAccess,
+    hooks::HookSettings,
+    logpipe::LogFilterSettings,
+    shell_log::{self, Level},
+    webview_prefs::WebviewPrefs,
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Filters applied to the sidecar's captured log output.
+    pub backend_log: LogFilterSettings,
+    /// Smaller minimum window size, 0.9 zoom and the condensed React layout.
+    pub compact_mode: bool,
+    /// Extra environment variables passed to the sidecar (e.g. carried over
+    /// from a legacy standalone install's config.yaml).
+    pub backend_env: BTreeMap<String, String>,
+    /// Set once the "copy to Applications" offer for a read-only install
+    /// has been shown, so it is never repeated.
+    pub move_to_applications_offered: bool,
+    /// Spellcheck and autofill behaviour of every app window.
+    pub webview: WebviewPrefs,
+    /// Sound and/or attention request for finished runs, crashes, exports.
+    pub alert_style: AlertStyle,
+    /// Closing the main window hides it to the tray instead of quitting.
+    pub close_to_tray: bool,
+    /// Site-specific scripts around backend start and stop.
+    pub hooks: HookSettings,
+    /// Bundle files, relative to `almready-backend/`, hashed against the
+    /// manifest before every spawn besides the executable.
+    pub critical_backend_files: Vec<String>,
+}
+
+/// Managed state: current settings plus the file they were loaded from.
+pub struct SettingsStore {
+    path: PathBuf,
+    tx: watch::Sender<Settings>,
+    /// File mtime as of the last load, for `reload_if_modified`.
+    modified: Mutex<Option<SystemTime>>,
+}
+
+impl SettingsStore {
+    /// Load settings from `path`.  A missing or unreadable file yields the
+    /// defaults – settings must never prevent the app from starting.
+    pub fn load(path: PathBuf) -> Self {
+        let modified = Mutex::new(modified(&path));
+        let settings = read_file(&path).unwrap_or_default();
+        let (tx, _) = watch::channel(settings);
+        SettingsStore { path, tx, modified }
+    }
+
+    pub fn get(&self) -> Settings {
+        self.tx.borrow().clone()
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<Settings> {
+        self.tx.subscribe()
+    }
+
+    /// Write `next` to disk and publish it to subscribers.
+    pub fn replace(&self, next: Settings) -> Result<(), String> {
+        write_file(&self.path, &next)?;
+        self.tx.send_replace(next);
+        Ok(())
+    }
+
+    /// Reload the file if its mtime changed since we last looked.  Run
+    /// periodically by the housekeeping scheduler.
+    pub fn reload_if_modified(&self) {
+        let now = modified(&self.path);
+        let mut last = self.modified.lock().unwrap();
+        if *last != now {
+            *last = now;
+            self.reload();
+        }
+    }
+
+    /// Re-read the file and publish it if it differs from what we hold.
+    fn reload(&self) {
+        match read_file(&self.path) {
+            Ok(settings) => {
+                self.tx.send_if_modified(|current| {
+                    if *current == settings {
+                        return false;
+                    }
+                    *current = settings;
+                    true
+                });
+            }
+            Err(e) => shell_log::log(
+                Level::Warn,
+                "settings",
+                format!("ignoring invalid settings file: {e}"),
+            ),
+        }
+    }
+}
+
+fn read_file(path: &Path) -> Result<Settings, String> {
+    let text = match DataDirAccess::unleased().with(&[path], || std::fs::read_to_string(path)) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Settings::default()),
+        Err(e) => return Err(format!("read {path:?}: {e}")),
+    };
+    serde_json::from_str(&text).map_err(|e| format!("parse {path:?}: {e}"))
+}
+
+fn write_file(path: &Path, settings: &Settings) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("create {dir:?}: {e}"))?;
+    }
+    let text = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    // Write-then-rename so a crash mid-write can't leave a truncated file.
+    let tmp = path.with_extension("json.tmp");
+    let access = DataDirAccess::unleased();
+    access
+        .with(&[&tmp], || std::fs::write(&tmp, &text))
+        .map_err(|e| format!("write {tmp:?}: {e}"))?;
+    access
+        .with(&[&tmp, path], || std::fs::rename(&tmp, path))
+        .map_err(|e| format!("rename {tmp:?}: {e}"))
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+// ── Import / export ─────────────────────────────────────────────────────────
+
+/// Keys an imported file may not set, whatever their value: a preferences
+/// file is something users get handed, and these change what runs on the
+/// machine.  `backend_env` would put e.g. `PYTHONPATH` or `LD_PRELOAD` into
+/// the sidecar's environment; `hooks` is only changed through
+/// `set_hook_settings`, which needs `Capability::Admin` and a confirmation.
+const NOT_IMPORTED: &[&str] = &["backend_env", "hooks"];
+
+/// Merge the top-level keys of an exported preferences object into
+/// `current`.  Each key is checked on its own against the `Settings` schema
+/// so one bad value doesn't reject the whole file; unknown keys, keys
+/// whose value has the wrong shape and `NOT_IMPORTED` keys are returned
+/// instead of applied.
+fn merge_preferences(
+    current: &Settings,
+    json_str: &str,
+) -> Result<(Settings, Vec<String>), String> {
+    let incoming: serde_json::Map<String, Value> = serde_json::from_str(json_str)
+        .map_err(|e| format!("preferences are not a JSON object: {e}"))?;
+
+    let Value::Object(mut merged) = serde_json::to_value(current).map_err(|e| e.to_string())?
+    else {
+        return Err("settings did not serialise to an object".into());
+    };
+
+    let mut rejected = Vec::new();
+    for (key, value) in incoming {
+        if !merged.contains_key(&key) || NOT_IMPORTED.contains(&key.as_str()) {
+            rejected.push(key);
+            continue;
+        }
+        let mut candidate = merged.clone();
+        candidate.insert(key.clone(), value.clone());
+        if serde_json::from_value::<Settings>(Value::Object(candidate)).is_ok() {
+            merged.insert(key, value);
+        } else {
+            rejected.push(key);
+        }
+    }
+
+    let settings = serde_json::from_value(Value::Object(merged)).map_err(|e| e.to_string())?;
+    Ok((settings, rejected))
+}
+
+/// Restore preferences from a JSON export.  Valid keys are applied and
+/// saved; the returned list names keys that were unknown or invalid.
+#[tauri::command]
+pub fn import_preferences(
+    store: tauri::State<'_, SettingsStore>,
+    json_str: String,
+) -> Result<Vec<String>, String> {
+    let (settings, rejected) = merge_preferences(&store.get(), &json_str)?;
+    store.replace(settings)?;
+    Ok(rejected)
+}
+
+#[tauri::command]
+pub fn export_preferences(store: tauri::State<'_, SettingsStore>) -> Result<String, String> {
+    serde_json::to_string_pretty(&store.get()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn import_cannot_set_the_backend_environment() {
+        let json = r#"{"compact_mode": true, "backend_env": {"LD_PRELOAD": "/tmp/x.so"}}"#;
+        let (settings, rejected) = merge_preferences(&Settings::default(), json).unwrap();
+        assert!(settings.compact_mode);
+        assert!(settings.backend_env.is_empty());
+        assert_eq!(rejected, ["backend_env"]);
+    }
+
+    #[test]
+    fn import_cannot_enable_hooks() {
+        let json = r#"{"hooks": {"enabled": true}, "close_to_tray": true}"#;
+        let (settings, rejected) = merge_preferences(&Settings::default(), json).unwrap();
+        assert!(!settings.hooks.enabled);
+        assert!(settings.close_to_tray);
+        assert_eq!(rejected, ["hooks"]);
+    }
+}
@@ -0,0 +1,489 @@
+//! The shell's own log: `{log_dir}/shell.log` plus live subscriptions.
+//!
+//! Backend output reaches the log viewer through `logpipe`; this covers the
+//! shell side – window lifecycle, command invocations, watchdog decisions.
+//! `log` prints each record to stderr as before (debug records excepted),
+//! appends it to `shell.log` as one JSON object per line, keeps it among
+//! the last `RECENT_CAPACITY` in memory (for support bundles, which must work
+//! even when the log directory doesn't) and hands it to every subscription
+//! whose filter it passes.
+//!
+//! `ALMREADY_LOG` (`error|warn|info|debug|trace`) sets the lowest level kept;
+//! unset, every record is written and info and above are echoed to stderr.
+//! `trace` is accepted for familiarity and means `debug`.  `shell.log` is a
+//! `logpipe::LogFile`, so it rotates like `backend.log`.
+//!
+//! A subscription is a bounded channel feeding a forwarder thread that sends
+//! `shell-log-records` batches to the subscribing window.  The logger only
+//! ever `try_send`s: when a subscriber falls behind, records are dropped and
+//! counted, and the count rides along with its next batch.  A slow window can
+//! therefore never block whoever is logging.  Subscriptions end with
+//! `unsubscribe_shell_log` or when their window is destroyed.
+
+use std::{
+    cell::Cell,
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+        Arc, Mutex, OnceLock,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow};
+
+use crate::{
+    events::{Capability, WindowCapabilities},
+    logpipe::{self, LogFile},
+};
+
+/// Records a subscription may have queued before new ones are dropped.
+const SUBSCRIBER_CAPACITY: usize = 1024;
+/// Most records sent in one event.
+const MAX_BATCH: usize = 200;
+/// Lowest level to keep, e.g. `ALMREADY_LOG=warn`.
+pub const LEVEL_ENV: &str = "ALMREADY_LOG";
+/// Records kept in memory by `recent`.
+pub const RECENT_CAPACITY: usize = 200;
+/// Pause between batches, so a busy logger yields a few large events
+/// rather than a stream of tiny ones.
+const BATCH_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShellLogRecord {
+    /// Unix time in milliseconds.
+    pub at_ms: u64,
+    pub level: Level,
+    /// Area of the shell, e.g. `watchdog`, `window`, `command`.
+    pub module: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct ShellLogFilter {
+    pub min_level: Option<Level>,
+    pub module_prefix: Option<String>,
+}
+
+impl ShellLogFilter {
+    fn matches(&self, record: &ShellLogRecord) -> bool {
+        self.min_level.map_or(true, |min| record.level >= min)
+            && self
+                .module_prefix
+                .as_deref()
+                .map_or(true, |prefix| record.module.starts_with(prefix))
+    }
+}
+
+// ── Logger ──────────────────────────────────────────────────────────────────
+
+struct Subscriber {
+    id: u64,
+    window: String,
+    filter: ShellLogFilter,
+    tx: SyncSender<ShellLogRecord>,
+    dropped: Arc<AtomicU64>,
+}
+
+/// The receiving end of a subscription.
+pub struct Subscription {
+    pub id: u64,
+    rx: Receiver<ShellLogRecord>,
+    /// Records dropped because the channel was full, since last taken.
+    dropped: Arc<AtomicU64>,
+}
+
+pub struct ShellLogger {
+    path: Option<PathBuf>,
+    file: Mutex<Option<LogFile>>,
+    recent: Mutex<VecDeque<ShellLogRecord>>,
+    subscribers: Mutex<Vec<Subscriber>>,
+    next_id: AtomicU64,
+}
+
+impl ShellLogger {
+    fn new(path: Option<PathBuf>) -> Self {
+        ShellLogger {
+            file: Mutex::new(path.clone().map(LogFile::open)),
+            path,
+            recent: Mutex::new(VecDeque::with_capacity(RECENT_CAPACITY)),
+            subscribers: Mutex::new(Vec::new()),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    fn record(&self, record: ShellLogRecord) {
+        if !WRITING.get() {
+            WRITING.set(true);
+            if let Some(file) = self.file.lock().unwrap().as_mut() {
+                if let Ok(line) = serde_json::to_string(&record) {
+                    file.write_line(&line);
+                }
+            }
+            WRITING.set(false);
+        }
+        {
+            let mut recent = self.recent.lock().unwrap();
+            if recent.len() == RECENT_CAPACITY {
+                recent.pop_front();
+            }
+            recent.push_back(record.clone());
+        }
+        for sub in self.subscribers.lock().unwrap().iter() {
+            if !sub.filter.matches(&record) {
+                continue;
+            }
+            if let Err(TrySendError::Full(_)) = sub.tx.try_send(record.clone()) {
+                sub.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn subscribe(&self, window: &str, filter: ShellLogFilter, capacity: usize) -> Subscription {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = mpsc::sync_channel(capacity);
+        let dropped = Arc::new(AtomicU64::new(0));
+        self.subscribers.lock().unwrap().push(Subscriber {
+            id,
+            window: window.to_string(),
+            filter,
+            tx,
+            dropped: dropped.clone(),
+        });
+        Subscription { id, rx, dropped }
+    }
+
+    /// Dropping the sender ends the subscription's forwarder.
+    fn unsubscribe(&self, id: u64) -> bool {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        let before = subscribers.len();
+        subscribers.retain(|sub| sub.id != id);
+        subscribers.len() != before
+    }
+
+    fn unsubscribe_window(&self, label: &str) {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|sub| sub.window != label);
+    }
+
+    /// The last `limit` records on disk that pass `filter`, oldest first.
+    fn read(&self, limit: usize, filter: &ShellLogFilter) -> Result<Vec<ShellLogRecord>, String> {
+        let Some(path) = &self.path else {
+            return Ok(Vec::new());
+        };
+        let mut records = Vec::new();
+        for path in [logpipe::rotated_path(path), path.clone()] {
+            let text = match std::fs::read_to_string(&path) {
+                Ok(text) => text,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(format!("read {path:?}: {e}")),
+            };
+            records.extend(
+                text.lines()
+                    .filter_map(|line| serde_json::from_str::<ShellLogRecord>(line).ok())
+                    .filter(|record| filter.matches(record)),
+            );
+        }
+        let skip = records.len().saturating_sub(limit);
+        Ok(records.split_off(skip))
+    }
+}
+
+static LOGGER: OnceLock<ShellLogger> = OnceLock::new();
+
+thread_local! {
+    /// Set while this thread writes to `shell.log`.  `LogFile` reports a
+    /// failed reopen through `log`, which must not wait on the file lock
+    /// its own caller holds; that record skips the file.
+    static WRITING: Cell<bool> = const { Cell::new(false) };
+}
+
+fn parse_level(value: &str) -> Option<Level> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "error" => Some(Level::Error),
+        "warn" | "warning" => Some(Level::Warn),
+        "info" => Some(Level::Info),
+        "debug" | "trace" => Some(Level::Debug),
+        _ => None,
+    }
+}
+
+/// `ALMREADY_LOG`, read once.
+fn threshold() -> Option<Level> {
+    static THRESHOLD: OnceLock<Option<Level>> = OnceLock::new();
+    *THRESHOLD.get_or_init(|| {
+        let value = std::env::var(LEVEL_ENV).ok()?;
+        let level = parse_level(&value);
+        if level.is_none() {
+            eprintln!(
+                "[ALMReady] ignoring {LEVEL_ENV}={value:?}: expected error, warn, info, debug or trace"
+            );
+        }
+        level
+    })
+}
+
+pub fn shell_log_path(log_dir: &Path) -> PathBuf {
+    log_dir.join("shell.log")
+}
+
+/// Start writing `shell.log`.  Records logged before this only go to stderr.
+pub fn init(log_dir: &Path) {
+    let _ = LOGGER.set(ShellLogger::new(Some(shell_log_path(log_dir))));
+}
+
+pub fn log(level: Level, module: &str, message: impl Into<String>) {
+    let threshold = threshold();
+    if threshold.is_some_and(|min| level < min) {
+        return;
+    }
+    let message = message.into();
+    if level >= threshold.unwrap_or(Level::Info) {
+        eprintln!("[ALMReady] {message}");
+    }
+    if let Some(logger) = LOGGER.get() {
+        logger.record(ShellLogRecord {
+            at_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            level,
+            module: module.to_string(),
+            message,
+        });
+    }
+}
+
+/// The last `RECENT_CAPACITY` records logged this run, oldest first.
+pub fn recent() -> Vec<ShellLogRecord> {
+    LOGGER
+        .get()
+        .map(|logger| logger.recent.lock().unwrap().iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Called from `on_window_event` on `Destroyed`.
+pub fn on_window_destroyed(label: &str) {
+    if let Some(logger) = LOGGER.get() {
+        logger.unsubscribe_window(label);
+    }
+}
+
+// ── Forwarding ──────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize)]
+struct ShellLogBatch {
+    subscription: u64,
+    records: Vec<ShellLogRecord>,
+    /// Records this subscription missed since the previous batch.
+    dropped: u64,
+}
+
+fn spawn_forwarder(app: AppHandle, window: String, sub: Subscription) {
+    std::thread::spawn(move || {
+        while let Ok(first) = sub.rx.recv() {
+            let mut records = vec![first];
+            records.extend(sub.rx.try_iter().take(MAX_BATCH - 1));
+            let batch = ShellLogBatch {
+                subscription: sub.id,
+                records,
+                dropped: sub.dropped.swap(0, Ordering::Relaxed),
+            };
+            if app.emit_to(&window, "shell-log-records", batch).is_err() {
+                break;
+            }
+            std::thread::sleep(BATCH_INTERVAL);
+        }
+    });
+}
+
+// ── Commands ────────────────────────────────────────────────────────────────
+
+/// Shell records name paths and sessions, so only windows registered for
+/// `Capability::ShellLogs` may read them.
+fn check_allowed(window: &WebviewWindow) -> Result<&'static ShellLogger, String> {
+    let windows = window.app_handle().state::<WindowCapabilities>();
+    if !windows.has(window.label(), Capability::ShellLogs) {
+        return Err("This window may not read the shell log.".into());
+    }
+    LOGGER
+        .get()
+        .ok_or_else(|| "The shell log is not open.".into())
+}
+
+/// Stream new records passing `filter` to the calling window as
+/// `shell-log-records` events.  Returns the subscription id.
+#[tauri::command]
+pub fn subscribe_shell_log(
+    window: WebviewWindow,
+    filter: Option<ShellLogFilter>,
+) -> Result<u64, String> {
+    let logger = check_allowed(&window)?;
+    let sub = logger.subscribe(
+        window.label(),
+        filter.unwrap_or_default(),
+        SUBSCRIBER_CAPACITY,
+    );
+    let id = sub.id;
+    spawn_forwarder(window.app_handle().clone(), window.label().to_string(), sub);
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn unsubscribe_shell_log(id: u64) -> bool {
+    LOGGER.get().is_some_and(|logger| logger.unsubscribe(id))
+}
+
+/// The last `limit` records on disk passing `filter`, oldest first: the
+/// backfill before subscribing.
+#[tauri::command]
+pub fn read_shell_log(
+    window: WebviewWindow,
+    limit: u32,
+    filter: Option<ShellLogFilter>,
+) -> Result<Vec<ShellLogRecord>, String> {
+    check_allowed(&window)?.read(limit as usize, &filter.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn record(level: Level, module: &str, message: &str) -> ShellLogRecord {
+        ShellLogRecord {
+            at_ms: 0,
+            level,
+            module: module.into(),
+            message: message.into(),
+        }
+    }
+
+    #[test]
+    fn filter_by_level_and_module_prefix() {
+        let filter = ShellLogFilter {
+            min_level: Some(Level::Info),
+            module_prefix: Some("watch".into()),
+        };
+        assert!(filter.matches(&record(Level::Warn, "watchdog", "down")));
+        assert!(!filter.matches(&record(Level::Debug, "watchdog", "probe")));
+        assert!(!filter.matches(&record(Level::Error, "window", "closed")));
+        assert!(ShellLogFilter::default().matches(&record(Level::Debug, "command", "x")));
+    }
+
+    #[test]
+    fn backfill_reads_the_tail_that_passes_the_filter() {
+        let dir = tempfile::tempdir().unwrap();
+        let logger = ShellLogger::new(Some(shell_log_path(dir.path())));
+        for i in 0..10 {
+            let level = if i % 2 == 0 {
+                Level::Info
+            } else {
+                Level::Debug
+            };
+            logger.record(record(level, "watchdog", &i.to_string()));
+        }
+
+        let filter = ShellLogFilter {
+            min_level: Some(Level::Info),
+            module_prefix: None,
+        };
+        let messages: Vec<_> = logger
+            .read(3, &filter)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.message)
+            .collect();
+        assert_eq!(messages, ["4", "6", "8"]);
+    }
+
+    #[test]
+    fn level_names_from_the_environment() {
+        assert_eq!(parse_level("warn"), Some(Level::Warn));
+        assert_eq!(parse_level(" INFO "), Some(Level::Info));
+        assert_eq!(parse_level("trace"), Some(Level::Debug));
+        assert_eq!(parse_level("verbose"), None);
+    }
+
+    #[test]
+    fn recent_records_are_a_ring() {
+        let logger = ShellLogger::new(None);
+        for i in 0..RECENT_CAPACITY + 5 {
+            logger.record(record(Level::Debug, "watchdog", &i.to_string()));
+        }
+        let recent = logger.recent.lock().unwrap();
+        assert_eq!(recent.len(), RECENT_CAPACITY);
+        assert_eq!(recent.front().unwrap().message, "5");
+        assert_eq!(
+            recent.back().unwrap().message,
+            (RECENT_CAPACITY + 4).to_string()
+        );
+    }
+
+    #[test]
+    fn slow_subscriber_never_blocks_a_busy_logger() {
+        const THREADS: u64 = 8;
+        const PER_THREAD: u64 = 5_000;
+        const CAPACITY: usize = 16;
+
+        let logger = Arc::new(ShellLogger::new(None));
+        // Never read until the end: as slow as a subscriber gets.
+        let stalled = logger.subscribe("stalled", ShellLogFilter::default(), CAPACITY);
+        // Reads, but slowly.
+        let slow = logger.subscribe("slow", ShellLogFilter::default(), CAPACITY);
+        let (slow_id, slow_dropped) = (slow.id, slow.dropped.clone());
+        let reader = std::thread::spawn(move || {
+            let mut received = 0u64;
+            while slow.rx.recv().is_ok() {
+                received += 1;
+                std::thread::sleep(Duration::from_micros(50));
+            }
+            received
+        });
+
+        let started = Instant::now();
+        let writers: Vec<_> = (0..THREADS)
+            .map(|t| {
+                let logger = logger.clone();
+                std::thread::spawn(move || {
+                    for i in 0..PER_THREAD {
+                        logger.record(record(Level::Info, "stress", &format!("{t}/{i}")));
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        assert!(
+            started.elapsed() < Duration::from_secs(10),
+            "logging stalled for {:?}",
+            started.elapsed()
+        );
+
+        let total = THREADS * PER_THREAD;
+        let queued = stalled.rx.try_iter().count() as u64;
+        assert_eq!(queued, CAPACITY as u64);
+        assert_eq!(stalled.dropped.load(Ordering::Relaxed), total - queued);
+
+        // Unsubscribing ends the reader once it has drained its channel.
+        assert!(logger.unsubscribe(slow_id));
+        let received = reader.join().unwrap();
+        assert_eq!(received + slow_dropped.load(Ordering::Relaxed), total);
+    }
+}
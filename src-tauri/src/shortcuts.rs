@@ -0,0 +1,85 @@
+//! The keyboard shortcut reference card.
+//!
+//! `get_keyboard_shortcuts` lists the shortcuts that work in the app: on
+//! macOS those of the native menu (see `menu`), elsewhere the webview's own
+//! editing keys; on every platform the View menu's zoom keys.  None is registered globally, and there are no
+//! user-defined bindings yet, so the list is fixed per platform.
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShortcutInfo {
+    pub action: &'static str,
+    /// In Tauri accelerator syntax, e.g. `Cmd+Shift+Z`.
+    pub shortcut: &'static str,
+    pub description: &'static str,
+    /// Works while another application has focus.
+    pub is_global: bool,
+}
+
+const fn local(
+    action: &'static str,
+    shortcut: &'static str,
+    description: &'static str,
+) -> ShortcutInfo {
+    ShortcutInfo {
+        action,
+        shortcut,
+        description,
+        is_global: false,
+    }
+}
+
+#[cfg(target_os = "macos")]
+const BUILT_IN: &[ShortcutInfo] = &[
+    local("undo", "Cmd+Z", "Undo"),
+    local("redo", "Cmd+Shift+Z", "Redo"),
+    local("cut", "Cmd+X", "Cut"),
+    local("copy", "Cmd+C", "Copy"),
+    local("paste", "Cmd+V", "Paste"),
+    local("select-all", "Cmd+A", "Select all"),
+    local("zoom-in", "Cmd+=", "Zoom in"),
+    local("zoom-out", "Cmd+-", "Zoom out"),
+    local("zoom-reset", "Cmd+0", "Actual size"),
+    local("minimize", "Cmd+M", "Minimize the window"),
+    local("close-window", "Cmd+W", "Close the window"),
+    local("hide", "Cmd+H", "Hide ALMReady"),
+    local("hide-others", "Cmd+Alt+H", "Hide other applications"),
+    local("quit", "Cmd+Q", "Quit ALMReady"),
+];
+
+#[cfg(not(target_os = "macos"))]
+const BUILT_IN: &[ShortcutInfo] = &[
+    local("undo", "Ctrl+Z", "Undo"),
+    local("redo", "Ctrl+Y", "Redo"),
+    local("cut", "Ctrl+X", "Cut"),
+    local("copy", "Ctrl+C", "Copy"),
+    local("paste", "Ctrl+V", "Paste"),
+    local("select-all", "Ctrl+A", "Select all"),
+    local("zoom-in", "Ctrl+=", "Zoom in"),
+    local("zoom-out", "Ctrl+-", "Zoom out"),
+    local("zoom-reset", "Ctrl+0", "Actual size"),
+    local("close-window", "Alt+F4", "Close the window"),
+];
+
+#[tauri::command]
+pub fn get_keyboard_shortcuts() -> Vec<ShortcutInfo> {
+    BUILT_IN.to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_action_and_shortcut_is_listed_once() {
+        let shortcuts = get_keyboard_shortcuts();
+        for (i, a) in shortcuts.iter().enumerate() {
+            for b in &shortcuts[i + 1..] {
+                assert_ne!(a.action, b.action);
+                assert_ne!(a.shortcut, b.shortcut);
+            }
+        }
+    }
+}
@@ -0,0 +1,251 @@
+//! Whether the running backend is the one bundled with the app, so the About
+//! dialog can show that an installed sidecar update has not taken effect
+//! yet without a full restart.
+//!
+//! The bundled version comes from `version.json` next to the sidecar
+//! executable (`{resource_dir}/almready-backend/version.json`), the running
+//! one from the backend's `GET /api/version`.  Both are `{"version": "…"}`
+//! and compared as plain strings: any difference means the running engine
+//! is not the bundled build.
+//!
+//! Before the main window opens, `check_compatible` also makes sure the
+//! backend speaks the frontend's schema: its version must have the app's
+//! major.minor (see `compatible`).  A build whose bundled backend is a
+//! release behind otherwise fails deep in the UI with schema errors.  On a
+//! mismatch a dialog names both versions and the app quits, unless
+//! `ALMREADY_ALLOW_VERSION_MISMATCH=1` lets a developer open it anyway.  A
+//! backend that answers 404 predates the endpoint and is let through with a
+//! warning in the shell log.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::{
+    http,
+    shell_log::{self, Level},
+    splash, BackendProcess,
+};
+
+/// Offers "Open Anyway" on a version mismatch.
+pub const ALLOW_MISMATCH_ENV: &str = "ALMREADY_ALLOW_VERSION_MISMATCH";
+
+#[derive(Deserialize)]
+struct VersionFile {
+    version: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SidecarVersionCheck {
+    pub bundled: String,
+    pub running: String,
+    /// The running backend is not the bundled one; a restart of the engine
+    /// picks the bundled one up.
+    pub update_available: bool,
+}
+
+impl SidecarVersionCheck {
+    fn new(bundled: String, running: String) -> Self {
+        let update_available = bundled.trim() != running.trim();
+        SidecarVersionCheck {
+            bundled,
+            running,
+            update_available,
+        }
+    }
+}
+
+fn parse_version(json: &str) -> Result<String, String> {
+    serde_json::from_str::<VersionFile>(json)
+        .map(|file| file.version)
+        .map_err(|e| format!("invalid version.json: {e}"))
+}
+
+fn bundled_version(resource_dir: &Path) -> Result<String, String> {
+    let path = resource_dir.join("almready-backend").join("version.json");
+    let json = std::fs::read_to_string(&path)
+        .map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+    parse_version(&json)
+}
+
+/// Compare the bundled sidecar's version with the running backend's.
+#[tauri::command]
+pub async fn check_sidecar_update_available(app: AppHandle) -> Result<SidecarVersionCheck, String> {
+    let resource_dir = app.path().resource_dir().map_err(|e| e.to_string())?;
+    let bundled = bundled_version(&resource_dir)?;
+
+    let backend = app.state::<BackendProcess>();
+    let port = backend.port.get().ok_or("The engine is not running.")?;
+    let running = http::fetch_version(port, backend.token().as_deref())
+        .await?
+        .ok_or("The engine does not report its version.")?;
+    Ok(SidecarVersionCheck::new(bundled, running))
+}
+
+// ── Compatibility ───────────────────────────────────────────────────────────
+
+/// Why the backend can't serve this frontend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Incompatible {
+    /// Different major.minor.
+    Mismatch { app: String, backend: String },
+    /// The backend reported no version, or one that isn't semver.
+    Unknown { app: String, reason: String },
+}
+
+fn parse_semver(version: &str) -> Result<semver::Version, String> {
+    let version = version.trim();
+    semver::Version::parse(version.strip_prefix('v').unwrap_or(version))
+        .map_err(|e| format!("{version:?} is not a version: {e}"))
+}
+
+/// Whether a backend reporting `backend` (or failing to, `Err`) can serve
+/// app version `app`: same major and minor; patch, pre-release and build
+/// metadata may differ.
+pub fn compatible(app: &str, backend: Result<&str, &str>) -> Result<(), Incompatible> {
+    let unknown = |reason: String| Incompatible::Unknown {
+        app: app.to_string(),
+        reason,
+    };
+    let app_version = parse_semver(app).map_err(&unknown)?;
+    let backend = backend.map_err(|e| unknown(e.to_string()))?;
+    let backend_version = parse_semver(backend).map_err(&unknown)?;
+    if (app_version.major, app_version.minor) == (backend_version.major, backend_version.minor) {
+        Ok(())
+    } else {
+        Err(Incompatible::Mismatch {
+            app: app.to_string(),
+            backend: backend.trim().to_string(),
+        })
+    }
+}
+
+fn mismatch_message(incompatible: &Incompatible) -> String {
+    let detail = match incompatible {
+        Incompatible::Mismatch { app, backend } => {
+            let required = parse_semver(app)
+                .map(|v| format!("{}.{}.x", v.major, v.minor))
+                .unwrap_or_else(|_| app.clone());
+            format!(
+                "ALMReady {app} needs a calculation engine of version {required}, but the \
+                 bundled engine is version {backend}."
+            )
+        }
+        Incompatible::Unknown { app, reason } => format!(
+            "ALMReady {app} could not determine the version of its calculation \
+             engine ({reason})."
+        ),
+    };
+    format!("{detail}\n\nThe installation is incomplete or damaged. Please reinstall ALMReady.")
+}
+
+/// Check the backend on `port` against the app version before the main
+/// window opens.  `false` when the app should quit.
+pub async fn check_compatible(app: &AppHandle, port: u16) -> bool {
+    let token = app.state::<BackendProcess>().token();
+    let backend = match http::fetch_version(port, token.as_deref()).await {
+        Ok(Some(version)) => Ok(version),
+        // An engine from before `/api/version` has nothing to compare; the
+        // frontend's own requests will show whether it can serve them.
+        Ok(None) => {
+            shell_log::log(
+                Level::Warn,
+                "version",
+                "backend has no /api/version, skipping the compatibility check",
+            );
+            return true;
+        }
+        Err(e) => Err(e),
+    };
+    let app_version = app.package_info().version.to_string();
+    let Err(incompatible) = compatible(&app_version, backend.as_deref().map_err(String::as_str))
+    else {
+        return true;
+    };
+    shell_log::log(
+        Level::Error,
+        "version",
+        format!("backend incompatible: {incompatible:?}"),
+    );
+
+    // No window exists yet; don't leave the splash behind the dialog.
+    splash::close(app);
+    let allow_anyway = std::env::var(ALLOW_MISMATCH_ENV).is_ok_and(|v| v == "1");
+    let buttons = if allow_anyway {
+        MessageDialogButtons::OkCancelCustom("Open Anyway".into(), "Quit".into())
+    } else {
+        MessageDialogButtons::OkCustom("Quit".into())
+    };
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .message(mismatch_message(&incompatible))
+        .title("ALMReady")
+        .kind(MessageDialogKind::Error)
+        .buttons(buttons)
+        .show(move |ok| {
+            let _ = tx.send(ok);
+        });
+    let open = allow_anyway && rx.await.unwrap_or(false);
+    if open {
+        shell_log::log(
+            Level::Warn,
+            "version",
+            format!("opening anyway ({ALLOW_MISMATCH_ENV}=1)"),
+        );
+    }
+    open
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_differing_in_anything_but_whitespace_mean_an_update() {
+        assert_eq!(parse_version(r#"{"version": "1.4.0"}"#), Ok("1.4.0".into()));
+        assert!(parse_version(r#"{"name": "almready"}"#).is_err());
+
+        assert!(!SidecarVersionCheck::new("1.4.0".into(), "1.4.0\n".into()).update_available);
+        assert!(SidecarVersionCheck::new("1.4.1".into(), "1.4.0".into()).update_available);
+    }
+
+    #[test]
+    fn same_major_minor_is_compatible_including_pre_releases() {
+        assert_eq!(compatible("1.4.0", Ok("1.4.0")), Ok(()));
+        assert_eq!(compatible("1.4.2", Ok("v1.4.0\n")), Ok(()));
+        assert_eq!(compatible("1.5.0-rc.1", Ok("1.5.0")), Ok(()));
+        assert_eq!(compatible("1.5.0", Ok("1.5.1-beta.2+build.7")), Ok(()));
+        assert_eq!(
+            compatible("1.5.0", Ok("1.4.3")),
+            Err(Incompatible::Mismatch {
+                app: "1.5.0".into(),
+                backend: "1.4.3".into()
+            })
+        );
+        assert!(compatible("2.0.0", Ok("1.9.0")).is_err());
+    }
+
+    #[test]
+    fn a_missing_or_malformed_version_is_not_compatible() {
+        let unknown = |result| matches!(result, Err(Incompatible::Unknown { .. }));
+        assert!(unknown(compatible("1.4.0", Err("missing field `version`"))));
+        assert!(unknown(compatible("1.4.0", Ok(""))));
+        assert!(unknown(compatible("1.4.0", Ok("1.4"))));
+        assert!(unknown(compatible("1.4.0", Ok("dev"))));
+    }
+
+    #[test]
+    fn mismatch_names_both_versions() {
+        let message = mismatch_message(&Incompatible::Mismatch {
+            app: "1.5.0".into(),
+            backend: "1.4.3".into(),
+        });
+        assert!(message.starts_with(
+            "ALMReady 1.5.0 needs a calculation engine of version 1.5.x, \
+             but the bundled engine is version 1.4.3."
+        ));
+    }
+}
@@ -0,0 +1,62 @@
+//! Frameless splash window shown while the sidecar warms up.
+//!
+//! The ProcessPoolExecutor warm-up takes 3–8 s, during which nothing else is
+//! on screen.  The splash loads the static `splash.html` (from `public/`) and
+//! its status line is driven by `splash-status` events from the startup
+//! task.  It is only shown when a sidecar was actually spawned, so dev mode
+//! never sees it.
+
+use tauri::{AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder};
+
+use crate::{
+    shell_log::{self, Level},
+    webview_prefs::WebviewOptions,
+};
+
+pub const LABEL: &str = "splash";
+
+pub const STARTING_ENGINE: &str = "Starting engine…";
+pub const WAITING_FOR_HEALTH: &str = "Waiting for health check…";
+pub const WAITING_FOR_COMPONENTS: &str = "Loading components…";
+pub const OPENING_WORKSPACE: &str = "Opening workspace…";
+
+/// Create the splash window if it isn't already open.
+pub fn show(app: &AppHandle) {
+    if app.get_webview_window(LABEL).is_some() {
+        return;
+    }
+    let builder = WebviewWindowBuilder::new(app, LABEL, WebviewUrl::App("splash.html".into()));
+    // Same options as the app windows: WebView2 requires every webview in
+    // the process to use the same browser arguments.
+    let _ = WebviewOptions::current(app)
+        .apply(builder)
+        .title("ALMReady")
+        .inner_size(420.0, 260.0)
+        .resizable(false)
+        .decorations(false)
+        .center()
+        .build()
+        // No app menu bar on Windows and Linux.
+        .inspect(|window| {
+            let _ = window.remove_menu();
+        })
+        .inspect_err(|e| {
+            shell_log::log(
+                Level::Error,
+                "splash",
+                format!("failed to create splash window: {e}"),
+            )
+        });
+}
+
+pub fn set_status(app: &AppHandle, status: &str) {
+    let _ = app.emit_to(LABEL, "splash-status", status);
+}
+
+/// Close the splash.  `destroy` rather than `close` so no `CloseRequested`
+/// reaches the window-event handler.
+pub fn close(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(LABEL) {
+        let _ = window.destroy();
+    }
+}
@@ -0,0 +1,110 @@
+//! Webview storage usage (IndexedDB) for the offline-mode datasets.
+//!
+//! Neither webview exposes a byte count through its native API – a
+//! `WKWebsiteDataRecord` carries only a display name and data types, and
+//! WebView2's `ClearBrowsingData` can only delete – so usage is measured on
+//! disk, under the webview's own data directory:
+//!
+//! macOS   → `~/Library/WebKit/{identifier}/WebsiteData/…/IndexedDB`
+//! Windows → `{app_local_data_dir}/EBWebView/Default/IndexedDB`
+//! Linux   → `{app_local_data_dir}/…/indexeddb` (WebKitGTK)
+//!
+//! Every directory named `IndexedDB` (any case) below the root is counted,
+//! which covers both the flat and the per-origin layouts WebKit has used.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    degradations::{self, Code, Degradation, Severity},
+    events::{self, Audience, Capability},
+};
+
+/// Above this the webview is sent `indexeddb-usage-high`.
+const HIGH_USAGE_BYTES: u64 = 50 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+struct UsageHigh {
+    bytes: u64,
+}
+
+fn webview_data_root(app: &AppHandle) -> Result<PathBuf, String> {
+    if cfg!(target_os = "macos") {
+        let home = app
+            .path()
+            .home_dir()
+            .map_err(|e| format!("home_dir: {e}"))?;
+        Ok(home.join("Library/WebKit").join(&app.config().identifier))
+    } else {
+        app.path()
+            .app_local_data_dir()
+            .map_err(|e| format!("app_local_data_dir: {e}"))
+    }
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| match entry.file_type() {
+            Ok(t) if t.is_dir() => dir_size(&entry.path()),
+            Ok(t) if t.is_file() => entry.metadata().map(|m| m.len()).unwrap_or(0),
+            _ => 0, // symlinks are not followed
+        })
+        .sum()
+}
+
+/// Total size of every `IndexedDB` directory below `root`.
+fn indexed_db_bytes(root: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return 0;
+    };
+    entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|entry| {
+            if entry.file_name().eq_ignore_ascii_case("indexeddb") {
+                dir_size(&entry.path())
+            } else {
+                indexed_db_bytes(&entry.path())
+            }
+        })
+        .sum()
+}
+
+/// Measure IndexedDB usage and warn the webview when it is high.  Blocking;
+/// also run hourly by the housekeeping scheduler.
+pub fn check_usage(app: &AppHandle) -> Result<u64, String> {
+    let bytes = indexed_db_bytes(&webview_data_root(app)?);
+    if bytes > HIGH_USAGE_BYTES {
+        events::emit(
+            app,
+            Audience::Capability(Capability::Diagnostics),
+            "indexeddb-usage-high",
+            UsageHigh { bytes },
+        );
+        degradations::set(
+            app,
+            Degradation::new(
+                Code::StorageHigh,
+                Severity::Warning,
+                serde_json::json!({ "bytes": bytes }),
+            ),
+        );
+    } else {
+        degradations::clear(app, Code::StorageHigh);
+    }
+    Ok(bytes)
+}
+
+/// Bytes of IndexedDB data stored by the webview.
+#[tauri::command]
+pub async fn get_indexed_db_usage(app: AppHandle) -> Result<u64, String> {
+    tauri::async_runtime::spawn_blocking(move || check_usage(&app))
+        .await
+        .map_err(|e| e.to_string())?
+}
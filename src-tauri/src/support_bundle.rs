@@ -0,0 +1,211 @@
+//! Support bundle: everything needed to look into "the app won't open",
+//! zipped into one file the user can attach to a ticket.
+//!
+//! `collect_diagnostics` (and the startup failure dialog's "Save
+//! Diagnostics…" button) asks where to save it and writes:
+//!
+//! * `backend.log` – the sidecar's log as it stands;
+//! * `shell-log.jsonl` – the shell's last `RECENT_CAPACITY` records, from
+//!   memory, so they are there even when `shell.log` could not be written;
+//! * `diagnostics.json` – app version, OS/arch, the resolved sidecar
+//!   executable and whether it exists, the `ALMREADY_DATA_DIR` value, the
+//!   sidecar's last exit code, the last check of the bundle against its
+//!   manifest, one fresh `/api/health` reply and, with
+//!   `ALMREADY_ALLOW_BENCH=1`, the data directory's write speed.
+//!
+//! Everything goes through `last_failure`'s redactor first: the session
+//! token becomes `<redacted>` and the home directory `~`.
+
+use std::{
+    io::Write as _,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::{
+    backend_status::BackendStatus,
+    bundle_check::{self, BundleIntegrity},
+    disk_bench, http,
+    last_failure::Redactor,
+    lifecycle::Lifecycle,
+    logpipe, paths,
+    reveal::RevealRoots,
+    shell_log::{self, Level},
+    sidecar_exe_path, BackendProcess,
+};
+
+/// The startup failure dialog's button.
+pub const SAVE_LABEL: &str = "Save Diagnostics…";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Diagnostics {
+    /// Local time, RFC 3339.
+    collected_at: String,
+    app_version: String,
+    os: &'static str,
+    arch: &'static str,
+    exe_path: Option<PathBuf>,
+    exe_exists: bool,
+    /// What the sidecar gets as `ALMREADY_DATA_DIR`.
+    data_dir: Option<PathBuf>,
+    status: BackendStatus,
+    last_exit_code: Option<i32>,
+    /// `None` before the first spawn.
+    bundle_integrity: Option<bundle_check::Report>,
+    /// `None` when no sidecar port is known.
+    health: Option<String>,
+    /// MB/s, or why it wasn't measured; `None` unless benchmarks are allowed.
+    disk_write_speed: Option<Result<f64, String>>,
+}
+
+async fn collect(app: &AppHandle) -> Diagnostics {
+    let backend = app.state::<BackendProcess>();
+    let exe_path = sidecar_exe_path(app).ok();
+    let health = match backend.port.get() {
+        Some(port) => Some(http::health_report(port, backend.token().as_deref()).await),
+        None => None,
+    };
+    let disk_write_speed = if disk_bench::allowed() {
+        Some(disk_bench::run(app).await)
+    } else {
+        None
+    };
+    Diagnostics {
+        collected_at: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+        app_version: app.package_info().version.to_string(),
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        exe_exists: exe_path.as_deref().is_some_and(Path::is_file),
+        exe_path,
+        data_dir: paths::data_dir(app).ok(),
+        status: backend.status(),
+        last_exit_code: app.state::<Lifecycle>().snapshot().last_exit_code,
+        bundle_integrity: app.state::<BundleIntegrity>().last(),
+        health,
+        disk_write_speed,
+    }
+}
+
+/// The bundle's files, redacted.
+async fn entries(app: &AppHandle) -> Result<Vec<(&'static str, Vec<u8>)>, String> {
+    let redactor = Redactor::for_app(app);
+
+    let mut diagnostics = serde_json::to_value(collect(app).await).map_err(|e| e.to_string())?;
+    redactor.value(&mut diagnostics);
+    let diagnostics = serde_json::to_vec_pretty(&diagnostics).map_err(|e| e.to_string())?;
+
+    let backend_log = match paths::log_dir(app) {
+        Ok(dir) => match tokio::fs::read(logpipe::backend_log_path(&dir)).await {
+            Ok(bytes) => redactor.text(&String::from_utf8_lossy(&bytes)),
+            Err(e) => format!("<not readable: {e}>"),
+        },
+        Err(e) => format!("<no log directory: {e}>"),
+    };
+
+    let mut shell_log = String::new();
+    for record in shell_log::recent() {
+        if let Ok(line) = serde_json::to_string(&record) {
+            shell_log.push_str(&redactor.text(&line));
+            shell_log.push('\n');
+        }
+    }
+
+    Ok(vec![
+        ("diagnostics.json", diagnostics),
+        ("backend.log", backend_log.into_bytes()),
+        ("shell-log.jsonl", shell_log.into_bytes()),
+    ])
+}
+
+fn write_zip(path: &Path, entries: &[(&str, Vec<u8>)]) -> Result<(), String> {
+    let file =
+        std::fs::File::create(path).map_err(|e| format!("create {}: {e}", path.display()))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    for (name, bytes) in entries {
+        zip.start_file(*name, options)
+            .and_then(|()| zip.write_all(bytes).map_err(Into::into))
+            .map_err(|e| format!("{name}: {e}"))?;
+    }
+    zip.finish()
+        .map(drop)
+        .map_err(|e| format!("write {}: {e}", path.display()))
+}
+
+async fn choose_destination(app: &AppHandle) -> Option<PathBuf> {
+    let name = format!(
+        "almready-diagnostics-{}.zip",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    );
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .set_title("Save Diagnostics")
+        .set_file_name(name)
+        .add_filter("Zip archive", &["zip"])
+        .save_file(move |path| {
+            let _ = tx.send(path);
+        });
+    rx.await.ok()??.into_path().ok()
+}
+
+/// Ask for a destination and write the bundle there.  `Ok(None)` when the
+/// user cancelled the save dialog.
+pub async fn save(app: &AppHandle) -> Result<Option<PathBuf>, String> {
+    let Some(destination) = choose_destination(app).await else {
+        return Ok(None);
+    };
+    let entries = entries(app).await?;
+    let path = destination.clone();
+    tauri::async_runtime::spawn_blocking(move || write_zip(&path, &entries))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    if let Some(dir) = destination.parent() {
+        app.state::<RevealRoots>().allow(dir);
+    }
+    shell_log::log(
+        Level::Info,
+        "support-bundle",
+        format!("saved {}", destination.display()),
+    );
+    Ok(Some(destination))
+}
+
+/// Save a support bundle where the user chooses.  Returns its path, or
+/// `None` if the save dialog was cancelled.
+#[tauri::command]
+pub async fn collect_diagnostics(app: AppHandle) -> Result<Option<PathBuf>, String> {
+    save(&app).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read as _;
+
+    #[test]
+    fn bundle_holds_every_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("support-bundle.zip");
+        let entries = [
+            ("diagnostics.json", b"{}".to_vec()),
+            ("backend.log", b"INFO started\n".to_vec()),
+        ];
+        write_zip(&path, &entries).unwrap();
+
+        let mut zip = zip::ZipArchive::new(std::fs::File::open(&path).unwrap()).unwrap();
+        assert_eq!(zip.len(), 2);
+        let mut log = String::new();
+        zip.by_name("backend.log")
+            .unwrap()
+            .read_to_string(&mut log)
+            .unwrap();
+        assert_eq!(log, "INFO started\n");
+    }
+}
@@ -0,0 +1,55 @@
+//! Taskbar button progress state.
+//!
+//! Windows 11's taskbar button shows more than a percentage: a green bar
+//! (`Normal`), a yellow one (`Paused`), a red one (`Error`) or a marquee
+//! (`Indeterminate`).  The frontend shows `Indeterminate` while the backend
+//! restarts and `Error` when its health check fails, and clears it with
+//! `None`.
+//!
+//! Tauri applies the state through `ITaskbarList3::SetProgressState`
+//! (`TBPF_NORMAL`, `TBPF_PAUSED`, `TBPF_ERROR`, `TBPF_INDETERMINATE`,
+//! `TBPF_NOPROGRESS`).  macOS and Linux have no paused or error states and
+//! show what their dock or launcher supports.
+
+use serde::Deserialize;
+use tauri::{
+    window::{ProgressBarState, ProgressBarStatus},
+    WebviewWindow,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProgressStyle {
+    None,
+    Normal,
+    Paused,
+    Error,
+    Indeterminate,
+}
+
+impl From<ProgressStyle> for ProgressBarStatus {
+    fn from(style: ProgressStyle) -> Self {
+        match style {
+            ProgressStyle::None => ProgressBarStatus::None,
+            ProgressStyle::Normal => ProgressBarStatus::Normal,
+            ProgressStyle::Paused => ProgressBarStatus::Paused,
+            ProgressStyle::Error => ProgressBarStatus::Error,
+            ProgressStyle::Indeterminate => ProgressBarStatus::Indeterminate,
+        }
+    }
+}
+
+/// Set the progress state of the calling window's taskbar button, keeping
+/// its current value.
+#[tauri::command]
+pub fn set_progress_window_style(
+    window: WebviewWindow,
+    style: ProgressStyle,
+) -> Result<(), String> {
+    window
+        .set_progress_bar(ProgressBarState {
+            status: Some(style.into()),
+            progress: None,
+        })
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,166 @@
+//! OS accent and system UI colours for the design system.
+//!
+//! macOS   → `NSColor.controlAccentColor` / `windowBackgroundColor` / `labelColor`
+//! Windows → `UISettings.GetColorValue(UIColorType::{Accent, Background, Foreground})`
+//! Linux   → GSettings `org.gnome.desktop.interface accent-color` and
+//!           `color-scheme`, queried through the `gsettings` CLI.
+//!
+//! Whenever the OS reports a theme change on the main window the colours are
+//! re-read and pushed to the webview as `theme-colors-changed`.
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::{
+    events::{self, Audience},
+    shell_log::{self, Level},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ThemeColors {
+    pub accent_hex: String,
+    pub background_hex: String,
+    pub foreground_hex: String,
+}
+
+impl ThemeColors {
+    /// Used when the platform query fails: the macOS/Windows default blue on
+    /// a light window.
+    fn fallback() -> Self {
+        ThemeColors {
+            accent_hex: "#0a84ff".into(),
+            background_hex: "#ffffff".into(),
+            foreground_hex: "#000000".into(),
+        }
+    }
+}
+
+fn hex(r: u8, g: u8, b: u8) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+// ── Platform queries ────────────────────────────────────────────────────────
+
+#[cfg(target_os = "macos")]
+fn query() -> Result<ThemeColors, String> {
+    use objc2_app_kit::{NSColor, NSColorSpace};
+
+    fn to_hex(color: &NSColor) -> Option<String> {
+        // Dynamic system colours must be resolved into a concrete colour
+        // space before their components can be read.
+        #[allow(unused_unsafe)]
+        unsafe {
+            let rgb = color.colorUsingColorSpace(&NSColorSpace::sRGBColorSpace())?;
+            let c = |v: f64| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+            Some(hex(
+                c(rgb.redComponent()),
+                c(rgb.greenComponent()),
+                c(rgb.blueComponent()),
+            ))
+        }
+    }
+
+    #[allow(unused_unsafe)]
+    let (accent, background, foreground) = unsafe {
+        (
+            NSColor::controlAccentColor(),
+            NSColor::windowBackgroundColor(),
+            NSColor::labelColor(),
+        )
+    };
+
+    Ok(ThemeColors {
+        accent_hex: to_hex(&accent).ok_or("accent colour not convertible to sRGB")?,
+        background_hex: to_hex(&background).ok_or("background colour not convertible to sRGB")?,
+        foreground_hex: to_hex(&foreground).ok_or("foreground colour not convertible to sRGB")?,
+    })
+}
+
+#[cfg(target_os = "windows")]
+fn query() -> Result<ThemeColors, String> {
+    use windows::UI::ViewManagement::{UIColorType, UISettings};
+
+    let settings = UISettings::new().map_err(|e| format!("UISettings: {e}"))?;
+    let color = |kind: UIColorType| {
+        settings
+            .GetColorValue(kind)
+            .map(|c| hex(c.R, c.G, c.B))
+            .map_err(|e| format!("GetColorValue: {e}"))
+    };
+
+    Ok(ThemeColors {
+        accent_hex: color(UIColorType::Accent)?,
+        background_hex: color(UIColorType::Background)?,
+        foreground_hex: color(UIColorType::Foreground)?,
+    })
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn query() -> Result<ThemeColors, String> {
+    fn gsetting(key: &str) -> Option<String> {
+        let out = std::process::Command::new("gsettings")
+            .args(["get", "org.gnome.desktop.interface", key])
+            .output()
+            .ok()?;
+        if !out.status.success() {
+            return None;
+        }
+        // gsettings prints GVariant strings quoted: 'blue'
+        Some(
+            String::from_utf8_lossy(&out.stdout)
+                .trim()
+                .trim_matches('\'')
+                .to_string(),
+        )
+    }
+
+    // Named accents introduced in GNOME 47, with libadwaita's hex values.
+    let accent = match gsetting("accent-color").as_deref() {
+        Some("teal") => "#2190a4",
+        Some("green") => "#3a944a",
+        Some("yellow") => "#c88800",
+        Some("orange") => "#ed5b00",
+        Some("red") => "#e62d42",
+        Some("pink") => "#d56199",
+        Some("purple") => "#9141ac",
+        Some("slate") => "#6f8396",
+        _ => "#3584e4", // blue, and the default on older GNOME
+    };
+
+    let dark = gsetting("color-scheme").as_deref() == Some("prefer-dark");
+    let (background, foreground) = if dark {
+        ("#242424", "#ffffff")
+    } else {
+        ("#fafafa", "#000000")
+    };
+
+    Ok(ThemeColors {
+        accent_hex: accent.into(),
+        background_hex: background.into(),
+        foreground_hex: foreground.into(),
+    })
+}
+
+fn current() -> ThemeColors {
+    query()
+        .inspect_err(|e| {
+            shell_log::log(
+                Level::Warn,
+                "theme",
+                format!("theme colour query failed: {e}"),
+            )
+        })
+        .unwrap_or_else(|_| ThemeColors::fallback())
+}
+
+// ── Events & commands ───────────────────────────────────────────────────────
+
+/// Called from `on_window_event` on `ThemeChanged`.
+pub fn on_theme_changed(app: &AppHandle) {
+    events::emit(app, Audience::AllWindows, "theme-colors-changed", current());
+}
+
+#[tauri::command]
+pub fn get_theme_colors() -> ThemeColors {
+    current()
+}
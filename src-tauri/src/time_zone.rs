@@ -0,0 +1,92 @@
+//! The user's local time zone, for dates on ALM timelines.
+//!
+//! Every window gets it as `window.__TZ__` in an initialization script, so
+//! the first render already formats dates correctly; `get_time_zone` returns
+//! it fresh (after a laptop crosses a zone, or a DST change while the app is
+//! open).
+//!
+//! The IANA name comes from the OS (`iana-time-zone`); when the OS doesn't
+//! give one, a fixed-offset `Etc/GMT±h` zone stands in, or `UTC`.  The
+//! offset is chrono's local offset right now.  `observes_dst` compares the
+//! offsets in January and July of the current year.
+
+use chrono::{Datelike, Local, TimeZone};
+use serde::Serialize;
+
+use crate::shell_log::{self, Level};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TimeZoneInfo {
+    pub iana_name: String,
+    /// East of UTC is positive (UTC+02:00 → 120).
+    pub utc_offset_minutes: i32,
+    pub observes_dst: bool,
+}
+
+impl TimeZoneInfo {
+    pub fn current() -> Self {
+        let now = Local::now();
+        let utc_offset_minutes = now.offset().local_minus_utc() / 60;
+        let iana_name = iana_time_zone::get_timezone().unwrap_or_else(|e| {
+            shell_log::log(
+                Level::Warn,
+                "time-zone",
+                format!("no IANA time zone from the OS: {e}"),
+            );
+            fallback_name(utc_offset_minutes)
+        });
+        let offset_on = |month| {
+            Local
+                .with_ymd_and_hms(now.year(), month, 1, 12, 0, 0)
+                .single()
+                .map(|t| t.offset().local_minus_utc())
+        };
+        TimeZoneInfo {
+            iana_name,
+            utc_offset_minutes,
+            observes_dst: offset_on(1) != offset_on(7),
+        }
+    }
+
+    pub fn init_script(&self) -> String {
+        let json = serde_json::to_string(self).unwrap_or_else(|_| "null".into());
+        format!("window.__TZ__ = {json};")
+    }
+}
+
+/// A zone name for a bare offset.  `Etc/GMT` names have the POSIX sign, so
+/// UTC+02:00 is `Etc/GMT-2`; there are none for fractional hours.
+fn fallback_name(offset_minutes: i32) -> String {
+    if offset_minutes == 0 || offset_minutes % 60 != 0 {
+        return "UTC".into();
+    }
+    format!("Etc/GMT{:+}", -offset_minutes / 60)
+}
+
+#[tauri::command]
+pub fn get_time_zone() -> TimeZoneInfo {
+    TimeZoneInfo::current()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fallback_names_use_the_posix_sign() {
+        assert_eq!(fallback_name(0), "UTC");
+        assert_eq!(fallback_name(120), "Etc/GMT-2");
+        assert_eq!(fallback_name(-300), "Etc/GMT+5");
+        assert_eq!(fallback_name(330), "UTC");
+
+        let info = TimeZoneInfo {
+            iana_name: "Europe/Madrid".into(),
+            utc_offset_minutes: 120,
+            observes_dst: true,
+        };
+        assert_eq!(
+            info.init_script(),
+            r#"window.__TZ__ = {"iana_name":"Europe/Madrid","utc_offset_minutes":120,"observes_dst":true};"#
+        );
+    }
+}
@@ -0,0 +1,252 @@
+//! Startup and shutdown timeouts, for machines where the defaults are too
+//! tight (2 cores and a paging disk can take 50 s to warm the
+//! ProcessPoolExecutor, so a healthy install "failed to start").
+//!
+//! Each value comes from, in order:
+//!
+//! 1. `ALMREADY_STARTUP_TIMEOUT_SECS` – the health-check budget only;
+//! 2. `{data_dir}/config.json`, all optional:
+//!    `{"startupTimeoutSecs": 90, "healthIntervalMs": 500,
+//!      "readinessTimeoutSecs": 30, "portTimeoutSecs": 45,
+//!      "shutdownGraceSecs": 5}`;
+//! 3. the defaults below.
+//!
+//! A value that isn't a whole number within its range is ignored with a
+//! warning.  What ends up in effect is logged once at startup, so support
+//! can see it in `shell.log`.
+
+use std::{path::Path, sync::OnceLock, time::Duration};
+
+use serde_json::Value;
+use tauri::AppHandle;
+
+use crate::{
+    data_dir_access::DataDirAccess,
+    paths,
+    shell_log::{self, Level},
+};
+
+pub const STARTUP_ENV: &str = "ALMREADY_STARTUP_TIMEOUT_SECS";
+const CONFIG_FILE: &str = "config.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timeouts {
+    /// Budget for the health check to pass once the port is known.
+    pub startup: Duration,
+    /// Pause between health probes.
+    pub health_interval: Duration,
+    /// Budget for the components to become ready once healthy.
+    pub readiness: Duration,
+    /// How long a running sidecar gets to print its `PORT:` line.
+    pub port: Duration,
+    /// How long a sidecar asked to shut down gets to finish in-flight
+    /// session writes before it is terminated.
+    pub shutdown_grace: Duration,
+}
+
+impl Timeouts {
+    pub const DEFAULT: Timeouts = Timeouts {
+        startup: Duration::from_secs(30),
+        health_interval: Duration::from_millis(500),
+        readiness: Duration::from_secs(30),
+        port: Duration::from_secs(45),
+        shutdown_grace: Duration::from_secs(5),
+    };
+}
+
+/// Probes `interval` apart that fit in `timeout`, at least one.
+pub fn attempts(timeout: Duration, interval: Duration) -> u32 {
+    let attempts = timeout.as_millis() / interval.as_millis().max(1);
+    u32::try_from(attempts).unwrap_or(u32::MAX).max(1)
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// One setting: its `config.json` key, accepted range and unit.
+struct Field {
+    key: &'static str,
+    range: (u64, u64),
+    millis: bool,
+}
+
+const STARTUP: Field = Field {
+    key: "startupTimeoutSecs",
+    range: (5, 1800),
+    millis: false,
+};
+const HEALTH_INTERVAL: Field = Field {
+    key: "healthIntervalMs",
+    range: (50, 10_000),
+    millis: true,
+};
+const READINESS: Field = Field {
+    key: "readinessTimeoutSecs",
+    range: (5, 1800),
+    millis: false,
+};
+const PORT: Field = Field {
+    key: "portTimeoutSecs",
+    range: (5, 1800),
+    millis: false,
+};
+const SHUTDOWN_GRACE: Field = Field {
+    key: "shutdownGraceSecs",
+    range: (1, 120),
+    millis: false,
+};
+
+impl Field {
+    /// `value` as a duration, or why it can't be one.
+    fn parse(&self, value: &Value) -> Result<Duration, String> {
+        let n = match value {
+            Value::Number(n) => n.as_u64(),
+            Value::String(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+        .filter(|n| (self.range.0..=self.range.1).contains(n))
+        .ok_or_else(|| {
+            format!(
+                "{} = {value} is not a whole number from {} to {}",
+                self.key, self.range.0, self.range.1
+            )
+        })?;
+        Ok(if self.millis {
+            Duration::from_millis(n)
+        } else {
+            Duration::from_secs(n)
+        })
+    }
+}
+
+/// Combine the env value and `config.json` over the defaults.  Returns the
+/// result and a warning per ignored value.
+fn resolve(env: Option<&str>, config: Option<&Value>) -> (Timeouts, Vec<String>) {
+    let mut timeouts = Timeouts::DEFAULT;
+    let mut warnings = Vec::new();
+    let mut apply = |field: &Field, value: Option<&Value>, target: &mut Duration| {
+        if let Some(value) = value {
+            match field.parse(value) {
+                Ok(duration) => *target = duration,
+                Err(e) => warnings.push(e),
+            }
+        }
+    };
+
+    let config = config.and_then(Value::as_object);
+    let get = |field: &Field| config.and_then(|c| c.get(field.key));
+    let env = env.map(|s| Value::String(s.to_string()));
+    apply(
+        &STARTUP,
+        env.as_ref().or(get(&STARTUP)),
+        &mut timeouts.startup,
+    );
+    apply(
+        &HEALTH_INTERVAL,
+        get(&HEALTH_INTERVAL),
+        &mut timeouts.health_interval,
+    );
+    apply(&READINESS, get(&READINESS), &mut timeouts.readiness);
+    apply(&PORT, get(&PORT), &mut timeouts.port);
+    apply(
+        &SHUTDOWN_GRACE,
+        get(&SHUTDOWN_GRACE),
+        &mut timeouts.shutdown_grace,
+    );
+    (timeouts, warnings)
+}
+
+fn read_config(path: &Path) -> Result<Option<Value>, String> {
+    match DataDirAccess::unleased().with(&[path], || std::fs::read_to_string(path)) {
+        Ok(text) => serde_json::from_str(&text)
+            .map(Some)
+            .map_err(|e| format!("{}: {e}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("{}: {e}", path.display())),
+    }
+}
+
+static CURRENT: OnceLock<Timeouts> = OnceLock::new();
+
+/// The timeouts in effect: those `init` loaded, else the defaults.
+pub fn current() -> Timeouts {
+    CURRENT.get().copied().unwrap_or_default()
+}
+
+/// Load the timeouts for this run and log them.  Called once from setup,
+/// before the sidecar starts.
+pub fn init(app: &AppHandle) {
+    let mut warnings = Vec::new();
+    let config = match paths::data_dir(app).map(|dir| dir.join(CONFIG_FILE)) {
+        Ok(path) => read_config(&path).unwrap_or_else(|e| {
+            warnings.push(format!("ignoring {e}"));
+            None
+        }),
+        Err(_) => None,
+    };
+    let env = std::env::var(STARTUP_ENV).ok();
+    let (timeouts, ignored) = resolve(env.as_deref(), config.as_ref());
+    warnings.extend(
+        ignored
+            .into_iter()
+            .map(|e| format!("{e}, using the default")),
+    );
+    for warning in warnings {
+        shell_log::log(Level::Warn, "timeouts", warning);
+    }
+    shell_log::log(
+        Level::Info,
+        "timeouts",
+        format!(
+            "startup {:?} (probe every {:?}), readiness {:?}, port {:?}, shutdown grace {:?}",
+            timeouts.startup,
+            timeouts.health_interval,
+            timeouts.readiness,
+            timeouts.port,
+            timeouts.shutdown_grace
+        ),
+    );
+    let _ = CURRENT.set(timeouts);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn env_beats_config_beats_defaults() {
+        let config = json!({
+            "startupTimeoutSecs": 90,
+            "readinessTimeoutSecs": 120,
+            "portTimeoutSecs": "60",
+        });
+        let (timeouts, warnings) = resolve(None, Some(&config));
+        assert!(warnings.is_empty());
+        assert_eq!(timeouts.startup, Duration::from_secs(90));
+        assert_eq!(timeouts.readiness, Duration::from_secs(120));
+        assert_eq!(timeouts.port, Duration::from_secs(60));
+        assert_eq!(timeouts.shutdown_grace, Timeouts::DEFAULT.shutdown_grace);
+        assert_eq!(attempts(timeouts.startup, timeouts.health_interval), 180);
+
+        let (timeouts, _) = resolve(Some("120"), Some(&config));
+        assert_eq!(timeouts.startup, Duration::from_secs(120));
+        assert_eq!(resolve(None, None), (Timeouts::DEFAULT, vec![]));
+        assert_eq!(
+            attempts(Duration::from_secs(30), Duration::from_millis(500)),
+            60
+        );
+    }
+
+    #[test]
+    fn invalid_values_fall_back_with_a_warning() {
+        let config = json!({ "healthIntervalMs": 0, "shutdownGraceSecs": "soon" });
+        let (timeouts, warnings) = resolve(Some("-5"), Some(&config));
+        assert_eq!(timeouts, Timeouts::DEFAULT);
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings[0].starts_with("startupTimeoutSecs"));
+    }
+}
@@ -0,0 +1,262 @@
+//! System tray icon showing whether the calculation engine is alive, for
+//! long runs with the window minimized or hidden.
+//!
+//! The icon is the app icon with a status dot – amber while starting, green
+//! when ready, red after a crash, grey when stopped – and the tooltip says
+//! the same, with the number of active degradations appended, a note
+//! while sleep is being inhibited (see `keep_awake`) and the number of
+//! events waiting for an away summary (see `journal`).  `refresh` is called
+//! wherever `BackendStatus` changes (startup, respawn), when sleep
+//! inhibition starts or stops and on every watchdog poll, which also
+//! catches a child that exited between polls.
+//!
+//! Its menu offers Show ALMReady (focus the main window, or rebuild it if
+//! it is gone), Restart Engine (as in the Help menu) and Quit, which exits
+//! through `RunEvent::ExitRequested` and so stops the sidecar the same way
+//! as closing the window.  A left click shows the window.
+//!
+//! With `Settings::close_to_tray`, closing the main window hides it instead
+//! of quitting; the backend keeps running until Quit.
+
+use std::sync::Mutex;
+
+use tauri::{
+    image::Image,
+    menu::{MenuBuilder, MenuEvent},
+    tray::{MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent},
+    AppHandle, Manager, State,
+};
+
+use crate::{
+    backend_status::BackendStatus,
+    create_main_window,
+    degradations::Degradations,
+    journal::ActivityJournal,
+    keep_awake::KeepAwake,
+    menu,
+    settings::SettingsStore,
+    shell_log::{self, Level},
+    window_factory::MAIN_LABEL,
+    BackendProcess,
+};
+
+const TRAY_ID: &str = "almready";
+
+const SHOW: &str = "tray-show";
+const RESTART: &str = "tray-restart";
+const QUIT: &str = "tray-quit";
+
+/// What the icon currently shows: status, degradation count, whether
+/// sleep is inhibited and the events waiting for an away summary.
+type Shown = (BackendStatus, usize, bool, u32);
+
+/// Managed state: what the icon currently shows.
+#[derive(Default)]
+pub struct TrayStatus(Mutex<Option<Shown>>);
+
+fn status_text(status: BackendStatus) -> &'static str {
+    match status {
+        BackendStatus::Starting => "ALMReady – engine starting…",
+        BackendStatus::Ready => "ALMReady – engine running",
+        BackendStatus::Crashed => "ALMReady – engine crashed",
+        BackendStatus::Stopped => "ALMReady – engine stopped",
+    }
+}
+
+/// The status, plus the number of active degradations (see
+/// `degradations`) when there are any, whether sleep is inhibited and the
+/// events that happened while the user was away.
+fn tooltip((status, degraded, awake, away): Shown) -> String {
+    let mut text = match degraded {
+        0 => status_text(status).to_string(),
+        1 => format!("{} (1 issue)", status_text(status)),
+        n => format!("{} ({n} issues)", status_text(status)),
+    };
+    if awake {
+        text.push_str("\nKeeping the computer awake");
+    }
+    match away {
+        0 => {}
+        1 => text.push_str("\n1 new event while you were away"),
+        n => text.push_str(&format!("\n{n} new events while you were away")),
+    }
+    text
+}
+
+fn shown(app: &AppHandle) -> Shown {
+    (
+        app.state::<BackendProcess>().status(),
+        app.state::<Degradations>().count(),
+        app.state::<KeepAwake>().is_active(),
+        app.state::<ActivityJournal>().pending(),
+    )
+}
+
+fn dot_color(status: BackendStatus) -> [u8; 3] {
+    match status {
+        BackendStatus::Starting => [0xF5, 0x9E, 0x0B],
+        BackendStatus::Ready => [0x22, 0xC5, 0x5E],
+        BackendStatus::Crashed => [0xEF, 0x44, 0x44],
+        BackendStatus::Stopped => [0x9C, 0xA3, 0xAF],
+    }
+}
+
+/// Paint an opaque dot of `color` over the bottom-right quarter of an RGBA
+/// image.
+fn paint_status_dot(rgba: &mut [u8], width: u32, height: u32, color: [u8; 3]) {
+    let radius = f64::from(width.min(height)) / 4.0;
+    let (cx, cy) = (f64::from(width) - radius, f64::from(height) - radius);
+    for y in 0..height {
+        for x in 0..width {
+            let (dx, dy) = (f64::from(x) + 0.5 - cx, f64::from(y) + 0.5 - cy);
+            if dx * dx + dy * dy <= radius * radius {
+                let i = ((y * width + x) * 4) as usize;
+                rgba[i..i + 4].copy_from_slice(&[color[0], color[1], color[2], 0xFF]);
+            }
+        }
+    }
+}
+
+fn icon(app: &AppHandle, status: BackendStatus) -> Option<Image<'static>> {
+    let base = app.default_window_icon()?;
+    let mut rgba = base.rgba().to_vec();
+    paint_status_dot(&mut rgba, base.width(), base.height(), dot_color(status));
+    Some(Image::new_owned(rgba, base.width(), base.height()))
+}
+
+/// Show the main window, or rebuild it if it was destroyed.
+fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(MAIN_LABEL) {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+        return;
+    }
+    let Some(port) = app.state::<BackendProcess>().port.get() else {
+        shell_log::log(
+            Level::Info,
+            "tray",
+            "no main window to show: the engine is not running",
+        );
+        return;
+    };
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move { create_main_window(&app, port).await });
+}
+
+fn on_menu_event(app: &AppHandle, event: MenuEvent) {
+    match event.id().as_ref() {
+        SHOW => show_main_window(app),
+        RESTART => menu::restart_engine(app),
+        QUIT => app.exit(0),
+        _ => {}
+    }
+}
+
+fn on_icon_event(tray: &TrayIcon, event: TrayIconEvent) {
+    if let TrayIconEvent::Click {
+        button: MouseButton::Left,
+        button_state: MouseButtonState::Up,
+        ..
+    } = event
+    {
+        show_main_window(tray.app_handle());
+    }
+}
+
+/// Create the tray icon.  Called once from `setup`.
+pub fn create(app: &AppHandle) -> tauri::Result<()> {
+    let menu = MenuBuilder::new(app)
+        .text(SHOW, "Show ALMReady")
+        .text(RESTART, "Restart Engine")
+        .separator()
+        .text(QUIT, "Quit")
+        .build()?;
+    let shown = shown(app);
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip(tooltip(shown))
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(on_menu_event)
+        .on_tray_icon_event(on_icon_event);
+    if let Some(icon) = icon(app, shown.0) {
+        builder = builder.icon(icon);
+    }
+    builder.build(app)?;
+    *app.state::<TrayStatus>().0.lock().unwrap() = Some(shown);
+    Ok(())
+}
+
+/// Bring the icon and tooltip up to date with the backend's status, the
+/// degradation count, sleep inhibition and waiting away events.
+pub fn refresh(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    let current = shown(app);
+    let tray_status = app.state::<TrayStatus>();
+    let previous = tray_status.0.lock().unwrap().replace(current);
+    if previous == Some(current) {
+        return;
+    }
+    let _ = tray.set_tooltip(Some(tooltip(current)));
+    if previous.map(|(s, ..)| s) != Some(current.0) {
+        let _ = tray.set_icon(icon(app, current.0));
+    }
+}
+
+/// Whether closing the main window should hide it to the tray.
+pub fn hides_on_close(app: &AppHandle) -> bool {
+    app.tray_by_id(TRAY_ID).is_some() && app.state::<SettingsStore>().get().close_to_tray
+}
+
+// ── Commands ────────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub fn get_close_to_tray(store: State<'_, SettingsStore>) -> bool {
+    store.get().close_to_tray
+}
+
+#[tauri::command]
+pub fn set_close_to_tray(store: State<'_, SettingsStore>, enabled: bool) -> Result<(), String> {
+    let mut settings = store.get();
+    settings.close_to_tray = enabled;
+    store.replace(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_dot_covers_the_bottom_right_corner_only() {
+        let (w, h) = (16, 16);
+        let mut rgba = vec![0u8; (w * h * 4) as usize];
+        paint_status_dot(&mut rgba, w, h, dot_color(BackendStatus::Crashed));
+        let pixel = |x: u32, y: u32| {
+            let i = ((y * w + x) * 4) as usize;
+            rgba[i..i + 4].to_vec()
+        };
+        assert_eq!(pixel(12, 12), [0xEF, 0x44, 0x44, 0xFF]);
+        assert_eq!(pixel(0, 0), [0, 0, 0, 0]);
+        assert_eq!(pixel(7, 7), [0, 0, 0, 0]);
+        assert_eq!(pixel(15, 8), [0, 0, 0, 0]);
+
+        assert_eq!(
+            tooltip((BackendStatus::Ready, 0, false, 0)),
+            "ALMReady – engine running"
+        );
+        assert_eq!(
+            tooltip((BackendStatus::Ready, 2, false, 0)),
+            "ALMReady – engine running (2 issues)"
+        );
+        assert_eq!(
+            tooltip((BackendStatus::Ready, 0, true, 0)),
+            "ALMReady – engine running\nKeeping the computer awake"
+        );
+        assert_eq!(
+            tooltip((BackendStatus::Crashed, 0, false, 3)),
+            "ALMReady – engine crashed\n3 new events while you were away"
+        );
+    }
+}
@@ -0,0 +1,260 @@
+//! In-app updates, driven by the frontend rather than the updater's silent
+//! default flow.
+//!
+//! `check_for_updates` asks the configured endpoints (`plugins.updater` in
+//! `tauri.conf.json`) and keeps what it found; `install_update` downloads
+//! it, sending `update-download-progress` to the main window as chunks
+//! arrive.  On Windows the installer cannot replace files the Python
+//! backend still holds open ("file in use"), so before installing the
+//! sidecar is stopped the same way as on quit – HTTP shutdown, then the
+//! process tree – with the watchdog aborted and `restarting` held so
+//! nothing respawns it.  Then `pending-restart` is written to the data
+//! directory and the update installed; on Windows the installer ends the
+//! process itself, elsewhere the app restarts.  If installing fails the
+//! marker is removed and the sidecar started again.
+//!
+//! On the next launch `take_installed` reads and deletes the marker, and
+//! once the main window is open `announce_installed` sends it
+//! `update-installed` with both versions, for the release notes.  A marker
+//! whose new version isn't the running one (the installer was cancelled)
+//! is only logged.
+
+use std::{
+    path::Path,
+    sync::{atomic::Ordering, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+use crate::{
+    backend_status,
+    data_dir_access::DataDirAccess,
+    events::{self, Audience},
+    paths,
+    shell_log::{self, Level},
+    terminate_backend, BackendProcess,
+};
+
+const MARKER: &str = "pending-restart";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstalledUpdate {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateInfo {
+    pub version: String,
+    pub current_version: String,
+    pub date: Option<String>,
+    /// Release notes from the update manifest.
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DownloadProgress {
+    downloaded: u64,
+    /// `None` when the server sends no length.
+    total: Option<u64>,
+}
+
+/// Managed state: the update found by the last check, and the one installed
+/// before this launch until the main window has been told.
+#[derive(Default)]
+pub struct Updates {
+    available: Mutex<Option<Update>>,
+    installed: Mutex<Option<InstalledUpdate>>,
+}
+
+fn read_marker(path: &Path) -> Option<InstalledUpdate> {
+    let text = DataDirAccess::unleased()
+        .with(&[path], || std::fs::read_to_string(path))
+        .ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+/// Read and delete the marker left by `install_update`.  Called in setup.
+pub fn take_installed(app: &AppHandle) {
+    let Ok(path) = paths::data_dir(app).map(|dir| dir.join(MARKER)) else {
+        return;
+    };
+    if !path.exists() {
+        return;
+    }
+    let marker = read_marker(&path);
+    let _ = DataDirAccess::unleased().with(&[&path], || std::fs::remove_file(&path));
+    let running = app.package_info().version.to_string();
+    match marker {
+        Some(update) if update.to == running => {
+            shell_log::log(
+                Level::Info,
+                "updater",
+                format!("updated from {} to {}", update.from, update.to),
+            );
+            *app.state::<Updates>().installed.lock().unwrap() = Some(update);
+        }
+        Some(update) => shell_log::log(
+            Level::Warn,
+            "updater",
+            format!(
+                "update to {} was not installed, still running {running}",
+                update.to
+            ),
+        ),
+        None => shell_log::log(
+            Level::Warn,
+            "updater",
+            format!("unreadable {MARKER} removed"),
+        ),
+    }
+}
+
+/// Send `update-installed` to the main window, once it is open.
+pub fn announce_installed(app: &AppHandle) {
+    if let Some(update) = app.state::<Updates>().installed.lock().unwrap().take() {
+        events::emit(app, Audience::MainOnly, "update-installed", update);
+    }
+}
+
+/// Ask the update endpoints for a newer version.  `None` when up to date.
+#[tauri::command]
+pub async fn check_for_updates(
+    app: AppHandle,
+    updates: State<'_, Updates>,
+) -> Result<Option<UpdateInfo>, String> {
+    let update = app
+        .updater()
+        .map_err(|e| e.to_string())?
+        .check()
+        .await
+        .map_err(|e| format!("Could not check for updates: {e}"))?;
+    let info = update.as_ref().map(|update| UpdateInfo {
+        version: update.version.clone(),
+        current_version: update.current_version.clone(),
+        date: update.date.map(|date| date.to_string()),
+        notes: update.body.clone(),
+    });
+    *updates.available.lock().unwrap() = update;
+    Ok(info)
+}
+
+/// Download and install the update found by `check_for_updates`, stopping
+/// the sidecar first.  Only returns on failure; success restarts the app.
+#[tauri::command]
+pub async fn install_update(app: AppHandle, updates: State<'_, Updates>) -> Result<(), String> {
+    let Some(update) = updates.available.lock().unwrap().take() else {
+        return Err("No update is available; check for updates first.".into());
+    };
+    let backend = app.state::<BackendProcess>();
+    if backend.is_shutting_down() {
+        return Err("The app is closing.".into());
+    }
+
+    shell_log::log(
+        Level::Info,
+        "updater",
+        format!("downloading {}", update.version),
+    );
+    let mut downloaded = 0u64;
+    let bytes = update
+        .download(
+            |chunk, total| {
+                downloaded += chunk as u64;
+                events::emit(
+                    &app,
+                    Audience::MainOnly,
+                    "update-download-progress",
+                    DownloadProgress { downloaded, total },
+                );
+            },
+            || {},
+        )
+        .await
+        .map_err(|e| format!("Could not download the update: {e}"))?;
+
+    if backend.restarting.swap(true, Ordering::SeqCst) {
+        *updates.available.lock().unwrap() = Some(update);
+        return Err("A backend restart is in progress; try again in a moment.".into());
+    }
+    if let Some(watchdog) = backend.watchdog.lock().unwrap().take() {
+        watchdog.abort();
+    }
+    shell_log::log(
+        Level::Info,
+        "updater",
+        "stopping the backend before installing",
+    );
+    terminate_backend(&app).await;
+
+    let marker = paths::data_dir(&app).map(|dir| dir.join(MARKER));
+    if let Ok(path) = &marker {
+        let installed = InstalledUpdate {
+            from: update.current_version.clone(),
+            to: update.version.clone(),
+        };
+        let json = serde_json::to_vec(&installed).unwrap();
+        let written = DataDirAccess::unleased().with(&[path], || std::fs::write(path, &json));
+        if let Err(e) = written {
+            shell_log::log(
+                Level::Warn,
+                "updater",
+                format!("could not write {MARKER}: {e}"),
+            );
+        }
+    }
+
+    match update.install(bytes) {
+        Ok(()) => {
+            shell_log::log(
+                Level::Info,
+                "updater",
+                format!("installed {}, restarting", update.version),
+            );
+            app.restart()
+        }
+        Err(e) => {
+            shell_log::log(
+                Level::Error,
+                "updater",
+                format!("installing {} failed: {e}", update.version),
+            );
+            if let Ok(path) = &marker {
+                let _ = DataDirAccess::unleased().with(&[path], || std::fs::remove_file(path));
+            }
+            backend.restarting.store(false, Ordering::SeqCst);
+            if let Err(restart) = backend_status::restart_backend(app.clone()).await {
+                shell_log::log(
+                    Level::Error,
+                    "updater",
+                    format!("backend not restarted: {restart}"),
+                );
+            }
+            Err(format!("Could not install the update: {e}"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marker_round_trips_and_rejects_garbage() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(MARKER);
+        let update = InstalledUpdate {
+            from: "1.0.0".into(),
+            to: "1.1.0".into(),
+        };
+        std::fs::write(&path, serde_json::to_vec(&update).unwrap()).unwrap();
+        assert_eq!(read_marker(&path), Some(update));
+        std::fs::write(&path, "not json").unwrap();
+        assert_eq!(read_marker(&path), None);
+    }
+}
@@ -0,0 +1,183 @@
+//! Per-user scoping on shared machines (terminal servers, Citrix), where
+//! several accounts run ALMReady at once.
+//!
+//! Machine-global resources and how each stays per user:
+//!
+//! * single-instance lock – the plugin's named mutex has no `Global\`
+//!   prefix, so Windows puts it in the session's own namespace; Linux uses
+//!   the session D-Bus, macOS a socket in the per-user `$TMPDIR`;
+//! * Job Objects – unnamed;
+//! * data, cache and log directories, including `backend.pid` and the port
+//!   file – per-user OS directories;
+//! * leftover sidecars – `process_tree::kill_if_running` also requires the
+//!   process to belong to the current user (`process_owner`), so a reused
+//!   pid running another user's backend from the shared install is never
+//!   killed;
+//! * loopback ports – shared by every session.  A port is only taken from
+//!   our own child (stdout, or the port file deleted before the spawn), and
+//!   it only counts as healthy with our per-spawn session token, which
+//!   another user's backend rejects – as ours rejects theirs;
+//! * shell listeners – there are none: the proxy check binds an ephemeral
+//!   127.0.0.1 port for a single request;
+//! * tray icon – identified within the process only, never by a
+//!   machine-wide GUID.
+//!
+//! `multi_user_session` feeds the diagnostics page, so support knows when
+//! these apply.  docs/MULTI_USER_TEST_PLAN.md covers what can't be tested
+//! automatically.
+
+/// The current user: the effective uid on Unix, the SID string on Windows.
+#[cfg(unix)]
+pub fn current_user() -> Option<String> {
+    // SAFETY: plain syscall without arguments.
+    Some(unsafe { libc::geteuid() }.to_string())
+}
+
+#[cfg(target_os = "linux")]
+pub fn process_owner(pid: u32) -> Option<String> {
+    use std::os::unix::fs::MetadataExt;
+
+    std::fs::metadata(format!("/proc/{pid}"))
+        .ok()
+        .map(|meta| meta.uid().to_string())
+}
+
+#[cfg(target_os = "macos")]
+pub fn process_owner(pid: u32) -> Option<String> {
+    // SAFETY: a plain C struct, for which all zeroes is a valid value.
+    let mut info: libc::proc_bsdinfo = unsafe { std::mem::zeroed() };
+    let size = std::mem::size_of::<libc::proc_bsdinfo>() as libc::c_int;
+    // SAFETY: `info` is writable for `size` bytes.
+    let written = unsafe {
+        libc::proc_pidinfo(
+            pid as libc::c_int,
+            libc::PROC_PIDTBSDINFO,
+            0,
+            (&mut info as *mut libc::proc_bsdinfo).cast(),
+            size,
+        )
+    };
+    (written == size).then(|| info.pbi_uid.to_string())
+}
+
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+pub fn process_owner(_pid: u32) -> Option<String> {
+    None
+}
+
+#[cfg(windows)]
+mod sid {
+    use windows::{
+        core::PWSTR,
+        Win32::{
+            Foundation::{CloseHandle, LocalFree, HANDLE, HLOCAL},
+            Security::{
+                Authorization::ConvertSidToStringSidW, GetTokenInformation, TokenUser, TOKEN_QUERY,
+                TOKEN_USER,
+            },
+            System::Threading::{
+                GetCurrentProcess, OpenProcess, OpenProcessToken, PROCESS_QUERY_LIMITED_INFORMATION,
+            },
+        },
+    };
+
+    /// SID string of the user `process` runs as.
+    ///
+    /// SAFETY: `process` must be a valid handle with query access.
+    unsafe fn token_user(process: HANDLE) -> Option<String> {
+        let mut token = HANDLE::default();
+        OpenProcessToken(process, TOKEN_QUERY, &mut token).ok()?;
+        let mut len = 0u32;
+        let _ = GetTokenInformation(token, TokenUser, None, 0, &mut len);
+        // u64 elements keep TOKEN_USER's pointer aligned.
+        let mut buf = vec![0u64; (len as usize).div_ceil(8)];
+        let read = GetTokenInformation(
+            token,
+            TokenUser,
+            Some(buf.as_mut_ptr().cast()),
+            len,
+            &mut len,
+        );
+        let _ = CloseHandle(token);
+        read.ok()?;
+        let user = &*(buf.as_ptr() as *const TOKEN_USER);
+        let mut sid = PWSTR::null();
+        ConvertSidToStringSidW(user.User.Sid, &mut sid).ok()?;
+        let text = sid.to_string().ok();
+        let _ = LocalFree(Some(HLOCAL(sid.0.cast())));
+        text
+    }
+
+    pub fn current_user() -> Option<String> {
+        // SAFETY: the pseudo-handle of our own process needs no closing.
+        unsafe { token_user(GetCurrentProcess()) }
+    }
+
+    pub fn process_owner(pid: u32) -> Option<String> {
+        // SAFETY: the handle is closed before returning.
+        unsafe {
+            let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false.into(), pid).ok()?;
+            let owner = token_user(process);
+            let _ = CloseHandle(process);
+            owner
+        }
+    }
+}
+
+#[cfg(windows)]
+pub use sid::{current_user, process_owner};
+
+// ── Multi-user sessions ─────────────────────────────────────────────────────
+
+/// Number of different accounts in `who` output.
+#[cfg(any(unix, test))]
+fn distinct_users(who: &str) -> usize {
+    let mut users: Vec<&str> = who
+        .lines()
+        .filter_map(|l| l.split_whitespace().next())
+        .collect();
+    users.sort_unstable();
+    users.dedup();
+    users.len()
+}
+
+/// Why this looks like a shared machine – a remote desktop session on
+/// Windows, several logged-in accounts on Unix – or `None`.
+#[cfg(windows)]
+pub fn multi_user_session() -> Option<String> {
+    use windows::Win32::UI::WindowsAndMessaging::{GetSystemMetrics, SM_REMOTESESSION};
+
+    let session = std::env::var("SESSIONNAME").unwrap_or_default();
+    // SAFETY: plain query.
+    let remote = unsafe { GetSystemMetrics(SM_REMOTESESSION) } != 0;
+    // Citrix sessions are named ICA-…, RDP ones RDP-Tcp#….
+    if remote || session.starts_with("ICA") || session.starts_with("RDP") {
+        return Some(format!("remote session {session}").trim().to_string());
+    }
+    None
+}
+
+#[cfg(unix)]
+pub fn multi_user_session() -> Option<String> {
+    let out = std::process::Command::new("who").output().ok()?;
+    let users = distinct_users(&String::from_utf8_lossy(&out.stdout));
+    (users > 1).then(|| format!("{users} accounts logged in"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn we_own_our_own_process_and_count_accounts_once() {
+        let me = current_user();
+        assert!(me.is_some());
+        assert_eq!(process_owner(std::process::id()), me);
+
+        let who = "alice  pts/0  2026-10-16 09:00\n\
+                   bob    pts/1  2026-10-16 09:05\n\
+                   alice  pts/2  2026-10-16 09:10\n";
+        assert_eq!(distinct_users(who), 2);
+        assert_eq!(distinct_users(""), 0);
+    }
+}
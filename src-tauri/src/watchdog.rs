@@ -0,0 +1,269 @@
+//! Backend watchdog.
+//!
+//! Once the main window is open, the sidecar is checked every
+//! `POLL_INTERVAL`, and at once if the process exits: has it exited, and
+//! does `/api/health` still answer?  A failed probe is re-checked a few
+//! times before the backend is declared down, so a single slow response is
+//! only journaled as a flap.
+//! Every probe, with its response time, goes into `SidecarHealthHistory`.
+//!
+//! When the backend is down the webview gets `backend-down` (for a banner),
+//! the old process tree is stopped and one respawn is attempted.  The new
+//! instance has a new session token and maybe a new port, so the webview
+//! gets `backend-port-changed` with both.  If it doesn't come up at all, or
+//! crashes again later, a native dialog reports the crashed engine's exit
+//! code and the watchdog stops: an engine that keeps crashing is not
+//! respawned over and over.  A restart the user asks for starts a new
+//! watchdog, with its respawn available again.
+//!
+//! Every poll also brings the tray icon up to date (see `tray`).
+//!
+//! The task is cancelled by `BackendProcess::shutdown` and `restart_backend`
+//! before they stop the child, so it never races the close/quit path or a
+//! requested restart.
+
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+
+use crate::{
+    alerts::{self, Alert},
+    events::{self, Audience, Capability},
+    failure::{self, FailureCategory},
+    health_history::SidecarHealthHistory,
+    http,
+    journal::{self, ActivityKind},
+    keep_awake,
+    last_failure::{self, FailureKind},
+    launch_sidecar, lifecycle,
+    shell_log::{self, Level},
+    terminate_backend, tray, BackendProcess, BackendStatus, Launch,
+};
+
+/// Time between checks while the backend is healthy.
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Extra probes after a failed one before the backend counts as down.
+const RECHECK_ATTEMPTS: u32 = 3;
+const RECHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Automatic respawns per watchdog.
+const MAX_RESPAWNS: u32 = 1;
+
+/// The automatic respawns a watchdog has left.
+#[derive(Debug)]
+struct Respawns(u32);
+
+impl Default for Respawns {
+    fn default() -> Self {
+        Respawns(MAX_RESPAWNS)
+    }
+}
+
+impl Respawns {
+    /// Use up a respawn for the crash just seen; false when none is left.
+    fn take(&mut self) -> bool {
+        let left = self.0 > 0;
+        self.0 = self.0.saturating_sub(1);
+        left
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct BackendDown {
+    reason: String,
+    exit_code: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct PortChanged {
+    port: u16,
+    token: Option<String>,
+}
+
+/// Tell every window the backend was replaced, so API bases and the token
+/// sent with requests can be updated.
+pub fn notify_port_changed(app: &AppHandle, port: u16) {
+    let token = app.state::<BackendProcess>().token();
+    events::emit(
+        app,
+        Audience::AllWindows,
+        "backend-port-changed",
+        PortChanged { port, token },
+    );
+}
+
+/// Start watching the sidecar that is currently stored in `BackendProcess`.
+pub fn spawn(app: &AppHandle) {
+    let handle = tauri::async_runtime::spawn(watch(app.clone()));
+    *app.state::<BackendProcess>().watchdog.lock().unwrap() = Some(handle);
+}
+
+/// Why the backend should be considered down, if it should.
+async fn check(app: &AppHandle, port: u16) -> Option<&'static str> {
+    if app.state::<BackendProcess>().has_exited() {
+        return Some("The engine process exited unexpectedly.");
+    }
+    if probe(app, port).await {
+        return None;
+    }
+    for _ in 0..RECHECK_ATTEMPTS {
+        tokio::time::sleep(RECHECK_INTERVAL).await;
+        if probe(app, port).await {
+            journal::record(
+                app,
+                ActivityKind::HealthFlap,
+                "A health check failed but the engine recovered.",
+            );
+            return None;
+        }
+    }
+    Some("The engine stopped responding to health checks.")
+}
+
+/// One `/api/health` probe, recorded in the health history.
+async fn probe(app: &AppHandle, port: u16) -> bool {
+    let started = Instant::now();
+    let token = app.state::<BackendProcess>().token();
+    let ok = http::probe_health(port, token.as_deref()).await;
+    app.state::<SidecarHealthHistory>()
+        .record(port, ok, started.elapsed());
+    ok
+}
+
+async fn watch(app: AppHandle) {
+    let backend = app.state::<BackendProcess>();
+    let mut respawns = Respawns::default();
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            status = backend.exited() => {
+                shell_log::log(Level::Warn, "watchdog", format!("backend exited ({status})"));
+            }
+        }
+        tray::refresh(&app);
+        let Some(port) = backend.port.get() else {
+            return;
+        };
+        let Some(reason) = check(&app, port).await else {
+            continue;
+        };
+        if backend.is_shutting_down() {
+            return;
+        }
+
+        let exit_code = terminate_backend(&app).await;
+        backend.set_status(BackendStatus::Crashed);
+        tray::refresh(&app);
+        // The run it was keeping awake is gone.
+        keep_awake::release_all(&app, "engine crashed");
+        let respawn = respawns.take();
+        if respawn {
+            lifecycle::restarting(&app);
+        }
+        shell_log::log(
+            Level::Warn,
+            "watchdog",
+            format!(
+                "backend down ({reason}, exit code {exit_code:?}), {}",
+                if respawn {
+                    "respawning"
+                } else {
+                    "crashed again, not respawning"
+                }
+            ),
+        );
+        events::emit(
+            &app,
+            Audience::Capability(Capability::Lifecycle),
+            "backend-down",
+            BackendDown {
+                reason: reason.into(),
+                exit_code,
+            },
+        );
+        journal::record(&app, ActivityKind::EngineCrash, reason);
+        alerts::raise(&app, Alert::BackendCrashed);
+
+        if !respawn {
+            let message = format!(
+                "The ALMReady calculation engine stopped unexpectedly again after it \
+                 was restarted.\n\n{reason}"
+            );
+            give_up(&app, FailureCategory::Unknown, exit_code, message);
+            return;
+        }
+        let (category, failure) = match launch_sidecar(&app, false).await {
+            Launch::Ready(new_port) => {
+                shell_log::log(
+                    Level::Info,
+                    "watchdog",
+                    format!("backend respawned on port {new_port}"),
+                );
+                journal::record(
+                    &app,
+                    ActivityKind::EngineRestart,
+                    "The engine was restarted automatically.",
+                );
+                notify_port_changed(&app, new_port);
+                continue;
+            }
+            Launch::Cancelled => return,
+            Launch::SpawnFailed(e) => (failure::category(&e, None), e),
+            Launch::Failed { error, exit_code } => (failure::category(&error, exit_code), error),
+        };
+
+        shell_log::log(
+            Level::Error,
+            "watchdog",
+            format!("backend respawn failed ({category:?}): {failure}"),
+        );
+        let message = format!(
+            "The ALMReady calculation engine stopped unexpectedly and could not be \
+             restarted.\n\n{reason}"
+        );
+        give_up(&app, category, exit_code, message);
+        return;
+    }
+}
+
+/// Record the crash and show it, with the engine's exit code, in a native
+/// dialog; the watchdog stops after this.
+fn give_up(
+    app: &AppHandle,
+    category: FailureCategory,
+    exit_code: Option<i32>,
+    mut message: String,
+) {
+    if let Some(code) = exit_code {
+        message.push_str(&format!("\n\nThe engine process exited with code {code}."));
+    }
+    tauri::async_runtime::spawn(last_failure::record(
+        app.clone(),
+        FailureKind::EngineCrashed,
+        category,
+        exit_code,
+        message.clone(),
+        false,
+    ));
+    app.dialog()
+        .message(message)
+        .title("ALMReady")
+        .kind(MessageDialogKind::Error)
+        .show(|_| {});
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_first_crash_is_respawned() {
+        let mut respawns = Respawns::default();
+        assert!(respawns.take(), "first crash");
+        assert!(!respawns.take(), "second crash");
+        assert!(!respawns.take());
+    }
+}
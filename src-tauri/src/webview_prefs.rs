@@ -0,0 +1,301 @@
+//! Webview spellcheck, autofill and spellcheck-language preferences.
+//!
+//! The prefs are persisted in `Settings::webview` and turned into
+//! `WebviewOptions`, which `WindowFactory` (and the splash) apply to every
+//! window they build:
+//!
+//! * an initialization script that sets `spellcheck` (and `lang`, when a
+//!   spellcheck language is chosen) on the document and, with autofill off,
+//!   `autocomplete="off"` on every form field, including ones React adds
+//!   later.  The same script is re-run in open windows when the prefs change.
+//! * on Windows, WebView2 browser arguments: `--lang` picks the spellcheck
+//!   dictionary.  All webviews share one WebView2 browser process whose
+//!   arguments are fixed when it starts, so the arguments are computed once
+//!   per run (`BrowserArgs`) and a language change needs an app restart.
+//!
+//! WKWebView and WebKitGTK spellcheck in the system's languages and don't
+//! offer to save passwords, so they need nothing beyond the script.
+//!
+//! Manual check, on each platform:
+//! 1. Spellcheck on: a misspelt word in a note field is underlined.
+//! 2. Spellcheck off: no underline, in the main window and a pop-out.
+//! 3. Autofill off: focusing a login-like field offers no saved entries.
+//! 4. Windows: set a language, restart, and check the dictionary changed.
+
+use serde::{Deserialize, Deserializer, Serialize};
+use tauri::{AppHandle, Manager, State, WebviewWindowBuilder, Wry};
+
+use crate::settings::SettingsStore;
+
+/// WebView2 arguments Tauri passes when none are given.  Supplying our own
+/// replaces them, so they are repeated here.
+const WEBVIEW2_DEFAULT_ARGS: &str =
+    "--disable-features=msWebOOUI,msPdfOOUI,msSmartScreenProtection";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebviewPrefs {
+    pub spellcheck: bool,
+    /// Browser autofill and password-save prompts.
+    pub autofill: bool,
+    /// BCP 47 tag such as `en-GB`; `None` follows the app locale.
+    pub spellcheck_language: Option<String>,
+}
+
+impl Default for WebviewPrefs {
+    fn default() -> Self {
+        WebviewPrefs {
+            spellcheck: true,
+            autofill: false,
+            spellcheck_language: None,
+        }
+    }
+}
+
+/// Fields to change; absent fields are kept.  `spellcheck_language: null`
+/// goes back to following the app locale.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct WebviewPrefsPatch {
+    pub spellcheck: Option<bool>,
+    pub autofill: Option<bool>,
+    #[serde(default, deserialize_with = "present")]
+    pub spellcheck_language: Option<Option<String>>,
+}
+
+/// Distinguishes an explicit `null` from a missing field.
+fn present<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Option<String>>, D::Error> {
+    Option::<String>::deserialize(d).map(Some)
+}
+
+impl WebviewPrefs {
+    fn patched(&self, patch: WebviewPrefsPatch) -> Result<Self, String> {
+        let mut next = self.clone();
+        if let Some(spellcheck) = patch.spellcheck {
+            next.spellcheck = spellcheck;
+        }
+        if let Some(autofill) = patch.autofill {
+            next.autofill = autofill;
+        }
+        if let Some(language) = patch.spellcheck_language {
+            if let Some(tag) = &language {
+                if !is_language_tag(tag) {
+                    return Err(format!("not a language tag: {tag:?}"));
+                }
+            }
+            next.spellcheck_language = language;
+        }
+        Ok(next)
+    }
+}
+
+/// Loose BCP 47 check; the value ends up in a browser argument.
+fn is_language_tag(tag: &str) -> bool {
+    (2..=35).contains(&tag.len())
+        && tag.starts_with(|c: char| c.is_ascii_alphabetic())
+        && tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+// ── Builder options ─────────────────────────────────────────────────────────
+
+/// What the prefs translate to for one webview.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebviewOptions {
+    /// WebView2 only; `None` keeps Tauri's defaults.
+    pub browser_args: Option<String>,
+    pub script: String,
+}
+
+impl WebviewOptions {
+    pub fn for_prefs(prefs: &WebviewPrefs, webview2: bool) -> Self {
+        let browser_args = match (&prefs.spellcheck_language, webview2) {
+            (Some(tag), true) => Some(format!("{WEBVIEW2_DEFAULT_ARGS} --lang={tag}")),
+            _ => None,
+        };
+        let prefs = serde_json::to_string(prefs).unwrap_or_else(|_| "{}".into());
+        WebviewOptions {
+            browser_args,
+            script: PREFS_SCRIPT.replace("__PREFS__", &prefs),
+        }
+    }
+
+    /// Options for a window built now: the current prefs, but the browser
+    /// arguments this run started with.
+    pub fn current(app: &AppHandle) -> Self {
+        let prefs = app.state::<SettingsStore>().get().webview;
+        WebviewOptions {
+            browser_args: app.state::<BrowserArgs>().0.clone(),
+            ..Self::for_prefs(&prefs, cfg!(windows))
+        }
+    }
+
+    pub fn apply<'a>(
+        &self,
+        builder: WebviewWindowBuilder<'a, Wry, AppHandle>,
+    ) -> WebviewWindowBuilder<'a, Wry, AppHandle> {
+        let builder = builder.initialization_script(&self.script);
+        match &self.browser_args {
+            Some(args) => builder.additional_browser_args(args),
+            None => builder,
+        }
+    }
+}
+
+/// Managed state: the WebView2 arguments fixed for this run.
+pub struct BrowserArgs(Option<String>);
+
+impl BrowserArgs {
+    pub fn new(prefs: &WebviewPrefs) -> Self {
+        BrowserArgs(WebviewOptions::for_prefs(prefs, cfg!(windows)).browser_args)
+    }
+}
+
+/// Idempotent: the first run installs a MutationObserver, later runs (from
+/// `set_webview_prefs`) only update the prefs and re-apply them.
+const PREFS_SCRIPT: &str = r#"(() => {
+  window.__ALMREADY_WEBVIEW_PREFS__ = __PREFS__;
+  if (window.__almreadyApplyWebviewPrefs) {
+    window.__almreadyApplyWebviewPrefs(document);
+    return;
+  }
+  const FIELDS = "form, input, textarea, select";
+  const applyFields = (root) => {
+    const prefs = window.__ALMREADY_WEBVIEW_PREFS__;
+    if (!root.querySelectorAll) return;
+    const fields = [...root.querySelectorAll(FIELDS)];
+    if (root.matches && root.matches(FIELDS)) fields.push(root);
+    for (const el of fields) {
+      const saved = "almreadyAutocomplete" in el.dataset;
+      if (!prefs.autofill) {
+        if (!saved) el.dataset.almreadyAutocomplete = el.getAttribute("autocomplete") ?? "";
+        el.setAttribute("autocomplete", "off");
+      } else if (saved) {
+        const original = el.dataset.almreadyAutocomplete;
+        if (original) el.setAttribute("autocomplete", original);
+        else el.removeAttribute("autocomplete");
+        delete el.dataset.almreadyAutocomplete;
+      }
+    }
+  };
+  const apply = (root) => {
+    const prefs = window.__ALMREADY_WEBVIEW_PREFS__;
+    const html = document.documentElement;
+    if (html) {
+      html.spellcheck = prefs.spellcheck;
+      if (prefs.spellcheck_language) html.lang = prefs.spellcheck_language;
+    }
+    applyFields(root);
+  };
+  window.__almreadyApplyWebviewPrefs = apply;
+  new MutationObserver((records) => {
+    for (const record of records) {
+      for (const node of record.addedNodes) {
+        if (node.nodeType === Node.ELEMENT_NODE) applyFields(node);
+      }
+    }
+  }).observe(document, { childList: true, subtree: true });
+  document.addEventListener("DOMContentLoaded", () => apply(document));
+  apply(document);
+})();"#;
+
+// ── Commands ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WebviewPrefsUpdate {
+    pub prefs: WebviewPrefs,
+    /// Some changes only reach windows after an app restart (the WebView2
+    /// spellcheck language).  Everything else is already applied.
+    pub requires_restart: bool,
+}
+
+#[tauri::command]
+pub fn get_webview_prefs(store: State<'_, SettingsStore>) -> WebviewPrefs {
+    store.get().webview
+}
+
+/// Persist a prefs change and apply it to every open window.
+#[tauri::command]
+pub fn set_webview_prefs(
+    app: AppHandle,
+    store: State<'_, SettingsStore>,
+    patch: WebviewPrefsPatch,
+) -> Result<WebviewPrefsUpdate, String> {
+    let mut settings = store.get();
+    let prefs = settings.webview.patched(patch)?;
+    settings.webview = prefs.clone();
+    store.replace(settings)?;
+
+    let options = WebviewOptions::for_prefs(&prefs, cfg!(windows));
+    for window in app.webview_windows().values() {
+        if window.label() != crate::splash::LABEL {
+            let _ = window.eval(&options.script);
+        }
+    }
+
+    Ok(WebviewPrefsUpdate {
+        requires_restart: options.browser_args != app.state::<BrowserArgs>().0,
+        prefs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prefs(spellcheck: bool, autofill: bool, language: Option<&str>) -> WebviewPrefs {
+        WebviewPrefs {
+            spellcheck,
+            autofill,
+            spellcheck_language: language.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn builder_options_for_each_combination() {
+        for spellcheck in [false, true] {
+            for autofill in [false, true] {
+                for language in [None, Some("de-DE")] {
+                    let prefs = prefs(spellcheck, autofill, language);
+                    let json = serde_json::to_string(&prefs).unwrap();
+                    for webview2 in [false, true] {
+                        let options = WebviewOptions::for_prefs(&prefs, webview2);
+                        assert!(options.script.contains(&json));
+                        let expected_args = match (language, webview2) {
+                            (Some(tag), true) => {
+                                Some(format!("{WEBVIEW2_DEFAULT_ARGS} --lang={tag}"))
+                            }
+                            _ => None,
+                        };
+                        assert_eq!(options.browser_args, expected_args);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn patch_keeps_absent_fields_and_clears_on_null() {
+        let current = prefs(true, false, Some("en-GB"));
+
+        let patch: WebviewPrefsPatch = serde_json::from_str(r#"{"autofill":true}"#).unwrap();
+        assert_eq!(
+            current.patched(patch).unwrap(),
+            prefs(true, true, Some("en-GB"))
+        );
+
+        let patch: WebviewPrefsPatch =
+            serde_json::from_str(r#"{"spellcheck_language":null}"#).unwrap();
+        assert_eq!(current.patched(patch).unwrap(), prefs(true, false, None));
+    }
+
+    #[test]
+    fn rejects_values_that_are_not_language_tags() {
+        let current = WebviewPrefs::default();
+        for tag in ["x", "en GB", "--enable-automation", "de\"DE"] {
+            let patch = WebviewPrefsPatch {
+                spellcheck_language: Some(Some(tag.into())),
+                ..Default::default()
+            };
+            assert!(current.patched(patch).is_err(), "{tag}");
+        }
+    }
+}
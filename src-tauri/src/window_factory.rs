@@ -0,0 +1,575 @@
+//! WindowFactory – the single place app webview windows are configured.
+//!
+//! Every window the shell opens gets the same initialization scripts
+//! (`window.__ALMREADY__` with the backend port, data directory, app
+//! version, platform and dev mode; the session token and the frontend
+//! config object; then the local time zone) and the size constraints /
+//! zoom of the active `WindowPreset`.  Keeping this in one place means a
+//! preset switch can be re-applied to live windows with the same rules used
+//! to build them.  Each window is also registered with the event capabilities
+//! it was built with, which decide what `events::emit` delivers to it, and
+//! gets the spellcheck/autofill options from `webview_prefs`.
+//!
+//! A window is described by a `WindowSpec`, turned into a `WindowConfig`
+//! (everything the window will receive: scripts, sizes, zoom, capabilities,
+//! feature flags) and built from that config alone.  The config is kept per
+//! window, so `dump_window_config` shows exactly what the shell injected –
+//! for "works in the browser but not in Tauri" bugs.  Options the shell
+//! doesn't set (user agent, data directory, theme) stay at the webview's
+//! defaults and are not listed.  The kept config has the session token
+//! redacted, so a dump can be pasted into a bug report.
+
+use std::{collections::BTreeMap, sync::Mutex};
+
+use serde::Serialize;
+use tauri::{
+    AppHandle, LogicalSize, Manager, State, WebviewUrl, WebviewWindow, WebviewWindowBuilder,
+};
+
+use crate::{
+    degradations::{Degradation, Degradations},
+    events::{self, Audience, Capability, WindowCapabilities},
+    license::LicenseStatus,
+    paths, session_token,
+    settings::SettingsStore,
+    shell_log::{self, Level},
+    time_zone::TimeZoneInfo,
+    webview_prefs::WebviewOptions,
+    BackendProcess,
+};
+
+pub const MAIN_LABEL: &str = "main";
+
+// ── Presets ─────────────────────────────────────────────────────────────────
+
+/// Size constraints and zoom for a window mode.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowPreset {
+    pub min_width: f64,
+    pub min_height: f64,
+    /// Multiplied into the webview zoom.  OS text scaling is applied by the
+    /// webview on top of this, so the two compose without special handling.
+    pub zoom: f64,
+}
+
+impl WindowPreset {
+    pub const STANDARD: WindowPreset = WindowPreset {
+        min_width: 1024.0,
+        min_height: 768.0,
+        zoom: 1.0,
+    };
+
+    /// For 13-inch laptops at 125 % scaling with the taskbar visible.
+    pub const COMPACT: WindowPreset = WindowPreset {
+        min_width: 900.0,
+        min_height: 600.0,
+        zoom: 0.9,
+    };
+
+    pub fn for_mode(compact: bool) -> Self {
+        if compact {
+            Self::COMPACT
+        } else {
+            Self::STANDARD
+        }
+    }
+}
+
+/// Values exposed to React as `window.__ALMREADY_CONFIG__`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FrontendConfig {
+    pub compact: bool,
+    /// Active degradations when the window was built; `degradations-changed`
+    /// carries later changes.
+    pub degradations: Vec<Degradation>,
+}
+
+/// Values exposed to React as `window.__ALMREADY__`, next to the backend
+/// port.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShellInfo {
+    /// Where sessions and logs are stored, shown in Settings.
+    pub data_dir: String,
+    /// Sent to the backend for compatibility checks.
+    pub app_version: String,
+    pub platform: &'static str,
+    /// Running from `cargo tauri dev`.
+    pub dev_mode: bool,
+}
+
+impl ShellInfo {
+    pub fn for_app(app: &AppHandle) -> Self {
+        ShellInfo {
+            data_dir: paths::data_dir(app)
+                .map(|dir| dir.display().to_string())
+                .unwrap_or_default(),
+            app_version: app.package_info().version.to_string(),
+            platform: std::env::consts::OS,
+            dev_mode: tauri::is_dev(),
+        }
+    }
+}
+
+/// `window.__ALMREADY__`.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ShellGlobals<'a> {
+    backend_port: u16,
+    #[serde(flatten)]
+    shell: &'a ShellInfo,
+}
+
+// ── Window config ───────────────────────────────────────────────────────────
+
+/// What a caller asks for.
+#[derive(Debug, Clone)]
+pub struct WindowSpec<'a> {
+    pub label: &'a str,
+    /// Page within the app bundle, e.g. `index.html`.
+    pub page: &'a str,
+    pub title: &'a str,
+    pub size: (f64, f64),
+    /// Overrides the preset's minimum size.
+    pub min_size: Option<(f64, f64)>,
+    pub center: bool,
+    pub preset: WindowPreset,
+    pub capabilities: &'a [Capability],
+}
+
+/// Everything a window receives, materialized before it is built.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WindowConfig {
+    pub label: String,
+    pub page: String,
+    pub title: String,
+    pub size: (f64, f64),
+    pub min_size: (f64, f64),
+    pub center: bool,
+    /// Logical outer position; set instead of `center` when restoring.
+    pub position: Option<(f64, f64)>,
+    pub maximized: bool,
+    /// The preset's zoom.
+    pub zoom: f64,
+    /// The user's zoom for this window (see `zoom`), multiplied into the
+    /// preset's.
+    pub zoom_factor: f64,
+    /// Initialization scripts in injection order.
+    pub init_scripts: Vec<String>,
+    /// WebView2 browser arguments, when not Tauri's defaults.
+    pub browser_args: Option<String>,
+    pub capabilities: Vec<Capability>,
+    /// Whether `Audience::MainOnly` events reach it.
+    pub main_only_events: bool,
+    /// Feature flags: `window.__ALMREADY_CONFIG__` and the license tier.
+    pub frontend_config: FrontendConfig,
+    pub license_tier: Option<String>,
+    pub backend_port: u16,
+    pub backend_token: Option<String>,
+}
+
+impl WindowConfig {
+    pub fn new(
+        spec: &WindowSpec,
+        port: u16,
+        token: Option<&str>,
+        shell: &ShellInfo,
+        frontend_config: FrontendConfig,
+        license_tier: Option<&str>,
+        webview: WebviewOptions,
+    ) -> Self {
+        let preset = spec.preset;
+        WindowConfig {
+            label: spec.label.to_string(),
+            page: spec.page.to_string(),
+            title: spec.title.to_string(),
+            size: spec.size,
+            min_size: spec
+                .min_size
+                .unwrap_or((preset.min_width, preset.min_height)),
+            center: spec.center,
+            position: None,
+            maximized: false,
+            zoom: preset.zoom,
+            zoom_factor: 1.0,
+            init_scripts: vec![
+                WindowFactory::init_script(port, token, shell, &frontend_config, license_tier),
+                webview.script,
+            ],
+            browser_args: webview.browser_args,
+            capabilities: spec.capabilities.to_vec(),
+            main_only_events: spec.label == MAIN_LABEL,
+            frontend_config,
+            license_tier: license_tier.map(str::to_string),
+            backend_port: port,
+            backend_token: token.map(str::to_string),
+        }
+    }
+
+    /// This config with the session token replaced by a placeholder
+    /// everywhere it appears.
+    pub fn redacted(&self) -> Self {
+        let mut config = self.clone();
+        if let Some(token) = config.backend_token.take() {
+            for script in &mut config.init_scripts {
+                *script = session_token::redact(script, &token);
+            }
+            config.backend_token = Some(session_token::REDACTED.to_string());
+        }
+        config
+    }
+
+    /// The config for `spec` with the app's current settings, license,
+    /// session token and local time zone.
+    pub fn for_app(app: &AppHandle, spec: &WindowSpec, port: u16) -> Self {
+        let compact = app.state::<SettingsStore>().get().compact_mode;
+        let license = app.state::<LicenseStatus>();
+        let token = app.state::<BackendProcess>().token();
+        let mut config = Self::new(
+            spec,
+            port,
+            token.as_deref(),
+            &ShellInfo::for_app(app),
+            FrontendConfig {
+                compact,
+                degradations: app.state::<Degradations>().report(),
+            },
+            license.tier(),
+            WebviewOptions::current(app),
+        );
+        config
+            .init_scripts
+            .push(TimeZoneInfo::current().init_script());
+        config
+    }
+}
+
+/// Managed state: the config every open factory-built window was built
+/// with, by label.
+#[derive(Default)]
+pub struct WindowConfigs(Mutex<BTreeMap<String, WindowConfig>>);
+
+impl WindowConfigs {
+    pub fn remove(&self, label: &str) {
+        self.0.lock().unwrap().remove(label);
+    }
+
+    /// Minimum size of the factory-built window `label`, logical pixels.
+    pub fn min_size(&self, label: &str) -> Option<(f64, f64)> {
+        Some(self.0.lock().unwrap().get(label)?.min_size)
+    }
+
+    /// The user's zoom factor of the factory-built window `label`.
+    pub fn zoom_factor(&self, label: &str) -> Option<f64> {
+        Some(self.0.lock().unwrap().get(label)?.zoom_factor)
+    }
+
+    /// Record `factor` for `label`; returns the preset zoom it multiplies.
+    pub fn set_zoom_factor(&self, label: &str, factor: f64) -> Option<f64> {
+        let mut configs = self.0.lock().unwrap();
+        let config = configs.get_mut(label)?;
+        config.zoom_factor = factor;
+        Some(config.zoom)
+    }
+
+    fn set_preset(&self, label: &str, preset: WindowPreset) {
+        if let Some(config) = self.0.lock().unwrap().get_mut(label) {
+            config.min_size = (preset.min_width, preset.min_height);
+            config.zoom = preset.zoom;
+        }
+    }
+}
+
+// ── Factory ─────────────────────────────────────────────────────────────────
+
+pub struct WindowFactory;
+
+impl WindowFactory {
+    /// initialization_script runs BEFORE any page scripts (React, Vite
+    /// bundle), so these globals are synchronously available when api.ts
+    /// evaluates its module-level API_BASE constant.
+    ///
+    /// Values are serialized with serde_json, never spliced in as text, so
+    /// a data directory with quotes, backslashes or any other character
+    /// can't break the script.
+    pub fn init_script(
+        port: u16,
+        token: Option<&str>,
+        shell: &ShellInfo,
+        config: &FrontendConfig,
+        license_tier: Option<&str>,
+    ) -> String {
+        let globals = ShellGlobals {
+            backend_port: port,
+            shell,
+        };
+        let globals = serde_json::to_string(&globals).unwrap_or_else(|_| "{}".into());
+        let config = serde_json::to_string(config).unwrap_or_else(|_| "{}".into());
+        // `__BACKEND_PORT__` is kept for one more release; new code reads
+        // `__ALMREADY__.backendPort`.
+        let mut script = format!(
+            "window.__ALMREADY__ = {globals};\n\
+             window.__BACKEND_PORT__ = {port};\n\
+             window.__ALMREADY_CONFIG__ = {config};"
+        );
+        if let Some(token) = token {
+            let token = serde_json::to_string(token).unwrap_or_else(|_| "null".into());
+            script.push_str(&format!("\nwindow.__BACKEND_TOKEN__ = {token};"));
+        }
+        if let Some(tier) = license_tier {
+            let tier = serde_json::to_string(tier).unwrap_or_else(|_| "null".into());
+            script.push_str(&format!("\nwindow.__LICENSE_TIER__ = {tier};"));
+        }
+        script
+    }
+
+    /// Build a window from `config` and nothing else, and keep the config
+    /// (redacted).
+    /// The window is registered for the events its capabilities allow (see
+    /// `events`); both are dropped again when it is destroyed.
+    pub fn build(app: &AppHandle, config: WindowConfig) -> tauri::Result<WebviewWindow> {
+        shell_log::log(
+            Level::Info,
+            "window",
+            format!(
+                "opening window {} with {:?}",
+                config.label, config.capabilities
+            ),
+        );
+        app.state::<WindowCapabilities>()
+            .register(&config.label, &config.capabilities);
+
+        let mut builder = WebviewWindowBuilder::new(
+            app,
+            &config.label,
+            WebviewUrl::App(config.page.clone().into()),
+        )
+        .title(&config.title)
+        .inner_size(config.size.0, config.size.1)
+        .min_inner_size(config.min_size.0, config.min_size.1)
+        // Files are dropped onto the page's own upload zones; with the
+        // native handler on, Windows never delivers HTML5 drops to them.
+        .disable_drag_drop_handler();
+        for script in &config.init_scripts {
+            builder = builder.initialization_script(script);
+        }
+        if let Some(args) = &config.browser_args {
+            builder = builder.additional_browser_args(args);
+        }
+        if let Some((x, y)) = config.position {
+            builder = builder.position(x, y);
+        } else if config.center {
+            builder = builder.center();
+        }
+        if config.maximized {
+            builder = builder.maximized(true);
+        }
+
+        let window = builder.build()?;
+        let _ = window.set_zoom(config.zoom * config.zoom_factor);
+        app.state::<WindowConfigs>()
+            .0
+            .lock()
+            .unwrap()
+            .insert(config.label.clone(), config.redacted());
+        Ok(window)
+    }
+
+    /// Re-apply a preset to a live window: new minimum size, size clamped
+    /// upward if it is now below the minimum, and the preset zoom times the
+    /// window's own `zoom_factor`.
+    pub fn apply_preset(window: &WebviewWindow, preset: WindowPreset, zoom_factor: f64) {
+        let _ = window.set_min_size(Some(LogicalSize::new(preset.min_width, preset.min_height)));
+
+        if let (Ok(size), Ok(scale)) = (window.inner_size(), window.scale_factor()) {
+            let size = size.to_logical::<f64>(scale);
+            if size.width < preset.min_width || size.height < preset.min_height {
+                let _ = window.set_size(LogicalSize::new(
+                    size.width.max(preset.min_width),
+                    size.height.max(preset.min_height),
+                ));
+            }
+        }
+
+        let _ = window.set_zoom(preset.zoom * zoom_factor);
+    }
+}
+
+// ── Commands ────────────────────────────────────────────────────────────────
+
+/// Toggle compact mode: persist it, re-apply constraints to every open app
+/// window and tell the frontend to switch layouts.
+#[tauri::command]
+pub fn set_compact_mode(
+    app: AppHandle,
+    store: State<'_, SettingsStore>,
+    enabled: bool,
+) -> Result<(), String> {
+    let mut settings = store.get();
+    settings.compact_mode = enabled;
+    store.replace(settings)?;
+
+    let preset = WindowPreset::for_mode(enabled);
+    let configs = app.state::<WindowConfigs>();
+    for window in app.webview_windows().values() {
+        if ![crate::splash::LABEL, crate::log_viewer::LABEL].contains(&window.label()) {
+            let zoom_factor = configs.zoom_factor(window.label()).unwrap_or(1.0);
+            WindowFactory::apply_preset(window, preset, zoom_factor);
+            configs.set_preset(window.label(), preset);
+        }
+    }
+
+    events::emit(&app, Audience::AllWindows, "compact-mode-changed", enabled);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_compact_mode(store: State<'_, SettingsStore>) -> bool {
+    store.get().compact_mode
+}
+
+/// Label of the ALMReady window that has OS focus, if any does – for
+/// shortcut handlers that must act on the active window only.
+#[tauri::command]
+pub fn get_active_window_label(app: AppHandle) -> Option<String> {
+    app.webview_windows()
+        .into_iter()
+        .find(|(_, window)| window.is_focused().unwrap_or(false))
+        .map(|(label, _)| label)
+}
+
+/// The configuration the window `label` was built with: injected scripts,
+/// builder options, capabilities and feature flags.  For debugging; the
+/// session token is redacted.
+#[tauri::command]
+pub fn dump_window_config(
+    configs: State<'_, WindowConfigs>,
+    label: String,
+) -> Result<WindowConfig, String> {
+    configs
+        .0
+        .lock()
+        .unwrap()
+        .get(&label)
+        .cloned()
+        .ok_or_else(|| format!("no window {label:?} built by the shell"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::webview_prefs::WebviewPrefs;
+
+    fn spec(label: &'static str, min_size: Option<(f64, f64)>) -> WindowSpec<'static> {
+        WindowSpec {
+            label,
+            page: "index.html",
+            title: "ALMReady",
+            size: (1440.0, 900.0),
+            min_size,
+            center: true,
+            preset: WindowPreset::COMPACT,
+            capabilities: &[Capability::Lifecycle],
+        }
+    }
+
+    fn shell_info() -> ShellInfo {
+        ShellInfo {
+            data_dir: "/home/ana/.local/share/com.almready.desktop".into(),
+            app_version: "1.4.0".into(),
+            platform: "linux",
+            dev_mode: false,
+        }
+    }
+
+    #[test]
+    fn config_holds_exactly_what_the_window_is_built_with() {
+        let shell = shell_info();
+        let webview = WebviewOptions::for_prefs(&WebviewPrefs::default(), true);
+        let frontend = FrontendConfig {
+            compact: true,
+            degradations: Vec::new(),
+        };
+        let config = WindowConfig::new(
+            &spec(MAIN_LABEL, None),
+            8123,
+            Some("0123abcd"),
+            &shell,
+            frontend.clone(),
+            Some("pro"),
+            webview.clone(),
+        );
+
+        assert_eq!(
+            config.init_scripts,
+            [
+                WindowFactory::init_script(8123, Some("0123abcd"), &shell, &frontend, Some("pro")),
+                webview.script
+            ]
+        );
+        assert!(config.init_scripts[0].contains("window.__BACKEND_PORT__ = 8123;"));
+        assert_eq!(config.browser_args, webview.browser_args);
+        assert_eq!(config.min_size, (900.0, 600.0));
+        assert_eq!(config.zoom, 0.9);
+        assert!(config.main_only_events);
+        assert_eq!(config.license_tier.as_deref(), Some("pro"));
+        assert_eq!(config.capabilities, [Capability::Lifecycle]);
+
+        assert!(config.init_scripts[0].contains("window.__BACKEND_TOKEN__ = \"0123abcd\";"));
+        let dumped = serde_json::to_string(&config.redacted()).unwrap();
+        assert!(!dumped.contains("0123abcd"), "{dumped}");
+        assert!(dumped.contains("window.__BACKEND_TOKEN__ = \\\"<redacted>\\\";"));
+    }
+
+    #[test]
+    fn explicit_min_size_wins_and_pop_outs_miss_main_only_events() {
+        let webview = WebviewOptions::for_prefs(&WebviewPrefs::default(), false);
+        let config = WindowConfig::new(
+            &spec("logs", Some((480.0, 320.0))),
+            1,
+            None,
+            &shell_info(),
+            FrontendConfig {
+                compact: false,
+                degradations: Vec::new(),
+            },
+            None,
+            webview,
+        );
+        assert_eq!(config.min_size, (480.0, 320.0));
+        assert!(!config.main_only_events);
+        assert!(!config.init_scripts[0].contains("__LICENSE_TIER__"));
+        assert!(!config.init_scripts[0].contains("__BACKEND_TOKEN__"));
+        assert_eq!(config.redacted(), config);
+    }
+
+    #[test]
+    fn shell_globals_are_json_whatever_the_data_dir() {
+        let shell = ShellInfo {
+            data_dir: r#"C:\Users\Zoë "Q"\AppData\Roaming\ALMReady</script>"#.into(),
+            ..shell_info()
+        };
+        let frontend = FrontendConfig {
+            compact: false,
+            degradations: Vec::new(),
+        };
+        let script = WindowFactory::init_script(8123, None, &shell, &frontend, None);
+        let line = script.lines().next().unwrap();
+        let json = line
+            .strip_prefix("window.__ALMREADY__ = ")
+            .and_then(|rest| rest.strip_suffix(';'))
+            .unwrap();
+        let globals: serde_json::Value = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            globals,
+            serde_json::json!({
+                "backendPort": 8123,
+                "dataDir": shell.data_dir,
+                "appVersion": "1.4.0",
+                "platform": "linux",
+                "devMode": false,
+            })
+        );
+        assert!(script.contains("\nwindow.__BACKEND_PORT__ = 8123;\n"));
+    }
+}
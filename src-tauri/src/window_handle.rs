@@ -0,0 +1,48 @@
+//! The calling window's native handle, for enterprise integrations that
+//! overlay native UI (e.g. a hardware video preview) on the webview.
+//!
+//! Windows → the `HWND`
+//! macOS   → the `NSWindow*`
+//! Linux   → the X11 window id (XID); Wayland has no global window handle
+//!
+//! Safety requirements for callers: the value is only meaningful inside
+//! this process (or, for an HWND or XID, to code that also talks to this
+//! window through the OS) and only until the window is destroyed – a pop-out
+//! closed and reopened gets a new one.  JavaScript must treat it as an
+//! opaque number and pass it on unchanged to a native plugin, which has to
+//! touch an `NSWindow` on the main thread only and must not take ownership:
+//! the shell still closes and releases the window.
+
+use tauri::WebviewWindow;
+
+#[cfg(target_os = "windows")]
+fn raw_handle(window: &WebviewWindow) -> Result<isize, String> {
+    let hwnd = window.hwnd().map_err(|e| e.to_string())?;
+    Ok(hwnd.0 as isize)
+}
+
+#[cfg(target_os = "macos")]
+fn raw_handle(window: &WebviewWindow) -> Result<isize, String> {
+    let ns_window = window.ns_window().map_err(|e| e.to_string())?;
+    Ok(ns_window as isize)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn raw_handle(window: &WebviewWindow) -> Result<isize, String> {
+    use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+
+    let handle = window.window_handle().map_err(|e| e.to_string())?;
+    match handle.as_raw() {
+        RawWindowHandle::Xlib(handle) => Ok(handle.window as isize),
+        RawWindowHandle::Xcb(handle) => Ok(handle.window.get() as isize),
+        RawWindowHandle::Wayland(_) => Err("Wayland windows have no native handle.".into()),
+        _ => Err("The window has no X11 handle.".into()),
+    }
+}
+
+/// Native handle of the calling window; see the module docs before using
+/// it.
+#[tauri::command]
+pub fn get_window_handle(window: WebviewWindow) -> Result<isize, String> {
+    raw_handle(&window)
+}
@@ -0,0 +1,125 @@
+//! Window opacity, so ALMReady can fade while unfocused and show what is
+//! behind it (focus-follows-mouse setups).  The frontend decides when; the
+//! shell only applies a value, clamped to `MIN_OPACITY..=1.0` so a window
+//! can't become invisible.
+//!
+//! macOS   → `NSWindow.alphaValue`
+//! Windows → `WS_EX_LAYERED` plus `SetLayeredWindowAttributes(LWA_ALPHA)`
+//! Linux   → the `_NET_WM_WINDOW_OPACITY` property, set with `xprop` (X11
+//!           only; Wayland has no client-side window opacity)
+
+use tauri::WebviewWindow;
+
+const MIN_OPACITY: f32 = 0.1;
+
+fn clamp(opacity: f32) -> Result<f32, String> {
+    if opacity.is_nan() {
+        return Err("Opacity must be a number.".into());
+    }
+    Ok(opacity.clamp(MIN_OPACITY, 1.0))
+}
+
+// ── Platform setters ────────────────────────────────────────────────────────
+
+#[cfg(target_os = "macos")]
+fn apply(window: &WebviewWindow, opacity: f32) -> Result<(), String> {
+    use objc2_app_kit::NSWindow;
+
+    // Raw pointers aren't Send; AppKit is only touched on the main thread.
+    let ns_window = window.ns_window().map_err(|e| e.to_string())? as usize;
+    window
+        .run_on_main_thread(move || {
+            // SAFETY: the pointer is this window's NSWindow, which outlives
+            // the closure, and we are on the main thread.
+            let ns_window = unsafe { &*(ns_window as *const NSWindow) };
+            ns_window.setAlphaValue(f64::from(opacity));
+        })
+        .map_err(|e| e.to_string())
+}
+
+/// `SetLayeredWindowAttributes` alpha for `opacity`.
+#[cfg(any(target_os = "windows", test))]
+fn alpha_byte(opacity: f32) -> u8 {
+    (opacity * 255.0).round() as u8
+}
+
+#[cfg(target_os = "windows")]
+fn apply(window: &WebviewWindow, opacity: f32) -> Result<(), String> {
+    use windows::Win32::{
+        Foundation::{COLORREF, HWND},
+        UI::WindowsAndMessaging::{
+            GetWindowLongPtrW, SetLayeredWindowAttributes, SetWindowLongPtrW, GWL_EXSTYLE,
+            LWA_ALPHA, WS_EX_LAYERED,
+        },
+    };
+
+    let hwnd = HWND(window.hwnd().map_err(|e| e.to_string())?.0);
+    // SAFETY: `hwnd` is this window's handle; the layered style must be set
+    // before its alpha can be.
+    unsafe {
+        let style = GetWindowLongPtrW(hwnd, GWL_EXSTYLE);
+        if style & WS_EX_LAYERED.0 as isize == 0 {
+            SetWindowLongPtrW(hwnd, GWL_EXSTYLE, style | WS_EX_LAYERED.0 as isize);
+        }
+        SetLayeredWindowAttributes(hwnd, COLORREF(0), alpha_byte(opacity), LWA_ALPHA)
+    }
+    .map_err(|e| format!("SetLayeredWindowAttributes: {e}"))
+}
+
+/// `_NET_WM_WINDOW_OPACITY` value for `opacity` (0xFFFFFFFF is opaque).
+#[cfg(any(all(unix, not(target_os = "macos")), test))]
+fn net_wm_opacity(opacity: f32) -> u32 {
+    (f64::from(opacity) * f64::from(u32::MAX)).round() as u32
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn apply(window: &WebviewWindow, opacity: f32) -> Result<(), String> {
+    use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+
+    let handle = window.window_handle().map_err(|e| e.to_string())?;
+    let xid = match handle.as_raw() {
+        RawWindowHandle::Xlib(handle) => u64::from(handle.window),
+        RawWindowHandle::Xcb(handle) => u64::from(handle.window.get()),
+        RawWindowHandle::Wayland(_) => {
+            return Err("Window opacity is not supported on Wayland.".into())
+        }
+        _ => return Err("Window opacity needs an X11 window.".into()),
+    };
+    let status = std::process::Command::new("xprop")
+        .args(["-id", &xid.to_string()])
+        .args(["-f", "_NET_WM_WINDOW_OPACITY", "32c", "-set"])
+        .args([
+            "_NET_WM_WINDOW_OPACITY",
+            &net_wm_opacity(opacity).to_string(),
+        ])
+        .status()
+        .map_err(|e| format!("xprop: {e}"))?;
+    if !status.success() {
+        return Err(format!("xprop exited with {status}"));
+    }
+    Ok(())
+}
+
+/// Set the calling window's opacity, clamped to `[0.1, 1.0]`.
+#[tauri::command]
+pub fn set_window_opacity(window: WebviewWindow, opacity: f32) -> Result<(), String> {
+    apply(&window, clamp(opacity)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opacity_is_clamped_and_scaled_per_platform() {
+        assert_eq!(clamp(0.0), Ok(0.1));
+        assert_eq!(clamp(0.5), Ok(0.5));
+        assert_eq!(clamp(7.0), Ok(1.0));
+        assert!(clamp(f32::NAN).is_err());
+
+        assert_eq!(alpha_byte(1.0), 255);
+        assert_eq!(alpha_byte(0.5), 128);
+        assert_eq!(net_wm_opacity(1.0), u32::MAX);
+        assert_eq!(net_wm_opacity(0.1), 0x1999_99A0);
+    }
+}
@@ -0,0 +1,133 @@
+//! Window position in physical pixels, for test harnesses that need to put
+//! windows at exact coordinates.
+//!
+//! `set_window_position` refuses positions that would leave less than
+//! `MIN_VISIBLE` × `MIN_VISIBLE` pixels of the window inside the work area
+//! (the monitor minus taskbar/dock/menu bar) of the monitor nearest to it,
+//! so a typo can't move a window out of reach.
+
+use tauri::{PhysicalPosition, WebviewWindow};
+
+/// Pixels of the window, each way, that must stay in a work area.
+const MIN_VISIBLE: i64 = 100;
+
+/// A physical rectangle: left, top, width, height.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Rect {
+    x: i64,
+    y: i64,
+    width: i64,
+    height: i64,
+}
+
+impl Rect {
+    fn right(&self) -> i64 {
+        self.x + self.width
+    }
+
+    fn bottom(&self) -> i64 {
+        self.y + self.height
+    }
+
+    /// Squared distance from `other`'s center to the nearest point of
+    /// `self`; 0 when the center is inside.
+    fn distance_to_center_of(&self, other: &Rect) -> i64 {
+        let (cx, cy) = (other.x + other.width / 2, other.y + other.height / 2);
+        let dx = (self.x - cx).max(cx - self.right()).max(0);
+        let dy = (self.y - cy).max(cy - self.bottom()).max(0);
+        dx * dx + dy * dy
+    }
+
+    /// Width and height of the intersection, 0 when disjoint.
+    fn overlap(&self, other: &Rect) -> (i64, i64) {
+        let width = self.right().min(other.right()) - self.x.max(other.x);
+        let height = self.bottom().min(other.bottom()) - self.y.max(other.y);
+        (width.max(0), height.max(0))
+    }
+}
+
+/// `Ok` if enough of `window` is inside the work area nearest to it.
+fn check_visible(window: Rect, work_areas: &[Rect]) -> Result<(), String> {
+    let nearest = work_areas
+        .iter()
+        .min_by_key(|area| area.distance_to_center_of(&window))
+        .ok_or("No monitor is available.")?;
+    let (width, height) = nearest.overlap(&window);
+    // A window smaller than the minimum only has to be entirely visible.
+    if width >= MIN_VISIBLE.min(window.width) && height >= MIN_VISIBLE.min(window.height) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Position ({}, {}) leaves only {width} × {height} px of the window on screen; \
+             at least {MIN_VISIBLE} × {MIN_VISIBLE} must be.",
+            window.x, window.y
+        ))
+    }
+}
+
+/// The calling window's outer position, in physical pixels.
+#[tauri::command]
+pub fn get_window_position(window: WebviewWindow) -> Result<PhysicalPosition<i32>, String> {
+    window.outer_position().map_err(|e| e.to_string())
+}
+
+/// Move the calling window's outer top-left corner to (`x`, `y`), in
+/// physical pixels.
+#[tauri::command]
+pub fn set_window_position(window: WebviewWindow, x: i32, y: i32) -> Result<(), String> {
+    let size = window.outer_size().map_err(|e| e.to_string())?;
+    let work_areas: Vec<Rect> = window
+        .available_monitors()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(|monitor| {
+            let area = monitor.work_area();
+            Rect {
+                x: i64::from(area.position.x),
+                y: i64::from(area.position.y),
+                width: i64::from(area.size.width),
+                height: i64::from(area.size.height),
+            }
+        })
+        .collect();
+    let target = Rect {
+        x: i64::from(x),
+        y: i64::from(y),
+        width: i64::from(size.width),
+        height: i64::from(size.height),
+    };
+    check_visible(target, &work_areas)?;
+    window
+        .set_position(PhysicalPosition::new(x, y))
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: i64, y: i64, width: i64, height: i64) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn needs_a_corner_inside_the_nearest_work_area() {
+        // 1920×1080 with a 40 px taskbar, and a second monitor to the right.
+        let areas = [rect(0, 0, 1920, 1040), rect(1920, 0, 2560, 1400)];
+        let window = |x, y| rect(x, y, 1200, 800);
+
+        assert!(check_visible(window(100, 100), &areas).is_ok());
+        assert!(check_visible(window(-1100, 940), &areas).is_ok());
+        assert!(check_visible(window(-1101, 100), &areas).is_err());
+        assert!(check_visible(window(100, 941), &areas).is_err());
+        // Mostly on the second monitor: that one's work area counts.
+        assert!(check_visible(window(2000, 1300), &areas).is_ok());
+        assert!(check_visible(window(2000, 1301), &areas).is_err());
+        assert!(check_visible(window(100, 100), &[]).is_err());
+    }
+}
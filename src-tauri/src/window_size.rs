@@ -0,0 +1,98 @@
+//! Window size in logical pixels, alongside `window_position` for test
+//! harnesses.
+//!
+//! `set_window_size` refuses sizes below the window's minimum (the preset's
+//! 1024 × 768, or 900 × 600 in compact mode, unless the window was built
+//! with its own) and above its monitor's work area, which is as large as a
+//! window can usefully be – no window sets a maximum size of its own.
+
+use serde::Serialize;
+use tauri::{LogicalSize, Manager, WebviewWindow};
+
+use crate::window_factory::{WindowConfigs, WindowPreset};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowSize {
+    /// Inner size, logical pixels.
+    pub width: f64,
+    pub height: f64,
+    /// Physical pixels per logical pixel.
+    pub scale_factor: f64,
+}
+
+/// `Ok` if `size` is within `min..=max` each way.
+fn check_size(size: (f64, f64), min: (f64, f64), max: Option<(f64, f64)>) -> Result<(), String> {
+    if size.0 < min.0 || size.1 < min.1 {
+        return Err(format!(
+            "{} × {} is below the minimum window size of {} × {}.",
+            size.0, size.1, min.0, min.1
+        ));
+    }
+    match max {
+        Some(max) if size.0 > max.0 || size.1 > max.1 => Err(format!(
+            "{} × {} is larger than the screen's {} × {}.",
+            size.0, size.1, max.0, max.1
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// The calling window's inner size and scale factor.
+#[tauri::command]
+pub fn get_window_size(window: WebviewWindow) -> Result<WindowSize, String> {
+    let scale_factor = window.scale_factor().map_err(|e| e.to_string())?;
+    let size = window
+        .inner_size()
+        .map_err(|e| e.to_string())?
+        .to_logical::<f64>(scale_factor);
+    Ok(WindowSize {
+        width: size.width,
+        height: size.height,
+        scale_factor,
+    })
+}
+
+/// Resize the calling window's content area to `width` × `height` logical
+/// pixels.
+#[tauri::command]
+pub fn set_window_size(window: WebviewWindow, width: u32, height: u32) -> Result<(), String> {
+    let preset = WindowPreset::STANDARD;
+    let min = window
+        .app_handle()
+        .state::<WindowConfigs>()
+        .min_size(window.label())
+        .unwrap_or((preset.min_width, preset.min_height));
+    let max = window
+        .current_monitor()
+        .map_err(|e| e.to_string())?
+        .map(|monitor| {
+            let area = monitor
+                .work_area()
+                .size
+                .to_logical::<f64>(monitor.scale_factor());
+            (area.width, area.height)
+        });
+    check_size((f64::from(width), f64::from(height)), min, max)?;
+    window
+        .set_size(LogicalSize::new(width, height))
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn size_must_fit_between_the_minimum_and_the_screen() {
+        let min = (1024.0, 768.0);
+        let screen = Some((1920.0, 1040.0));
+        assert!(check_size((1024.0, 768.0), min, screen).is_ok());
+        assert!(check_size((1920.0, 1040.0), min, screen).is_ok());
+        assert!(check_size((1023.0, 800.0), min, screen).is_err());
+        assert!(check_size((1200.0, 767.0), min, screen).is_err());
+        assert!(check_size((1921.0, 800.0), min, screen).is_err());
+        // No monitor reported: only the minimum applies.
+        assert!(check_size((5000.0, 3000.0), min, None).is_ok());
+    }
+}
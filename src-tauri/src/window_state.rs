@@ -0,0 +1,354 @@
+//! Window geometry, remembered between sessions.
+//!
+//! The main window's outer position, inner size, maximized flag and the
+//! monitor it was on go to `{app_data_dir}/window-state.json` (a detached
+//! window's to `window-state-{label}.json`, see `detached_windows`) when it
+//! is closed and, `SAVE_DEBOUNCE` after the last event, whenever it is moved
+//! or resized.  The window is built with them instead of the centered default – unless the saved monitor is gone or the saved
+//! rectangle is entirely off-screen, so unplugging a monitor can't leave the
+//! window out of sight.  A restored size never goes below the preset's
+//! minimum.
+//!
+//! Geometry is saved in physical pixels, as monitors report it, and
+//! converted with the saved monitor's current scale factor on restore.
+//! While the window is maximized only the flag is updated, so it
+//! un-maximizes to the last normal size next session.  A minimized window
+//! is not saved (Windows reports it at -32000, -32000).
+//!
+//! The window's zoom factor (see `zoom`) is saved alongside and restored
+//! wherever the window ends up.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Monitor, Window};
+
+use crate::{
+    data_dir_access::DataDirAccess,
+    paths,
+    shell_log::{self, Level},
+    window_factory::{WindowConfig, WindowConfigs, MAIN_LABEL},
+    zoom,
+};
+
+const STATE_FILE: &str = "window-state.json";
+
+/// Quiet period after the last move/resize before the geometry is written.
+const SAVE_DEBOUNCE: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct SavedGeometry {
+    /// Outer position.
+    x: i32,
+    y: i32,
+    /// Inner size.
+    width: u32,
+    height: u32,
+    maximized: bool,
+    /// Monitor name, where the platform reports one.
+    monitor: Option<String>,
+    /// The user's zoom factor; absent in files from before zoom.
+    #[serde(default)]
+    zoom: Option<f64>,
+}
+
+/// A monitor's physical work area and scale.
+#[derive(Debug, Clone, PartialEq)]
+struct MonitorArea {
+    name: Option<String>,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    scale: f64,
+}
+
+impl From<&Monitor> for MonitorArea {
+    fn from(monitor: &Monitor) -> Self {
+        MonitorArea {
+            name: monitor.name().cloned(),
+            x: monitor.position().x,
+            y: monitor.position().y,
+            width: monitor.size().width,
+            height: monitor.size().height,
+            scale: monitor.scale_factor(),
+        }
+    }
+}
+
+/// Logical geometry to build the window with.
+#[derive(Debug, Clone, PartialEq)]
+struct Restored {
+    position: (f64, f64),
+    size: (f64, f64),
+    maximized: bool,
+}
+
+fn overlaps(saved: &SavedGeometry, monitor: &MonitorArea) -> bool {
+    let (left, top) = (i64::from(saved.x), i64::from(saved.y));
+    let (right, bottom) = (left + i64::from(saved.width), top + i64::from(saved.height));
+    let (m_left, m_top) = (i64::from(monitor.x), i64::from(monitor.y));
+    let (m_right, m_bottom) = (
+        m_left + i64::from(monitor.width),
+        m_top + i64::from(monitor.height),
+    );
+    left < m_right && m_left < right && top < m_bottom && m_top < bottom
+}
+
+/// Where to put the window, or `None` for the centered default.
+fn restore(
+    saved: &SavedGeometry,
+    monitors: &[MonitorArea],
+    min_size: (f64, f64),
+) -> Option<Restored> {
+    if !monitors.iter().any(|m| overlaps(saved, m)) {
+        return None;
+    }
+    let target = match &saved.monitor {
+        Some(name) => monitors.iter().find(|m| m.name.as_ref() == Some(name))?,
+        None => monitors.iter().find(|m| overlaps(saved, m))?,
+    };
+    let scale = if target.scale > 0.0 {
+        target.scale
+    } else {
+        1.0
+    };
+    Some(Restored {
+        position: (f64::from(saved.x) / scale, f64::from(saved.y) / scale),
+        size: (
+            (f64::from(saved.width) / scale).max(min_size.0),
+            (f64::from(saved.height) / scale).max(min_size.1),
+        ),
+        maximized: saved.maximized,
+    })
+}
+
+/// `window-state.json` for the main window, `window-state-{label}.json`
+/// for any other.
+fn file_name(label: &str) -> String {
+    if label == MAIN_LABEL {
+        STATE_FILE.to_string()
+    } else {
+        format!("window-state-{label}.json")
+    }
+}
+
+fn state_file(app: &AppHandle, label: &str) -> Result<PathBuf, String> {
+    paths::data_dir(app).map(|dir| dir.join(file_name(label)))
+}
+
+fn read(path: &Path) -> Option<SavedGeometry> {
+    let text = DataDirAccess::unleased()
+        .with(&[path], || std::fs::read_to_string(path))
+        .ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+fn write(path: &Path, geometry: &SavedGeometry) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("create {dir:?}: {e}"))?;
+    }
+    let text = serde_json::to_string_pretty(geometry).map_err(|e| e.to_string())?;
+    DataDirAccess::unleased()
+        .with(&[path], || std::fs::write(path, &text))
+        .map_err(|e| format!("write {path:?}: {e}"))
+}
+
+/// Replace `config`'s size and centering with the saved geometry, if it
+/// can still be shown.
+pub fn restore_into(app: &AppHandle, config: &mut WindowConfig) {
+    let Some(saved) = state_file(app, &config.label)
+        .ok()
+        .and_then(|path| read(&path))
+    else {
+        return;
+    };
+    if let Some(zoom) = saved.zoom.filter(|&zoom| zoom::is_valid(zoom)) {
+        config.zoom_factor = zoom;
+    }
+    let monitors: Vec<MonitorArea> = app
+        .available_monitors()
+        .unwrap_or_default()
+        .iter()
+        .map(MonitorArea::from)
+        .collect();
+    match restore(&saved, &monitors, config.min_size) {
+        Some(restored) => {
+            config.position = Some(restored.position);
+            config.size = restored.size;
+            config.center = false;
+            config.maximized = restored.maximized;
+        }
+        None => shell_log::log(
+            Level::Info,
+            "window-state",
+            "saved window position is off-screen, centering",
+        ),
+    }
+}
+
+// ── Saving ──────────────────────────────────────────────────────────────────
+
+/// The window's current geometry, unless it is minimized.
+fn capture(window: &Window) -> Option<SavedGeometry> {
+    if window.is_minimized().unwrap_or(false) {
+        return None;
+    }
+    let position = window.outer_position().ok()?;
+    let size = window.inner_size().ok()?;
+    Some(SavedGeometry {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+        maximized: window.is_maximized().unwrap_or(false),
+        monitor: window
+            .current_monitor()
+            .ok()
+            .flatten()
+            .and_then(|m| m.name().cloned()),
+        zoom: window
+            .app_handle()
+            .state::<WindowConfigs>()
+            .zoom_factor(window.label()),
+    })
+}
+
+/// Write the window's geometry now.
+pub fn save(window: &Window) {
+    let Ok(path) = state_file(window.app_handle(), window.label()) else {
+        return;
+    };
+    let Some(mut geometry) = capture(window) else {
+        return;
+    };
+    if geometry.maximized {
+        // Keep the normal-size rectangle from before maximizing.
+        if let Some(previous) = read(&path) {
+            geometry = SavedGeometry {
+                maximized: true,
+                zoom: geometry.zoom,
+                ..previous
+            };
+        }
+    }
+    if let Err(e) = write(&path, &geometry) {
+        shell_log::log(
+            Level::Warn,
+            "window-state",
+            format!("cannot save window geometry: {e}"),
+        );
+    }
+}
+
+/// Managed state: a counter per window label, bumped on every move/resize
+/// so only the last scheduled save in a burst runs.
+#[derive(Default)]
+pub struct GeometrySaver(Mutex<HashMap<String, u64>>);
+
+impl GeometrySaver {
+    fn bump(&self, label: &str) -> u64 {
+        let mut generations = self.0.lock().unwrap();
+        let generation = generations.entry(label.to_string()).or_default();
+        *generation += 1;
+        *generation
+    }
+
+    fn current(&self, label: &str) -> u64 {
+        self.0
+            .lock()
+            .unwrap()
+            .get(label)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// Save `SAVE_DEBOUNCE` after the last of a series of moves and resizes.
+pub fn schedule_save(window: &Window) {
+    let generation = window
+        .app_handle()
+        .state::<GeometrySaver>()
+        .bump(window.label());
+    let window = window.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(SAVE_DEBOUNCE).await;
+        let current = window
+            .app_handle()
+            .state::<GeometrySaver>()
+            .current(window.label());
+        if current == generation {
+            let _ = tauri::async_runtime::spawn_blocking(move || save(&window)).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(name: &str, x: i32, width: u32, scale: f64) -> MonitorArea {
+        MonitorArea {
+            name: Some(name.into()),
+            x,
+            y: 0,
+            width,
+            height: 1440,
+            scale,
+        }
+    }
+
+    fn saved(x: i32, monitor: Option<&str>) -> SavedGeometry {
+        SavedGeometry {
+            x,
+            y: 100,
+            width: 2400,
+            height: 1200,
+            maximized: false,
+            monitor: monitor.map(str::to_string),
+            zoom: None,
+        }
+    }
+
+    const MIN: (f64, f64) = (1024.0, 768.0);
+
+    #[test]
+    fn restores_on_the_saved_monitor_in_its_scale() {
+        let monitors = [
+            monitor("Built-in", 0, 2880, 2.0),
+            monitor("Ultrawide", 2880, 5120, 1.0),
+        ];
+        let restored = restore(&saved(3000, Some("Ultrawide")), &monitors, MIN).unwrap();
+        assert_eq!(restored.position, (3000.0, 100.0));
+        assert_eq!(restored.size, (2400.0, 1200.0));
+
+        let restored = restore(&saved(100, Some("Built-in")), &monitors, MIN).unwrap();
+        assert_eq!(restored.position, (50.0, 50.0));
+        assert_eq!(restored.size, (1200.0, 768.0), "height held at the minimum");
+    }
+
+    #[test]
+    fn falls_back_when_the_monitor_is_gone_or_the_window_off_screen() {
+        let monitors = [monitor("Built-in", 0, 2880, 2.0)];
+        // Unplugged ultrawide.
+        assert_eq!(
+            restore(&saved(3000, Some("Ultrawide")), &monitors, MIN),
+            None
+        );
+        // Entirely off-screen, whatever the name.
+        assert_eq!(
+            restore(&saved(-5000, Some("Built-in")), &monitors, MIN),
+            None
+        );
+        assert_eq!(restore(&saved(-5000, None), &monitors, MIN), None);
+        // Unnamed monitors (some Linux setups) match by overlap.
+        assert!(restore(&saved(100, None), &monitors, MIN).is_some());
+        // Partly off-screen is fine.
+        assert!(restore(&saved(-1000, Some("Built-in")), &monitors, MIN).is_some());
+    }
+}
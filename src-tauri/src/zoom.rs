@@ -0,0 +1,138 @@
+//! Browser-style zoom, per window.
+//!
+//! View → Zoom In / Zoom Out / Actual Size (Cmd/Ctrl `=`, `-`, `0`) act on
+//! the focused window; `set_zoom`/`get_zoom` on the calling one.  The
+//! factor moves in 10 % steps between `MIN` and `MAX`, counted in whole
+//! tenths so stepping never drifts and Actual Size is exactly 1.0.  It
+//! multiplies the preset's zoom (compact mode's 0.9, see `window_factory`).
+//!
+//! For the main and detached windows the factor is saved with the geometry
+//! (see `window_state`) and applied again when the window is next built, so
+//! a popped-out results window keeps its own.  Every change sends
+//! `zoom-changed { label, factor }` for the frontend's "125 %" badge.
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State, WebviewWindow};
+
+use crate::{
+    detached_windows,
+    events::{self, Audience},
+    window_factory::{WindowConfigs, MAIN_LABEL},
+    window_state,
+};
+
+pub const MIN: f64 = 0.5;
+pub const MAX: f64 = 3.0;
+
+pub const ZOOM_IN: &str = "zoom-in";
+pub const ZOOM_OUT: &str = "zoom-out";
+pub const ZOOM_RESET: &str = "zoom-reset";
+
+#[derive(Debug, Clone, Serialize)]
+struct ZoomChanged {
+    label: String,
+    factor: f64,
+}
+
+/// `factor` moved by `steps`, clamped to `MIN..=MAX`.
+fn stepped(factor: f64, steps: i32) -> f64 {
+    let tenths = (factor * 10.0).round() as i32 + steps;
+    let (min, max) = ((MIN * 10.0) as i32, (MAX * 10.0) as i32);
+    f64::from(tenths.clamp(min, max)) / 10.0
+}
+
+fn check(factor: f64) -> Result<f64, String> {
+    if !(MIN..=MAX).contains(&factor) {
+        return Err(format!(
+            "Zoom must be between {MIN} and {MAX}, got {factor}."
+        ));
+    }
+    Ok(factor)
+}
+
+/// Whether a saved factor can be used as is.
+pub fn is_valid(factor: f64) -> bool {
+    check(factor).is_ok()
+}
+
+/// Zoom `window` to `factor`, remember it and tell the frontend.
+fn apply(window: &WebviewWindow, factor: f64) -> Result<(), String> {
+    let app = window.app_handle();
+    let preset = app
+        .state::<WindowConfigs>()
+        .set_zoom_factor(window.label(), factor)
+        .ok_or_else(|| format!("Window {:?} was not built by the shell.", window.label()))?;
+    window
+        .set_zoom(preset * factor)
+        .map_err(|e| format!("Could not zoom: {e}"))?;
+    if window.label() == MAIN_LABEL || detached_windows::is_detached(window.label()) {
+        window_state::schedule_save(&window.as_ref().window());
+    }
+    events::emit(
+        app,
+        Audience::AllWindows,
+        "zoom-changed",
+        ZoomChanged {
+            label: window.label().to_string(),
+            factor,
+        },
+    );
+    Ok(())
+}
+
+/// Handle a View menu zoom item for the focused window.
+pub fn on_menu(app: &AppHandle, id: &str) {
+    let Some(window) = app
+        .webview_windows()
+        .into_values()
+        .find(|window| window.is_focused().unwrap_or(false))
+    else {
+        return;
+    };
+    let current = app
+        .state::<WindowConfigs>()
+        .zoom_factor(window.label())
+        .unwrap_or(1.0);
+    let factor = match id {
+        ZOOM_IN => stepped(current, 1),
+        ZOOM_OUT => stepped(current, -1),
+        _ => 1.0,
+    };
+    let _ = apply(&window, factor);
+}
+
+#[tauri::command]
+pub fn get_zoom(window: WebviewWindow, configs: State<'_, WindowConfigs>) -> f64 {
+    configs.zoom_factor(window.label()).unwrap_or(1.0)
+}
+
+/// Zoom the calling window to `factor`, `MIN` to `MAX`.
+#[tauri::command]
+pub fn set_zoom(window: WebviewWindow, factor: f64) -> Result<(), String> {
+    apply(&window, check(factor)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_in_tenths_within_bounds() {
+        let mut factor = 1.0;
+        for _ in 0..7 {
+            factor = stepped(factor, 1);
+        }
+        assert_eq!(factor, 1.7);
+        for _ in 0..7 {
+            factor = stepped(factor, -1);
+        }
+        assert_eq!(factor, 1.0, "back exactly, no drift");
+        assert_eq!(stepped(0.5, -1), MIN);
+        assert_eq!(stepped(3.0, 1), MAX);
+        assert_eq!(stepped(1.23, 1), 1.3);
+
+        assert!(is_valid(1.25));
+        assert!(!is_valid(3.5));
+        assert!(!is_valid(f64::NAN));
+    }
+}
@@ -11,7 +11,7 @@ import { Badge } from '@/components/ui/badge';
 import {
   getBalanceDetails,
   appendListParam,
-  API_BASE,
+  downloadFile,
   type BalanceDetailsResponse,
 } from '@/lib/api';
 import { DETAIL_CONTEXT_LABELS } from '@/config/balanceSchema';
@@ -195,8 +195,8 @@ export function BalanceDetailsModal({
     if (groupByDims.length > 0) qs.set('group_by', groupByDims.join(','));
 
     const query = qs.toString();
-    const url = `${API_BASE}/api/sessions/${encodeURIComponent(sessionId)}/balance/export${query ? `?${query}` : ''}`;
-    window.open(url, '_blank');
+    const path = `/api/sessions/${encodeURIComponent(sessionId)}/balance/export${query ? `?${query}` : ''}`;
+    downloadFile(path, 'balance_export.xlsx').catch((err) => setError(getErrorMessage(err)));
   };
 
   const allFilterBadges: { key: string; category: keyof BalanceFilters; value: string }[] = [
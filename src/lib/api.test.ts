@@ -0,0 +1,76 @@
+import { afterEach, beforeEach, describe, expect, it, vi } from "vitest";
+
+describe("backend address", () => {
+  const fetchMock = vi.fn();
+
+  function shell(platform: string) {
+    window.__ALMREADY__ = {
+      backendPort: 8123,
+      dataDir: "/data",
+      appVersion: "1.4.0",
+      platform,
+      devMode: false,
+    };
+  }
+
+  beforeEach(() => {
+    vi.resetModules();
+    fetchMock.mockReset();
+    fetchMock.mockResolvedValue(new Response(JSON.stringify({ status: "ok" })));
+    vi.stubGlobal("fetch", fetchMock);
+    window.__BACKEND_TOKEN__ = "s3cret";
+  });
+
+  afterEach(() => {
+    vi.unstubAllGlobals();
+    delete window.__ALMREADY__;
+    delete window.__BACKEND_TOKEN__;
+  });
+
+  function lastRequest(): { url: string; token: string | null } {
+    const [url, init] = fetchMock.mock.calls.at(-1) as [string, RequestInit | undefined];
+    return { url, token: new Headers(init?.headers).get("X-ALMReady-Token") };
+  }
+
+  it("goes through almready:// without holding the token", async () => {
+    shell("macos");
+    const { health } = await import("@/lib/api");
+    await health();
+    expect(lastRequest()).toEqual({ url: "almready://localhost/api/health", token: null });
+  });
+
+  it("uses WebView2's form of the scheme on Windows", async () => {
+    shell("windows");
+    const { health } = await import("@/lib/api");
+    await health();
+    expect(lastRequest().url).toBe("http://almready.localhost/api/health");
+  });
+
+  it("talks to the dev server without a shell", async () => {
+    const { health, backend } = await import("@/lib/api");
+    await health();
+    expect(lastRequest().url).toBe(`${backend.base}/api/health`);
+    expect(backend.base).not.toContain("almready");
+  });
+
+  it("downloads through the scheme", async () => {
+    shell("linux");
+    fetchMock.mockResolvedValue(
+      new Response("xlsx", {
+        headers: { "Content-Disposition": 'attachment; filename="balance_export_ab.xlsx"' },
+      }),
+    );
+    URL.createObjectURL = vi.fn(() => "blob:export");
+    URL.revokeObjectURL = vi.fn();
+    const click = vi.spyOn(HTMLAnchorElement.prototype, "click").mockImplementation(() => {});
+
+    const { downloadFile } = await import("@/lib/api");
+    await downloadFile("/api/sessions/ab/balance/export?currency=EUR", "fallback.xlsx");
+    expect(lastRequest()).toEqual({
+      url: "almready://localhost/api/sessions/ab/balance/export?currency=EUR",
+      token: null,
+    });
+    expect((click.mock.contexts[0] as HTMLAnchorElement).download).toBe("balance_export_ab.xlsx");
+    click.mockRestore();
+  });
+});
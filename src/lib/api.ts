@@ -22,20 +22,29 @@
  *   For retrieving cached results on page refresh.
  */
 
-// In a packaged Tauri app, the Rust shell injects window.__BACKEND_PORT__ via
-// initialization_script() before this module loads, so the IIFE always finds
-// the value set.  In dev (npm run dev + uvicorn) the variable is undefined and
-// we fall back to VITE_API_BASE_URL or the default uvicorn port.
-const API_BASE: string = (() => {
-  if (typeof window !== "undefined" && window.__BACKEND_PORT__) {
-    return `http://127.0.0.1:${window.__BACKEND_PORT__}`;
+/**
+ * Where the backend is.  In the desktop app every request goes through the
+ * shell's almready:// scheme (src-tauri/src/api_protocol.rs), which forwards
+ * it to the sidecar's current port with the session token: the page holds
+ * neither, and a backend restart on a new port changes nothing here.
+ * WebView2 (Windows) serves the scheme as http://almready.localhost.
+ *
+ * In dev (npm run dev + uvicorn) there is no shell: we fall back to
+ * VITE_API_BASE_URL or the default uvicorn port, which needs no token.
+ */
+function backendBase(): string {
+  const shell = typeof window !== "undefined" ? window.__ALMREADY__ : undefined;
+  if (shell) {
+    return shell.platform === "windows" ? "http://almready.localhost" : "almready://localhost";
   }
   return import.meta.env.VITE_API_BASE_URL ?? "http://localhost:8000";
-})();
+}
+
+export const backend: { base: string } = { base: backendBase() };
 
 /** Generic HTTP helper. All API calls flow through here. */
 async function http<T>(path: string, init?: RequestInit): Promise<T> {
-  const res = await fetch(`${API_BASE}${path}`, init);
+  const res = await fetch(`${backend.base}${path}`, init);
   if (!res.ok) {
     const text = await res.text().catch(() => "");
     throw new Error(`HTTP ${res.status} ${res.statusText} on ${path}: ${text}`);
@@ -43,6 +52,29 @@ async function http<T>(path: string, init?: RequestInit): Promise<T> {
   return (await res.json()) as T;
 }
 
+/**
+ * Download `path` and save it under the name from its Content-Disposition
+ * (else `fallbackName`).  Fetched rather than opened with window.open, which
+ * would navigate the webview to the scheme instead of saving.
+ */
+export async function downloadFile(path: string, fallbackName: string): Promise<void> {
+  const res = await fetch(`${backend.base}${path}`);
+  if (!res.ok) {
+    const text = await res.text().catch(() => "");
+    throw new Error(`HTTP ${res.status} ${res.statusText} on ${path}: ${text}`);
+  }
+  const disposition = res.headers.get("Content-Disposition") ?? "";
+  const name = /filename="?([^";]+)"?/i.exec(disposition)?.[1] ?? fallbackName;
+  const url = URL.createObjectURL(await res.blob());
+  const link = document.createElement("a");
+  link.href = url;
+  link.download = name;
+  document.body.appendChild(link);
+  link.click();
+  link.remove();
+  setTimeout(() => URL.revokeObjectURL(url), 0);
+}
+
 /**
  * XHR-based multipart POST with two-phase progress hooks.
  * onProgress(0→80): fires as bytes are sent over the wire.
@@ -58,7 +90,7 @@ function xhrUpload<T>(
 ): Promise<T> {
   return new Promise<T>((resolve, reject) => {
     const xhr = new XMLHttpRequest();
-    xhr.open("POST", `${API_BASE}${path}`);
+    xhr.open("POST", `${backend.base}${path}`);
 
     if (onProgress) {
       xhr.upload.onprogress = (e) => {
@@ -383,8 +415,6 @@ export type CalculationResultsResponse = {
   warnings: string[];
 };
 
-export { API_BASE };
-
 export function appendListParam(qs: URLSearchParams, key: string, values?: string[]) {
   if (!values || values.length === 0) return;
   qs.set(key, values.join(","));
@@ -0,0 +1,44 @@
+import { afterEach, describe, expect, it, vi } from "vitest";
+
+import { listen } from "@/lib/tauri";
+
+describe("listen", () => {
+  afterEach(() => {
+    delete window.__TAURI__;
+  });
+
+  it("does nothing outside the desktop app", async () => {
+    const unlisten = await listen("zoom-changed", () => {});
+    expect(() => unlisten()).not.toThrow();
+  });
+
+  it("reports every add and remove to the shell once", async () => {
+    const invoke = vi.fn(() => Promise.resolve());
+    const removed = vi.fn();
+    let deliver: (event: { payload: unknown }) => void = () => {};
+    window.__TAURI__ = {
+      core: { invoke: invoke as never },
+      event: {
+        listen: (_event, handler) => {
+          deliver = handler as typeof deliver;
+          return Promise.resolve(removed);
+        },
+      },
+    };
+
+    const handler = vi.fn();
+    const unlisten = await listen<number>("zoom-changed", handler);
+    deliver({ payload: 1.25 });
+    expect(handler).toHaveBeenCalledWith(1.25);
+    expect(invoke).toHaveBeenCalledWith("track_listener", { event: "zoom-changed", added: true });
+
+    unlisten();
+    unlisten();
+    expect(removed).toHaveBeenCalledTimes(1);
+    expect(invoke).toHaveBeenCalledTimes(2);
+    expect(invoke).toHaveBeenLastCalledWith("track_listener", {
+      event: "zoom-changed",
+      added: false,
+    });
+  });
+});
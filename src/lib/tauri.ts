@@ -0,0 +1,30 @@
+/**
+ * tauri.ts – access to the desktop shell, when there is one.
+ *
+ * The shell sets `withGlobalTauri`, so its API is on window.__TAURI__
+ * instead of being imported from @tauri-apps/api.  In a plain browser
+ * (npm run dev) it is absent and these helpers do nothing.
+ */
+
+export type Unlisten = () => void;
+
+/**
+ * Call `handler` with the payload of every `event` the shell emits.
+ *
+ * Every add and remove is reported to the shell (track_listener), whose
+ * get_event_listener_count shows listeners per window and event – Tauri
+ * itself keeps no count.  Use this rather than __TAURI__.event.listen.
+ */
+export async function listen<T>(event: string, handler: (payload: T) => void): Promise<Unlisten> {
+  const tauri = typeof window !== "undefined" ? window.__TAURI__ : undefined;
+  if (!tauri) return () => {};
+  const unlisten = await tauri.event.listen<T>(event, (e) => handler(e.payload));
+  void tauri.core.invoke("track_listener", { event, added: true }).catch(() => {});
+  let removed = false;
+  return () => {
+    if (removed) return;
+    removed = true;
+    unlisten();
+    void tauri.core.invoke("track_listener", { event, added: false }).catch(() => {});
+  };
+}
@@ -1,8 +1,59 @@
 /// <reference types="vite/client" />
 
 // Injected by the Tauri Rust shell via WebviewWindowBuilder::initialization_script()
-// before any page scripts run.  Set to the dynamic port chosen by sidecar_main.py.
-// Undefined in browser/dev contexts – api.ts falls back to VITE_API_BASE_URL.
+// before any page scripts run.  Undefined in browser/dev contexts – api.ts then
+// falls back to VITE_API_BASE_URL.
 interface Window {
+  // Shell facts for this window (see src-tauri/src/window_factory.rs);
+  // supersedes __BACKEND_PORT__, which is kept for one more release.
+  __ALMREADY__?: {
+    backendPort: number;
+    // Where sessions and logs are stored.
+    dataDir: string;
+    appVersion: string;
+    // Rust's std::env::consts::OS: "windows", "macos", "linux", …
+    platform: string;
+    // Running from `cargo tauri dev`.
+    devMode: boolean;
+  };
+  // The dynamic port chosen by sidecar_main.py.
   __BACKEND_PORT__?: number;
+  // Per-session secret the backend requires as X-ALMReady-Token (see
+  // src-tauri/src/session_token.rs).  Undefined in dev.  api.ts needs neither:
+  // almready:// adds the token itself.
+  __BACKEND_TOKEN__?: string;
+  // Shell-side UI configuration (see src-tauri/src/window_factory.rs).
+  __ALMREADY_CONFIG__?: {
+    compact: boolean;
+    // Active degradations at window creation, most severe first; updates
+    // arrive as `degradations-changed` (see src-tauri/src/degradations.rs).
+    degradations: {
+      code: string;
+      severity: "info" | "warning" | "error";
+      messageKey: string;
+      details: unknown;
+    }[];
+  };
+  // Tier of a valid license.key; absent when unlicensed (see license.rs).
+  __LICENSE_TIER__?: string;
+  // Local time zone for timeline dates (see src-tauri/src/time_zone.rs).
+  __TZ__?: {
+    iana_name: string;
+    utc_offset_minutes: number;
+    observes_dst: boolean;
+  };
+  // The shell's JS API (withGlobalTauri); only what src/lib/tauri.ts uses.
+  // Undefined outside the desktop app.
+  __TAURI__?: {
+    core: {
+      invoke<T = unknown>(command: string, args?: Record<string, unknown>): Promise<T>;
+    };
+    event: {
+      listen<T>(event: string, handler: (event: { payload: T }) => void): Promise<() => void>;
+    };
+  };
+  // Lock overlay hooks called by the shell's lock_screen / unlock_screen
+  // (see src-tauri/src/screen_lock.rs).
+  showLockScreen?: () => void;
+  hideLockScreen?: () => void;
 }